@@ -0,0 +1,220 @@
+//! [`Arbitrary`] implementations, which only produce values that can be written and read again
+//! without losing any information (e.g. for fuzzing round trips).
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{header::LatLng, Compression, Directory, Entry, Header, PMTiles, TileType};
+
+/// Maximum zoom level of tiles in generated archives, which keeps archives small.
+const MAX_ZOOM: u8 = 5;
+
+/// Maximum number of tiles in generated archives.
+const MAX_TILES: usize = 64;
+
+/// Maximum length of tiles in generated entries (see [`ReadLimits::max_tile_length`](crate::ReadLimits::max_tile_length)).
+const MAX_TILE_LENGTH: u32 = 256 * 1024 * 1024;
+
+impl<'a> Arbitrary<'a> for Compression {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        u.choose(&[
+            Self::Unknown,
+            Self::None,
+            Self::GZip,
+            Self::Brotli,
+            Self::ZStd,
+        ])
+        .copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for TileType {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        u.choose(&[
+            Self::Unknown,
+            Self::Mvt,
+            Self::Png,
+            Self::Jpeg,
+            Self::WebP,
+            Self::Avif,
+        ])
+        .copied()
+    }
+}
+
+impl<'a> Arbitrary<'a> for LatLng {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // positions are stored as integers, so only those can be read back unchanged
+        Ok(Self {
+            longitude: f64::from(u.int_in_range(-1_800_000_000..=1_800_000_000)?) / 10_000_000.0,
+            latitude: f64::from(u.int_in_range(-900_000_000..=900_000_000)?) / 10_000_000.0,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Header {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            spec_version: 3,
+            root_directory_offset: u.arbitrary()?,
+            root_directory_length: u.arbitrary()?,
+            json_metadata_offset: u.arbitrary()?,
+            json_metadata_length: u.arbitrary()?,
+            leaf_directories_offset: u.arbitrary()?,
+            leaf_directories_length: u.arbitrary()?,
+            tile_data_offset: u.arbitrary()?,
+            tile_data_length: u.arbitrary()?,
+            num_addressed_tiles: u.arbitrary()?,
+            num_tile_entries: u.arbitrary()?,
+            num_tile_content: u.arbitrary()?,
+            clustered: u.arbitrary()?,
+            internal_compression: u.arbitrary()?,
+            tile_compression: u.arbitrary()?,
+            tile_type: u.arbitrary()?,
+            min_zoom: u.arbitrary()?,
+            max_zoom: u.arbitrary()?,
+            min_pos: u.arbitrary()?,
+            max_pos: u.arbitrary()?,
+            center_zoom: u.arbitrary()?,
+            center_pos: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Entry {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            tile_id: u.int_in_range(0..=u64::MAX / 2)?,
+            // offsets are stored incremented by one and are added to lengths while writing
+            offset: u.int_in_range(0..=u64::MAX / 2)?,
+            // entries must be neither empty nor exceed the default read limits
+            length: u.int_in_range(1..=MAX_TILE_LENGTH)?,
+            run_length: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Directory {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut entries = Vec::<Entry>::new();
+        let mut next_tile_id = 0u64;
+
+        u.arbitrary_loop(None, Some(256), |u| {
+            let gap: u32 = u.arbitrary()?;
+            let entry = Entry {
+                tile_id: next_tile_id.saturating_add(gap.into()),
+                offset: u.int_in_range(0..=u64::MAX / 2)?,
+                length: u.int_in_range(1..=MAX_TILE_LENGTH)?,
+                run_length: u.arbitrary()?,
+            };
+
+            // entries are sorted by tile id and do not overlap
+            next_tile_id = match entry
+                .tile_id
+                .checked_add(u64::from(entry.run_length.max(1)))
+            {
+                Some(next) => next,
+                None => return Ok(std::ops::ControlFlow::Break(())),
+            };
+
+            entries.push(entry);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+
+        Ok(entries.into())
+    }
+}
+
+impl<'a, R> Arbitrary<'a> for PMTiles<R> {
+    /// Generates a small archive with up to 64 tiles of up to zoom level 5.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut pm_tiles = Self::default();
+        pm_tiles.tile_type = u.arbitrary()?;
+        pm_tiles.tile_compression = u.arbitrary()?;
+        pm_tiles.internal_compression = *u.choose(&[
+            Compression::None,
+            Compression::GZip,
+            Compression::Brotli,
+            Compression::ZStd,
+        ])?;
+        pm_tiles.min_zoom = u.int_in_range(0..=MAX_ZOOM)?;
+        pm_tiles.max_zoom = u.int_in_range(pm_tiles.min_zoom..=MAX_ZOOM)?;
+
+        let max_tile_id = crate::util::tile_id(MAX_ZOOM + 1, 0, 0) - 1;
+        let num_tiles = u.int_in_range(0..=MAX_TILES)?;
+        for _ in 0..num_tiles {
+            let tile_id = u.int_in_range(0..=max_tile_id)?;
+            let mut data: Vec<u8> = u.arbitrary()?;
+            if data.is_empty() {
+                data.push(u.arbitrary()?);
+            }
+
+            pm_tiles
+                .add_tile(tile_id, data)
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        }
+
+        Ok(pm_tiles)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Returns some pseudo random bytes to generate values from.
+    #[allow(clippy::cast_possible_truncation)]
+    fn bytes(seed: u8) -> Vec<u8> {
+        (0..4096u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8 ^ seed)
+            .collect()
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        for seed in 0..16 {
+            let header = Header::arbitrary(&mut Unstructured::new(&bytes(seed))).unwrap();
+
+            let mut buf = Vec::new();
+            header.to_writer(&mut buf).unwrap();
+            assert_eq!(Header::from_bytes(&buf).unwrap(), header);
+        }
+    }
+
+    #[test]
+    fn test_directory_round_trip() {
+        for seed in 0..16 {
+            let directory = Directory::arbitrary(&mut Unstructured::new(&bytes(seed))).unwrap();
+            assert!(directory.check_entries().is_ok());
+
+            let mut buf = Vec::new();
+            directory.to_writer(&mut buf, Compression::GZip).unwrap();
+            assert_eq!(
+                Directory::from_bytes(&buf, Compression::GZip).unwrap(),
+                directory
+            );
+        }
+    }
+
+    #[test]
+    fn test_pm_tiles_round_trip() {
+        for seed in 0..16 {
+            let mut pm_tiles =
+                PMTiles::<Cursor<&[u8]>>::arbitrary(&mut Unstructured::new(&bytes(seed))).unwrap();
+
+            let expected = pm_tiles
+                .tile_ids_sorted()
+                .into_iter()
+                .map(|tile_id| (tile_id, pm_tiles.get_tile_by_id(tile_id).unwrap()))
+                .collect::<Vec<_>>();
+
+            let mut read = PMTiles::from_bytes(pm_tiles.to_vec().unwrap()).unwrap();
+            assert_eq!(read.num_tiles(), expected.len());
+            for (tile_id, data) in expected {
+                assert_eq!(read.get_tile_by_id(tile_id).unwrap(), data);
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+/// A bounding box of WGS84 coordinates (in degrees).
+///
+/// Functions taking a bounding box accept anything, which converts into a [`BBox`]: A tuple
+/// `(min_longitude, min_latitude, max_longitude, max_latitude)` or (with the `geo` feature)
+/// a [`geo_types::Rect`], whose x coordinates are longitudes and y coordinates are latitudes.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::BBox;
+/// let bbox = BBox::from((11.15, 43.72, 11.33, 43.84));
+///
+/// assert_eq!(bbox.min_longitude, 11.15);
+/// assert_eq!(bbox.max_latitude, 43.84);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BBox {
+    /// Minimum longitude (western edge)
+    pub min_longitude: f64,
+
+    /// Minimum latitude (southern edge)
+    pub min_latitude: f64,
+
+    /// Maximum longitude (eastern edge)
+    pub max_longitude: f64,
+
+    /// Maximum latitude (northern edge)
+    pub max_latitude: f64,
+}
+
+impl BBox {
+    /// Returns the bounding box as `(min_longitude, min_latitude, max_longitude, max_latitude)`.
+    pub const fn to_tuple(self) -> (f64, f64, f64, f64) {
+        (
+            self.min_longitude,
+            self.min_latitude,
+            self.max_longitude,
+            self.max_latitude,
+        )
+    }
+}
+
+impl From<(f64, f64, f64, f64)> for BBox {
+    fn from(
+        (min_longitude, min_latitude, max_longitude, max_latitude): (f64, f64, f64, f64),
+    ) -> Self {
+        Self {
+            min_longitude,
+            min_latitude,
+            max_longitude,
+            max_latitude,
+        }
+    }
+}
+
+impl From<BBox> for (f64, f64, f64, f64) {
+    fn from(bbox: BBox) -> Self {
+        bbox.to_tuple()
+    }
+}
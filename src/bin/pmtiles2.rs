@@ -0,0 +1,364 @@
+//! Command line interface for inspecting, validating, converting and serving `PMTiles` archives.
+
+#![warn(clippy::cargo)]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![warn(clippy::unwrap_used)]
+#![warn(clippy::expect_used)]
+#![allow(clippy::multiple_crate_versions)]
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use pmtiles2::{BBox, Compression, ExtractOptions, PMTiles, Result, TileScheme};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Prints the header and meta data of an archive
+    Info {
+        /// Path of the archive
+        input: PathBuf,
+
+        /// Print header and meta data as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Checks an archive for violations of the specification
+    Validate {
+        /// Path of the archive
+        input: PathBuf,
+    },
+
+    /// Copies all tiles within a bounding box and zoom range into a new archive
+    Extract {
+        /// Path of the archive to extract tiles from
+        input: PathBuf,
+
+        /// Path of the archive to write
+        output: PathBuf,
+
+        /// Bounding box as `min_longitude,min_latitude,max_longitude,max_latitude`
+        #[arg(long, value_parser = parse_bbox, default_value = "-180,-90,180,90")]
+        bbox: BBox,
+
+        /// Minimum zoom level (defaults to the minimum zoom level of the archive)
+        #[arg(long)]
+        min_zoom: Option<u8>,
+
+        /// Maximum zoom level (defaults to the maximum zoom level of the archive)
+        #[arg(long)]
+        max_zoom: Option<u8>,
+    },
+
+    /// Converts between archives and tile directories (or other supported formats)
+    ///
+    /// The input may be an archive, a directory with the structure `{z}/{x}/{y}.{ext}` or (if
+    /// enabled) a `.tar`, `.zip` or `.comtiles` file. Outputs ending with `.pmtiles` (or
+    /// `.comtiles`) are written as archives, any other output as tile directory.
+    Convert {
+        /// Path of the input
+        input: PathBuf,
+
+        /// Path of the output
+        output: PathBuf,
+
+        /// Re-compress tiles with this compression before writing
+        #[arg(long)]
+        tile_compression: Option<TileCompression>,
+
+        /// Tiling scheme of tile directories (and tar or zip files)
+        #[arg(long, default_value = "xyz")]
+        scheme: Scheme,
+    },
+
+    /// Serves the tiles of an archive at `/{z}/{x}/{y}`
+    Serve {
+        /// Path of the archive
+        input: PathBuf,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        address: SocketAddr,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum TileCompression {
+    None,
+    Gzip,
+    Brotli,
+    Zstd,
+}
+
+impl From<TileCompression> for Compression {
+    fn from(compression: TileCompression) -> Self {
+        match compression {
+            TileCompression::None => Self::None,
+            TileCompression::Gzip => Self::GZip,
+            TileCompression::Brotli => Self::Brotli,
+            TileCompression::Zstd => Self::ZStd,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Scheme {
+    Xyz,
+    Tms,
+}
+
+impl From<Scheme> for TileScheme {
+    fn from(scheme: Scheme) -> Self {
+        match scheme {
+            Scheme::Xyz => Self::Xyz,
+            Scheme::Tms => Self::Tms,
+        }
+    }
+}
+
+/// Parses a bounding box from four comma separated numbers.
+fn parse_bbox(value: &str) -> std::result::Result<BBox, String> {
+    let values = value
+        .split(',')
+        .map(|v| v.trim().parse::<f64>().map_err(|err| err.to_string()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    match values[..] {
+        [min_longitude, min_latitude, max_longitude, max_latitude] => Ok(BBox::from((
+            min_longitude,
+            min_latitude,
+            max_longitude,
+            max_latitude,
+        ))),
+        _ => Err(format!(
+            "expected 4 comma separated numbers, got {}",
+            values.len()
+        )),
+    }
+}
+
+/// Returns the lowercase extension of `path`.
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+}
+
+fn info(input: &Path, json: bool) -> Result<()> {
+    let summary = PMTiles::read_summary(&mut BufReader::new(File::open(input)?))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+
+    let header = summary.header;
+
+    println!("spec version:         {}", header.spec_version);
+    println!("tile type:            {:?}", header.tile_type);
+    println!("tile compression:     {:?}", header.tile_compression);
+    println!("internal compression: {:?}", header.internal_compression);
+    println!("clustered:            {}", header.clustered);
+    println!(
+        "zoom levels:          {} - {}",
+        header.min_zoom, header.max_zoom
+    );
+    println!(
+        "bounds:               {}, {}, {}, {}",
+        header.min_pos.longitude,
+        header.min_pos.latitude,
+        header.max_pos.longitude,
+        header.max_pos.latitude
+    );
+    println!(
+        "center:               {}, {} (zoom {})",
+        header.center_pos.longitude, header.center_pos.latitude, header.center_zoom
+    );
+    println!("addressed tiles:      {}", header.num_addressed_tiles);
+    println!("tile entries:         {}", header.num_tile_entries);
+    println!("tile contents:        {}", header.num_tile_content);
+    println!(
+        "meta data:            {}",
+        serde_json::to_string_pretty(&summary.meta_data)?
+    );
+
+    Ok(())
+}
+
+fn validate(input: &Path) -> Result<ExitCode> {
+    let report = PMTiles::validate(&mut BufReader::new(File::open(input)?))?;
+
+    if report.is_valid() {
+        println!("{} is valid", input.display());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    for violation in &report.violations {
+        println!("{violation}");
+    }
+    println!(
+        "{} has {} violation(s)",
+        input.display(),
+        report.violations.len()
+    );
+
+    Ok(ExitCode::FAILURE)
+}
+
+fn extract(
+    input: &Path,
+    output: &Path,
+    bbox: BBox,
+    min_zoom: Option<u8>,
+    max_zoom: Option<u8>,
+) -> Result<()> {
+    let mut pm_tiles = PMTiles::from_reader(BufReader::new(File::open(input)?))?;
+
+    let zoom_range = min_zoom.unwrap_or(pm_tiles.min_zoom)..=max_zoom.unwrap_or(pm_tiles.max_zoom);
+    let extract = pm_tiles.extract(bbox, zoom_range)?;
+
+    println!("Extracted {} tiles", extract.num_tiles());
+    extract.to_writer(&mut BufWriter::new(File::create(output)?))
+}
+
+fn convert(
+    input: &Path,
+    output: &Path,
+    tile_compression: Option<Compression>,
+    scheme: TileScheme,
+) -> Result<()> {
+    if input.is_dir() {
+        return write_converted(
+            PMTiles::from_dir_with_scheme(input, scheme)?,
+            output,
+            tile_compression,
+            scheme,
+        );
+    }
+
+    let reader = BufReader::new(File::open(input)?);
+    match extension(input).as_deref() {
+        #[cfg(feature = "tar")]
+        Some("tar") => write_converted(
+            PMTiles::from_tar_reader_with_scheme(reader, scheme)?,
+            output,
+            tile_compression,
+            scheme,
+        ),
+        #[cfg(feature = "zip")]
+        Some("zip") => write_converted(
+            PMTiles::from_zip_reader_with_scheme(reader, scheme)?,
+            output,
+            tile_compression,
+            scheme,
+        ),
+        #[cfg(feature = "comtiles")]
+        Some("comtiles") => write_converted(
+            PMTiles::from_comtiles_reader(reader)?,
+            output,
+            tile_compression,
+            scheme,
+        ),
+        _ => write_converted(
+            PMTiles::from_reader(reader)?,
+            output,
+            tile_compression,
+            scheme,
+        ),
+    }
+}
+
+fn write_converted<R: Read + Seek>(
+    mut pm_tiles: PMTiles<R>,
+    output: &Path,
+    tile_compression: Option<Compression>,
+    scheme: TileScheme,
+) -> Result<()> {
+    if let Some(compression) = tile_compression {
+        pm_tiles.transcode_tile_compression(compression, None)?;
+    }
+
+    println!("Converting {} tiles", pm_tiles.num_tiles());
+
+    match extension(output).as_deref() {
+        Some("pmtiles") => pm_tiles.to_writer(&mut BufWriter::new(File::create(output)?)),
+        #[cfg(feature = "comtiles")]
+        Some("comtiles") => pm_tiles.to_comtiles_writer(&mut BufWriter::new(File::create(output)?)),
+        _ => pm_tiles.extract_to_dir(
+            output,
+            ExtractOptions {
+                scheme,
+                ..ExtractOptions::default()
+            },
+        ),
+    }
+}
+
+async fn serve(input: &Path, address: SocketAddr) -> Result<()> {
+    let pm_tiles = PMTiles::from_reader(File::open(input)?)?;
+
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    println!(
+        "Serving {} at http://{address}/{{z}}/{{x}}/{{y}}",
+        input.display()
+    );
+
+    axum::serve(listener, pmtiles2::server::router(pm_tiles)).await?;
+
+    Ok(())
+}
+
+fn run(command: Command) -> Result<ExitCode> {
+    match command {
+        Command::Info { input, json } => info(&input, json)?,
+        Command::Validate { input } => return validate(&input),
+        Command::Extract {
+            input,
+            output,
+            bbox,
+            min_zoom,
+            max_zoom,
+        } => extract(&input, &output, bbox, min_zoom, max_zoom)?,
+        Command::Convert {
+            input,
+            output,
+            tile_compression,
+            scheme,
+        } => convert(
+            &input,
+            &output,
+            tile_compression.map(Into::into),
+            scheme.into(),
+        )?,
+        Command::Serve { input, address } => {
+            tokio::runtime::Runtime::new()?.block_on(serve(&input, address))?;
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(cli.command) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::io::Cursor;
+
+use serde_json::{Map as JSONMap, Value as JSONValue};
+
+use crate::{Compression, Error, PMTiles, Result, TileType};
+
+/// A builder for [`PMTiles`] archives, which validates the combination of all fields before the
+/// archive is created.
+///
+/// Fields, which are not set explicitly, default to the values of [`PMTiles::new`], except for the
+/// bounds, which default to the whole world. The center defaults to the center of the bounds and
+/// the center zoom to the minimum zoom.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{Compression, PMTilesBuilder, TileType};
+/// # use serde_json::json;
+/// let pm_tiles = PMTilesBuilder::new()
+///     .tile_type(TileType::Mvt)
+///     .tile_compression(Compression::GZip)
+///     .zoom_range(0, 14)
+///     .bounds(11.1, 43.7, 11.4, 43.8)
+///     .meta_data_entry("vector_layers", json!([{ "id": "roads", "fields": {} }]))
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(pm_tiles.max_zoom, 14);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PMTilesBuilder {
+    tile_type: TileType,
+    tile_compression: Compression,
+    internal_compression: Compression,
+    min_zoom: u8,
+    max_zoom: u8,
+    center_zoom: Option<u8>,
+    bounds: (f64, f64, f64, f64),
+    center: Option<(f64, f64)>,
+    meta_data: JSONMap<String, JSONValue>,
+}
+
+impl Default for PMTilesBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PMTilesBuilder {
+    /// Creates a new builder with default values.
+    pub fn new() -> Self {
+        Self {
+            tile_type: TileType::Unknown,
+            tile_compression: Compression::Unknown,
+            internal_compression: Compression::GZip,
+            min_zoom: 0,
+            max_zoom: 0,
+            center_zoom: None,
+            bounds: (-180.0, -85.051_129, 180.0, 85.051_129),
+            center: None,
+            meta_data: JSONMap::new(),
+        }
+    }
+
+    /// Sets the type of tiles.
+    #[must_use]
+    pub const fn tile_type(mut self, tile_type: TileType) -> Self {
+        self.tile_type = tile_type;
+        self
+    }
+
+    /// Sets the compression of tiles.
+    #[must_use]
+    pub const fn tile_compression(mut self, tile_compression: Compression) -> Self {
+        self.tile_compression = tile_compression;
+        self
+    }
+
+    /// Sets the compression of directories and meta data (default GZIP).
+    #[must_use]
+    pub const fn internal_compression(mut self, internal_compression: Compression) -> Self {
+        self.internal_compression = internal_compression;
+        self
+    }
+
+    /// Sets the minimum and maximum zoom of all tiles.
+    #[must_use]
+    pub const fn zoom_range(mut self, min_zoom: u8, max_zoom: u8) -> Self {
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    /// Sets the center zoom (defaults to the minimum zoom).
+    #[must_use]
+    pub const fn center_zoom(mut self, center_zoom: u8) -> Self {
+        self.center_zoom = Some(center_zoom);
+        self
+    }
+
+    /// Sets the bounds of available tiles (in degrees).
+    #[must_use]
+    pub const fn bounds(
+        mut self,
+        min_longitude: f64,
+        min_latitude: f64,
+        max_longitude: f64,
+        max_latitude: f64,
+    ) -> Self {
+        self.bounds = (min_longitude, min_latitude, max_longitude, max_latitude);
+        self
+    }
+
+    /// Sets the center longitude and latitude (defaults to the center of the bounds).
+    #[must_use]
+    pub const fn center(mut self, longitude: f64, latitude: f64) -> Self {
+        self.center = Some((longitude, latitude));
+        self
+    }
+
+    /// Sets the JSON meta data, replacing all previously set entries.
+    #[must_use]
+    pub fn meta_data(mut self, meta_data: JSONMap<String, JSONValue>) -> Self {
+        self.meta_data = meta_data;
+        self
+    }
+
+    /// Sets a single entry of the JSON meta data.
+    #[must_use]
+    pub fn meta_data_entry(mut self, key: impl Into<String>, value: impl Into<JSONValue>) -> Self {
+        self.meta_data.insert(key.into(), value.into());
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(Error::Validation(msg));
+        let (min_lon, min_lat, max_lon, max_lat) = self.bounds;
+
+        if self.tile_type == TileType::Mvt
+            && !self
+                .meta_data
+                .get("vector_layers")
+                .is_some_and(JSONValue::is_array)
+        {
+            return invalid(
+                "Archives with vector tiles require a \"vector_layers\" array in their meta data"
+                    .to_string(),
+            );
+        }
+
+        if self.min_zoom > self.max_zoom {
+            return invalid(format!(
+                "Minimum zoom ({}) is greater than maximum zoom ({})",
+                self.min_zoom, self.max_zoom
+            ));
+        }
+
+        if let Some(center_zoom) = self.center_zoom {
+            if !(self.min_zoom..=self.max_zoom).contains(&center_zoom) {
+                return invalid(format!(
+                    "Center zoom ({center_zoom}) is not within zoom range ({}-{})",
+                    self.min_zoom, self.max_zoom
+                ));
+            }
+        }
+
+        let lon_range = -180.0..=180.0;
+        let lat_range = -90.0..=90.0;
+        if !lon_range.contains(&min_lon)
+            || !lon_range.contains(&max_lon)
+            || !lat_range.contains(&min_lat)
+            || !lat_range.contains(&max_lat)
+        {
+            return invalid(format!(
+                "Bounds ({min_lon}, {min_lat}, {max_lon}, {max_lat}) are not valid coordinates"
+            ));
+        }
+
+        if min_lon > max_lon || min_lat > max_lat {
+            return invalid(format!(
+                "Minimum of bounds ({min_lon}, {min_lat}) is greater than maximum ({max_lon}, {max_lat})"
+            ));
+        }
+
+        if let Some((lon, lat)) = self.center {
+            if !(min_lon..=max_lon).contains(&lon) || !(min_lat..=max_lat).contains(&lat) {
+                return invalid(format!("Center ({lon}, {lat}) is not within bounds"));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn build_into<R>(self, mut pm_tiles: PMTiles<R>) -> Result<PMTiles<R>> {
+        self.validate()?;
+
+        let (min_lon, min_lat, max_lon, max_lat) = self.bounds;
+        let (center_lon, center_lat) = self.center.unwrap_or_else(|| {
+            (
+                f64::midpoint(min_lon, max_lon),
+                f64::midpoint(min_lat, max_lat),
+            )
+        });
+
+        pm_tiles.tile_type = self.tile_type;
+        pm_tiles.tile_compression = self.tile_compression;
+        pm_tiles.internal_compression = self.internal_compression;
+        pm_tiles.min_zoom = self.min_zoom;
+        pm_tiles.max_zoom = self.max_zoom;
+        pm_tiles.center_zoom = self.center_zoom.unwrap_or(self.min_zoom);
+        pm_tiles.min_longitude = min_lon;
+        pm_tiles.min_latitude = min_lat;
+        pm_tiles.max_longitude = max_lon;
+        pm_tiles.max_latitude = max_lat;
+        pm_tiles.center_longitude = center_lon;
+        pm_tiles.center_latitude = center_lat;
+        pm_tiles.meta_data = self.meta_data;
+
+        Ok(pm_tiles)
+    }
+
+    /// Validates all fields and builds a new, empty `PMTiles` archive.
+    ///
+    /// # Errors
+    /// Will return [`Error::Validation`] if...
+    /// - the tile type is [`TileType::Mvt`] and the meta data does not include a `vector_layers` array
+    /// - the minimum zoom is greater than the maximum zoom
+    /// - the center zoom is not within the zoom range
+    /// - the bounds are no valid coordinates or their minimum is greater than their maximum
+    /// - the center is not within the bounds
+    pub fn build<'a>(self) -> Result<PMTiles<Cursor<&'a [u8]>>> {
+        self.build_into(PMTiles::default())
+    }
+
+    /// Async version of [`build`](Self::build).
+    ///
+    /// Validates all fields and builds a new, empty `PMTiles` archive, that works with asynchronous
+    /// readers / writers.
+    ///
+    /// # Errors
+    /// See [`build`](Self::build) for details on possible errors.
+    #[cfg(feature = "async")]
+    pub fn build_async<'a>(self) -> Result<PMTiles<futures::io::Cursor<&'a [u8]>>> {
+        self.build_into(PMTiles::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_build() -> Result<()> {
+        let pm_tiles = PMTilesBuilder::new()
+            .tile_type(TileType::Png)
+            .tile_compression(Compression::None)
+            .zoom_range(2, 10)
+            .bounds(10.0, 40.0, 12.0, 44.0)
+            .meta_data_entry("name", "test")
+            .build()?;
+
+        assert_eq!(pm_tiles.tile_type, TileType::Png);
+        assert_eq!(pm_tiles.min_zoom, 2);
+        assert_eq!(pm_tiles.max_zoom, 10);
+        assert_eq!(pm_tiles.center_zoom, 2);
+        assert!((pm_tiles.center_longitude - 11.0).abs() < f64::EPSILON);
+        assert!((pm_tiles.center_latitude - 42.0).abs() < f64::EPSILON);
+        assert_eq!(pm_tiles.meta_data.get("name"), Some(&json!("test")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        let is_invalid =
+            |builder: PMTilesBuilder| matches!(builder.build(), Err(Error::Validation(_)));
+
+        assert!(is_invalid(PMTilesBuilder::new().tile_type(TileType::Mvt)));
+        assert!(is_invalid(
+            PMTilesBuilder::new()
+                .tile_type(TileType::Mvt)
+                .meta_data_entry("vector_layers", "roads")
+        ));
+        assert!(is_invalid(PMTilesBuilder::new().zoom_range(5, 4)));
+        assert!(is_invalid(
+            PMTilesBuilder::new().zoom_range(0, 4).center_zoom(5)
+        ));
+        assert!(is_invalid(
+            PMTilesBuilder::new().bounds(-181.0, 0.0, 0.0, 1.0)
+        ));
+        assert!(is_invalid(PMTilesBuilder::new().bounds(1.0, 0.0, 0.0, 1.0)));
+        assert!(is_invalid(
+            PMTilesBuilder::new()
+                .bounds(0.0, 0.0, 1.0, 1.0)
+                .center(2.0, 0.5)
+        ));
+
+        assert!(!is_invalid(
+            PMTilesBuilder::new()
+                .tile_type(TileType::Mvt)
+                .meta_data_entry("vector_layers", json!([]))
+        ));
+    }
+}
@@ -0,0 +1,266 @@
+use std::io::{Cursor, Read};
+
+use crate::{
+    tile_dir::{parse_tile_path, TileCollector},
+    PMTiles, Result, TileScheme,
+};
+
+impl PMTiles<Cursor<&[u8]>> {
+    /// Builds a `PMTiles` archive from a tar file holding tiles with the structure `{z}/{x}/{y}.{ext}`
+    /// (XYZ tiling scheme), like raster bundles or zipped tile caches.
+    ///
+    /// The tar file is read as a stream, so it does not have to be extracted to disk (or even be seekable).
+    /// Compressed tar files can be read by wrapping `input` in a decoder (e.g. [`flate2::read::GzDecoder`]).
+    ///
+    /// Entries are matched by the last three components of their paths, so tiles may be nested
+    /// in other directories (e.g. `tiles/{z}/{x}/{y}.png`). Tile type, tile compression and zoom
+    /// levels are inferred like in [`from_dir`](Self::from_dir); entries, which do not match the
+    /// structure or have an unknown extension, are ignored. All tiles are read into memory.
+    ///
+    /// # Arguments
+    /// * `input` - Reader holding the tar file
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an I/O error while reading the tar file, a tile is empty,
+    /// has coordinates out of range ([`Error::InvalidTileCoordinates`](crate::Error::InvalidTileCoordinates))
+    /// or tiles with different tile types were found ([`Error::MixedTileTypes`](crate::Error::MixedTileTypes)).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use pmtiles2::PMTiles;
+    /// let input = std::fs::File::open("./tiles.tar").unwrap();
+    ///
+    /// let pm_tiles = PMTiles::from_tar_reader(input).unwrap();
+    /// ```
+    #[cfg(feature = "tar")]
+    pub fn from_tar_reader(input: impl Read) -> Result<Self> {
+        Self::from_tar_reader_with_scheme(input, TileScheme::Xyz)
+    }
+
+    /// Same as [`from_tar_reader`](Self::from_tar_reader), but with an extra parameter.
+    ///
+    /// # Arguments
+    /// * `input` - Reader holding the tar file
+    /// * `scheme` - Tiling scheme of the tiles in the tar file
+    ///
+    /// # Errors
+    /// See [`from_tar_reader`](Self::from_tar_reader) for details on possible errors.
+    #[cfg(feature = "tar")]
+    pub fn from_tar_reader_with_scheme(input: impl Read, scheme: TileScheme) -> Result<Self> {
+        let mut collector = TileCollector::new(scheme);
+
+        for entry in tar::Archive::new(input).entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let path = entry.path()?;
+            let Some((z, x, y, tile_type)) = path.to_str().and_then(parse_tile_path) else {
+                continue;
+            };
+
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+
+            collector.add_tile(z, x, y, tile_type, data)?;
+        }
+
+        Ok(collector.finish())
+    }
+
+    /// Builds a `PMTiles` archive from a zip file holding tiles with the structure `{z}/{x}/{y}.{ext}`
+    /// (XYZ tiling scheme), like raster bundles or zipped tile caches.
+    ///
+    /// The zip file is read as a stream (using the local headers of its entries), so it does not have
+    /// to be extracted to disk (or even be seekable). Entries are matched like in
+    /// [`from_tar_reader`](Self::from_tar_reader). Stored and deflated entries are supported.
+    ///
+    /// # Arguments
+    /// * `input` - Reader holding the zip file
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading the zip file (e.g. because it uses
+    /// an unsupported compression method), a tile is empty, has coordinates out of range
+    /// ([`Error::InvalidTileCoordinates`](crate::Error::InvalidTileCoordinates)) or tiles with
+    /// different tile types were found ([`Error::MixedTileTypes`](crate::Error::MixedTileTypes)).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use pmtiles2::PMTiles;
+    /// let input = std::fs::File::open("./tiles.zip").unwrap();
+    ///
+    /// let pm_tiles = PMTiles::from_zip_reader(input).unwrap();
+    /// ```
+    #[cfg(feature = "zip")]
+    pub fn from_zip_reader(input: impl Read) -> Result<Self> {
+        Self::from_zip_reader_with_scheme(input, TileScheme::Xyz)
+    }
+
+    /// Same as [`from_zip_reader`](Self::from_zip_reader), but with an extra parameter.
+    ///
+    /// # Arguments
+    /// * `input` - Reader holding the zip file
+    /// * `scheme` - Tiling scheme of the tiles in the zip file
+    ///
+    /// # Errors
+    /// See [`from_zip_reader`](Self::from_zip_reader) for details on possible errors.
+    #[cfg(feature = "zip")]
+    pub fn from_zip_reader_with_scheme(mut input: impl Read, scheme: TileScheme) -> Result<Self> {
+        let mut collector = TileCollector::new(scheme);
+
+        while let Some(mut file) =
+            zip::read::read_zipfile_from_stream(&mut input).map_err(std::io::Error::from)?
+        {
+            if !file.is_file() {
+                continue;
+            }
+
+            let Some((z, x, y, tile_type)) = parse_tile_path(file.name()) else {
+                continue;
+            };
+
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+
+            collector.add_tile(z, x, y, tile_type, data)?;
+        }
+
+        Ok(collector.finish())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{util::tile_id, Compression, TileType};
+
+    const TILES: [(&str, &[u8]); 5] = [
+        ("bundle/2/1/3.png", &[2, 1, 3]),
+        ("bundle/4/15/0.PNG", &[4, 15, 0]),
+        ("bundle/3/2/1.png", &[3, 2, 1]),
+        ("bundle/metadata.json", b"{}"),
+        ("bundle/3/2/readme.txt", b"foo"),
+    ];
+
+    fn check_archive(mut pm_tiles: PMTiles<Cursor<&[u8]>>) -> Result<()> {
+        assert_eq!(pm_tiles.tile_type, TileType::Png);
+        assert_eq!(pm_tiles.tile_compression, Compression::None);
+        assert_eq!(pm_tiles.min_zoom, 2);
+        assert_eq!(pm_tiles.max_zoom, 4);
+        assert_eq!(
+            pm_tiles.tile_ids_sorted(),
+            [tile_id(2, 1, 3), tile_id(3, 2, 1), tile_id(4, 15, 0)]
+        );
+        assert_eq!(pm_tiles.get_tile(1, 3, 2)?, Some(vec![2, 1, 3]));
+        assert_eq!(pm_tiles.get_tile(15, 0, 4)?, Some(vec![4, 15, 0]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_tile_path() {
+        assert_eq!(parse_tile_path("2/1/3.png"), Some((2, 1, 3, TileType::Png)));
+        assert_eq!(
+            parse_tile_path("./tiles/14/8529/5974.pbf"),
+            Some((14, 8529, 5974, TileType::Mvt))
+        );
+        assert_eq!(parse_tile_path("1/3.png"), None);
+        assert_eq!(parse_tile_path("2/1/3"), None);
+        assert_eq!(parse_tile_path("2/1/3.txt"), None);
+        assert_eq!(parse_tile_path("a/1/3.png"), None);
+    }
+
+    #[cfg(feature = "tar")]
+    fn tar_file(tiles: &[(&str, &[u8])]) -> Result<Vec<u8>> {
+        let mut builder = tar::Builder::new(Vec::new());
+
+        for (path, data) in tiles {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, path, *data)?;
+        }
+
+        Ok(builder.into_inner()?)
+    }
+
+    #[cfg(feature = "zip")]
+    fn zip_file(tiles: &[(&str, &[u8])]) -> Result<Vec<u8>> {
+        use std::io::Write;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .add_directory("bundle/", zip::write::SimpleFileOptions::default())
+            .map_err(std::io::Error::from)?;
+
+        for (path, data) in tiles {
+            writer
+                .start_file(*path, zip::write::SimpleFileOptions::default())
+                .map_err(std::io::Error::from)?;
+            writer.write_all(data)?;
+        }
+
+        Ok(writer.finish().map_err(std::io::Error::from)?.into_inner())
+    }
+
+    #[test]
+    #[cfg(feature = "tar")]
+    fn test_from_tar_reader() -> Result<()> {
+        let bytes = tar_file(&TILES)?;
+
+        check_archive(PMTiles::from_tar_reader(bytes.as_slice())?)?;
+
+        // stream compressed with gzip
+        let compressed = crate::util::compress_all(Compression::GZip, &bytes)?;
+        check_archive(PMTiles::from_tar_reader(flate2::read::GzDecoder::new(
+            compressed.as_slice(),
+        ))?)
+    }
+
+    #[test]
+    #[cfg(feature = "tar")]
+    fn test_from_tar_reader_with_scheme() -> Result<()> {
+        let bytes = tar_file(&[("1/0/1.png", &[1, 3, 3, 7])])?;
+
+        let mut pm_tiles = PMTiles::from_tar_reader_with_scheme(bytes.as_slice(), TileScheme::Tms)?;
+
+        assert_eq!(pm_tiles.get_tile(0, 0, 1)?, Some(vec![1, 3, 3, 7]));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "tar")]
+    fn test_from_tar_reader_invalid() -> Result<()> {
+        let bytes = tar_file(&[("1/0/1.png", &[1]), ("1/1/1.jpg", &[2])])?;
+        assert!(matches!(
+            PMTiles::from_tar_reader(bytes.as_slice()),
+            Err(crate::Error::MixedTileTypes { .. })
+        ));
+
+        let bytes = tar_file(&[("1/2/0.png", &[1])])?;
+        assert!(matches!(
+            PMTiles::from_tar_reader(bytes.as_slice()),
+            Err(crate::Error::InvalidTileCoordinates { z: 1, x: 2, y: 0 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "zip")]
+    fn test_from_zip_reader() -> Result<()> {
+        let bytes = zip_file(&TILES)?;
+
+        check_archive(PMTiles::from_zip_reader(bytes.as_slice())?)?;
+
+        let bytes = zip_file(&[("1/0/1.png", &[1, 3, 3, 7])])?;
+        let mut pm_tiles = PMTiles::from_zip_reader_with_scheme(bytes.as_slice(), TileScheme::Tms)?;
+        assert_eq!(pm_tiles.get_tile(0, 0, 1)?, Some(vec![1, 3, 3, 7]));
+
+        assert!(PMTiles::from_zip_reader([1, 3, 3, 7].as_slice()).is_err());
+
+        Ok(())
+    }
+}
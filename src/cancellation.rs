@@ -0,0 +1,74 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::{Error, Result};
+
+/// A token to cancel long running operations, like reading or writing a large archive.
+///
+/// Clones of a token share their state, so one clone can be handed to an operation while another one
+/// is kept to cancel it (e.g. when a server request was abandoned by the client). Operations check the
+/// token between reads / writes and return [`Error::Cancelled`] as soon as possible after it was cancelled.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::CancellationToken;
+/// let token = CancellationToken::new();
+///
+/// // hand a clone to the operation, e.g. `PMTiles::from_async_reader_with_cancellation`
+/// let clone = token.clone();
+///
+/// token.cancel();
+/// assert!(clone.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new token, which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all operations using this token or any of its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns [`Error::Cancelled`] if the token was cancelled.
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cancellation_token() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+
+        assert!(!token.is_cancelled());
+        assert!(token.check().is_ok());
+
+        clone.cancel();
+
+        assert!(token.is_cancelled());
+        assert!(matches!(token.check(), Err(Error::Cancelled)));
+        assert!(!CancellationToken::new().is_cancelled());
+    }
+}
@@ -0,0 +1,742 @@
+//! Import and export of archives in the [COMTiles](https://github.com/mactrem/com-tiles) format.
+//!
+//! A `COMTiles` archive consists of a header (magic, version, length of the meta data and length
+//! of the index), the meta data as JSON, the index and the tile data. The index holds an entry
+//! (offset relative to the tile data and length) for every tile inside the limits of the tile
+//! matrices listed in the meta data, in row-major order. Missing tiles have a length of 0.
+
+use std::{collections::HashMap, sync::Arc};
+
+#[cfg(feature = "async")]
+use futures::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use std::io::{Read, Seek, Write};
+
+use duplicate::duplicate_item;
+use serde_json::{json, Map as JSONMap, Value as JSONValue};
+
+use crate::{
+    read_limits::check_limit,
+    util::{bounds_to_range, detect_compression, tile_id, zxy},
+    Compression, Entry, Error, PMTiles, ReadLimits, Result, TileType,
+};
+
+/// Magic bytes at the start of every `COMTiles` archive.
+const MAGIC: &[u8; 4] = b"COMT";
+
+/// The only supported version of the `COMTiles` format.
+const VERSION: u32 = 1;
+
+/// Length of the header (magic, version, length of the meta data and length of the index).
+const HEADER_BYTES: usize = 17;
+
+/// Length of the offset of an index entry.
+const OFFSET_BYTES: usize = 5;
+
+/// Length of the tile length of an index entry.
+const SIZE_BYTES: usize = 4;
+
+/// Length of a single index entry.
+const INDEX_ENTRY_BYTES: usize = OFFSET_BYTES + SIZE_BYTES;
+
+/// Meta data keys, which are moved into fields of [`PMTiles`] or describe the index.
+const HEADER_KEYS: [&str; 6] = [
+    "tileFormat",
+    "tileOffsetBytes",
+    "tileSizeBytes",
+    "tileMatrixSet",
+    "bounds",
+    "center",
+];
+
+/// Limits of the tiles of a single zoom level, which are included in the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TileMatrix {
+    zoom: u8,
+    min_col: u64,
+    min_row: u64,
+    max_col: u64,
+    max_row: u64,
+}
+
+impl TileMatrix {
+    /// Returns the number of tiles inside the limits of this tile matrix.
+    const fn num_tiles(&self) -> u64 {
+        (self.max_col - self.min_col + 1) * (self.max_row - self.min_row + 1)
+    }
+
+    /// Returns the ids of all tiles inside the limits of this tile matrix in row-major order.
+    fn tile_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        (self.min_row..=self.max_row)
+            .flat_map(move |row| (self.min_col..=self.max_col).map(move |col| (row, col)))
+            .map(|(row, col)| tile_id(self.zoom, col, row))
+    }
+
+    /// Parses a tile matrix of the `tileMatrixSet` of the meta data.
+    fn from_json(value: &JSONValue) -> Result<Self> {
+        let invalid = || Error::DirectoryDecode(format!("Invalid tile matrix: {value}"));
+        let number = |value: &JSONValue, key: &str| value.get(key).and_then(JSONValue::as_u64);
+
+        // the coefficient is -1 for indexes without aggregated fragments
+        if value
+            .get("aggregationCoefficient")
+            .and_then(JSONValue::as_i64)
+            .is_some_and(|coefficient| coefficient != -1)
+        {
+            return Err(Error::Unsupported(
+                "Only COMTiles archives without aggregated index fragments are supported",
+            ));
+        }
+
+        let zoom = number(value, "zoom")
+            .and_then(|zoom| u8::try_from(zoom).ok())
+            .filter(|zoom| *zoom < 32)
+            .ok_or_else(invalid)?;
+        let limits = value.get("tileMatrixLimits").ok_or_else(invalid)?;
+        let matrix = Self {
+            zoom,
+            min_col: number(limits, "minTileCol").ok_or_else(invalid)?,
+            min_row: number(limits, "minTileRow").ok_or_else(invalid)?,
+            max_col: number(limits, "maxTileCol").ok_or_else(invalid)?,
+            max_row: number(limits, "maxTileRow").ok_or_else(invalid)?,
+        };
+
+        let max = (1u64 << zoom) - 1;
+        if matrix.min_col > matrix.max_col
+            || matrix.min_row > matrix.max_row
+            || matrix.max_col > max
+            || matrix.max_row > max
+        {
+            return Err(invalid());
+        }
+
+        Ok(matrix)
+    }
+
+    /// Returns the tile matrix set of the meta data.
+    fn to_json(self) -> JSONValue {
+        json!({
+            "zoom": self.zoom,
+            "aggregationCoefficient": -1,
+            "tileMatrixLimits": {
+                "minTileCol": self.min_col,
+                "minTileRow": self.min_row,
+                "maxTileCol": self.max_col,
+                "maxTileRow": self.max_row,
+            },
+        })
+    }
+
+    /// Returns the smallest tile matrices (one per zoom level), which include all `tile_ids`.
+    fn covering(tile_ids: &[u64]) -> Result<Vec<Self>> {
+        let mut matrices: Vec<Self> = Vec::new();
+
+        for tile_id in tile_ids {
+            let (zoom, col, row) = zxy(*tile_id).map_err(|_| Error::InvalidTileId(*tile_id))?;
+
+            match matrices.last_mut() {
+                Some(matrix) if matrix.zoom == zoom => {
+                    matrix.min_col = matrix.min_col.min(col);
+                    matrix.min_row = matrix.min_row.min(row);
+                    matrix.max_col = matrix.max_col.max(col);
+                    matrix.max_row = matrix.max_row.max(row);
+                }
+                _ => matrices.push(Self {
+                    zoom,
+                    min_col: col,
+                    min_row: row,
+                    max_col: col,
+                    max_row: row,
+                }),
+            }
+        }
+
+        Ok(matrices)
+    }
+}
+
+/// Contents of a `COMTiles` archive.
+#[derive(Debug)]
+struct ComTilesArchive {
+    /// Meta data as stored in the archive
+    meta_data: JSONMap<String, JSONValue>,
+
+    /// Tile matrices of the index, sorted as stored in the archive
+    tile_matrices: Vec<TileMatrix>,
+
+    /// Tile entries with absolute offsets
+    entries: Vec<Entry>,
+
+    /// Compression of tiles, as detected from the first tile
+    detected_compression: Compression,
+}
+
+impl ComTilesArchive {
+    /// Sets all fields of `pm_tiles`, which are stored in the meta data of `COMTiles` archives.
+    ///
+    /// Keys, which correspond to fields of the header or describe the index, are removed from
+    /// the meta data.
+    fn apply_meta_data<R>(mut self, pm_tiles: &mut PMTiles<R>) {
+        let meta_data = &mut self.meta_data;
+
+        pm_tiles.tile_type = meta_data
+            .get("tileFormat")
+            .and_then(JSONValue::as_str)
+            .map_or(TileType::Unknown, TileType::from_format);
+        pm_tiles.tile_compression = self.detected_compression;
+
+        let zooms = self.tile_matrices.iter().map(|matrix| matrix.zoom);
+        pm_tiles.min_zoom = zooms.clone().min().unwrap_or(0);
+        pm_tiles.max_zoom = zooms.max().unwrap_or(0);
+
+        let bounds = numbers(meta_data.get("bounds"));
+        if let [min_longitude, min_latitude, max_longitude, max_latitude] = bounds[..] {
+            pm_tiles.min_longitude = min_longitude;
+            pm_tiles.min_latitude = min_latitude;
+            pm_tiles.max_longitude = max_longitude;
+            pm_tiles.max_latitude = max_latitude;
+        } else {
+            pm_tiles.min_longitude = -180.0;
+            pm_tiles.min_latitude = -85.0;
+            pm_tiles.max_longitude = 180.0;
+            pm_tiles.max_latitude = 85.0;
+        }
+
+        let center = numbers(meta_data.get("center"));
+        if let [longitude, latitude, ..] = center[..] {
+            pm_tiles.center_longitude = longitude;
+            pm_tiles.center_latitude = latitude;
+        } else {
+            pm_tiles.center_longitude =
+                f64::midpoint(pm_tiles.min_longitude, pm_tiles.max_longitude);
+            pm_tiles.center_latitude = f64::midpoint(pm_tiles.min_latitude, pm_tiles.max_latitude);
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let center_zoom = center
+            .get(2)
+            .filter(|zoom| (0.0..=f64::from(u8::MAX)).contains(*zoom))
+            .map(|zoom| *zoom as u8);
+        pm_tiles.center_zoom = center_zoom.unwrap_or(pm_tiles.min_zoom);
+
+        for key in HEADER_KEYS {
+            meta_data.remove(key);
+        }
+
+        pm_tiles.meta_data = self.meta_data;
+    }
+}
+
+/// Returns a list of numbers, which is stored as an array.
+fn numbers(value: Option<&JSONValue>) -> Vec<f64> {
+    value
+        .and_then(JSONValue::as_array)
+        .map(|values| values.iter().filter_map(JSONValue::as_f64).collect())
+        .unwrap_or_default()
+}
+
+/// Parses a little endian unsigned integer of up to 8 bytes.
+fn parse_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, byte| (value << 8) | u64::from(*byte))
+}
+
+/// Returns the tile matrices of the `tileMatrixSet` of `meta_data`.
+fn parse_tile_matrices(meta_data: &JSONMap<String, JSONValue>) -> Result<Vec<TileMatrix>> {
+    for (key, expected) in [
+        ("tileOffsetBytes", OFFSET_BYTES),
+        ("tileSizeBytes", SIZE_BYTES),
+    ] {
+        if meta_data
+            .get(key)
+            .and_then(JSONValue::as_u64)
+            .is_some_and(|bytes| bytes != expected as u64)
+        {
+            return Err(Error::Unsupported(
+                "Only COMTiles archives with index entries of 5 byte offsets and 4 byte lengths are supported",
+            ));
+        }
+    }
+
+    meta_data
+        .get("tileMatrixSet")
+        .and_then(|tile_matrix_set| tile_matrix_set.get("tileMatrix"))
+        .and_then(JSONValue::as_array)
+        .ok_or_else(|| {
+            Error::DirectoryDecode("Meta data does not include a tile matrix set".to_string())
+        })?
+        .iter()
+        .map(TileMatrix::from_json)
+        .collect()
+}
+
+/// Parses the `index` into tile entries with offsets relative to the start of the archive.
+fn parse_index(
+    index: &[u8],
+    tile_matrices: &[TileMatrix],
+    data_offset: u64,
+    limits: &ReadLimits,
+) -> Result<Vec<Entry>> {
+    let num_index_entries = tile_matrices
+        .iter()
+        .fold(0u64, |sum, matrix| sum.saturating_add(matrix.num_tiles()));
+    check_limit("number of entries", num_index_entries, limits.max_entries)?;
+
+    if num_index_entries.saturating_mul(INDEX_ENTRY_BYTES as u64) != index.len() as u64 {
+        return Err(Error::DirectoryDecode(format!(
+            "Index has a length of {} bytes, but the tile matrix set includes {num_index_entries} tiles",
+            index.len()
+        )));
+    }
+
+    let tile_ids = tile_matrices.iter().flat_map(TileMatrix::tile_ids);
+    let mut entries = Vec::new();
+    for (tile_id, index_entry) in tile_ids.zip(index.chunks_exact(INDEX_ENTRY_BYTES)) {
+        let (offset, length) = index_entry.split_at(OFFSET_BYTES);
+
+        #[allow(clippy::cast_possible_truncation)]
+        let length = parse_uint(length) as u32;
+        if length == 0 {
+            continue;
+        }
+
+        let entry = Entry {
+            tile_id,
+            offset: data_offset + parse_uint(offset),
+            length,
+            run_length: 1,
+        };
+        limits.check_tile_length(&entry)?;
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Returns the meta data of a `COMTiles` archive holding the tiles of `tile_matrices`.
+fn meta_data<R>(pm_tiles: &PMTiles<R>, tile_matrices: &[TileMatrix]) -> JSONMap<String, JSONValue> {
+    let mut meta_data = pm_tiles.meta_data.clone();
+
+    if let Some(format) = pm_tiles.tile_type.format() {
+        meta_data.insert("tileFormat".to_string(), format.into());
+    }
+    meta_data.insert("tileOffsetBytes".to_string(), OFFSET_BYTES.into());
+    meta_data.insert("tileSizeBytes".to_string(), SIZE_BYTES.into());
+    meta_data.insert(
+        "bounds".to_string(),
+        json!([
+            pm_tiles.min_longitude,
+            pm_tiles.min_latitude,
+            pm_tiles.max_longitude,
+            pm_tiles.max_latitude,
+        ]),
+    );
+    meta_data.insert(
+        "center".to_string(),
+        json!([
+            pm_tiles.center_longitude,
+            pm_tiles.center_latitude,
+            pm_tiles.center_zoom,
+        ]),
+    );
+    meta_data.insert(
+        "tileMatrixSet".to_string(),
+        json!({
+            "tileMatrixCRS": "WebMercatorQuad",
+            "fragmentOrdering": "RowMajor",
+            "tileOrdering": "RowMajor",
+            "tileMatrix": tile_matrices.iter().map(|matrix| matrix.to_json()).collect::<Vec<_>>(),
+        }),
+    );
+
+    meta_data
+}
+
+/// Appends an index entry to `index`.
+fn write_index_entry(index: &mut Vec<u8>, offset: u64, length: usize) -> Result<()> {
+    if offset >> (OFFSET_BYTES * 8) != 0 {
+        return Err(Error::Unsupported(
+            "Tile data of COMTiles archives must not exceed 1 TiB",
+        ));
+    }
+    let length = u32::try_from(length).map_err(|_| Error::TileTooLarge)?;
+
+    index.extend_from_slice(&offset.to_le_bytes()[..OFFSET_BYTES]);
+    index.extend_from_slice(&length.to_le_bytes());
+
+    Ok(())
+}
+
+/// Returns the header of a `COMTiles` archive.
+fn header(meta_data_length: usize, index_length: usize) -> Result<Vec<u8>> {
+    let meta_data_length = u32::try_from(meta_data_length)
+        .map_err(|_| Error::Unsupported("Meta data of COMTiles archives must not exceed 4 GiB"))?;
+    let index_length = index_length as u64;
+    if index_length >> (OFFSET_BYTES * 8) != 0 {
+        return Err(Error::Unsupported(
+            "Index of COMTiles archives must not exceed 1 TiB",
+        ));
+    }
+
+    let mut header = Vec::with_capacity(HEADER_BYTES);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header.extend_from_slice(&meta_data_length.to_le_bytes());
+    header.extend_from_slice(&index_length.to_le_bytes()[..OFFSET_BYTES]);
+
+    Ok(header)
+}
+
+#[duplicate_item(
+    fn_name                cfg_async_filter       async    add_await(code) seek_start(reader, offset)                                input_traits;
+    [read_comtiles]        [cfg(all())]           []       [code]          [reader.seek(std::io::SeekFrom::Start(offset))]           [(impl Read + Seek)];
+    [read_comtiles_async]  [cfg(feature="async")] [async]  [code.await]    [reader.seek(futures::io::SeekFrom::Start(offset)).await] [(impl Unpin + Send + AsyncReadExt + AsyncSeekExt)];
+)]
+#[cfg_async_filter]
+/// Reads the meta data and the index of a `COMTiles` archive.
+async fn fn_name(reader: &mut input_traits, limits: &ReadLimits) -> Result<ComTilesArchive> {
+    let mut header = [0; HEADER_BYTES];
+    seek_start([reader], [0])?;
+    add_await([reader.read_exact(&mut header)])?;
+
+    if !header.starts_with(MAGIC) {
+        return Err(Error::InvalidMagic);
+    }
+    if parse_uint(&header[4..8]) != u64::from(VERSION) {
+        return Err(Error::Unsupported(
+            "Only version 1 of COMTiles archives is supported",
+        ));
+    }
+
+    // meta data and index directly follow the header
+    let meta_data_length = parse_uint(&header[8..12]);
+    let index_length = parse_uint(&header[12..HEADER_BYTES]);
+    check_limit(
+        "meta data length",
+        meta_data_length,
+        limits.max_meta_data_length,
+    )?;
+    check_limit("index length", index_length, limits.max_directory_length)?;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let mut buf = vec![0; (meta_data_length + index_length) as usize];
+    add_await([reader.read_exact(&mut buf)])?;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let (meta_data, index) = buf.split_at(meta_data_length as usize);
+    let JSONValue::Object(meta_data) = serde_json::from_slice(meta_data)? else {
+        return Err(Error::MetaDataNotAnObject);
+    };
+
+    let tile_matrices = parse_tile_matrices(&meta_data)?;
+    let data_offset = HEADER_BYTES as u64 + meta_data_length + index_length;
+    let entries = parse_index(index, &tile_matrices, data_offset, limits)?;
+
+    // the compression of tiles is not stored in the meta data
+    let detected_compression = match entries.iter().min_by_key(|entry| entry.offset) {
+        Some(entry) => {
+            let mut magic = vec![0; entry.length.min(4) as usize];
+            seek_start([reader], [entry.offset])?;
+            add_await([reader.read_exact(&mut magic)])?;
+
+            detect_compression(&magic).unwrap_or(Compression::None)
+        }
+        None => Compression::None,
+    };
+
+    Ok(ComTilesArchive {
+        meta_data,
+        tile_matrices,
+        entries,
+        detected_compression,
+    })
+}
+
+#[duplicate_item(
+    fn_name                        cfg_async_filter       async    add_await(code) RTraits                                                  read_comtiles;
+    [from_comtiles_reader]         [cfg(all())]           []       [code]          [Read + Seek]                                            [read_comtiles];
+    [from_comtiles_async_reader]   [cfg(feature="async")] [async]  [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [read_comtiles_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Reads an archive in the [COMTiles](https://github.com/mactrem/com-tiles) format.
+    ///
+    /// The tile matrices of the meta data are mapped to zoom levels and tile ids, the `tileFormat`
+    /// to the [tile type](Self::tile_type), and `bounds` and `center` to the corresponding fields.
+    /// All other meta data is kept as is. As `COMTiles` archives do not state the compression of
+    /// their tiles, it is detected from the first tile.
+    ///
+    /// Tiles are not read into memory, so `input` must be kept.
+    ///
+    /// # Arguments
+    /// * `input` - Reader holding a `COMTiles` archive
+    ///
+    /// # Errors
+    /// Will return [`Err`] if `input` does not hold a `COMTiles` archive, its index consists of
+    /// aggregated fragments (an `aggregationCoefficient` other than `-1`), it exceeds the
+    /// [default limits](ReadLimits::default) or there was an error while reading it.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use pmtiles2::PMTiles;
+    /// let input = std::fs::File::open("./archive.comt").unwrap();
+    /// let mut output = std::fs::File::create("./archive.pmtiles").unwrap();
+    ///
+    /// PMTiles::from_comtiles_reader(input).unwrap().to_writer(&mut output).unwrap();
+    /// ```
+    pub async fn fn_name(mut input: R) -> Result<Self> {
+        let archive = add_await([read_comtiles(&mut input, &ReadLimits::default())])?;
+
+        let mut pm_tiles =
+            Self::from_offset_entries(input, &archive.entries, &[bounds_to_range(&..)])?;
+        archive.apply_meta_data(&mut pm_tiles);
+
+        Ok(pm_tiles)
+    }
+}
+
+#[duplicate_item(
+    fn_name                    cfg_async_filter       async    add_await(code) RTraits                                                  WTraits                     get_tile_shared_by_id;
+    [to_comtiles_writer]       [cfg(all())]           []       [code]          [Read + Seek]                                            [Write]                     [get_tile_shared_by_id];
+    [to_comtiles_async_writer] [cfg(feature="async")] [async]  [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [AsyncWrite + Unpin + Send] [get_tile_shared_by_id_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Writes the archive in the [COMTiles](https://github.com/mactrem/com-tiles) format.
+    ///
+    /// The index includes one tile matrix per zoom level, spanning the smallest range of columns
+    /// and rows including all tiles of that zoom level (so sparse archives result in large
+    /// indexes). Tiles with the same content are only stored once. The [tile type](Self::tile_type),
+    /// bounds and center are stored in the meta data; the compression of tiles is not stored.
+    ///
+    /// # Arguments
+    /// * `output` - Writer to write the `COMTiles` archive to
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the tile data of the archive exceeds 1 TiB or there was an error
+    /// while reading tiles or writing to `output`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use pmtiles2::PMTiles;
+    /// let input = std::fs::File::open("./archive.pmtiles").unwrap();
+    /// let mut output = std::fs::File::create("./archive.comt").unwrap();
+    ///
+    /// PMTiles::from_reader(input).unwrap().to_comtiles_writer(&mut output).unwrap();
+    /// ```
+    pub async fn fn_name<W: WTraits>(mut self, output: &mut W) -> Result<()> {
+        let tile_matrices = TileMatrix::covering(&self.tile_ids_sorted())?;
+
+        let mut index = Vec::new();
+        let mut tiles = Vec::new();
+        let mut offsets = HashMap::<Arc<[u8]>, u64>::new();
+        let mut tile_data_length = 0;
+
+        for tile_id in tile_matrices.iter().flat_map(TileMatrix::tile_ids) {
+            let Some(data) = add_await([self.get_tile_shared_by_id(tile_id)])? else {
+                write_index_entry(&mut index, 0, 0)?;
+                continue;
+            };
+
+            let offset = *offsets.entry(data.clone()).or_insert_with(|| {
+                let offset = tile_data_length;
+                tile_data_length += data.len() as u64;
+                tiles.push(data.clone());
+                offset
+            });
+            write_index_entry(&mut index, offset, data.len())?;
+        }
+
+        let meta_data = serde_json::to_vec(&meta_data(&self, &tile_matrices))?;
+
+        add_await([output.write_all(&header(meta_data.len(), index.len())?)])?;
+        add_await([output.write_all(&meta_data)])?;
+        add_await([output.write_all(&index)])?;
+        for data in tiles {
+            add_await([output.write_all(&data)])?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Returns a `COMTiles` archive with the tile matrices `0/0/0` and `1/0-1/1`, holding tiles
+    /// `0/0/0` and `1/1/1` (with the same content as `0/0/0`) and `1/0/1` missing.
+    fn comtiles_archive(meta_data: &str) -> Vec<u8> {
+        let mut index = Vec::new();
+        write_index_entry(&mut index, 0, 4).unwrap();
+        write_index_entry(&mut index, 0, 0).unwrap();
+        write_index_entry(&mut index, 0, 4).unwrap();
+
+        let mut bytes = header(meta_data.len(), index.len()).unwrap();
+        bytes.extend_from_slice(meta_data.as_bytes());
+        bytes.extend(index);
+        bytes.extend_from_slice(&[1, 3, 3, 7]);
+
+        bytes
+    }
+
+    const TILE_MATRIX_SET: &str = r#"{"tileMatrix":[
+        {"zoom":0,"aggregationCoefficient":-1,"tileMatrixLimits":{"minTileCol":0,"minTileRow":0,"maxTileCol":0,"maxTileRow":0}},
+        {"zoom":1,"aggregationCoefficient":-1,"tileMatrixLimits":{"minTileCol":0,"minTileRow":1,"maxTileCol":1,"maxTileRow":1}}
+    ]}"#;
+
+    #[test]
+    fn test_from_comtiles_reader() -> Result<()> {
+        let bytes = comtiles_archive(&format!(
+            r#"{{"name":"test","tileFormat":"png","bounds":[1,2,3,4],"tileMatrixSet":{TILE_MATRIX_SET}}}"#
+        ));
+
+        let mut pm_tiles = PMTiles::from_comtiles_reader(Cursor::new(&bytes))?;
+
+        assert_eq!(pm_tiles.tile_type, TileType::Png);
+        assert_eq!(pm_tiles.tile_compression, Compression::None);
+        assert_eq!((pm_tiles.min_zoom, pm_tiles.max_zoom), (0, 1));
+        assert_eq!(pm_tiles.center_zoom, 0);
+        assert_eq!((pm_tiles.min_longitude, pm_tiles.max_latitude), (1.0, 4.0));
+        assert_eq!(
+            (pm_tiles.center_longitude, pm_tiles.center_latitude),
+            (2.0, 3.0)
+        );
+        assert_eq!(
+            pm_tiles.meta_data,
+            json!({"name":"test"}).as_object().unwrap().clone()
+        );
+
+        assert_eq!(pm_tiles.tile_ids_sorted(), [0, tile_id(1, 1, 1)]);
+        assert_eq!(pm_tiles.get_tile(0, 0, 0)?, Some(vec![1, 3, 3, 7]));
+        assert_eq!(pm_tiles.get_tile(1, 1, 1)?, Some(vec![1, 3, 3, 7]));
+        assert_eq!(pm_tiles.get_tile(0, 1, 1)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_comtiles_reader_invalid() {
+        assert!(matches!(
+            PMTiles::from_comtiles_reader(Cursor::new(b"PMTiles..........")),
+            Err(Error::InvalidMagic)
+        ));
+
+        let aggregated = TILE_MATRIX_SET.replace("-1", "6");
+        let bytes = comtiles_archive(&format!(r#"{{"tileMatrixSet":{aggregated}}}"#));
+        assert!(matches!(
+            PMTiles::from_comtiles_reader(Cursor::new(&bytes)),
+            Err(Error::Unsupported(_))
+        ));
+
+        let bytes = comtiles_archive(r#"{"name":"test"}"#);
+        assert!(matches!(
+            PMTiles::from_comtiles_reader(Cursor::new(&bytes)),
+            Err(Error::DirectoryDecode(_))
+        ));
+
+        // the index does not match the tile matrix set
+        let larger = TILE_MATRIX_SET.replace(r#""minTileRow":1"#, r#""minTileRow":0"#);
+        let bytes = comtiles_archive(&format!(r#"{{"tileMatrixSet":{larger}}}"#));
+        assert!(matches!(
+            PMTiles::from_comtiles_reader(Cursor::new(&bytes)),
+            Err(Error::DirectoryDecode(_))
+        ));
+    }
+
+    #[test]
+    fn test_to_comtiles_writer() -> Result<()> {
+        let mut pm_tiles = PMTiles::<Cursor<Vec<u8>>>::default();
+        pm_tiles.tile_type = TileType::Mvt;
+        pm_tiles.center_zoom = 2;
+        pm_tiles.meta_data.insert("name".to_string(), "test".into());
+        pm_tiles.add_tile(tile_id(2, 1, 3), vec![1, 2, 3])?;
+        pm_tiles.add_tile(tile_id(2, 2, 1), vec![1, 2, 3])?;
+        pm_tiles.add_tile(tile_id(3, 7, 7), vec![4, 5])?;
+
+        let mut bytes = Vec::new();
+        pm_tiles.to_comtiles_writer(&mut bytes)?;
+
+        let archive = read_comtiles(&mut Cursor::new(&bytes), &ReadLimits::default())?;
+        assert_eq!(
+            archive.tile_matrices,
+            [
+                TileMatrix {
+                    zoom: 2,
+                    min_col: 1,
+                    min_row: 1,
+                    max_col: 2,
+                    max_row: 3,
+                },
+                TileMatrix {
+                    zoom: 3,
+                    min_col: 7,
+                    min_row: 7,
+                    max_col: 7,
+                    max_row: 7,
+                },
+            ]
+        );
+        // tiles with the same content are stored once
+        assert_eq!(bytes.len() as u64 - archive.entries[0].offset, 5);
+
+        let mut read = PMTiles::from_comtiles_reader(Cursor::new(bytes))?;
+        assert_eq!(read.tile_type, TileType::Mvt);
+        assert_eq!((read.min_zoom, read.max_zoom, read.center_zoom), (2, 3, 2));
+        assert_eq!(read.meta_data.get("name").unwrap(), "test");
+        assert_eq!(read.num_tiles(), 3);
+        assert_eq!(read.get_tile(1, 3, 2)?, Some(vec![1, 2, 3]));
+        assert_eq!(read.get_tile(2, 1, 2)?, Some(vec![1, 2, 3]));
+        assert_eq!(read.get_tile(7, 7, 3)?, Some(vec![4, 5]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+        let mut pm_tiles = PMTiles::from_bytes(bytes)?;
+        let expected = pm_tiles
+            .tile_ids_sorted()
+            .into_iter()
+            .map(|tile_id| (tile_id, pm_tiles.get_tile_by_id(tile_id).unwrap()))
+            .collect::<Vec<_>>();
+        let tile_type = pm_tiles.tile_type;
+
+        let mut comtiles = Vec::new();
+        pm_tiles.to_comtiles_writer(&mut comtiles)?;
+        let mut read = PMTiles::from_comtiles_reader(Cursor::new(comtiles))?;
+
+        assert_eq!(read.tile_type, tile_type);
+        assert_eq!(read.num_tiles(), expected.len());
+        for (tile_id, data) in expected {
+            assert_eq!(read.get_tile_by_id(tile_id)?, data);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_comtiles_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let bytes = comtiles_archive(&format!(r#"{{"tileMatrixSet":{TILE_MATRIX_SET}}}"#));
+
+            let pm_tiles =
+                PMTiles::from_comtiles_async_reader(futures::io::Cursor::new(bytes)).await?;
+            assert_eq!(pm_tiles.num_tiles(), 2);
+
+            let mut written = futures::io::Cursor::new(Vec::new());
+            pm_tiles.to_comtiles_async_writer(&mut written).await?;
+
+            let mut read = PMTiles::from_comtiles_reader(Cursor::new(written.into_inner()))?;
+            assert_eq!(read.tile_ids_sorted(), [0, tile_id(1, 1, 1)]);
+            assert_eq!(read.get_tile(1, 1, 1)?, Some(vec![1, 3, 3, 7]));
+
+            Ok(())
+        })
+    }
+}
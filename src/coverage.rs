@@ -0,0 +1,176 @@
+use std::{
+    io::{Read, Seek},
+    ops::RangeInclusive,
+};
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+use crate::{util::tile_id_ranges_in_bbox, BBox, PMTiles, PMTilesReader, Result};
+
+/// Returns the ids of all tiles, which intersect `bbox` and are within `zoom_range`, in ascending order.
+///
+/// Ids are yielded lazily from the (few) ranges of tile ids covering `bbox` at each zoom level, so
+/// no ids are collected up front.
+fn tile_ids_in_region(bbox: BBox, zoom_range: RangeInclusive<u8>) -> impl Iterator<Item = u64> {
+    zoom_range.flat_map(move |z| {
+        tile_id_ranges_in_bbox(bbox.to_tuple(), z)
+            .into_iter()
+            .flatten()
+    })
+}
+
+impl<R> PMTiles<R> {
+    /// Returns the ids of all tiles, which intersect a bounding box and are within a zoom range,
+    /// but are not included in this archive.
+    ///
+    /// This can be used to detect holes in the coverage of an archive (e.g. introduced by failed
+    /// upstream jobs). No tile data is read. Tile ids are returned in ascending order.
+    ///
+    /// Every tile within the bounding box is checked, so the cost grows as `4^z` with the maximum zoom
+    /// level (a bounding box covering the whole world contains about 10<sup>12</sup> tiles at zoom
+    /// level 20). Keep the zoom range small or the bounding box tight for high zoom levels.
+    ///
+    /// # Arguments
+    /// * `bbox` - Bounding box (e.g. `(min_longitude, min_latitude, max_longitude, max_latitude)`, see [`BBox`])
+    /// * `zoom_range` - Zoom levels to check
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{util::tile_id, PMTiles};
+    /// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+    /// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+    /// assert!(pm_tiles.missing_tiles((-180.0, -85.0, 180.0, 85.0), 0..=3).is_empty());
+    ///
+    /// pm_tiles.remove_tile(tile_id(2, 3, 0));
+    /// assert_eq!(
+    ///     pm_tiles.missing_tiles((0.0, 1.0, 180.0, 85.0), 0..=4).len(),
+    ///     1 + 16 * 16 / 4 // tile 2/3/0 and the north-eastern quarter of zoom level 4
+    /// );
+    /// ```
+    pub fn missing_tiles(&self, bbox: impl Into<BBox>, zoom_range: RangeInclusive<u8>) -> Vec<u64> {
+        tile_ids_in_region(bbox.into(), zoom_range)
+            .filter(|tile_id| !self.contains_tile(*tile_id))
+            .collect()
+    }
+}
+
+#[duplicate_item(
+    fn_name               cfg_async_filter       async   add_await(code) RTraits                                                  contains_tile;
+    [missing_tiles]       [cfg(all())]           []      [code]          [Read + Seek]                                            [contains_tile];
+    [missing_tiles_async] [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [contains_tile_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTilesReader<R> {
+    /// Returns the ids of all tiles, which intersect a bounding box and are within a zoom range,
+    /// but are not included in the archive.
+    ///
+    /// Only the directories required to look up the tiles are read, but no tile data.
+    /// See [`PMTiles::missing_tiles`] for details (including the cost at high zoom levels).
+    ///
+    /// # Arguments
+    /// * `bbox` - Bounding box (e.g. `(min_longitude, min_latitude, max_longitude, max_latitude)`, see [`BBox`])
+    /// * `zoom_range` - Zoom levels to check
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading leaf directories.
+    pub async fn fn_name(
+        &mut self,
+        bbox: impl Into<BBox>,
+        zoom_range: RangeInclusive<u8>,
+    ) -> Result<Vec<u64>> {
+        let mut missing = Vec::new();
+
+        for tile_id in tile_ids_in_region(bbox.into(), zoom_range) {
+            if !add_await([self.contains_tile(tile_id)])? {
+                missing.push(tile_id);
+            }
+        }
+
+        Ok(missing)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::util::{tile_id, zoom_range};
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    const WORLD: (f64, f64, f64, f64) = (-180.0, -85.0, 180.0, 85.0);
+
+    #[test]
+    fn test_missing_tiles() -> Result<()> {
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+        assert!(pm_tiles.missing_tiles(WORLD, 0..=3).is_empty());
+        assert_eq!(
+            pm_tiles.missing_tiles(WORLD, 3..=4),
+            zoom_range(4).collect::<Vec<_>>()
+        );
+
+        pm_tiles.remove_tile(tile_id(3, 7, 7));
+        pm_tiles.remove_tile(tile_id(1, 0, 0));
+        assert_eq!(
+            pm_tiles.missing_tiles(WORLD, 0..=3),
+            [tile_id(1, 0, 0), tile_id(3, 7, 7)]
+        );
+
+        // only the south-eastern quarter of the world
+        assert_eq!(
+            pm_tiles.missing_tiles((1.0, -85.0, 180.0, -1.0), 0..=3),
+            [tile_id(3, 7, 7)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tile_ids_in_region_lazy() {
+        // ids of high zoom levels must not be collected up front
+        let mut tile_ids = tile_ids_in_region((-180.0, -90.0, 180.0, 90.0).into(), 20..=31);
+        assert_eq!(tile_ids.next(), Some(zoom_range(20).start));
+        assert_eq!(tile_ids.next(), Some(zoom_range(20).start + 1));
+    }
+
+    #[test]
+    fn test_missing_tiles_reader() -> Result<()> {
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        pm_tiles.remove_tile(tile_id(2, 1, 2));
+        let bytes = pm_tiles.to_vec()?;
+
+        let mut reader = PMTilesReader::from_reader(Cursor::new(&bytes))?;
+        let missing = reader.missing_tiles(WORLD, 0..=4)?;
+
+        assert_eq!(missing[0], tile_id(2, 1, 2));
+        assert_eq!(missing.len(), 1 + 256);
+        assert_eq!(
+            missing,
+            PMTiles::from_bytes(&bytes)?.missing_tiles(WORLD, 0..=4)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_missing_tiles_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let mut reader =
+                PMTilesReader::from_async_reader(futures::io::Cursor::new(PM_TILES_BYTES)).await?;
+
+            assert!(reader.missing_tiles_async(WORLD, 0..=3).await?.is_empty());
+            assert_eq!(
+                reader.missing_tiles_async(WORLD, 3..=4).await?,
+                zoom_range(4).collect::<Vec<_>>()
+            );
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,97 @@
+use std::hash::Hasher;
+
+use ahash::AHasher;
+
+/// Algorithm used to hash the content of tiles, so tiles with the same content are only stored once
+/// (see [`TileManager::set_dedupe_hash`](crate::TileManager::set_dedupe_hash)).
+///
+/// Tiles with the same hash are considered to have the same content, so a hash collision results in a
+/// tile with wrong data. The chance of a collision depends on the number of tiles and the size of the hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum DedupeHash {
+    /// 64 bit [aHash](https://github.com/tkaitchuck/aHash) (default)
+    ///
+    /// _Very fast and sufficient for most archives._
+    #[default]
+    AHash,
+
+    /// 128 bit [XXH3](https://github.com/Cyan4973/xxHash) (requires the `xxhash` feature)
+    ///
+    /// _About as fast as aHash for large tiles, but much less likely to collide, as its hash is twice as long._
+    #[cfg(feature = "xxhash")]
+    XxHash3,
+
+    /// [BLAKE3](https://github.com/BLAKE3-team/BLAKE3), truncated to 128 bits (requires the `blake3` feature)
+    ///
+    /// _Slower, but a cryptographic hash, so collisions can not be crafted deliberately (e.g. with
+    /// tiles from untrusted sources)._
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+impl DedupeHash {
+    /// Returns the hash of `data`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::DedupeHash;
+    /// let hash = DedupeHash::AHash.hash(&[1, 3, 3, 7]);
+    ///
+    /// assert_eq!(hash, DedupeHash::AHash.hash(&[1, 3, 3, 7]));
+    /// assert_ne!(hash, DedupeHash::AHash.hash(&[4, 2]));
+    /// ```
+    pub fn hash(self, data: &[u8]) -> u128 {
+        match self {
+            Self::AHash => {
+                let mut hasher = AHasher::default();
+                hasher.write(data);
+                u128::from(hasher.finish())
+            }
+            #[cfg(feature = "xxhash")]
+            Self::XxHash3 => xxhash_rust::xxh3::xxh3_128(data),
+            #[cfg(feature = "blake3")]
+            Self::Blake3 => {
+                let hash = blake3::hash(data);
+                let mut bytes = [0; 16];
+                bytes.copy_from_slice(&hash.as_bytes()[..16]);
+                u128::from_le_bytes(bytes)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        let algorithms = [
+            DedupeHash::AHash,
+            #[cfg(feature = "xxhash")]
+            DedupeHash::XxHash3,
+            #[cfg(feature = "blake3")]
+            DedupeHash::Blake3,
+        ];
+
+        for algorithm in algorithms {
+            assert_eq!(algorithm.hash(&[1, 2, 3]), algorithm.hash(&[1, 2, 3]));
+            assert_ne!(algorithm.hash(&[1, 2, 3]), algorithm.hash(&[1, 2, 4]));
+            assert_ne!(algorithm.hash(&[0]), algorithm.hash(&[0, 0]));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "blake3")]
+    fn test_hash_blake3() {
+        // first 16 bytes of the BLAKE3 hash of an empty input
+        assert_eq!(
+            DedupeHash::Blake3.hash(&[]).to_le_bytes(),
+            [
+                0xaf, 0x13, 0x49, 0xb9, 0xf5, 0xf9, 0xa1, 0xa6, 0xa0, 0x40, 0x4d, 0xea, 0x36, 0xdc,
+                0xc9, 0x49
+            ]
+        );
+    }
+}
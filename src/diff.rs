@@ -0,0 +1,379 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    io::{Read, Seek},
+};
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+use crate::{util::decompress_all, DedupeHash, PMTiles, Result};
+
+/// Determines how tiles, which exist in both archives, are compared by [`PMTiles::diff`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffMode {
+    /// Only compare tile ids, so no tile is reported as changed and no tile data is read
+    Ids,
+
+    /// Compare hashes of the data of tiles (see [`DedupeHash`])
+    ///
+    /// Hashes of tiles held in memory are known already, so their data is not read at all. The data
+    /// of all other tiles is hashed right after it was read, so only one tile is held at a time, and
+    /// data shared by multiple tiles of an archive is only read once. Tiles with the same hash are
+    /// considered to be equal, so a hash collision hides a changed tile.
+    Hashes,
+
+    /// Compare the data of tiles byte by byte
+    #[default]
+    Bytes,
+}
+
+/// Hashes of tile data, which was read from the reader of an archive, by the offset and length
+/// of the data, so data shared by multiple tiles is only read once.
+type LocationHashes = HashMap<(u64, u32), u128>;
+
+/// The differences between two archives, as returned by [`PMTiles::diff`].
+///
+/// All tile ids are sorted in ascending order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArchiveDiff {
+    /// Ids of tiles, which only exist in the other archive
+    pub added: Vec<u64>,
+
+    /// Ids of tiles, which only exist in this archive
+    pub removed: Vec<u64>,
+
+    /// Ids of tiles, which exist in both archives, but whose data differs
+    pub changed: Vec<u64>,
+}
+
+impl ArchiveDiff {
+    /// Returns `true` if there are no differences.
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[duplicate_item(
+    fn_name      cfg_async_filter       async   add_await(code) RTraits                                                  get_tile_by_id         content_eq         tile_hash         tiles_eq;
+    [diff]       [cfg(all())]           []      [code]          [Read + Seek]                                            [get_tile_by_id]       [content_eq]       [tile_hash]       [tiles_eq];
+    [diff_async] [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [get_tile_by_id_async] [content_eq_async] [tile_hash_async] [tiles_eq_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Compares the tiles of this archive to the tiles of `other` and returns the ids of all
+    /// added, removed and changed tiles.
+    ///
+    /// Only tiles are compared, so differences in the header fields or meta data are not reported.
+    /// If the tile compressions of both archives differ, tiles are decompressed before they are compared.
+    ///
+    /// # Arguments
+    /// * `other` - Archive to compare this archive to (e.g. a rebuild of this archive)
+    /// * `mode` - How tiles, which exist in both archives, are compared
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading tile data.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{Compression, DiffMode, PMTiles, TileType};
+    /// let mut old = PMTiles::new(TileType::Png, Compression::None);
+    /// old.add_tile(0, vec![1]).unwrap();
+    /// old.add_tile(1, vec![1]).unwrap();
+    ///
+    /// let mut new = PMTiles::new(TileType::Png, Compression::None);
+    /// new.add_tile(1, vec![2]).unwrap();
+    /// new.add_tile(2, vec![1]).unwrap();
+    ///
+    /// let diff = old.diff(&mut new, DiffMode::Bytes).unwrap();
+    /// assert_eq!(diff.added, vec![2]);
+    /// assert_eq!(diff.removed, vec![0]);
+    /// assert_eq!(diff.changed, vec![1]);
+    /// ```
+    pub async fn fn_name<R2: RTraits>(
+        &mut self,
+        other: &mut PMTiles<R2>,
+        mode: DiffMode,
+    ) -> Result<ArchiveDiff> {
+        let ids = self.tile_ids_sorted();
+        let other_ids = other.tile_ids_sorted();
+
+        let mut diff = ArchiveDiff::default();
+        let mut hashes = LocationHashes::default();
+        let mut other_hashes = LocationHashes::default();
+        let (mut i, mut j) = (0, 0);
+
+        while i < ids.len() || j < other_ids.len() {
+            let ordering = match (ids.get(i), other_ids.get(j)) {
+                (Some(id), Some(other_id)) => id.cmp(other_id),
+                (Some(_), None) => Ordering::Less,
+                _ => Ordering::Greater,
+            };
+
+            match ordering {
+                Ordering::Less => {
+                    diff.removed.push(ids[i]);
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    diff.added.push(other_ids[j]);
+                    j += 1;
+                }
+                Ordering::Equal => {
+                    let tile_id = ids[i];
+                    i += 1;
+                    j += 1;
+
+                    let equal = match mode {
+                        DiffMode::Ids => true,
+                        DiffMode::Hashes => {
+                            let dedupe_hash = self.tile_manager.dedupe_hash();
+                            let decompress = self.tile_compression != other.tile_compression;
+
+                            let hash = add_await([self.tile_hash(
+                                tile_id,
+                                dedupe_hash,
+                                decompress,
+                                &mut hashes,
+                            )])?;
+                            hash == add_await([other.tile_hash(
+                                tile_id,
+                                dedupe_hash,
+                                decompress,
+                                &mut other_hashes,
+                            )])?
+                        }
+                        DiffMode::Bytes => add_await([self.tiles_eq(other, tile_id)])?,
+                    };
+
+                    if !equal {
+                        diff.changed.push(tile_id);
+                    }
+                }
+            }
+        }
+
+        Ok(diff)
+    }
+
+    /// Returns `true` if this archive and `other` hold the same content, i.e. the same tile type,
+    /// the same meta data and the same tiles after decompressing them.
+    ///
+    /// In contrast to comparing the bytes of two archives, this ignores differences in compression
+    /// (algorithms or parameters), the clustering of tile data, the layout of leaf directories and
+    /// the order of meta data keys, so it can be used to verify, that rewriting an archive preserved
+    /// its data. All other header fields (e.g. bounds or zoom levels) are ignored as well.
+    ///
+    /// # Arguments
+    /// * `other` - Archive to compare this archive to (e.g. a rewrite of this archive)
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading or decompressing tile data.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{Compression, PMTiles, TileType};
+    /// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+    /// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+    ///
+    /// let mut rewritten = PMTiles::from_bytes(bytes).unwrap();
+    /// rewritten.transcode_tile_compression(Compression::ZStd, None).unwrap();
+    ///
+    /// assert!(pm_tiles.content_eq(&mut rewritten).unwrap());
+    /// ```
+    pub async fn content_eq<R2: RTraits>(&mut self, other: &mut PMTiles<R2>) -> Result<bool> {
+        if self.tile_type != other.tile_type
+            || self.meta_data != other.meta_data
+            || self.tile_ids_sorted() != other.tile_ids_sorted()
+        {
+            return Ok(false);
+        }
+
+        for tile_id in self.tile_ids_sorted() {
+            if !add_await([self.tiles_eq(other, tile_id)])? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Returns `true` if the tile `tile_id` exists in this archive and `other` and holds the same data,
+    /// which is decompressed if the tile compressions of both archives differ.
+    async fn tiles_eq<R2: RTraits>(
+        &mut self,
+        other: &mut PMTiles<R2>,
+        tile_id: u64,
+    ) -> Result<bool> {
+        let data = add_await([self.get_tile_by_id(tile_id)])?;
+        let other_data = add_await([other.get_tile_by_id(tile_id)])?;
+
+        let (Some(data), Some(other_data)) = (data, other_data) else {
+            return Ok(false);
+        };
+
+        if self.tile_compression == other.tile_compression {
+            return Ok(data == other_data);
+        }
+
+        Ok(decompress_all(self.tile_compression, &data)?
+            == decompress_all(other.tile_compression, &other_data)?)
+    }
+
+    /// Returns the hash of the (decompressed) data of the tile `tile_id` or [`None`] if it does not exist.
+    ///
+    /// Hashes of data, which has to be read from the reader, are cached in `hashes`.
+    async fn tile_hash(
+        &mut self,
+        tile_id: u64,
+        dedupe_hash: DedupeHash,
+        decompress: bool,
+        hashes: &mut LocationHashes,
+    ) -> Result<Option<u128>> {
+        if !decompress && self.tile_manager.dedupe_hash() == dedupe_hash {
+            if let Some(hash) = self.tile_manager.get_tile_hash(tile_id) {
+                return Ok(Some(hash));
+            }
+        }
+
+        let location = self.tile_manager.get_tile_offset(tile_id);
+        if let Some(hash) = location.and_then(|location| hashes.get(&location)) {
+            return Ok(Some(*hash));
+        }
+
+        let Some(data) = add_await([self.get_tile_by_id(tile_id)])? else {
+            return Ok(None);
+        };
+        let hash = if decompress {
+            dedupe_hash.hash(&decompress_all(self.tile_compression, &data)?)
+        } else {
+            dedupe_hash.hash(&data)
+        };
+
+        if let Some(location) = location {
+            hashes.insert(location, hash);
+        }
+
+        Ok(Some(hash))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Compression, Header, TileType, WriteOptions};
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    #[test]
+    fn test_diff() -> Result<()> {
+        let mut old = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        let mut new = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+        assert!(old.diff(&mut new, DiffMode::Bytes)?.is_empty());
+
+        new.remove_tile(3);
+        new.add_tile(19, vec![1, 3, 3, 7])?;
+        new.add_tile(100, vec![1])?;
+
+        for mode in [DiffMode::Bytes, DiffMode::Hashes] {
+            let diff = old.diff(&mut new, mode)?;
+            assert_eq!(diff.added, vec![100]);
+            assert_eq!(diff.removed, vec![3]);
+            assert_eq!(diff.changed, vec![19]);
+        }
+
+        let diff = old.diff(&mut new, DiffMode::Ids)?;
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.added, vec![100]);
+
+        let mut empty = PMTiles::new(TileType::Png, Compression::None);
+        let diff = empty.diff(&mut old, DiffMode::Ids)?;
+        assert_eq!(diff.added.len(), 85);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_different_compression() -> Result<()> {
+        let mut old = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        let mut new = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        new.transcode_tile_compression(Compression::GZip, Some(9))?;
+        new.add_tile_uncompressed(19, [1, 3, 3, 7])?;
+
+        for mode in [DiffMode::Bytes, DiffMode::Hashes] {
+            let diff = old.diff(&mut new, mode)?;
+            assert!(diff.added.is_empty());
+            assert!(diff.removed.is_empty());
+            assert_eq!(diff.changed, vec![19]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_eq() -> Result<()> {
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        let mut other = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        assert!(pm_tiles.content_eq(&mut other)?);
+
+        // different compression and leaf directory layout
+        other.transcode_tile_compression(Compression::GZip, Some(9))?;
+        let bytes = other.to_vec_with_options(WriteOptions {
+            max_root_dir_length: Some(100),
+            ..WriteOptions::default()
+        })?;
+        assert!(Header::from_bytes(&bytes)?.leaf_directories_length > 0);
+        let mut other = PMTiles::from_bytes(bytes)?;
+        assert_eq!(other.tile_compression, Compression::GZip);
+        assert!(pm_tiles.content_eq(&mut other)?);
+        assert!(other.content_eq(&mut pm_tiles)?);
+
+        let mut changed = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        changed.add_tile(19, vec![1, 3, 3, 7])?;
+        assert!(!pm_tiles.content_eq(&mut changed)?);
+
+        let mut changed = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        changed.remove_tile(19);
+        assert!(!pm_tiles.content_eq(&mut changed)?);
+
+        let mut changed = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        changed
+            .meta_data
+            .insert("name".to_string(), "changed".into());
+        assert!(!pm_tiles.content_eq(&mut changed)?);
+
+        let mut changed = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        changed.tile_type = TileType::Jpeg;
+        assert!(!pm_tiles.content_eq(&mut changed)?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_diff_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let reader = futures::io::Cursor::new(PM_TILES_BYTES);
+            let mut old = PMTiles::from_async_reader(reader).await?;
+            let mut new = PMTiles::new_async(TileType::Png, Compression::None);
+            new.add_tile(0, vec![1])?;
+
+            for mode in [DiffMode::Bytes, DiffMode::Hashes] {
+                let diff = old.diff_async(&mut new, mode).await?;
+                assert_eq!(diff.removed.len(), 84);
+                assert_eq!(diff.changed, vec![0]);
+            }
+            assert!(!old.content_eq_async(&mut new).await?);
+
+            let mut same =
+                PMTiles::from_async_reader(futures::io::Cursor::new(PM_TILES_BYTES)).await?;
+            assert!(old.content_eq_async(&mut same).await?);
+
+            Ok(())
+        })
+    }
+}
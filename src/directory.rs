@@ -1,18 +1,25 @@
 use duplicate::duplicate_item;
 use integer_encoding::{VarIntReader, VarIntWriter};
-use std::io::{Read, Result, Write};
+use std::io::{Read, Write};
 use std::ops::{Index, IndexMut, Range};
 use std::slice::{Iter, SliceIndex};
 
 #[cfg(feature = "async")]
 use futures::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
 #[cfg(feature = "async")]
-use integer_encoding::{VarIntAsyncReader, VarIntAsyncWriter};
+use integer_encoding::VarIntAsyncWriter;
 
-use crate::util::{compress, decompress};
 #[cfg(feature = "async")]
-use crate::util::{compress_async, decompress_async};
-use crate::Compression;
+use crate::util::{compress_async_with_codec, decompress_async_with_codec};
+use crate::util::{compress_with_codec, decompress_with_codec, Codec};
+use crate::{read_limits::check_limit, Compression, Error, ParseWarning, ReadLimits, Result};
+
+/// Maximum number of directories (including the root directory) traversed to find a tile.
+///
+/// Leaf directories may point to other leaf directories, but readers are not expected to follow
+/// more than three levels of them, which limits the recursion on malformed archives.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) const MAX_DIRECTORY_DEPTH: usize = 4;
 
 /// A structure representing a directory entry.
 ///
@@ -57,7 +64,7 @@ impl Entry {
 /// [`iter`](Self::iter) to obtain information about that list of entries.
 ///
 /// Use [`from_reader`](Self::from_reader) and [`to_writer`](Self::to_writer) or their respective asynchronous versions ([`from_async_reader`](Self::from_async_reader) and [`to_async_writer`](Self::to_async_writer)) to read and write the directory from / to bytes.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Directory {
@@ -66,15 +73,62 @@ pub struct Directory {
 
 impl Directory {
     /// Returns the number of entries in the directory, also referred to as its 'length'.
-    pub fn len(&self) -> usize {
+    pub const fn len(&self) -> usize {
         self.entries.len()
     }
 
     /// Returns `true` if the directory contains no entries.
-    pub fn is_empty(&self) -> bool {
+    pub const fn is_empty(&self) -> bool {
         self.entries.is_empty()
     }
 
+    /// Returns an iterator over all entries for tile data (i.e. all entries, which are no leaf directory entries).
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{Directory, Entry};
+    /// let directory: Directory = vec![
+    ///     Entry { tile_id: 0, offset: 0, length: 1, run_length: 2 },
+    ///     Entry { tile_id: 5, offset: 0, length: 1, run_length: 0 },
+    /// ].into();
+    ///
+    /// assert_eq!(directory.tile_entries().map(|e| e.tile_id).collect::<Vec<_>>(), [0]);
+    /// assert_eq!(directory.leaf_entries().map(|e| e.tile_id).collect::<Vec<_>>(), [5]);
+    /// assert_eq!(directory.addressed_tile_count(), 2);
+    /// ```
+    pub fn tile_entries(&self) -> impl Iterator<Item = &Entry> + '_ {
+        self.entries
+            .iter()
+            .filter(|entry| !entry.is_leaf_dir_entry())
+    }
+
+    /// Returns an iterator over all entries, which point to leaf directories.
+    pub fn leaf_entries(&self) -> impl Iterator<Item = &Entry> + '_ {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_leaf_dir_entry())
+    }
+
+    /// Returns the number of entries for tile data.
+    pub fn num_tile_entries(&self) -> usize {
+        self.tile_entries().count()
+    }
+
+    /// Returns the number of entries, which point to leaf directories.
+    pub fn num_leaf_entries(&self) -> usize {
+        self.leaf_entries().count()
+    }
+
+    /// Returns the number of tiles addressed by the entries of this directory, i.e. the sum of their run lengths.
+    ///
+    /// Tiles addressed by leaf directories are not included, as those are not part of this directory.
+    pub fn addressed_tile_count(&self) -> u64 {
+        self.entries
+            .iter()
+            .map(|entry| u64::from(entry.run_length))
+            .sum()
+    }
+
     /// Returns an iterator over the directory.
     ///
     /// The iterator yields all entries from start to end.
@@ -97,28 +151,54 @@ impl<'a> IntoIterator for &'a Directory {
 
 impl Directory {
     #[duplicate_item(
-        fn_name                  cfg_async_filter       input_traits                         decompress(compression, binding)              read_varint(type, reader)                  async;
-        [from_reader_impl]       [cfg(all())]           [impl Read]                          [decompress(compression, &mut binding)]       [reader.read_varint::<type>()]             [];
-        [from_async_reader_impl] [cfg(feature="async")] [(impl Unpin + Send + AsyncReadExt)] [decompress_async(compression, &mut binding)] [reader.read_varint_async::<type>().await] [async];
+        fn_name                  cfg_async_filter       input_traits                         decompress(compression, codec, binding)                         add_await(code) async;
+        [from_reader_impl]       [cfg(all())]           [impl Read]                          [decompress_with_codec(compression, codec, &mut binding)]       [code]          [];
+        [from_async_reader_impl] [cfg(feature="async")] [(impl Unpin + Send + AsyncReadExt)] [decompress_async_with_codec(compression, codec, &mut binding)] [code.await]    [async];
     )]
-    #[allow(clippy::needless_range_loop)]
     #[cfg_async_filter]
-    async fn fn_name(
+    #[allow(clippy::redundant_pub_crate)]
+    pub(crate) async fn fn_name(
         input: &mut input_traits,
         length: u64,
         compression: Compression,
+        codec: Option<&dyn Codec>,
+        limits: &ReadLimits,
     ) -> Result<Self> {
         let mut binding = input.take(length);
-        let mut reader = decompress([compression], [binding])?;
+        let reader = decompress([compression], [codec], [binding])?;
+
+        // read at most one byte more than allowed, so a decompression bomb is detected early
+        let mut data = Vec::new();
+        add_await([reader
+            .take(limits.max_directory_length.saturating_add(1))
+            .read_to_end(&mut data)])?;
+        check_limit(
+            "directory length",
+            data.len() as u64,
+            limits.max_directory_length,
+        )?;
+
+        Self::from_decompressed(&data, limits)
+    }
 
-        let num_entries = read_varint([usize], [reader])?;
+    #[allow(clippy::needless_range_loop)]
+    fn from_decompressed(mut data: &[u8], limits: &ReadLimits) -> Result<Self> {
+        let reader = &mut data;
+
+        let num_entries = reader.read_varint::<usize>()?;
+        check_limit(
+            "number of directory entries",
+            num_entries as u64,
+            limits.max_entries,
+        )?;
 
-        let mut entries = Vec::<Entry>::with_capacity(num_entries);
+        // every entry takes up at least 4 bytes, so do not trust the number of entries blindly
+        let mut entries = Vec::<Entry>::with_capacity(num_entries.min(reader.len() / 4));
 
         // read tile_id
         let mut last_id = 0u64;
         for _ in 0..num_entries {
-            let tmp = read_varint([u64], [reader])?;
+            let tmp = reader.read_varint::<u64>()?;
 
             last_id += tmp;
             entries.push(Entry {
@@ -131,31 +211,35 @@ impl Directory {
 
         // read run_length
         for i in 0..num_entries {
-            entries[i].run_length = read_varint([_], [reader])?;
+            entries[i].run_length = reader.read_varint()?;
         }
 
         // read length
         for i in 0..num_entries {
-            let len = read_varint([_], [reader])?;
+            let len = reader.read_varint()?;
 
             if len == 0 {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "Length of a directory entry must be greater than 0.",
+                return Err(Error::DirectoryDecode(
+                    "Length of a directory entry must be greater than 0.".to_string(),
                 ));
             }
 
             entries[i].length = len;
+            limits.check_tile_length(&entries[i])?;
         }
 
         // read offset
         for i in 0..num_entries {
-            let val = read_varint([u64], [reader])?;
+            let val = reader.read_varint::<u64>()?;
 
             entries[i].offset = if i > 0 && val == 0 {
                 entries[i - 1].offset + u64::from(entries[i - 1].length)
             } else {
-                val - 1
+                val.checked_sub(1).ok_or_else(|| {
+                    Error::DirectoryDecode(
+                        "Offset of the first directory entry must not be 0.".to_string(),
+                    )
+                })?
             };
         }
 
@@ -163,13 +247,19 @@ impl Directory {
     }
 
     #[duplicate_item(
-        fn_name                cfg_async_filter       input_traits                       compress         flush   write_varint(writer, value)              add_await(code) async;
-        [to_writer_impl]       [cfg(all())]           [impl Write]                       [compress]       [flush] [writer.write_varint(value)]             [code]          [];
-        [to_async_writer_impl] [cfg(feature="async")] [(impl AsyncWrite + Unpin + Send)] [compress_async] [close] [writer.write_varint_async(value).await] [code.await]    [async];
+        fn_name                cfg_async_filter       input_traits                       compress(compression, codec, output)                     flush   write_varint(writer, value)              add_await(code) async;
+        [to_writer_impl]       [cfg(all())]           [impl Write]                       [compress_with_codec(compression, None, codec, output)]  [flush] [writer.write_varint(value)]             [code]          [];
+        [to_async_writer_impl] [cfg(feature="async")] [(impl AsyncWrite + Unpin + Send)] [compress_async_with_codec(compression, codec, output)]  [close] [writer.write_varint_async(value).await] [code.await]    [async];
     )]
     #[cfg_async_filter]
-    async fn fn_name(&self, output: &mut input_traits, compression: Compression) -> Result<()> {
-        let mut writer = compress(compression, output)?;
+    #[allow(clippy::redundant_pub_crate)]
+    pub(crate) async fn fn_name(
+        &self,
+        output: &mut input_traits,
+        compression: Compression,
+        codec: Option<&dyn Codec>,
+    ) -> Result<()> {
+        let mut writer = compress([compression], [codec], [output])?;
 
         write_varint([writer], [self.entries.len()])?;
 
@@ -188,10 +278,7 @@ impl Directory {
         // write length
         for entry in &self.entries {
             if entry.length == 0 {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "Length of a directory entry must be greater than 0.",
-                ));
+                return Err(Error::ZeroLengthEntry(entry.tile_id));
             }
             write_varint([writer], [entry.length])?;
         }
@@ -226,8 +313,8 @@ impl Directory {
     ///
     /// # Errors
     /// Will return [`Err`] if `compression` is set to [`Compression::Unknown`], the data is not compressed correctly
-    /// according to `compression`, the directory includes a entry with a length of 0  or an I/O error occurred while
-    /// reading from `input`.
+    /// according to `compression`, the directory could not be decoded ([`Error::DirectoryDecode`]) or an I/O error
+    /// occurred while reading from `input`.
     ///
     /// # Example
     /// ```rust
@@ -244,7 +331,27 @@ impl Directory {
         length: u64,
         compression: Compression,
     ) -> Result<Self> {
-        Self::from_reader_impl(input, length, compression)
+        Self::from_reader_impl(input, length, compression, None, &ReadLimits::default())
+    }
+
+    /// Same as [`from_reader`](Self::from_reader), but enforces `limits` instead of the default [`ReadLimits`].
+    ///
+    /// # Arguments
+    /// * `input` - Reader including directory bytes
+    /// * `length` - Length of the directory (in bytes)
+    /// * `compression` - Compression of the directory
+    /// * `limits` - Limits of the decompressed length, the number of entries and the lengths of tiles
+    ///
+    /// # Errors
+    /// See [`from_reader`](Self::from_reader). Additionally, will return [`Error::LimitExceeded`]
+    /// if any of `limits` was exceeded.
+    pub fn from_reader_with_limits(
+        input: &mut impl Read,
+        length: u64,
+        compression: Compression,
+        limits: &ReadLimits,
+    ) -> Result<Self> {
+        Self::from_reader_impl(input, length, compression, None, limits)
     }
 
     /// Reads a directory from anything that can be turned into a byte slice (e.g. [`Vec<u8>`]).
@@ -255,8 +362,8 @@ impl Directory {
     ///
     /// # Errors
     /// Will return [`Err`] if `compression` is set to [`Compression::Unknown`], the data is not compressed correctly
-    /// according to `compression`, the directory includes a entry with a length of 0  or an I/O error occurred while
-    /// reading from `input`.
+    /// according to `compression`, the directory could not be decoded ([`Error::DirectoryDecode`]) or an I/O error
+    /// occurred while reading from `input`.
     ///
     /// # Example
     /// ```rust
@@ -265,7 +372,7 @@ impl Directory {
     /// let directory = Directory::from_bytes(&bytes[127..], Compression::GZip).unwrap();
     /// ```
     ///
-    pub fn from_bytes(bytes: impl AsRef<[u8]>, compression: Compression) -> std::io::Result<Self> {
+    pub fn from_bytes(bytes: impl AsRef<[u8]>, compression: Compression) -> Result<Self> {
         let length = bytes.as_ref().len() as u64;
         let mut reader = std::io::Cursor::new(bytes);
 
@@ -283,8 +390,8 @@ impl Directory {
     ///
     /// # Errors
     /// Will return [`Err`] if `compression` is set to [`Compression::Unknown`], the data is not compressed correctly
-    /// according to `compression`, the directory includes a entry with a length of 0  or an I/O error occurred while
-    /// reading from `input`.
+    /// according to `compression`, the directory could not be decoded ([`Error::DirectoryDecode`]) or an I/O error
+    /// occurred while reading from `input`.
     ///
     /// # Example
     /// ```rust
@@ -304,7 +411,24 @@ impl Directory {
         length: u64,
         compression: Compression,
     ) -> Result<Self> {
-        Self::from_async_reader_impl(input, length, compression).await
+        Self::from_async_reader_impl(input, length, compression, None, &ReadLimits::default()).await
+    }
+
+    /// Async version of [`from_reader_with_limits`](Self::from_reader_with_limits).
+    ///
+    /// # Arguments
+    /// See [`from_reader_with_limits`](Self::from_reader_with_limits).
+    ///
+    /// # Errors
+    /// See [`from_reader_with_limits`](Self::from_reader_with_limits).
+    #[cfg(feature = "async")]
+    pub async fn from_async_reader_with_limits(
+        input: &mut (impl Unpin + Send + AsyncReadExt),
+        length: u64,
+        compression: Compression,
+        limits: &ReadLimits,
+    ) -> Result<Self> {
+        Self::from_async_reader_impl(input, length, compression, None, limits).await
     }
 
     /// Writes the directory to a [`std::io::Write`].
@@ -315,7 +439,7 @@ impl Directory {
     ///
     /// # Errors
     /// Will return [`Err`] if `compression` is set to [`Compression::Unknown`], the
-    /// directory includes a entry with a length of 0 or an I/O error occurred
+    /// directory includes a entry with a length of 0 ([`Error::ZeroLengthEntry`]) or an I/O error occurred
     /// while writing to `output`.
     ///
     /// # Example
@@ -328,7 +452,7 @@ impl Directory {
     /// directory.to_writer(&mut output, Compression::GZip).unwrap();
     /// ```
     pub fn to_writer(&self, output: &mut impl Write, compression: Compression) -> Result<()> {
-        self.to_writer_impl(output, compression)
+        self.to_writer_impl(output, compression, None)
     }
 
     /// Async version of [`to_writer`](Self::to_writer).
@@ -341,7 +465,7 @@ impl Directory {
     ///
     /// # Errors
     /// Will return [`Err`] if `compression` is set to [`Compression::Unknown`], the
-    /// directory includes a entry with a length of 0 or an I/O error occurred
+    /// directory includes a entry with a length of 0 ([`Error::ZeroLengthEntry`]) or an I/O error occurred
     /// while writing to `output`.
     ///
     /// # Example
@@ -361,7 +485,7 @@ impl Directory {
         output: &mut (impl AsyncWrite + Unpin + Send),
         compression: Compression,
     ) -> Result<()> {
-        self.to_async_writer_impl(output, compression).await
+        self.to_async_writer_impl(output, compression, None).await
     }
 }
 
@@ -371,9 +495,186 @@ impl Directory {
     /// Returns [`None`] if the directory does not include a [`Entry`] that matches `tile_id`.
     ///
     pub fn find_entry_for_tile_id(&self, tile_id: u64) -> Option<&Entry> {
-        self.into_iter()
-            .find(|e| !e.is_leaf_dir_entry() && e.tile_id_range().contains(&tile_id))
+        self.tile_entries()
+            .find(|e| e.tile_id_range().contains(&tile_id))
+    }
+
+    /// Find the entry, which either includes `tile_id` or points to the leaf directory,
+    /// which may include `tile_id`.
+    ///
+    /// In contrast to [`find_entry_for_tile_id`](Self::find_entry_for_tile_id), this uses a binary search
+    /// and also returns leaf directory entries, as described by the lookup algorithm of the specification.
+    /// Entries must be sorted in ascending order by their tile ids.
+    ///
+    /// Returns [`None`] if `tile_id` is neither included in an entry, nor in the range of a leaf directory.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{Directory, Entry};
+    /// let directory: Directory = vec![
+    ///     Entry { tile_id: 0, offset: 0, length: 1, run_length: 2 },
+    ///     Entry { tile_id: 5, offset: 0, length: 1, run_length: 0 },
+    /// ].into();
+    ///
+    /// assert_eq!(directory.find_entry(1), Some(&directory[0]));
+    /// assert_eq!(directory.find_entry(3), None);
+    /// assert_eq!(directory.find_entry(42), Some(&directory[1])); // leaf directory
+    /// ```
+    pub fn find_entry(&self, tile_id: u64) -> Option<&Entry> {
+        let index = self
+            .entries
+            .partition_point(|e| e.tile_id <= tile_id)
+            .checked_sub(1)?;
+        let entry = &self.entries[index];
+
+        if entry.is_leaf_dir_entry() || tile_id - entry.tile_id < u64::from(entry.run_length) {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    /// Checks, that entries are sorted in strictly ascending order by their tile ids and that the
+    /// tile ids covered by an entry do not overlap with the ones of the next entry.
+    ///
+    /// Lookups (e.g. [`find_entry`](Self::find_entry)) return wrong entries for directories,
+    /// which violate this.
+    ///
+    /// # Errors
+    /// Will return [`Error::Inconsistent`] with [`ParseWarning::EntriesNotAscending`] for the first
+    /// entry, which does not follow its preceding entry.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{Directory, Entry};
+    /// let directory: Directory = vec![
+    ///     Entry { tile_id: 0, offset: 0, length: 1, run_length: 2 },
+    ///     Entry { tile_id: 1, offset: 1, length: 1, run_length: 1 },
+    /// ].into();
+    ///
+    /// assert!(directory.check_entries().is_err());
+    /// ```
+    pub fn check_entries(&self) -> Result<()> {
+        unordered_entry(&self.entries).map_or(Ok(()), |warning| Err(warning.into()))
+    }
+
+    /// Checks all invariants of a directory, which can be written: Entries must be sorted as
+    /// described in [`check_entries`](Self::check_entries) and their lengths must be greater than 0.
+    ///
+    /// Directories built via [`push`](Self::push) and [`insert_sorted`](Self::insert_sorted) always
+    /// satisfy these invariants, while directories converted from a [`Vec<Entry>`] or modified via
+    /// indexing may not.
+    ///
+    /// # Errors
+    /// Will return [`Error::Inconsistent`] if entries are not sorted (see [`check_entries`](Self::check_entries))
+    /// or [`Error::ZeroLengthEntry`] for the first entry with a length of 0.
+    pub fn validate(&self) -> Result<()> {
+        self.check_entries()?;
+
+        self.entries
+            .iter()
+            .find(|entry| entry.length == 0)
+            .map_or(Ok(()), |entry| Err(Error::ZeroLengthEntry(entry.tile_id)))
+    }
+
+    /// Appends an entry to the end of the directory.
+    ///
+    /// # Arguments
+    /// * `entry` - Entry to append, which has to start after all tile ids covered by the last entry
+    ///
+    /// # Errors
+    /// Will return [`Error::ZeroLengthEntry`] if the length of `entry` is 0, or [`Error::Inconsistent`]
+    /// with [`ParseWarning::EntriesNotAscending`] if `entry` does not follow the last entry.
+    /// In both cases the directory is left unchanged.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{Directory, Entry};
+    /// let mut directory = Directory::default();
+    ///
+    /// directory.push(Entry { tile_id: 0, offset: 0, length: 1, run_length: 2 }).unwrap();
+    /// directory.push(Entry { tile_id: 2, offset: 1, length: 1, run_length: 1 }).unwrap();
+    ///
+    /// // overlaps with the tile ids 0 and 1 covered by the first entry
+    /// assert!(directory.push(Entry { tile_id: 1, offset: 2, length: 1, run_length: 1 }).is_err());
+    /// assert_eq!(directory.len(), 2);
+    /// ```
+    pub fn push(&mut self, entry: Entry) -> Result<()> {
+        self.check_neighbors(self.entries.len(), entry)?;
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    /// Inserts an entry at the position given by its tile id, so entries stay sorted.
+    ///
+    /// # Arguments
+    /// * `entry` - Entry to insert, which must not overlap with the tile ids covered by other entries
+    ///
+    /// # Errors
+    /// Will return [`Error::ZeroLengthEntry`] if the length of `entry` is 0, or [`Error::Inconsistent`]
+    /// with [`ParseWarning::EntriesNotAscending`] if `entry` overlaps with its neighboring entries.
+    /// In both cases the directory is left unchanged.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{Directory, Entry};
+    /// let mut directory = Directory::default();
+    ///
+    /// directory.insert_sorted(Entry { tile_id: 5, offset: 0, length: 1, run_length: 1 }).unwrap();
+    /// directory.insert_sorted(Entry { tile_id: 1, offset: 1, length: 1, run_length: 4 }).unwrap();
+    ///
+    /// assert_eq!(directory[0].tile_id, 1);
+    /// assert!(directory.insert_sorted(Entry { tile_id: 4, offset: 2, length: 1, run_length: 1 }).is_err());
+    /// ```
+    pub fn insert_sorted(&mut self, entry: Entry) -> Result<()> {
+        let index = self.entries.partition_point(|e| e.tile_id < entry.tile_id);
+
+        self.check_neighbors(index, entry)?;
+        self.entries.insert(index, entry);
+
+        Ok(())
     }
+
+    /// Sorts the entries in ascending order by their tile ids.
+    ///
+    /// This does not resolve overlapping entries, so use [`validate`](Self::validate) afterwards,
+    /// if entries may overlap.
+    pub fn sort(&mut self) {
+        self.entries.sort_by_key(|entry| entry.tile_id);
+    }
+
+    /// Checks, that `entry` can be inserted at `index` without violating the invariants of the directory.
+    fn check_neighbors(&self, index: usize, entry: Entry) -> Result<()> {
+        if entry.length == 0 {
+            return Err(Error::ZeroLengthEntry(entry.tile_id));
+        }
+
+        let previous = index.checked_sub(1).map(|i| [self.entries[i], entry]);
+        let next = self.entries.get(index).map(|next| [entry, *next]);
+
+        previous
+            .into_iter()
+            .chain(next)
+            .find_map(|pair| unordered_entry(&pair))
+            .map_or(Ok(()), |warning| Err(warning.into()))
+    }
+}
+
+/// Returns a warning for the first entry of `entries`, which does not start after the tile ids
+/// covered by its preceding entry (leaf directory entries cover at least their first tile id).
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn unordered_entry(entries: &[Entry]) -> Option<ParseWarning> {
+    entries.windows(2).find_map(|pair| {
+        let previous_end = pair[0]
+            .tile_id
+            .saturating_add(u64::from(pair[0].run_length.max(1)));
+
+        (pair[1].tile_id < previous_end).then_some(ParseWarning::EntriesNotAscending {
+            tile_id: pair[1].tile_id,
+            previous: pair[0].tile_id,
+        })
+    })
 }
 
 impl<I: SliceIndex<[Entry]>> Index<I> for Directory {
@@ -460,6 +761,41 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_find_entry() {
+        let directory: Directory = vec![
+            Entry {
+                tile_id: 3,
+                offset: 0,
+                length: 1,
+                run_length: 1,
+            },
+            Entry {
+                tile_id: 4,
+                offset: 1,
+                length: 1,
+                run_length: 3,
+            },
+            Entry {
+                tile_id: 10,
+                offset: 0,
+                length: 42,
+                run_length: 0,
+            },
+        ]
+        .into();
+
+        assert_eq!(directory.find_entry(0), None);
+        assert_eq!(directory.find_entry(3), Some(&directory[0]));
+        assert_eq!(directory.find_entry(4), Some(&directory[1]));
+        assert_eq!(directory.find_entry(6), Some(&directory[1]));
+        assert_eq!(directory.find_entry(7), None);
+        assert_eq!(directory.find_entry(10), Some(&directory[2]));
+        assert_eq!(directory.find_entry(u64::MAX), Some(&directory[2]));
+
+        assert_eq!(Directory::from(Vec::new()).find_entry(0), None);
+    }
+
     #[test]
     fn test_to_writer() -> Result<()> {
         let mut reader = Cursor::new(PM_TILES_BYTES);
@@ -501,4 +837,211 @@ mod test {
         let mut writer = Cursor::new(&mut buf);
         assert!(dir.to_writer(&mut writer, ROOT_DIR_COMPRESSION).is_err());
     }
+
+    #[test]
+    fn test_from_reader_with_limits() {
+        // directory claiming u64::MAX entries without containing any of them
+        let bytes = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+
+        let result = Directory::from_reader_with_limits(
+            &mut Cursor::new(bytes),
+            bytes.len() as u64,
+            Compression::None,
+            &ReadLimits::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded {
+                limit: "number of directory entries",
+                ..
+            })
+        ));
+
+        // a number of entries within the limits must not be allocated up front either
+        let result = Directory::from_reader_with_limits(
+            &mut Cursor::new(bytes),
+            bytes.len() as u64,
+            Compression::None,
+            &ReadLimits::unlimited(),
+        );
+        assert!(matches!(result, Err(Error::Io(_))));
+
+        let mut reader = Cursor::new(PM_TILES_BYTES);
+        reader.set_position(ROOT_DIR_OFFSET);
+        let result = Directory::from_reader_with_limits(
+            &mut reader,
+            ROOT_DIR_LENGTH,
+            ROOT_DIR_COMPRESSION,
+            &ReadLimits {
+                max_directory_length: 100,
+                ..ReadLimits::default()
+            },
+        );
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded {
+                limit: "directory length",
+                max: 100,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_check_entries() {
+        let entry = |tile_id, run_length| Entry {
+            tile_id,
+            offset: 0,
+            length: 1,
+            run_length,
+        };
+
+        let directory: Directory = vec![entry(0, 2), entry(2, 0), entry(3, 1)].into();
+        assert!(directory.check_entries().is_ok());
+        assert!(Directory::from(Vec::new()).check_entries().is_ok());
+
+        for (entries, tile_id, previous) in [
+            // overlapping run lengths
+            (vec![entry(0, 2), entry(1, 1)], 1, 0),
+            // out of order
+            (vec![entry(0, 1), entry(5, 1), entry(3, 1)], 3, 5),
+            // leaf directory entry with the same tile id as the preceding one
+            (vec![entry(0, 1), entry(4, 0), entry(4, 1)], 4, 4),
+        ] {
+            assert!(matches!(
+                Directory::from(entries).check_entries(),
+                Err(Error::Inconsistent(ParseWarning::EntriesNotAscending { tile_id: t, previous: p }))
+                    if t == tile_id && p == previous
+            ));
+        }
+    }
+
+    #[test]
+    fn test_push() -> Result<()> {
+        let entry = |tile_id, run_length| Entry {
+            tile_id,
+            offset: 0,
+            length: 1,
+            run_length,
+        };
+
+        let mut directory = Directory::default();
+        directory.push(entry(0, 2))?;
+        directory.push(entry(2, 0))?;
+        directory.push(entry(3, 1))?;
+        assert_eq!(
+            Vec::from(directory.clone()),
+            [entry(0, 2), entry(2, 0), entry(3, 1)]
+        );
+
+        assert!(matches!(
+            directory.push(entry(3, 1)),
+            Err(Error::Inconsistent(ParseWarning::EntriesNotAscending {
+                tile_id: 3,
+                previous: 3
+            }))
+        ));
+        assert!(matches!(
+            directory.push(Entry {
+                length: 0,
+                ..entry(4, 1)
+            }),
+            Err(Error::ZeroLengthEntry(4))
+        ));
+        assert_eq!(directory.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_sorted() -> Result<()> {
+        let entry = |tile_id, run_length| Entry {
+            tile_id,
+            offset: 0,
+            length: 1,
+            run_length,
+        };
+
+        let mut directory = Directory::default();
+        directory.insert_sorted(entry(10, 1))?;
+        directory.insert_sorted(entry(0, 2))?;
+        directory.insert_sorted(entry(5, 5))?;
+        directory.insert_sorted(entry(2, 0))?;
+        assert_eq!(
+            Vec::from(directory.clone()),
+            [entry(0, 2), entry(2, 0), entry(5, 5), entry(10, 1)]
+        );
+
+        // overlaps with preceding entry
+        assert!(directory.insert_sorted(entry(9, 1)).is_err());
+        // overlaps with next entry
+        assert!(directory.insert_sorted(entry(3, 3)).is_err());
+        // same tile id as existing entry
+        assert!(directory.insert_sorted(entry(10, 0)).is_err());
+        assert_eq!(directory.len(), 4);
+
+        directory.validate()
+    }
+
+    #[test]
+    fn test_sort_and_validate() {
+        let entry = |tile_id, length| Entry {
+            tile_id,
+            offset: 0,
+            length,
+            run_length: 1,
+        };
+
+        let mut directory: Directory = vec![entry(3, 1), entry(1, 1), entry(2, 1)].into();
+        assert!(matches!(directory.validate(), Err(Error::Inconsistent(_))));
+
+        directory.sort();
+        assert_eq!(
+            Vec::from(directory.clone()),
+            [entry(1, 1), entry(2, 1), entry(3, 1)]
+        );
+        assert!(directory.validate().is_ok());
+
+        directory[1].length = 0;
+        assert!(matches!(
+            directory.validate(),
+            Err(Error::ZeroLengthEntry(2))
+        ));
+    }
+
+    #[test]
+    fn test_tile_and_leaf_entries() -> Result<()> {
+        let dir = Directory::from_bytes(
+            &PM_TILES_BYTES[ROOT_DIR_OFFSET as usize..],
+            ROOT_DIR_COMPRESSION,
+        )?;
+
+        assert_eq!(dir.num_tile_entries(), 84);
+        assert_eq!(dir.num_leaf_entries(), 0);
+        assert_eq!(dir.addressed_tile_count(), 85);
+
+        let entry = |tile_id, run_length| Entry {
+            tile_id,
+            offset: 0,
+            length: 1,
+            run_length,
+        };
+        let directory: Directory =
+            vec![entry(0, 3), entry(3, 0), entry(10, 2), entry(20, 0)].into();
+
+        assert_eq!(
+            directory.tile_entries().copied().collect::<Vec<_>>(),
+            [entry(0, 3), entry(10, 2)]
+        );
+        assert_eq!(
+            directory.leaf_entries().copied().collect::<Vec<_>>(),
+            [entry(3, 0), entry(20, 0)]
+        );
+        assert_eq!(directory.num_tile_entries(), 2);
+        assert_eq!(directory.num_leaf_entries(), 2);
+        assert_eq!(directory.addressed_tile_count(), 5);
+        assert_eq!(Directory::default().addressed_tile_count(), 0);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,174 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite};
+
+use crate::{util::OffsetIo, PMTiles, Result, WriteOptions};
+
+#[duplicate_item(
+    from_fn_name             to_fn_name            to_options_fn_name                 cfg_async_filter       async   add_await(code) RTraits                                                  WTraits                                    SeekFrom                from_reader         to_writer_with_options;
+    [from_reader_at]         [to_writer_at]        [to_writer_at_with_options]        [cfg(all())]           []      [code]          [Read + Seek]                                            [Write + Seek]                             [SeekFrom]              [from_reader]       [to_writer_with_options];
+    [from_async_reader_at]   [to_async_writer_at]  [to_async_writer_at_with_options]  [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [AsyncWrite + Send + Unpin + AsyncSeekExt] [futures::io::SeekFrom] [from_async_reader] [to_async_writer_with_options];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Reads a `PMTiles` archive, which is embedded at a byte offset inside a larger container
+    /// (e.g. a game or app bundle packing multiple assets into one file).
+    ///
+    /// All offsets of the archive are relative to its first byte, so the reader is wrapped in an
+    /// [`OffsetIo`], which shifts all positions by `offset`.
+    ///
+    /// # Arguments
+    /// * `input` - Reader of the container
+    /// * `offset` - Position of the first byte of the archive in `input`
+    ///
+    /// # Errors
+    /// See [`from_reader`](Self::from_reader) for details on possible errors.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::PMTiles;
+    /// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+    ///
+    /// let mut container = b"some other asset".to_vec();
+    /// let offset = container.len() as u64;
+    /// container.extend_from_slice(bytes);
+    ///
+    /// let mut pm_tiles = PMTiles::from_reader_at(std::io::Cursor::new(container), offset).unwrap();
+    /// assert!(pm_tiles.get_tile(0, 0, 0).unwrap().is_some());
+    /// ```
+    pub async fn from_fn_name(input: R, offset: u64) -> Result<PMTiles<OffsetIo<R>>> {
+        add_await([PMTiles::from_reader(OffsetIo::new(input, offset))])
+    }
+
+    /// Writes the archive into a larger container at a byte offset.
+    ///
+    /// All offsets of the archive are written relative to its first byte, so the archive can be read
+    /// with [`from_reader_at`](Self::from_reader_at) later on. Data of the container before `offset`
+    /// is left untouched. After writing, `output` is positioned at the end of the archive.
+    ///
+    /// # Arguments
+    /// * `output` - Writer of the container
+    /// * `offset` - Position, at which the first byte of the archive is written
+    ///
+    /// # Errors
+    /// See [`to_writer`](Self::to_writer) for details on possible errors.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{PMTiles, TileType, Compression};
+    /// # use std::io::{Cursor, Write};
+    /// let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+    /// pm_tiles.add_tile(0, vec![1, 2, 3]).unwrap();
+    ///
+    /// let mut container = Cursor::new(Vec::new());
+    /// container.write_all(b"some other asset").unwrap();
+    /// pm_tiles.to_writer_at(&mut container, 16).unwrap();
+    ///
+    /// let mut pm_tiles = PMTiles::from_reader_at(container, 16).unwrap();
+    /// assert_eq!(pm_tiles.get_tile_by_id(0).unwrap(), Some(vec![1, 2, 3]));
+    /// ```
+    pub async fn to_fn_name(self, output: &mut (impl WTraits), offset: u64) -> Result<()> {
+        add_await([self.to_options_fn_name(output, offset, WriteOptions::default())])
+    }
+
+    /// Same as [`to_writer_at`](Self::to_writer_at), but with an extra parameter.
+    ///
+    /// Writes the archive into a larger container at a byte offset with the given [`WriteOptions`].
+    ///
+    /// # Arguments
+    /// * `output` - Writer of the container
+    /// * `offset` - Position, at which the first byte of the archive is written
+    /// * `options` - Options for writing the archive
+    ///
+    /// # Errors
+    /// See [`to_writer_with_options`](Self::to_writer_with_options) for details on possible errors.
+    pub async fn to_options_fn_name(
+        self,
+        output: &mut (impl WTraits),
+        offset: u64,
+        options: WriteOptions<'_>,
+    ) -> Result<()> {
+        let mut output = OffsetIo::new(output, offset);
+        add_await([output.seek(SeekFrom::Start(0))])?;
+
+        add_await([self.to_writer_with_options(&mut output, options)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{Compression, TileType};
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    #[test]
+    fn test_from_reader_at() -> Result<()> {
+        let mut container = vec![42; 1000];
+        container.extend_from_slice(PM_TILES_BYTES);
+        container.extend_from_slice(&[42; 1000]);
+
+        let mut embedded = PMTiles::from_reader_at(Cursor::new(container), 1000)?;
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+        assert_eq!(embedded.header(), pm_tiles.header());
+        assert_eq!(embedded.meta_data, pm_tiles.meta_data);
+
+        for tile_id in pm_tiles.tile_ids_sorted() {
+            assert_eq!(
+                embedded.get_tile_by_id(tile_id)?,
+                pm_tiles.get_tile_by_id(tile_id)?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_writer_at() -> Result<()> {
+        let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+        pm_tiles.add_tile(0, vec![1, 2, 3])?;
+        pm_tiles.add_tile(5, vec![4, 5])?;
+
+        let mut container = Cursor::new(vec![42; 100]);
+        pm_tiles.to_writer_at(&mut container, 50)?;
+
+        let end = container.position();
+        let bytes = container.into_inner();
+        assert_eq!(end, bytes.len() as u64);
+        assert_eq!(&bytes[..50], &[42; 50]);
+
+        let mut pm_tiles = PMTiles::from_bytes(&bytes[50..])?;
+        assert_eq!(pm_tiles.get_tile_by_id(0)?, Some(vec![1, 2, 3]));
+        assert_eq!(pm_tiles.get_tile_by_id(5)?, Some(vec![4, 5]));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_async_reader_writer_at() -> Result<()> {
+        tokio_test::block_on(async {
+            let pm_tiles =
+                PMTiles::from_async_reader(futures::io::Cursor::new(PM_TILES_BYTES)).await?;
+            let num_tiles = pm_tiles.num_tiles();
+
+            let mut container = futures::io::Cursor::new(vec![42; 10]);
+            pm_tiles.to_async_writer_at(&mut container, 10).await?;
+
+            let mut embedded = PMTiles::from_async_reader_at(container, 10).await?;
+            assert_eq!(embedded.num_tiles(), num_tiles);
+            assert_eq!(
+                embedded.get_tile_async(0, 0, 0).await?,
+                PMTiles::from_bytes(PM_TILES_BYTES)?.get_tile(0, 0, 0)?
+            );
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{Compression, ParseWarning, TileType};
+
+/// Errors, which can occur while reading, writing or modifying `PMTiles` archives.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The data does not start with the `PMTiles` magic bytes, so it is no `PMTiles` archive.
+    #[error("Data is no PMTiles archive (invalid magic bytes)")]
+    InvalidMagic,
+
+    /// The archive uses a version of the specification, which is not supported.
+    #[error("Unsupported PMTiles spec version {0} (only version 3 is supported)")]
+    UnsupportedSpecVersion(u8),
+
+    /// The header could not be decoded.
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+
+    /// Data with [`Compression::Unknown`](crate::Compression::Unknown) was to be compressed or decompressed,
+    /// but no [custom codec](crate::util::Codec) was given.
+    #[error("Cannot compress or decompress for Compression Unknown")]
+    UnknownCompression,
+
+    /// A directory could not be decoded.
+    #[error("Invalid directory: {0}")]
+    DirectoryDecode(String),
+
+    /// A limit configured in [`ReadLimits`](crate::ReadLimits) was exceeded while reading an archive.
+    #[error("{limit} ({value}) exceeds the limit of {max}")]
+    LimitExceeded {
+        /// Name of the exceeded limit (e.g. `directory length`)
+        limit: &'static str,
+        /// Actual value (or a lower bound of it, if reading was stopped early)
+        value: u64,
+        /// Configured maximum
+        max: u64,
+    },
+
+    /// The archive deviates from the specification in a way, which is not tolerated by the
+    /// [`ParseOptions`](crate::ParseOptions) it was read with.
+    #[error("Inconsistent archive: {0}")]
+    Inconsistent(ParseWarning),
+
+    /// A directory entry with a length of 0 was to be written.
+    #[error("Length of the directory entry for tile id {0} must be greater than 0.")]
+    ZeroLengthEntry(u64),
+
+    /// The meta data could not be parsed or serialized.
+    #[error("Invalid meta data: {0}")]
+    MetaData(#[from] serde_json::Error),
+
+    /// The meta data is valid JSON, but no JSON object.
+    #[error("PMTiles' metadata must be JSON Object")]
+    MetaDataNotAnObject,
+
+    /// A tile without any data was to be added.
+    #[error("A tile must have at least 1 byte of data.")]
+    EmptyTile,
+
+    /// The data of a tile is larger than the maximum tile size of 4 GiB.
+    #[error("Tile data is too large")]
+    TileTooLarge,
+
+    /// The data of a tile does not look like it is compressed with the tile compression of the archive.
+    #[error("Tile data does not match tile compression of archive ({0:?}).")]
+    TileCompressionMismatch(Compression),
+
+    /// New tile data is larger than the tile data it is to replace in place.
+    #[error("New tile data ({new} bytes) is larger than existing tile data ({existing} bytes).")]
+    ReplacementTooLarge {
+        /// Length of the new tile data
+        new: usize,
+        /// Length of the existing tile data
+        existing: u32,
+    },
+
+    /// The archive does not include a tile with the specified id.
+    #[error("Archive does not include a tile with id {0}.")]
+    TileNotFound(u64),
+
+    /// A tile id is out of range (i.e. its zoom level is greater than the maximum zoom level).
+    #[error("Tile id {0} is out of range")]
+    InvalidTileId(u64),
+
+    /// Tile coordinates are out of range for their zoom level.
+    #[error("Tile coordinates {z}/{x}/{y} are out of range")]
+    InvalidTileCoordinates {
+        /// Zoom level
+        z: u8,
+        /// X coordinate
+        x: u64,
+        /// Y coordinate
+        y: u64,
+    },
+
+    /// Tiles were not added in strictly ascending order of their tile ids.
+    #[error("Tiles must be added in strictly ascending order of their tile ids (got {tile_id} after {previous})")]
+    TileIdNotAscending {
+        /// Id of the tile, which was to be added
+        tile_id: u64,
+        /// Id of the previously added tile
+        previous: u64,
+    },
+
+    /// Multiple archives, which were to be merged, include a tile with the same id.
+    #[error("Multiple archives include a tile with id {0}")]
+    TileConflict(u64),
+
+    /// Tiles of different tile types were to be added to the same archive.
+    #[error("Tile type {found:?} does not match tile type of previous tiles ({expected:?})")]
+    MixedTileTypes {
+        /// Tile type of previous tiles
+        expected: TileType,
+        /// Tile type of the tile, which was to be added
+        found: TileType,
+    },
+
+    /// A combination of fields is not valid (e.g. when building an archive with [`PMTilesBuilder`](crate::PMTilesBuilder)).
+    #[error("Invalid archive configuration: {0}")]
+    Validation(String),
+
+    /// Tile data was to be read from the underlying reader, but there is none.
+    #[error("Tried to read from non-existent reader")]
+    NoReader,
+
+    /// A Mapbox Vector Tile could not be decoded.
+    #[cfg(feature = "mvt")]
+    #[error("Invalid vector tile: {0}")]
+    MvtDecode(String),
+
+    /// The operation was cancelled via a [`CancellationToken`](crate::CancellationToken).
+    #[error("Operation was cancelled")]
+    Cancelled,
+
+    /// The requested operation is not supported for this archive.
+    #[error("{0}")]
+    Unsupported(&'static str),
+
+    /// An I/O error occurred.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A specialized [`Result`](std::result::Result) type for operations of this crate.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        use std::io::ErrorKind;
+
+        let kind = match err {
+            Error::Io(err) => return err,
+            Error::UnknownCompression | Error::Unsupported(_) | Error::NoReader => {
+                ErrorKind::Unsupported
+            }
+            Error::EmptyTile
+            | Error::TileTooLarge
+            | Error::TileCompressionMismatch(_)
+            | Error::ReplacementTooLarge { .. }
+            | Error::InvalidTileCoordinates { .. }
+            | Error::TileIdNotAscending { .. }
+            | Error::ZeroLengthEntry(_)
+            | Error::MixedTileTypes { .. }
+            | Error::TileConflict(_)
+            | Error::Validation(_) => ErrorKind::InvalidInput,
+            Error::TileNotFound(_) => ErrorKind::NotFound,
+            _ => ErrorKind::InvalidData,
+        };
+
+        Self::new(kind, err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_into_io_error() {
+        let err = std::io::Error::from(Error::Io(std::io::ErrorKind::UnexpectedEof.into()));
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        let err = std::io::Error::from(Error::InvalidMagic);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), Error::InvalidMagic.to_string());
+    }
+}
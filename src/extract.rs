@@ -0,0 +1,274 @@
+use std::{
+    io::{Cursor, Read, Seek},
+    ops::RangeInclusive,
+};
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+use crate::{
+    util::{tile_id_ranges_in_bbox, zxy},
+    BBox, Error, PMTiles, Result,
+};
+
+#[duplicate_item(
+    fn_name         split_by_zoom         cfg_async_filter       async   add_await(code) RTraits                                                  CursorType                                 from_header         get_tile_by_id;
+    [extract]       [split_by_zoom]       [cfg(all())]           []      [code]          [Read + Seek]                                            [Cursor<&'static [u8]>]                    [from_header]       [get_tile_by_id];
+    [extract_async] [split_by_zoom_async] [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [futures::io::Cursor<&'static [u8]>]       [from_header_async] [get_tile_by_id_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Copies all tiles, which intersect a bounding box and are within a zoom range, into a new
+    /// archive, which holds all tiles in memory.
+    ///
+    /// The new archive has the same tile type, compressions and meta data as this archive. Its
+    /// bounds and zoom range are the intersection of those of this archive with `bounds` and
+    /// `zoom_range` and its center is moved into the new bounds if necessary.
+    ///
+    /// # Arguments
+    /// * `bounds` - Bounding box (e.g. `(min_longitude, min_latitude, max_longitude, max_latitude)`, see [`BBox`](crate::BBox))
+    /// * `zoom_range` - Zoom levels to extract
+    ///
+    /// # Errors
+    /// Will return [`Err`] if...
+    /// - `bounds` are not valid WGS84 coordinates, their minimum exceeds their maximum or
+    ///   `zoom_range` is empty ([`Error::Validation`])
+    /// - there was an error while reading tile data
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::PMTiles;
+    /// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+    /// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+    ///
+    /// // north-eastern quarter of the world at zoom levels 1 and 2
+    /// let extract = pm_tiles.extract((0.0, 1.0, 180.0, 85.0), 1..=2).unwrap();
+    ///
+    /// assert_eq!(extract.num_tiles(), 1 + 4);
+    /// assert_eq!((extract.min_zoom, extract.max_zoom), (1, 2));
+    /// ```
+    pub async fn fn_name(
+        &mut self,
+        bounds: impl Into<BBox>,
+        zoom_range: RangeInclusive<u8>,
+    ) -> Result<PMTiles<CursorType>> {
+        let bounds = bounds.into().to_tuple();
+        let (min_lon, min_lat, max_lon, max_lat) = bounds;
+
+        if !(-180.0..=180.0).contains(&min_lon)
+            || !(-180.0..=180.0).contains(&max_lon)
+            || !(-90.0..=90.0).contains(&min_lat)
+            || !(-90.0..=90.0).contains(&max_lat)
+        {
+            return Err(Error::Validation(format!(
+                "Bounds {bounds:?} are not valid WGS84 coordinates"
+            )));
+        }
+
+        if min_lon > max_lon || min_lat > max_lat {
+            return Err(Error::Validation(format!(
+                "Minimum of bounds {bounds:?} exceeds their maximum"
+            )));
+        }
+
+        if zoom_range.is_empty() {
+            return Err(Error::Validation(format!(
+                "Zoom range {zoom_range:?} is empty"
+            )));
+        }
+
+        let (min_zoom, max_zoom) = (*zoom_range.start(), *zoom_range.end());
+
+        let mut result = PMTiles::from_header(&self.header());
+        result.meta_data.clone_from(&self.meta_data);
+        result.min_zoom = self.min_zoom.clamp(min_zoom, max_zoom);
+        result.max_zoom = self.max_zoom.clamp(min_zoom, max_zoom);
+        result.center_zoom = self.center_zoom.clamp(result.min_zoom, result.max_zoom);
+        result.min_longitude = self.min_longitude.clamp(min_lon, max_lon);
+        result.min_latitude = self.min_latitude.clamp(min_lat, max_lat);
+        result.max_longitude = self.max_longitude.clamp(min_lon, max_lon);
+        result.max_latitude = self.max_latitude.clamp(min_lat, max_lat);
+        result.center_longitude = self
+            .center_longitude
+            .clamp(result.min_longitude, result.max_longitude);
+        result.center_latitude = self
+            .center_latitude
+            .clamp(result.min_latitude, result.max_latitude);
+
+        let tile_ids = self.tile_ids_sorted();
+
+        // zoom levels above the one of the last tile do not hold any tiles, so their (possibly
+        // many) ranges are not computed at all
+        let Some(&last_tile_id) = tile_ids.last() else {
+            return Ok(result);
+        };
+        let (last_zoom, _, _) =
+            zxy(last_tile_id).map_err(|_| Error::InvalidTileId(last_tile_id))?;
+
+        for z in min_zoom..=max_zoom.min(last_zoom) {
+            for range in tile_id_ranges_in_bbox(bounds, z) {
+                let start = tile_ids.partition_point(|tile_id| *tile_id < range.start);
+                let end = tile_ids.partition_point(|tile_id| *tile_id < range.end);
+
+                for &tile_id in &tile_ids[start..end] {
+                    if let Some(data) = add_await([self.get_tile_by_id(tile_id)])? {
+                        result.add_tile(tile_id, data)?;
+                    }
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Splits this archive into multiple archives, which hold all tiles in memory, by zoom levels
+    /// (e.g. a "hot" archive with zoom levels `0..=8` and a "cold" archive with zoom levels `9..=14`).
+    ///
+    /// Returns one archive per zoom range, in the same order as `zoom_ranges`. Each archive has the
+    /// same tile type, compressions, bounds and meta data as this archive, its zoom range is set to
+    /// the intersection of the zoom range of this archive with the respective zoom range and tiles
+    /// with the same content are stored only once within it.
+    ///
+    /// # Arguments
+    /// * `zoom_ranges` - Zoom levels of each archive
+    ///
+    /// # Errors
+    /// Will return [`Err`] if any of `zoom_ranges` is empty ([`Error::Validation`]) or there was an
+    /// error while reading tile data.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::PMTiles;
+    /// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+    /// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+    ///
+    /// let archives = pm_tiles.split_by_zoom(&[0..=1, 2..=3]).unwrap();
+    ///
+    /// assert_eq!(archives[0].num_tiles(), 1 + 4);
+    /// assert_eq!(archives[1].num_tiles(), 16 + 64);
+    /// assert_eq!((archives[1].min_zoom, archives[1].max_zoom), (2, 3));
+    /// ```
+    pub async fn split_by_zoom(
+        &mut self,
+        zoom_ranges: &[RangeInclusive<u8>],
+    ) -> Result<Vec<PMTiles<CursorType>>> {
+        let mut archives = Vec::with_capacity(zoom_ranges.len());
+
+        for zoom_range in zoom_ranges {
+            let world = (-180.0, -90.0, 180.0, 90.0);
+            archives.push(add_await([self.fn_name(world, zoom_range.clone())])?);
+        }
+
+        Ok(archives)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::tile_id;
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    #[test]
+    fn test_extract() -> Result<()> {
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+        let mut extract = pm_tiles.extract((0.0, 1.0, 180.0, 85.0), 1..=2)?;
+
+        let mut expected = vec![
+            tile_id(1, 1, 0),
+            tile_id(2, 2, 0),
+            tile_id(2, 3, 0),
+            tile_id(2, 2, 1),
+            tile_id(2, 3, 1),
+        ];
+        expected.sort_unstable();
+        assert_eq!(extract.tile_ids_sorted(), expected);
+        assert_eq!(
+            extract.get_tile_by_id(tile_id(2, 3, 1))?,
+            pm_tiles.get_tile_by_id(tile_id(2, 3, 1))?
+        );
+        assert_eq!((extract.min_zoom, extract.max_zoom), (1, 2));
+        assert!((extract.min_longitude - 0.0).abs() < f64::EPSILON);
+        assert!((extract.min_latitude - 1.0).abs() < f64::EPSILON);
+        assert!(extract.center_longitude >= 0.0);
+        assert_eq!(extract.meta_data, pm_tiles.meta_data);
+
+        let everything = pm_tiles.extract((-180.0, -90.0, 180.0, 90.0), 0..=31)?;
+        assert_eq!(everything.num_tiles(), pm_tiles.num_tiles());
+        assert_eq!((everything.min_zoom, everything.max_zoom), (0, 3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_invalid() -> Result<()> {
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+        for (bounds, zoom_range) in [
+            ((10.0, 0.0, 0.0, 10.0), 0..=3),
+            ((0.0, 0.0, 200.0, 10.0), 0..=3),
+            ((0.0, 0.0, 10.0, 10.0), RangeInclusive::new(3, 0)),
+        ] {
+            assert!(matches!(
+                pm_tiles.extract(bounds, zoom_range),
+                Err(Error::Validation(_))
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_by_zoom() -> Result<()> {
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+        let mut archives = pm_tiles.split_by_zoom(&[0..=0, 1..=2, 3..=10])?;
+
+        assert_eq!(
+            archives.iter().map(PMTiles::num_tiles).collect::<Vec<_>>(),
+            vec![1, 4 + 16, 64]
+        );
+        assert_eq!(
+            archives
+                .iter()
+                .map(|a| (a.min_zoom, a.max_zoom))
+                .collect::<Vec<_>>(),
+            vec![(0, 0), (1, 2), (3, 3)]
+        );
+
+        // tile 58 is stored with a run length of 2 and stays deduplicated
+        let stats = archives[2].stats();
+        assert!(stats.num_unique_tiles < stats.num_tiles());
+        assert_eq!(
+            archives[2].get_tile_by_id(58)?,
+            pm_tiles.get_tile_by_id(58)?
+        );
+
+        assert!(matches!(
+            pm_tiles.split_by_zoom(&[RangeInclusive::new(2, 1)]),
+            Err(Error::Validation(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_extract_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let reader = futures::io::Cursor::new(PM_TILES_BYTES);
+            let mut pm_tiles = PMTiles::from_async_reader(reader).await?;
+
+            let extract = pm_tiles
+                .extract_async((-180.0, -85.0, -1.0, -1.0), 3..=3)
+                .await?;
+            assert_eq!(extract.num_tiles(), 16);
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,181 @@
+use serde_json::{json, Value as JSONValue};
+
+use crate::{
+    util::{tile_bounds, zxy},
+    PMTiles,
+};
+
+/// Options for [`PMTiles::tile_footprints`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FootprintOptions {
+    /// Minimum zoom level of included tiles; if [`None`] there is no lower limit
+    pub min_zoom: Option<u8>,
+
+    /// Maximum zoom level of included tiles; if [`None`] there is no upper limit
+    pub max_zoom: Option<u8>,
+
+    /// Whether the coordinates of tiles should be added as `z`, `x` and `y` properties (default `false`)
+    pub include_zoom: bool,
+
+    /// Whether the length of the tile data (in bytes) should be added as `size` property (default `false`)
+    pub include_size: bool,
+}
+
+impl<R> PMTiles<R> {
+    /// Returns a `GeoJSON` `FeatureCollection` with the footprint (a polygon of its bounds) of every
+    /// tile of the archive, e.g. to visualize the coverage of an archive in QGIS or on a web map.
+    ///
+    /// Features are sorted by tile id and their `id` is the tile id. No tile data is read, as sizes
+    /// are taken from the directories (or from the data held in memory).
+    ///
+    /// # Arguments
+    /// * `options` - Zoom levels of included tiles and properties of features
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{FootprintOptions, PMTiles};
+    /// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+    /// let pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+    ///
+    /// let options = FootprintOptions {
+    ///     max_zoom: Some(1),
+    ///     include_size: true,
+    ///     ..FootprintOptions::default()
+    /// };
+    /// let geojson = pm_tiles.tile_footprints(options);
+    ///
+    /// assert_eq!(geojson["features"].as_array().unwrap().len(), 1 + 4);
+    /// ```
+    pub fn tile_footprints(&self, options: FootprintOptions) -> JSONValue {
+        let min_zoom = options.min_zoom.unwrap_or(u8::MIN);
+        let max_zoom = options.max_zoom.unwrap_or(u8::MAX);
+
+        let features = self
+            .tile_ids_sorted()
+            .into_iter()
+            .filter_map(|tile_id| {
+                let (z, x, y) = zxy(tile_id).ok()?;
+                if z < min_zoom || z > max_zoom {
+                    return None;
+                }
+
+                let (min_longitude, min_latitude, max_longitude, max_latitude) =
+                    tile_bounds(tile_id).ok()?;
+
+                let mut properties = serde_json::Map::new();
+                if options.include_zoom {
+                    properties.insert("z".to_string(), z.into());
+                    properties.insert("x".to_string(), x.into());
+                    properties.insert("y".to_string(), y.into());
+                }
+                if options.include_size {
+                    properties.insert("size".to_string(), self.tile_size(tile_id).into());
+                }
+
+                Some(json!({
+                    "type": "Feature",
+                    "id": tile_id,
+                    "properties": properties,
+                    "geometry": {
+                        "type": "Polygon",
+                        // exterior rings are counterclockwise (RFC 7946)
+                        "coordinates": [[
+                            [min_longitude, min_latitude],
+                            [max_longitude, min_latitude],
+                            [max_longitude, max_latitude],
+                            [min_longitude, max_latitude],
+                            [min_longitude, min_latitude],
+                        ]],
+                    },
+                }))
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "type": "FeatureCollection",
+            "features": features,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::util::tile_id;
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    #[test]
+    fn test_tile_footprints() {
+        let pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES).unwrap();
+
+        let geojson = pm_tiles.tile_footprints(FootprintOptions::default());
+        let features = geojson["features"].as_array().unwrap();
+
+        assert_eq!(geojson["type"], "FeatureCollection");
+        assert_eq!(features.len(), pm_tiles.num_tiles());
+        assert_eq!(features[0]["id"], 0);
+        assert_eq!(features[0]["properties"], json!({}));
+        assert_eq!(
+            features[0]["geometry"]["coordinates"][0][1][0],
+            json!(180.0)
+        );
+
+        let ring = features[4]["geometry"]["coordinates"][0]
+            .as_array()
+            .unwrap();
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring[0], ring[4]);
+    }
+
+    #[test]
+    fn test_tile_footprints_options() {
+        let pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES).unwrap();
+
+        let geojson = pm_tiles.tile_footprints(FootprintOptions {
+            min_zoom: Some(2),
+            max_zoom: Some(2),
+            include_zoom: true,
+            include_size: true,
+        });
+        let features = geojson["features"].as_array().unwrap();
+
+        assert_eq!(features.len(), 16);
+        for feature in features {
+            let properties = &feature["properties"];
+            let (z, x, y) = zxy(feature["id"].as_u64().unwrap()).unwrap();
+
+            assert_eq!(properties["z"], z);
+            assert_eq!(properties["x"], x);
+            assert_eq!(properties["y"], y);
+            assert_eq!(
+                properties["size"],
+                pm_tiles
+                    .tile_byte_range(feature["id"].as_u64().unwrap())
+                    .map(|range| range.end - range.start)
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_tile_footprints_in_memory() {
+        let mut pm_tiles = PMTiles::<Cursor<&[u8]>>::default();
+        pm_tiles
+            .add_tile(tile_id(1, 1, 0), vec![1, 3, 3, 7])
+            .unwrap();
+
+        let geojson = pm_tiles.tile_footprints(FootprintOptions {
+            include_size: true,
+            ..FootprintOptions::default()
+        });
+
+        let feature = &geojson["features"][0];
+        assert_eq!(feature["properties"]["size"], 4);
+        assert_eq!(feature["geometry"]["coordinates"][0][0], json!([0.0, 0.0]));
+    }
+}
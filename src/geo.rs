@@ -0,0 +1,169 @@
+//! Conversions between bounds and centers of archives and [`geo_types`].
+
+use geo_types::{coord, Point, Rect};
+
+use crate::{header::LatLng, BBox, Header, PMTiles};
+
+impl From<Rect<f64>> for BBox {
+    fn from(rect: Rect<f64>) -> Self {
+        Self {
+            min_longitude: rect.min().x,
+            min_latitude: rect.min().y,
+            max_longitude: rect.max().x,
+            max_latitude: rect.max().y,
+        }
+    }
+}
+
+impl From<BBox> for Rect<f64> {
+    fn from(bbox: BBox) -> Self {
+        Self::new(
+            coord! { x: bbox.min_longitude, y: bbox.min_latitude },
+            coord! { x: bbox.max_longitude, y: bbox.max_latitude },
+        )
+    }
+}
+
+impl From<LatLng> for Point<f64> {
+    fn from(position: LatLng) -> Self {
+        Self::new(position.longitude, position.latitude)
+    }
+}
+
+impl From<Point<f64>> for LatLng {
+    fn from(point: Point<f64>) -> Self {
+        Self {
+            longitude: point.x(),
+            latitude: point.y(),
+        }
+    }
+}
+
+impl Header {
+    /// Returns the bounds of the tiles of the archive
+    /// ([`min_pos`](Self::min_pos) and [`max_pos`](Self::max_pos)) as [`Rect`].
+    pub fn bounds_rect(&self) -> Rect<f64> {
+        Rect::new(Point::from(self.min_pos), Point::from(self.max_pos))
+    }
+
+    /// Returns the [`center_pos`](Self::center_pos) as [`Point`].
+    pub fn center_point(&self) -> Point<f64> {
+        self.center_pos.into()
+    }
+}
+
+impl<R> PMTiles<R> {
+    /// Returns the bounds of the tiles of the archive as [`Rect`].
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{Compression, PMTiles, TileType};
+    /// # use geo_types::{coord, Rect};
+    /// let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+    /// pm_tiles.set_bounds_rect(Rect::new(coord! { x: 11.15, y: 43.72 }, coord! { x: 11.33, y: 43.84 }));
+    ///
+    /// assert_eq!((pm_tiles.min_longitude, pm_tiles.max_latitude), (11.15, 43.84));
+    /// assert_eq!(pm_tiles.bounds_rect().min(), coord! { x: 11.15, y: 43.72 });
+    /// ```
+    pub fn bounds_rect(&self) -> Rect<f64> {
+        BBox {
+            min_longitude: self.min_longitude,
+            min_latitude: self.min_latitude,
+            max_longitude: self.max_longitude,
+            max_latitude: self.max_latitude,
+        }
+        .into()
+    }
+
+    /// Sets the bounds of the tiles of the archive to `rect`.
+    pub fn set_bounds_rect(&mut self, rect: Rect<f64>) {
+        let bbox = BBox::from(rect);
+
+        self.min_longitude = bbox.min_longitude;
+        self.min_latitude = bbox.min_latitude;
+        self.max_longitude = bbox.max_longitude;
+        self.max_latitude = bbox.max_latitude;
+    }
+
+    /// Returns the center of the archive as [`Point`].
+    pub fn center_point(&self) -> Point<f64> {
+        Point::new(self.center_longitude, self.center_latitude)
+    }
+
+    /// Sets the center of the archive to `point`.
+    pub fn set_center_point(&mut self, point: Point<f64>) {
+        self.center_longitude = point.x();
+        self.center_latitude = point.y();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::io::Cursor;
+
+    use geo_types::point;
+
+    use super::*;
+
+    #[test]
+    fn test_bbox_rect() {
+        let rect = Rect::new(coord! { x: 1.0, y: 4.0 }, coord! { x: 3.0, y: 2.0 });
+        let bbox = BBox::from(rect);
+
+        // corners of rects are normalized
+        assert_eq!(bbox.to_tuple(), (1.0, 2.0, 3.0, 4.0));
+        assert_eq!(Rect::from(bbox), rect);
+    }
+
+    #[test]
+    fn test_header() {
+        let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+        let header = PMTiles::from_bytes(bytes).unwrap().header();
+
+        assert_eq!(
+            header.bounds_rect().min(),
+            coord! { x: header.min_pos.longitude, y: header.min_pos.latitude }
+        );
+        assert_eq!(
+            header.bounds_rect().max(),
+            coord! { x: header.max_pos.longitude, y: header.max_pos.latitude }
+        );
+        assert_eq!(
+            header.center_point(),
+            point! { x: header.center_pos.longitude, y: header.center_pos.latitude }
+        );
+        assert_eq!(LatLng::from(header.center_point()), header.center_pos);
+    }
+
+    #[test]
+    fn test_pm_tiles() {
+        let mut pm_tiles = PMTiles::<Cursor<&[u8]>>::default();
+
+        pm_tiles.set_center_point(point! { x: 11.25, y: 43.77 });
+        assert_eq!(
+            (pm_tiles.center_longitude, pm_tiles.center_latitude),
+            (11.25, 43.77)
+        );
+        assert_eq!(pm_tiles.center_point(), point! { x: 11.25, y: 43.77 });
+
+        let rect = Rect::new(coord! { x: -10.0, y: -5.0 }, coord! { x: 10.0, y: 5.0 });
+        pm_tiles.set_bounds_rect(rect);
+        assert_eq!(pm_tiles.bounds_rect(), rect);
+        assert_eq!(pm_tiles.header().bounds_rect(), rect);
+    }
+
+    #[test]
+    fn test_partial_read_with_rect() {
+        let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+        // north-eastern quarter of the world at zoom levels 1 and 2
+        let rect = Rect::new(coord! { x: 0.0, y: 1.0 }, coord! { x: 180.0, y: 85.0 });
+        let pm_tiles =
+            PMTiles::from_reader_partially_bbox(Cursor::new(bytes), rect, 1..=2).unwrap();
+        assert_eq!(pm_tiles.num_tiles(), 1 + 4);
+
+        let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+        assert_eq!(pm_tiles.extract(rect, 1..=2).unwrap().num_tiles(), 1 + 4);
+    }
+}
@@ -3,7 +3,7 @@ use deku::{
     prelude::*,
 };
 
-#[derive(DekuRead, DekuWrite, Debug, PartialEq)]
+#[derive(DekuRead, DekuWrite, Debug, Clone, Copy, PartialEq)]
 #[deku(endian = "endian", ctx = "_endian: deku::ctx::Endian")]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LatLng {
@@ -30,7 +30,8 @@ impl LatLng {
 
     #[allow(clippy::cast_possible_truncation)]
     fn write_lat_lon(output: &mut BitVec<u8, Msb0>, field: f64) -> Result<(), DekuError> {
-        let value = (field * LAT_LONG_FACTOR) as i32;
+        // round instead of truncating, so values read from an archive are written unchanged
+        let value = (field * LAT_LONG_FACTOR).round() as i32;
         value.write(output, ())
     }
 }
@@ -71,6 +72,27 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_write_lat_lon_round_trip() -> Result<(), DekuError> {
+        // these values are not exactly representable after dividing by the factor, so truncating
+        // them would write e.g. 20 instead of 21
+        for value in [21, -1_799_999_988, 1_234_567, i32::MAX, i32::MIN] {
+            let bytes = value.to_le_bytes();
+            let (_, field) = LatLng::read_lat_lon(BitSlice::from_slice(&bytes))?;
+
+            let mut output = BitVec::with_capacity(32);
+            LatLng::write_lat_lon(&mut output, field)?;
+            assert_eq!(output, bytes.view_bits::<Msb0>(), "{value}");
+        }
+
+        // values between two steps are written as the nearest step
+        let mut output = BitVec::with_capacity(32);
+        LatLng::write_lat_lon(&mut output, 0.000_002_16)?;
+        assert_eq!(output, 22i32.to_le_bytes().view_bits::<Msb0>());
+
+        Ok(())
+    }
+
     #[test]
     fn test_deku_read() -> Result<(), DekuError> {
         let slice = BitSlice::from_slice(&[0x00, 0x2E, 0xB6, 0x94, 0x80, 0x07, 0x56, 0xCD]);
@@ -12,10 +12,26 @@ use deku::bitvec::{BitVec, BitView};
 use deku::prelude::*;
 use std::io::{Read, Write};
 
+use crate::{Error, ParseOptions, ParseWarning};
+
 pub const HEADER_BYTES: u8 = 127;
 
+/// Header and root directory must be located within the first 16 KiB of an archive.
+pub const MAX_ROOT_DIRECTORY_END: u64 = 16_384;
+
+const MAGIC: &[u8] = b"PMTiles";
+
+/// Archives of version 2 start with these bytes, followed by the version as a little endian `u16`.
+const V2_MAGIC: &[u8] = b"PM";
+
+/// Indices of single byte fields within the header, which are checked before decoding it.
+const SPEC_VERSION_INDEX: usize = 7;
+const INTERNAL_COMPRESSION_INDEX: usize = 97;
+const TILE_COMPRESSION_INDEX: usize = 98;
+const TILE_TYPE_INDEX: usize = 99;
+
 /// A structure representing a `PMTiles` header.
-#[derive(DekuRead, DekuWrite, Debug)]
+#[derive(DekuRead, DekuWrite, Debug, Clone, PartialEq)]
 #[deku(magic = b"PMTiles")]
 #[deku(endian = "little")]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -111,21 +127,81 @@ impl Header {
         self.tile_compression.http_content_encoding()
     }
 
+    fn parse(buf: &[u8; HEADER_BYTES as usize]) -> crate::Result<Self> {
+        Self::parse_with_options(buf, &ParseOptions::default(), &mut Vec::new())
+    }
+
+    /// Parses a header and records deviations from the specification, which are tolerated by
+    /// `options`, in `warnings`.
+    fn parse_with_options(
+        buf: &[u8; HEADER_BYTES as usize],
+        options: &ParseOptions,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> crate::Result<Self> {
+        if is_v2_header(buf) {
+            return Err(Error::UnsupportedSpecVersion(2));
+        }
+        if !buf.starts_with(MAGIC) {
+            return Err(Error::InvalidMagic);
+        }
+
+        // bytes, which are tolerated, are replaced by ones deku is able to read
+        let mut buf = *buf;
+
+        let spec_version = buf[SPEC_VERSION_INDEX];
+        if spec_version != 3 {
+            options.tolerate(ParseWarning::UnsupportedSpecVersion(spec_version), warnings)?;
+            buf[SPEC_VERSION_INDEX] = 3;
+        }
+
+        for (index, field) in [
+            (INTERNAL_COMPRESSION_INDEX, "internal compression"),
+            (TILE_COMPRESSION_INDEX, "tile compression"),
+        ] {
+            let value = buf[index];
+            if value > Compression::ZStd as u8 {
+                options.tolerate(ParseWarning::UnknownCompression { field, value }, warnings)?;
+                buf[index] = Compression::Unknown as u8;
+            }
+        }
+
+        let tile_type = buf[TILE_TYPE_INDEX];
+        if tile_type > TileType::Avif as u8 {
+            options.tolerate(ParseWarning::UnknownTileType(tile_type), warnings)?;
+        }
+
+        let (_, mut header) =
+            Self::read(buf.view_bits(), ()).map_err(|err| Error::InvalidHeader(err.to_string()))?;
+        header.spec_version = spec_version;
+
+        let root_directory_end = header
+            .root_directory_offset
+            .saturating_add(header.root_directory_length);
+        if root_directory_end > MAX_ROOT_DIRECTORY_END {
+            options.tolerate(
+                ParseWarning::RootDirectoryTooLarge(root_directory_end),
+                warnings,
+            )?;
+        }
+
+        Ok(header)
+    }
+
     /// Reads a header from a [`std::io::Read`] and returns it.
     ///
     /// # Arguments
     /// * `input` - Reader
     ///
     /// # Errors
-    /// Will return [`Err`] an I/O error occurred while reading from `input`.
+    /// Will return [`Err`] if an I/O error occurred while reading from `input`, the data does not
+    /// start with the `PMTiles` magic bytes ([`Error::InvalidMagic`]), the spec version is not 3
+    /// ([`Error::UnsupportedSpecVersion`]) or the header could not be decoded.
     ///
-    pub fn from_reader(input: &mut impl Read) -> std::io::Result<Self> {
+    pub fn from_reader(input: &mut impl Read) -> crate::Result<Self> {
         let mut buf = [0; HEADER_BYTES as usize];
         input.read_exact(&mut buf)?;
 
-        let (_, header) = Self::read(buf.to_vec().view_bits(), ())?;
-
-        Ok(header)
+        Self::parse(&buf)
     }
 
     /// Reads a header from a anything that can be turned into a byte slice (e.g. [`Vec<u8>`]).
@@ -134,7 +210,9 @@ impl Header {
     /// * `bytes` - Input bytes
     ///
     /// # Errors
-    /// Will return [`Err`] an I/O error occurred while reading from `input`.
+    /// Will return [`Err`] if an I/O error occurred while reading from `input`, the data does not
+    /// start with the `PMTiles` magic bytes ([`Error::InvalidMagic`]), the spec version is not 3
+    /// ([`Error::UnsupportedSpecVersion`]) or the header could not be decoded.
     ///
     /// # Example
     /// ```rust
@@ -143,7 +221,7 @@ impl Header {
     /// let header = Header::from_bytes(bytes).unwrap();
     /// ```
     ///
-    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> std::io::Result<Self> {
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> crate::Result<Self> {
         let mut reader = std::io::Cursor::new(bytes);
 
         Self::from_reader(&mut reader)
@@ -157,19 +235,45 @@ impl Header {
     /// * `input` - Reader
     ///
     /// # Errors
-    /// Will return [`Err`] an I/O error occurred while reading from `input`.
+    /// Will return [`Err`] if an I/O error occurred while reading from `input`, the data does not
+    /// start with the `PMTiles` magic bytes ([`Error::InvalidMagic`]), the spec version is not 3
+    /// ([`Error::UnsupportedSpecVersion`]) or the header could not be decoded.
     ///
     #[cfg(feature = "async")]
     pub async fn from_async_reader(
         input: &mut (impl AsyncRead + Unpin + Send),
-    ) -> std::io::Result<Self> {
+    ) -> crate::Result<Self> {
         let mut buf = [0; HEADER_BYTES as usize];
 
         input.read_exact(&mut buf).await?;
 
-        let (_, header) = Self::read(buf.to_vec().view_bits(), ())?;
+        Self::parse(&buf)
+    }
 
-        Ok(header)
+    /// Same as [`from_reader`](Self::from_reader), but tolerates deviations from the specification
+    /// according to `options` and records them in `warnings`.
+    pub(crate) fn from_reader_with_options(
+        input: &mut impl Read,
+        options: &ParseOptions,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> crate::Result<Self> {
+        let mut buf = [0; HEADER_BYTES as usize];
+        input.read_exact(&mut buf)?;
+
+        Self::parse_with_options(&buf, options, warnings)
+    }
+
+    /// Async version of [`from_reader_with_options`](Self::from_reader_with_options).
+    #[cfg(feature = "async")]
+    pub(crate) async fn from_async_reader_with_options(
+        input: &mut (impl AsyncRead + Unpin + Send),
+        options: &ParseOptions,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> crate::Result<Self> {
+        let mut buf = [0; HEADER_BYTES as usize];
+        input.read_exact(&mut buf).await?;
+
+        Self::parse_with_options(&buf, options, warnings)
     }
 
     /// Writes the header to a [`std::io::Write`].
@@ -180,9 +284,10 @@ impl Header {
     /// # Errors
     /// Will return [`Err`] if an I/O error occurred while writing to `output`.
     ///
-    pub fn to_writer(&self, output: &mut impl Write) -> std::io::Result<()> {
+    pub fn to_writer(&self, output: &mut impl Write) -> crate::Result<()> {
         let mut bit_vec = BitVec::with_capacity(8 * HEADER_BYTES as usize);
-        self.write(&mut bit_vec, ())?;
+        self.write(&mut bit_vec, ())
+            .map_err(|err| Error::InvalidHeader(err.to_string()))?;
         output.write_all(bit_vec.as_raw_slice())?;
 
         Ok(())
@@ -202,8 +307,10 @@ impl Header {
     pub async fn to_async_writer(
         &self,
         output: &mut (impl AsyncWrite + Unpin + Send),
-    ) -> std::io::Result<()> {
-        let vec = self.to_bytes()?;
+    ) -> crate::Result<()> {
+        let vec = self
+            .to_bytes()
+            .map_err(|err| Error::InvalidHeader(err.to_string()))?;
         output.write_all(&vec).await?;
         output.flush().await?;
 
@@ -249,6 +356,12 @@ impl Default for Header {
     }
 }
 
+/// Returns `true` if `buf` starts with the header of an archive of version 2 of the specification.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn is_v2_header(buf: &[u8]) -> bool {
+    buf.starts_with(V2_MAGIC) && buf.get(2..4) == Some(&2u16.to_le_bytes())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -281,6 +394,121 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_from_bytes_invalid() {
+        let mut bytes = include_bytes!("../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles")
+            [..HEADER_BYTES as usize]
+            .to_vec();
+
+        bytes[7] = 2;
+        assert!(matches!(
+            Header::from_bytes(&bytes),
+            Err(Error::UnsupportedSpecVersion(2))
+        ));
+
+        bytes[0] = b'X';
+        assert!(matches!(
+            Header::from_bytes(&bytes),
+            Err(Error::InvalidMagic)
+        ));
+
+        // archives of version 2 use a different header
+        bytes[..4].copy_from_slice(b"PM\x02\x00");
+        assert!(matches!(
+            Header::from_bytes(&bytes),
+            Err(Error::UnsupportedSpecVersion(2))
+        ));
+
+        assert!(matches!(
+            Header::from_bytes(&bytes[..10]),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_with_options() -> crate::Result<()> {
+        let mut buf = [0; HEADER_BYTES as usize];
+        buf.copy_from_slice(
+            &include_bytes!("../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles")
+                [..HEADER_BYTES as usize],
+        );
+        buf[SPEC_VERSION_INDEX] = 2;
+        buf[TILE_COMPRESSION_INDEX] = 42;
+        buf[TILE_TYPE_INDEX] = 43;
+
+        let mut warnings = Vec::new();
+        let header = Header::parse_with_options(&buf, &ParseOptions::lenient(), &mut warnings)?;
+        assert_eq!(header.spec_version, 2);
+        assert_eq!(header.tile_compression, Compression::Unknown);
+        assert_eq!(header.tile_type, TileType::Unknown);
+        assert_eq!(header.internal_compression, Compression::GZip);
+        assert_eq!(
+            warnings,
+            vec![
+                ParseWarning::UnsupportedSpecVersion(2),
+                ParseWarning::UnknownCompression {
+                    field: "tile compression",
+                    value: 42
+                },
+                ParseWarning::UnknownTileType(43),
+            ]
+        );
+
+        let options = ParseOptions {
+            allow_other_spec_versions: true,
+            ..ParseOptions::default()
+        };
+        assert!(matches!(
+            Header::parse_with_options(&buf, &options, &mut Vec::new()),
+            Err(Error::InvalidHeader(_))
+        ));
+
+        buf[TILE_COMPRESSION_INDEX] = Compression::None as u8;
+        let mut warnings = Vec::new();
+        Header::parse_with_options(&buf, &options, &mut warnings)?;
+        assert_eq!(warnings.len(), 2);
+
+        assert!(matches!(
+            Header::parse_with_options(&buf, &ParseOptions::strict(), &mut Vec::new()),
+            Err(Error::UnsupportedSpecVersion(2))
+        ));
+
+        buf[SPEC_VERSION_INDEX] = 3;
+        assert!(matches!(
+            Header::parse_with_options(&buf, &ParseOptions::strict(), &mut Vec::new()),
+            Err(Error::InvalidHeader(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_root_directory_too_large() -> crate::Result<()> {
+        let header = Header {
+            root_directory_offset: u64::from(HEADER_BYTES),
+            root_directory_length: MAX_ROOT_DIRECTORY_END,
+            ..Header::default()
+        };
+        let mut buf = [0; HEADER_BYTES as usize];
+        header.to_writer(&mut &mut buf[..])?;
+
+        let mut warnings = Vec::new();
+        Header::parse_with_options(&buf, &ParseOptions::default(), &mut warnings)?;
+        assert_eq!(
+            warnings,
+            vec![ParseWarning::RootDirectoryTooLarge(
+                MAX_ROOT_DIRECTORY_END + u64::from(HEADER_BYTES)
+            )]
+        );
+
+        assert!(matches!(
+            Header::parse_with_options(&buf, &ParseOptions::strict(), &mut Vec::new()),
+            Err(Error::Inconsistent(ParseWarning::RootDirectoryTooLarge(_)))
+        ));
+
+        Ok(())
+    }
+
     #[test]
     fn test_deku_read1() -> Result<(), DekuError> {
         let header_bytes = include_bytes!("../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
@@ -1,7 +1,13 @@
-use deku::prelude::*;
+use deku::{
+    bitvec::{BitSlice, Msb0},
+    prelude::*,
+};
 
 /// A tile type, which is supported in `PMTiles` archives.
-#[derive(DekuRead, DekuWrite, Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Tile types, which are not known to this crate (e.g. because they were added in a newer
+/// version of the specification), are read as [`TileType::Unknown`].
+#[derive(DekuWrite, Debug, Clone, Copy, PartialEq, Eq)]
 #[deku(type = "u8")]
 #[deku(endian = "endian", ctx = "endian: deku::ctx::Endian")]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -23,10 +29,171 @@ pub enum TileType {
     WebP,
 
     #[allow(missing_docs)]
-    AVIF,
+    Avif,
+}
+
+impl<'a> DekuRead<'a, deku::ctx::Endian> for TileType {
+    fn read(
+        input: &'a BitSlice<u8, Msb0>,
+        ctx: deku::ctx::Endian,
+    ) -> Result<(&'a BitSlice<u8, Msb0>, Self), DekuError> {
+        let (rest, value) = u8::read(input, ctx)?;
+
+        let tile_type = match value {
+            0x01 => Self::Mvt,
+            0x02 => Self::Png,
+            0x03 => Self::Jpeg,
+            0x04 => Self::WebP,
+            0x05 => Self::Avif,
+            _ => Self::Unknown,
+        };
+
+        Ok((rest, tile_type))
+    }
+}
+
+const PNG_MAGIC: &[u8] = &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+const JPEG_MAGIC: &[u8] = &[0xff, 0xd8, 0xff];
+
+/// Protobuf tag of the `layers` field (field number 3, wire type 2) of a vector tile.
+const MVT_LAYERS_TAG: u8 = 0x1a;
+
+/// Reads a protobuf varint from the start of `data` and returns it together with the remaining bytes.
+fn read_varint(data: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value: u64 = 0;
+
+    for (i, byte) in data.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+
+        if byte & 0x80 == 0 {
+            return Some((value, &data[i + 1..]));
+        }
+    }
+
+    None
+}
+
+/// Returns `true` if `data` is a sequence of length-delimited `layers` fields,
+/// which is what every non-empty vector tile consists of.
+fn looks_like_mvt(mut data: &[u8]) -> bool {
+    if data.is_empty() {
+        return false;
+    }
+
+    while let Some((&tag, rest)) = data.split_first() {
+        if tag != MVT_LAYERS_TAG {
+            return false;
+        }
+
+        let Some((length, rest)) = read_varint(rest) else {
+            return false;
+        };
+
+        let Some(rest) = usize::try_from(length)
+            .ok()
+            .and_then(|length| rest.get(length..))
+        else {
+            return false;
+        };
+
+        data = rest;
+    }
+
+    true
+}
+
+/// Returns `true` if `data` starts with an ISO BMFF `ftyp` box listing an AVIF brand.
+fn looks_like_avif(data: &[u8]) -> bool {
+    if data.get(4..8) != Some(b"ftyp") {
+        return false;
+    }
+
+    let box_size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let Some(brands) = data.get(8..box_size.min(data.len())) else {
+        return false;
+    };
+
+    // major brand, minor version and compatible brands
+    brands
+        .chunks_exact(4)
+        .enumerate()
+        .any(|(i, brand)| i != 1 && (brand == b"avif" || brand == b"avis"))
 }
 
 impl TileType {
+    /// Tries to detect the tile type of (uncompressed) tile data by its magic bytes.
+    ///
+    /// PNG, JPEG, WebP and AVIF images are detected by their signatures. Data is detected as
+    /// [`TileType::Mvt`] if it consists of length-delimited protobuf `layers` fields only.
+    /// Will return [`None`] if the tile type could not be detected (e.g. because the data is
+    /// still compressed).
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::TileType;
+    /// let png = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0, 0, 0, 0];
+    ///
+    /// assert_eq!(TileType::detect(&png), Some(TileType::Png));
+    /// assert_eq!(TileType::detect(&[1, 3, 3, 7]), None);
+    /// ```
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(PNG_MAGIC) {
+            return Some(Self::Png);
+        }
+
+        if data.starts_with(JPEG_MAGIC) {
+            return Some(Self::Jpeg);
+        }
+
+        if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+            return Some(Self::WebP);
+        }
+
+        if looks_like_avif(data) {
+            return Some(Self::Avif);
+        }
+
+        if looks_like_mvt(data) {
+            return Some(Self::Mvt);
+        }
+
+        None
+    }
+
+    /// Returns the tile type of a `format` as stored in the meta data of version 2 archives
+    /// (or the `tileFormat` of `COMTiles` archives).
+    #[cfg(any(feature = "v2", feature = "comtiles"))]
+    #[allow(clippy::redundant_pub_crate)]
+    pub(crate) fn from_format(format: &str) -> Self {
+        match format {
+            "pbf" | "mvt" => Self::Mvt,
+            "png" => Self::Png,
+            "jpg" | "jpeg" => Self::Jpeg,
+            "webp" => Self::WebP,
+            "avif" => Self::Avif,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Returns the format of this tile type as stored in the meta data of `COMTiles` archives.
+    #[cfg(feature = "comtiles")]
+    #[allow(clippy::redundant_pub_crate)]
+    pub(crate) const fn format(self) -> Option<&'static str> {
+        match self {
+            Self::Mvt => Some("pbf"),
+            Self::Png => Some("png"),
+            Self::Jpeg => Some("jpg"),
+            Self::WebP => Some("webp"),
+            Self::Avif => Some("avif"),
+            Self::Unknown => None,
+        }
+    }
+
+    /// Deprecated alias of [`TileType::Avif`].
+    #[deprecated(note = "use `TileType::Avif` instead")]
+    #[allow(non_upper_case_globals)]
+    pub const AVIF: Self = Self::Avif;
+
     /// Returns a option containing the value to which the
     /// `Content-Type` HTTP header should be set, when serving
     /// tiles from this type.
@@ -38,7 +205,7 @@ impl TileType {
             Self::Png => Some("image/png"),
             Self::Jpeg => Some("image/jpeg"),
             Self::WebP => Some("image/webp"),
-            Self::AVIF => Some("image/avif"),
+            Self::Avif => Some("image/avif"),
             Self::Unknown => None,
         }
     }
@@ -64,7 +231,46 @@ mod test {
 
         assert_eq!(TileType::WebP.http_content_type(), Some("image/webp"));
 
-        assert_eq!(TileType::AVIF.http_content_type(), Some("image/avif"));
+        assert_eq!(TileType::Avif.http_content_type(), Some("image/avif"));
+    }
+
+    #[test]
+    fn test_detect() -> crate::Result<()> {
+        let webp = b"RIFF\x24\x00\x00\x00WEBPVP8 ";
+        let avif = b"\x00\x00\x00\x1cftypavif\x00\x00\x00\x00avifmif1miaf";
+        let avif_compatible = b"\x00\x00\x00\x18ftypmif1\x00\x00\x00\x00avifmiaf";
+        let heic = b"\x00\x00\x00\x18ftypheic\x00\x00\x00\x00mif1heic";
+
+        assert_eq!(
+            TileType::detect(&[0xff, 0xd8, 0xff, 0xe0]),
+            Some(TileType::Jpeg)
+        );
+        assert_eq!(TileType::detect(webp), Some(TileType::WebP));
+        assert_eq!(TileType::detect(avif), Some(TileType::Avif));
+        assert_eq!(TileType::detect(avif_compatible), Some(TileType::Avif));
+        assert_eq!(TileType::detect(heic), None);
+        assert_eq!(
+            TileType::detect(&[0x1a, 0x02, 0x78, 0x02]),
+            Some(TileType::Mvt)
+        );
+        assert_eq!(TileType::detect(&[0x1a, 0x05, 0x78, 0x02]), None);
+        assert_eq!(TileType::detect(&[]), None);
+
+        let mut raster = crate::PMTiles::from_bytes(include_bytes!(
+            "../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles"
+        ))?;
+        let tile = raster.get_tile_by_id(0)?.unwrap_or_default();
+        assert_eq!(TileType::detect(&tile), Some(TileType::Png));
+
+        let mut vector = crate::PMTiles::from_bytes(include_bytes!(
+            "../../test/protomaps(vector)ODbL_firenze.pmtiles"
+        ))?;
+        let tile = vector.get_tile(0, 0, 0)?.unwrap_or_default();
+        assert_eq!(TileType::detect(&tile), None);
+        let tile = crate::util::decompress_all(vector.tile_compression, &tile)?;
+        assert_eq!(TileType::detect(&tile), Some(TileType::Mvt));
+
+        Ok(())
     }
 
     #[test]
@@ -92,7 +298,12 @@ mod test {
 
         let slice = BitSlice::from_slice(&[5]);
         let (_, tt4) = TileType::read(slice, deku::ctx::Endian::Little)?;
-        assert_eq!(tt4, TileType::AVIF);
+        assert_eq!(tt4, TileType::Avif);
+
+        let slice = BitSlice::from_slice(&[0x42]);
+        let (rest, tt) = TileType::read(slice, deku::ctx::Endian::Little)?;
+        assert_eq!(tt, TileType::Unknown);
+        assert_eq!(rest.len(), 0);
 
         Ok(())
     }
@@ -120,7 +331,7 @@ mod test {
         assert_eq!(output, bitvec!(0, 0, 0, 0, 0, 1, 0, 0));
 
         let mut output = BitVec::new();
-        TileType::AVIF.write(&mut output, deku::ctx::Endian::Little)?;
+        TileType::Avif.write(&mut output, deku::ctx::Endian::Little)?;
         assert_eq!(output, bitvec!(0, 0, 0, 0, 0, 1, 0, 1));
 
         Ok(())
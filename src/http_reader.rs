@@ -0,0 +1,519 @@
+use std::{
+    io::{Error, ErrorKind, Result, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll},
+};
+
+use futures::{future::BoxFuture, AsyncRead, AsyncSeek, FutureExt};
+use reqwest::{
+    header::{CONTENT_LENGTH, RANGE},
+    Client, IntoUrl, StatusCode, Url,
+};
+
+use crate::{PMTiles, PMTilesReader, DEFAULT_MIN_REQUEST_SIZE};
+
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsRecorder;
+
+/// A pending ranged GET request, resolving to the offset and the data of the requested range.
+type RangeRequest = BoxFuture<'static, Result<(u64, Vec<u8>)>>;
+
+/// A reader, which reads from a file served over HTTP(S) using ranged GET requests.
+///
+/// Only the ranges actually read are requested, so opening an archive and reading tiles does not
+/// download the whole file. Every read, which can not be served from the previously requested range,
+/// results in a new request for at least [`min_request_size`](Self::with_min_request_size) bytes.
+///
+/// Connections are reused between requests (and between readers sharing the same [`Client`]).
+///
+/// # Example
+/// ```rust,no_run
+/// # use pmtiles2::{HttpReader, PMTiles, PMTilesReader};
+/// # tokio_test::block_on(async {
+/// let client = reqwest::Client::new();
+/// let reader = HttpReader::new(client, "https://example.com/planet.pmtiles").await.unwrap();
+///
+/// // only reads the header and the root directory
+/// let mut pm_tiles = PMTilesReader::from_async_reader(reader).await.unwrap();
+/// let tile = pm_tiles.get_tile_async(0, 0, 0).await.unwrap();
+/// # })
+/// ```
+pub struct HttpReader {
+    client: Client,
+    url: Url,
+    size: u64,
+    position: u64,
+    min_request_size: u64,
+    buffer: Vec<u8>,
+    buffer_offset: u64,
+    // only accessed mutably using `Mutex::get_mut`, which makes the reader `Sync` without locking
+    request: Mutex<Option<RangeRequest>>,
+
+    #[cfg(feature = "metrics")]
+    metrics: Option<std::sync::Arc<dyn MetricsRecorder>>,
+}
+
+impl std::fmt::Debug for HttpReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpReader")
+            .field("url", &self.url.as_str())
+            .field("size", &self.size)
+            .field("position", &self.position)
+            .field("min_request_size", &self.min_request_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HttpReader {
+    /// Creates a new reader for the file at `url`.
+    ///
+    /// This issues a HEAD request to determine the size of the file.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if `url` is invalid or the HEAD request failed (e.g. because the file does not exist).
+    pub async fn new(client: Client, url: impl IntoUrl) -> crate::Result<Self> {
+        let url = url.into_url().map_err(to_io_error)?;
+
+        let response = client
+            .head(url.clone())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(to_io_error)?;
+
+        // `Response::content_length` is always `Some(0)` for HEAD requests, as there is no body
+        let size = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok()?.parse().ok())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing Content-Length header"))?;
+
+        Ok(Self::with_size(client, url, size))
+    }
+
+    /// Creates a new reader for the file at `url`, whose size is already known.
+    pub fn with_size(client: Client, url: Url, size: u64) -> Self {
+        Self {
+            client,
+            url,
+            size,
+            position: 0,
+            min_request_size: DEFAULT_MIN_REQUEST_SIZE,
+            buffer: Vec::new(),
+            buffer_offset: 0,
+            request: Mutex::new(None),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// Sets the minimum number of bytes requested at once (defaults to [`DEFAULT_MIN_REQUEST_SIZE`]).
+    #[must_use]
+    pub const fn with_min_request_size(mut self, min_request_size: u64) -> Self {
+        self.min_request_size = min_request_size;
+        self
+    }
+
+    /// Sets the metrics, into which all range requests and the number of bytes received are reported.
+    #[cfg(feature = "metrics")]
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: std::sync::Arc<dyn MetricsRecorder>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Returns the size of the file in bytes.
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the URL of the file.
+    pub const fn url(&self) -> &Url {
+        &self.url
+    }
+
+    fn request_mut(&mut self) -> &mut Option<RangeRequest> {
+        self.request
+            .get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    const fn buffered_range(&self) -> Range<u64> {
+        self.buffer_offset..self.buffer_offset + self.buffer.len() as u64
+    }
+}
+
+fn to_io_error(err: reqwest::Error) -> Error {
+    match err.status() {
+        Some(StatusCode::NOT_FOUND) => Error::new(ErrorKind::NotFound, err),
+        _ => Error::other(err),
+    }
+}
+
+async fn get_range(client: Client, url: Url, range: Range<u64>) -> Result<(u64, Vec<u8>)> {
+    let response = client
+        .get(url)
+        .header(RANGE, format!("bytes={}-{}", range.start, range.end - 1))
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(to_io_error)?;
+
+    // a server ignoring the range header responds with the whole file
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "Server does not support range requests",
+        ));
+    }
+
+    let bytes = response.bytes().await.map_err(to_io_error)?;
+
+    Ok((range.start, bytes.to_vec()))
+}
+
+impl AsyncRead for HttpReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        if buf.is_empty() || self.position >= self.size {
+            return Poll::Ready(Ok(0));
+        }
+
+        if !self.buffered_range().contains(&self.position) {
+            let start = self.position;
+            let end = self
+                .size
+                .min(start + self.min_request_size.max(buf.len() as u64));
+            let client = self.client.clone();
+            let url = self.url.clone();
+
+            let request = get_range(client, url, start..end);
+            #[cfg(feature = "metrics")]
+            let request = crate::metrics::record_request(self.metrics.clone(), request);
+
+            let request = self.request_mut().get_or_insert_with(|| request.boxed());
+
+            let (offset, buffer) = match request.poll_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => {
+                    *self.request_mut() = None;
+                    result?
+                }
+            };
+
+            self.buffer = buffer;
+            self.buffer_offset = offset;
+
+            if !self.buffered_range().contains(&self.position) {
+                return Poll::Ready(Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "Server returned less data than requested",
+                )));
+            }
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let start = (self.position - self.buffer_offset) as usize;
+        let len = buf.len().min(self.buffer.len() - start);
+
+        buf[..len].copy_from_slice(&self.buffer[start..start + len]);
+        self.position += len as u64;
+
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncSeek for HttpReader {
+    fn poll_seek(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<Result<u64>> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        let Some(position) = position else {
+            return Poll::Ready(Err(Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )));
+        };
+
+        // a pending request is for the previous position
+        *self.request_mut() = None;
+        self.position = position;
+
+        Poll::Ready(Ok(position))
+    }
+}
+
+impl PMTiles<HttpReader> {
+    /// Reads a `PMTiles` archive served over HTTP(S).
+    ///
+    /// Only the header, meta data and directories are requested. Tiles are requested on demand
+    /// using ranged GET requests. Use [`HttpReader::new`] to share a [`Client`] between archives.
+    ///
+    /// # Arguments
+    /// * `url` - URL of the archive
+    ///
+    /// # Errors
+    /// Will return [`Err`] if `url` is invalid, a request failed, the server does not support range
+    /// requests, the file was no valid `PMTiles` archive or the internal compression of the archive
+    /// is set to "Unknown".
+    pub async fn from_url(url: impl IntoUrl) -> crate::Result<Self> {
+        let reader = HttpReader::new(Client::new(), url).await?;
+
+        Self::from_async_reader(reader).await
+    }
+}
+
+impl PMTilesReader<HttpReader> {
+    /// Opens a `PMTiles` archive served over HTTP(S) by requesting its header and root directory.
+    ///
+    /// Leaf directories, meta data and tiles are requested on demand using ranged GET requests, which
+    /// makes this the preferred way to read single tiles from large remote archives.
+    ///
+    /// # Arguments
+    /// * `url` - URL of the archive
+    ///
+    /// # Errors
+    /// See [`PMTiles::from_url`] for details on possible errors.
+    pub async fn from_url(url: impl IntoUrl) -> crate::Result<Self> {
+        let reader = HttpReader::new(Client::new(), url).await?;
+
+        Self::from_async_reader(reader).await
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use futures::{AsyncReadExt, AsyncSeekExt};
+
+    use super::*;
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    /// Handles all requests of a (keep-alive) connection, supporting only HEAD and ranged GET requests.
+    fn handle_connection(stream: TcpStream, data: &[u8]) -> Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut stream = stream;
+
+        loop {
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line)? == 0 {
+                return Ok(());
+            }
+
+            let mut range = None;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line)?;
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                    let (start, end) = value.split_once('-').unwrap_or_default();
+                    range = Some((
+                        start.parse::<usize>().unwrap_or_default(),
+                        end.parse::<usize>().unwrap_or_default(),
+                    ));
+                }
+            }
+
+            if request_line.contains("/missing") {
+                write!(
+                    stream,
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"
+                )?;
+            } else if request_line.starts_with("GET /unavailable") {
+                write!(
+                    stream,
+                    "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n"
+                )?;
+            } else if request_line.starts_with("HEAD") {
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                    data.len()
+                )?;
+            } else if let Some((start, end)) = range {
+                let body = &data[start..=end.min(data.len() - 1)];
+                write!(
+                    stream,
+                    "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {start}-{}/{}\r\n\r\n",
+                    body.len(),
+                    start + body.len() - 1,
+                    data.len()
+                )?;
+                stream.write_all(body)?;
+            } else {
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                    data.len()
+                )?;
+                stream.write_all(data)?;
+            }
+        }
+    }
+
+    /// Starts a minimal HTTP server serving `PM_TILES_BYTES` and returns its base URL.
+    fn serve() -> Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                thread::spawn(move || handle_connection(stream, PM_TILES_BYTES));
+            }
+        });
+
+        Ok(format!("http://{addr}"))
+    }
+
+    #[test]
+    fn test_read_and_seek() -> Result<()> {
+        let base_url = serve()?;
+
+        tokio_test::block_on(async {
+            let reader =
+                HttpReader::new(Client::new(), format!("{base_url}/stamen.pmtiles")).await?;
+            let mut reader = reader.with_min_request_size(4);
+
+            assert_eq!(reader.size(), PM_TILES_BYTES.len() as u64);
+
+            let mut buf = [0u8; 7];
+            reader.read_exact(&mut buf).await?;
+            assert_eq!(buf, PM_TILES_BYTES[..7]);
+
+            reader.seek(SeekFrom::End(-10)).await?;
+            let mut rest = Vec::new();
+            reader.read_to_end(&mut rest).await?;
+            assert_eq!(rest, PM_TILES_BYTES[PM_TILES_BYTES.len() - 10..]);
+
+            reader.seek(SeekFrom::Start(0)).await?;
+            assert!(reader.seek(SeekFrom::Current(-1)).await.is_err());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn test_metrics() -> Result<()> {
+        let base_url = serve()?;
+        let metrics = std::sync::Arc::new(crate::metrics::Metrics::new());
+
+        tokio_test::block_on(async {
+            let reader =
+                HttpReader::new(Client::new(), format!("{base_url}/stamen.pmtiles")).await?;
+            let mut reader = reader
+                .with_min_request_size(4)
+                .with_metrics(metrics.clone());
+
+            let mut buf = [0u8; 7];
+            reader.read_exact(&mut buf).await?;
+
+            reader.seek(SeekFrom::End(-10)).await?;
+            reader.read_to_end(&mut Vec::new()).await?;
+
+            Ok::<_, crate::Error>(())
+        })?;
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.read_requests, 2);
+        assert_eq!(snapshot.bytes_read, 17);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_not_found() -> Result<()> {
+        let base_url = serve()?;
+
+        tokio_test::block_on(async {
+            let result = HttpReader::new(Client::new(), format!("{base_url}/missing")).await;
+
+            assert!(
+                matches!(result, Err(crate::Error::Io(err)) if err.kind() == ErrorKind::NotFound)
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_retry_status() -> Result<()> {
+        use std::time::Duration;
+
+        use crate::{RetryPolicy, RetryReader};
+
+        let base_url = serve()?;
+
+        tokio_test::block_on(async {
+            let mut reader =
+                HttpReader::new(Client::new(), format!("{base_url}/unavailable")).await?;
+            let policy = RetryPolicy {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(1),
+                ..RetryPolicy::default()
+            };
+
+            let err = reader.read(&mut [0; 4]).await.unwrap_err();
+            assert!(policy.should_retry(&err));
+            assert!(!RetryPolicy {
+                retry_server_errors: false,
+                ..policy
+            }
+            .should_retry(&err));
+
+            let mut reader = RetryReader::new(reader, policy);
+            assert!(reader.read(&mut [0; 4]).await.is_err());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_from_url() -> Result<()> {
+        let base_url = serve()?;
+
+        tokio_test::block_on(async {
+            let url = format!("{base_url}/stamen.pmtiles");
+            let mut expected = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+            let mut pm_tiles = PMTiles::from_url(&url).await?;
+            assert_eq!(pm_tiles.num_tiles(), expected.num_tiles());
+            assert_eq!(
+                pm_tiles.get_tile_async(1, 1, 1).await?,
+                expected.get_tile(1, 1, 1)?
+            );
+
+            let mut reader = PMTilesReader::from_url(&url).await?;
+            assert_eq!(
+                reader.get_tile_async(1, 1, 1).await?,
+                expected.get_tile(1, 1, 1)?
+            );
+
+            Ok(())
+        })
+    }
+}
@@ -17,7 +17,7 @@
 //! use std::fs::File;
 //! use pmtiles2::PMTiles;
 //!
-//! fn main () -> std::io::Result<()> {
+//! fn main () -> pmtiles2::Result<()> {
 //!     let file_path = "./test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles";
 //!     
 //!     let mut file = File::open(file_path)?; // file implements std::io::Read
@@ -32,7 +32,7 @@
 //! use std::fs::File;
 //! use pmtiles2::{PMTiles, Compression, TileType};
 //!
-//! fn main () -> std::io::Result<()> {
+//! fn main () -> pmtiles2::Result<()> {
 //!     // create temp directory
 //!     let dir = temp_dir::TempDir::new()?;
 //!     let file_path = dir.path().join("foo.pmtiles");
@@ -69,18 +69,151 @@
 #![allow(clippy::multiple_crate_versions)]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
+mod bbox;
+mod builder;
+#[cfg(any(feature = "tar", feature = "zip"))]
+mod bundle;
+mod cancellation;
+#[cfg(feature = "comtiles")]
+mod comtiles;
+mod coverage;
+mod dedupe_hash;
+mod diff;
 mod directory;
-#[allow(clippy::ignored_unit_patterns)]
+mod embedded;
+mod error;
+mod extract;
+mod footprint;
+#[cfg(feature = "geo")]
+mod geo;
+#[allow(clippy::ignored_unit_patterns, clippy::manual_div_ceil)]
 mod header;
+#[cfg(feature = "reqwest")]
+mod http_reader;
+mod merge;
+/// Counters describing how archives are accessed and an exporter to the Prometheus text format.
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "object-store")]
+mod object_store_reader;
+mod overzoom;
+mod parse_options;
 mod pmtiles;
+mod progress;
+#[cfg(feature = "proptest")]
+mod proptest_impl;
+mod read_limits;
+mod reader;
+mod retry;
+mod section_order;
+/// An [`axum`] router serving the tiles of an archive (requires the `axum` feature).
+#[cfg(feature = "axum")]
+pub mod server;
+mod stats;
+mod stream_writer;
+mod summary;
+mod tile_cache;
+mod tile_dir;
 mod tile_manager;
+mod tile_transform;
+mod transcode;
+#[cfg(feature = "v2")]
+mod v2;
+mod validate;
+#[cfg(feature = "mvt")]
+mod vector_layers;
+mod write_options;
+#[cfg(feature = "zstd-dictionary")]
+mod zstd_dictionary;
 
 /// Utilities for reading and writing `PMTiles` archives.
 pub mod util;
 
 pub use self::pmtiles::PMTiles;
+pub use bbox::BBox;
+pub use builder::PMTilesBuilder;
+pub use cancellation::CancellationToken;
+pub use dedupe_hash::DedupeHash;
+pub use diff::{ArchiveDiff, DiffMode};
 pub use directory::{Directory, Entry};
+pub use error::{Error, Result};
+pub use footprint::FootprintOptions;
 pub use header::{Compression, Header, TileType};
+#[cfg(feature = "reqwest")]
+pub use http_reader::HttpReader;
+pub use merge::ConflictStrategy;
+#[cfg(feature = "object-store")]
+pub use object_store_reader::ObjectStoreReader;
+pub use overzoom::OverzoomedTile;
+pub use parse_options::{ParseOptions, ParseWarning};
+pub use progress::{ReadProgress, WritePhase, WriteProgress};
+pub use read_limits::ReadLimits;
+pub use reader::PMTilesReader;
+pub use retry::{RetryPolicy, RetryReader};
+pub use section_order::{Section, SectionOrder};
+pub use stats::{TileStats, ZoomStats};
+pub use stream_writer::PMTilesStreamWriter;
+pub use summary::ArchiveSummary;
+pub use tile_cache::TileCache;
+pub use tile_dir::{ExtractOptions, TileScheme};
+pub use tile_manager::{FinishResult, TileManager};
+pub use tile_transform::TileTransform;
+pub use validate::{ValidationReport, Violation};
+pub use write_options::WriteOptions;
 
 /// The recommended MIME Type for a `PMTiles` archive
 pub const MIME_TYPE: &str = "application/vnd.pmtiles";
+
+/// Default minimum number of bytes requested at once by remote readers (16 KiB).
+///
+/// The `PMTiles` specification guarantees, that the header and root directory fit into the first 16 KiB
+/// of an archive, so opening an archive requires only a single request for those.
+#[cfg(any(feature = "object-store", feature = "reqwest"))]
+pub const DEFAULT_MIN_REQUEST_SIZE: u64 = 16_384;
+
+#[cfg(test)]
+mod test {
+    use std::{fs::File, io::Cursor};
+
+    use super::*;
+
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    // compile-time checks, that archives can be shared between threads (e.g. behind an `Arc`)
+    const _: () = {
+        assert_send_sync::<PMTiles<File>>();
+        assert_send_sync::<PMTiles<Cursor<Vec<u8>>>>();
+        assert_send_sync::<PMTiles<util::ReadAtCursor<std::sync::Arc<File>>>>();
+        assert_send_sync::<PMTilesReader<File>>();
+        assert_send_sync::<PMTilesStreamWriter<File>>();
+        assert_send_sync::<TileCache<File>>();
+        assert_send_sync::<TileManager<File>>();
+        assert_send_sync::<RetryReader<File>>();
+        assert_send_sync::<util::OffsetIo<File>>();
+        assert_send_sync::<PMTiles<util::SharedReader<File>>>();
+        assert_send_sync::<PMTiles<util::ReaderPool<File>>>();
+        assert_send_sync::<Directory>();
+        assert_send_sync::<Header>();
+        assert_send_sync::<ArchiveSummary>();
+        assert_send_sync::<CancellationToken>();
+        assert_send_sync::<Error>();
+        assert_send_sync::<ParseOptions>();
+    };
+
+    #[cfg(feature = "async")]
+    const _: () = {
+        assert_send_sync::<PMTiles<futures::io::Cursor<Vec<u8>>>>();
+        assert_send_sync::<RetryReader<futures::io::Cursor<Vec<u8>>>>();
+    };
+
+    #[cfg(feature = "metrics")]
+    const _: () = assert_send_sync::<metrics::MeteredReader<File>>();
+
+    #[cfg(feature = "object-store")]
+    const _: () = assert_send_sync::<PMTiles<ObjectStoreReader>>();
+
+    #[cfg(feature = "reqwest")]
+    const _: () = assert_send_sync::<PMTiles<HttpReader>>();
+}
@@ -0,0 +1,276 @@
+use std::io::{Cursor, Read, Seek};
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt, TryStreamExt};
+use serde_json::{Map as JSONMap, Value as JSONValue};
+
+use crate::{Error, PMTiles, Result};
+
+/// Determines what happens, if multiple archives passed to [`PMTiles::merge`] include a tile with the same id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Keep the tile (and meta data entries) of the archive, which comes first
+    #[default]
+    KeepFirst,
+
+    /// Keep the tile (and meta data entries) of the archive, which comes last
+    KeepLast,
+
+    /// Fail with [`Error::TileConflict`]
+    Error,
+}
+
+/// Merges the meta data of `other` into `meta_data`.
+///
+/// The `vector_layers` of both are combined (layers are identified by their `id`). For all other
+/// keys, which exist in both, `strategy` decides which value is kept.
+fn merge_meta_data(
+    meta_data: &mut JSONMap<String, JSONValue>,
+    other: JSONMap<String, JSONValue>,
+    strategy: ConflictStrategy,
+) {
+    for (key, value) in other {
+        match (meta_data.get_mut(&key), value) {
+            (None, value) => {
+                meta_data.insert(key, value);
+            }
+            (Some(JSONValue::Array(layers)), JSONValue::Array(other_layers))
+                if key == "vector_layers" =>
+            {
+                for layer in other_layers {
+                    let id = layer.get("id");
+                    match layers.iter_mut().find(|l| l.get("id") == id) {
+                        None => layers.push(layer),
+                        Some(existing) if strategy == ConflictStrategy::KeepLast => {
+                            *existing = layer;
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+            (Some(existing), value) => {
+                if strategy == ConflictStrategy::KeepLast {
+                    *existing = value;
+                }
+            }
+        }
+    }
+}
+
+#[duplicate_item(
+    fn_name       cfg_async_filter       async   add_await(code) pin(code)              RTraits                                                  CursorType                      from_header         tiles         try_next;
+    [merge]       [cfg(all())]           []      [code]          [code]                 [Read + Seek]                                            [Cursor<&[u8]>]                 [from_header]       [tiles]       [next().transpose];
+    [merge_async] [cfg(feature="async")] [async] [code.await]    [std::pin::pin!(code)] [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [futures::io::Cursor<&[u8]>]    [from_header_async] [tiles_async] [try_next];
+)]
+#[cfg_async_filter]
+impl PMTiles<CursorType> {
+    /// Merges multiple archives into a new archive, which holds all tiles in memory.
+    ///
+    /// The tile type, tile compression and internal compression are taken from the first archive.
+    /// The bounds and zoom range of the new archive span those of all archives and the center is
+    /// set to the center of the bounds. Tiles with the same content are stored only once.
+    ///
+    /// The meta data of all archives is merged as well: `vector_layers` are combined (layers are
+    /// identified by their `id`) and for all other keys `strategy` decides which value is kept.
+    ///
+    /// # Arguments
+    /// * `archives` - Archives to merge
+    /// * `strategy` - What to do if multiple archives include a tile with the same id
+    ///
+    /// # Errors
+    /// Will return [`Err`] if...
+    /// - the archives have different tile types ([`Error::MixedTileTypes`]) or tile compressions
+    ///   ([`Error::TileCompressionMismatch`])
+    /// - multiple archives include a tile with the same id and `strategy` is
+    ///   [`ConflictStrategy::Error`] ([`Error::TileConflict`])
+    /// - there was an error while reading tile data
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{Compression, ConflictStrategy, PMTiles, TileType};
+    /// let mut a = PMTiles::new(TileType::Png, Compression::None);
+    /// a.add_tile(1, vec![1]).unwrap();
+    ///
+    /// let mut b = PMTiles::new(TileType::Png, Compression::None);
+    /// b.add_tile(2, vec![2]).unwrap();
+    ///
+    /// let merged = PMTiles::merge([a, b], ConflictStrategy::Error).unwrap();
+    /// assert_eq!(merged.num_tiles(), 2);
+    /// ```
+    pub async fn fn_name<R: RTraits>(
+        archives: impl IntoIterator<Item = PMTiles<R>>,
+        strategy: ConflictStrategy,
+    ) -> Result<Self> {
+        let mut merged: Option<Self> = None;
+
+        for mut archive in archives {
+            let result = match &mut merged {
+                None => merged.insert(Self::from_header(&archive.header())),
+                Some(result) => {
+                    if archive.tile_type != result.tile_type {
+                        return Err(Error::MixedTileTypes {
+                            expected: result.tile_type,
+                            found: archive.tile_type,
+                        });
+                    }
+
+                    if archive.tile_compression != result.tile_compression {
+                        return Err(Error::TileCompressionMismatch(result.tile_compression));
+                    }
+
+                    result.min_zoom = result.min_zoom.min(archive.min_zoom);
+                    result.max_zoom = result.max_zoom.max(archive.max_zoom);
+                    result.min_longitude = result.min_longitude.min(archive.min_longitude);
+                    result.min_latitude = result.min_latitude.min(archive.min_latitude);
+                    result.max_longitude = result.max_longitude.max(archive.max_longitude);
+                    result.max_latitude = result.max_latitude.max(archive.max_latitude);
+                    result.center_longitude =
+                        f64::midpoint(result.min_longitude, result.max_longitude);
+                    result.center_latitude =
+                        f64::midpoint(result.min_latitude, result.max_latitude);
+                    result.center_zoom = result.center_zoom.clamp(result.min_zoom, result.max_zoom);
+
+                    result
+                }
+            };
+
+            merge_meta_data(
+                &mut result.meta_data,
+                std::mem::take(&mut archive.meta_data),
+                strategy,
+            );
+
+            let mut tiles = pin([archive.tiles()]);
+            while let Some((tile_id, data)) = add_await([tiles.try_next()])? {
+                if result.contains_tile(tile_id) {
+                    match strategy {
+                        ConflictStrategy::KeepFirst => continue,
+                        ConflictStrategy::KeepLast => {}
+                        ConflictStrategy::Error => return Err(Error::TileConflict(tile_id)),
+                    }
+                }
+
+                result.add_tile(tile_id, data)?;
+            }
+        }
+
+        Ok(merged.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Compression, TileType};
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    fn archive(tiles: &[(u64, u8)], min_longitude: f64) -> Result<PMTiles<Cursor<&[u8]>>> {
+        let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+        pm_tiles.min_longitude = min_longitude;
+        pm_tiles.max_longitude = min_longitude + 10.0;
+        for (tile_id, data) in tiles {
+            pm_tiles.add_tile(*tile_id, vec![*data])?;
+        }
+        Ok(pm_tiles)
+    }
+
+    #[test]
+    fn test_merge() -> Result<()> {
+        let mut a = archive(&[(1, 1), (2, 2)], 0.0)?;
+        a.meta_data = json!({ "name": "a", "vector_layers": [{ "id": "roads" }] })
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        let mut b = archive(&[(2, 3), (3, 1)], 20.0)?;
+        b.max_zoom = 3;
+        b.meta_data = json!({ "name": "b", "vector_layers": [{ "id": "water" }] })
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+
+        let mut merged = PMTiles::merge([a, b], ConflictStrategy::KeepFirst)?;
+
+        assert_eq!(merged.tile_ids_sorted(), vec![1, 2, 3]);
+        assert_eq!(merged.get_tile_by_id(2)?, Some(vec![2]));
+        assert_eq!(merged.stats().num_unique_tiles, 2);
+        assert_eq!(merged.max_zoom, 3);
+        assert!((merged.min_longitude - 0.0).abs() < f64::EPSILON);
+        assert!((merged.max_longitude - 30.0).abs() < f64::EPSILON);
+        assert!((merged.center_longitude - 15.0).abs() < f64::EPSILON);
+        assert_eq!(merged.meta_data["name"], json!("a"));
+        assert_eq!(
+            merged.meta_data["vector_layers"],
+            json!([{ "id": "roads" }, { "id": "water" }])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_strategies() -> Result<()> {
+        let archives = || Ok::<_, Error>([archive(&[(2, 2)], 0.0)?, archive(&[(2, 3)], 0.0)?]);
+
+        let mut merged = PMTiles::merge(archives()?, ConflictStrategy::KeepLast)?;
+        assert_eq!(merged.get_tile_by_id(2)?, Some(vec![3]));
+
+        let result = PMTiles::merge(archives()?, ConflictStrategy::Error);
+        assert!(matches!(result, Err(Error::TileConflict(2))));
+
+        let mut jpeg = archive(&[(1, 1)], 0.0)?;
+        jpeg.tile_type = TileType::Jpeg;
+        let result = PMTiles::merge([archive(&[], 0.0)?, jpeg], ConflictStrategy::Error);
+        assert!(matches!(result, Err(Error::MixedTileTypes { .. })));
+
+        assert_eq!(
+            PMTiles::merge(
+                Vec::<PMTiles<Cursor<&[u8]>>>::new(),
+                ConflictStrategy::Error
+            )?
+            .num_tiles(),
+            0
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_from_reader() -> Result<()> {
+        let mut source = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        let mut low = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        low.remove_tiles_in_range(21..);
+        let mut high = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        high.remove_tiles_in_range(..21);
+
+        let mut merged = PMTiles::merge([low, high], ConflictStrategy::Error)?;
+        assert_eq!(merged.num_tiles(), source.num_tiles());
+        assert_eq!(merged.stats().unique_bytes, source.stats().unique_bytes);
+
+        for tile_id in source.tile_ids_sorted() {
+            assert_eq!(
+                merged.get_tile_by_id(tile_id)?,
+                source.get_tile_by_id(tile_id)?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_merge_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let a = PMTiles::from_async_reader(futures::io::Cursor::new(PM_TILES_BYTES)).await?;
+            let b = PMTiles::from_async_reader(futures::io::Cursor::new(PM_TILES_BYTES)).await?;
+
+            let merged = PMTiles::merge_async([a, b], ConflictStrategy::KeepFirst).await?;
+            assert_eq!(merged.num_tiles(), 85);
+
+            Ok(())
+        })
+    }
+}
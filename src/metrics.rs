@@ -0,0 +1,479 @@
+use std::{
+    fmt::Write as _,
+    io::{Read, Result, Seek, SeekFrom},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncSeek};
+
+/// A receiver of metrics describing how an archive is accessed.
+///
+/// Readers (e.g. [`MeteredReader`] or [`HttpReader`](crate::HttpReader)) and caches
+/// (e.g. [`TileCache`](crate::TileCache) or [`PMTilesReader`](crate::PMTilesReader)) report into
+/// an implementation of this trait, if one was configured using their `with_metrics` method.
+///
+/// All methods do nothing by default, so an implementation only has to handle the metrics
+/// it is interested in. Use [`Metrics`] for a ready-to-use implementation based on atomic counters,
+/// or implement this trait to forward the metrics to another metrics library.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{metrics::MetricsRecorder, PMTiles, TileCache};
+/// # use std::sync::{atomic::{AtomicU64, Ordering}, Arc};
+/// #[derive(Debug, Default)]
+/// struct CacheHits(AtomicU64);
+///
+/// impl MetricsRecorder for CacheHits {
+///     fn record_cache_hit(&self) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+/// let hits = Arc::new(CacheHits::default());
+///
+/// let mut cache = TileCache::new(PMTiles::from_bytes(bytes).unwrap(), 1024 * 1024)
+///     .with_metrics(hits.clone());
+///
+/// cache.get_tile(0, 0, 0).unwrap();
+/// cache.get_tile(0, 0, 0).unwrap();
+///
+/// assert_eq!(hits.0.load(Ordering::Relaxed), 1);
+/// ```
+pub trait MetricsRecorder: std::fmt::Debug + Send + Sync {
+    /// Records that a tile was read (or served from a cache).
+    fn record_tile_read(&self) {}
+
+    /// Records that a tile or directory was served from a cache.
+    fn record_cache_hit(&self) {}
+
+    /// Records that a tile or directory was not found in a cache.
+    fn record_cache_miss(&self) {}
+
+    /// Records a new (range) request to the underlying storage.
+    fn record_read_request(&self) {}
+
+    /// Records that `bytes` bytes were read from the underlying storage, which took `duration`.
+    fn record_bytes_read(&self, bytes: u64, duration: Duration) {
+        let _ = (bytes, duration);
+    }
+}
+
+/// A collection of counters describing how an archive is accessed.
+///
+/// All counters can be updated through a shared reference, so a single instance can be
+/// wrapped in an [`Arc`] and shared between readers, caches and request handlers.
+///
+/// Use [`snapshot`](Self::snapshot) to obtain the current values and
+/// [`MetricsSnapshot::to_prometheus`] to render them in the Prometheus text exposition format.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    tile_reads: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    bytes_read: AtomicU64,
+    read_requests: AtomicU64,
+    timed_reads: AtomicU64,
+    read_duration_nanos: AtomicU64,
+}
+
+impl Metrics {
+    /// Creates a new instance with all counters set to `0`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that a tile was read.
+    pub fn record_tile_read(&self) {
+        self.tile_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a tile or directory was served from a cache.
+    pub fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a tile or directory was not found in a cache.
+    pub fn record_cache_miss(&self) {
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a new (range) request to the underlying storage.
+    pub fn record_read_request(&self) {
+        self.read_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that `bytes` bytes were read from the underlying storage, which took `duration`.
+    pub fn record_bytes_read(&self, bytes: u64, duration: Duration) {
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+        self.timed_reads.fetch_add(1, Ordering::Relaxed);
+        self.read_duration_nanos.fetch_add(
+            u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Returns the current values of all counters.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            tile_reads: self.tile_reads.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            read_requests: self.read_requests.load(Ordering::Relaxed),
+            timed_reads: self.timed_reads.load(Ordering::Relaxed),
+            read_duration: Duration::from_nanos(self.read_duration_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl MetricsRecorder for Metrics {
+    fn record_tile_read(&self) {
+        Self::record_tile_read(self);
+    }
+
+    fn record_cache_hit(&self) {
+        Self::record_cache_hit(self);
+    }
+
+    fn record_cache_miss(&self) {
+        Self::record_cache_miss(self);
+    }
+
+    fn record_read_request(&self) {
+        Self::record_read_request(self);
+    }
+
+    fn record_bytes_read(&self, bytes: u64, duration: Duration) {
+        Self::record_bytes_read(self, bytes, duration);
+    }
+}
+
+/// Records `request` as a new read request in `metrics` and the received bytes, once it completed successfully.
+#[cfg(any(feature = "object-store", feature = "reqwest"))]
+pub(crate) async fn record_request(
+    metrics: Option<Arc<dyn MetricsRecorder>>,
+    request: impl std::future::Future<Output = Result<(u64, Vec<u8>)>>,
+) -> Result<(u64, Vec<u8>)> {
+    let start = Instant::now();
+    let result = request.await;
+
+    if let (Some(metrics), Ok((_, data))) = (&metrics, &result) {
+        metrics.record_read_request();
+        metrics.record_bytes_read(data.len() as u64, start.elapsed());
+    }
+
+    result
+}
+
+/// The values of all counters of [`Metrics`] at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetricsSnapshot {
+    /// Number of tiles read
+    pub tile_reads: u64,
+
+    /// Number of cache hits
+    pub cache_hits: u64,
+
+    /// Number of cache misses
+    pub cache_misses: u64,
+
+    /// Number of bytes read from the underlying storage
+    pub bytes_read: u64,
+
+    /// Number of (range) requests to the underlying storage
+    pub read_requests: u64,
+
+    /// Number of reads from the underlying storage, whose duration is included in
+    /// [`read_duration`](Self::read_duration) (a single request may be read in multiple reads)
+    pub timed_reads: u64,
+
+    /// Total time spent reading from the underlying storage
+    pub read_duration: Duration,
+}
+
+impl MetricsSnapshot {
+    /// Renders all values in the [Prometheus text exposition format](https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format).
+    ///
+    /// # Arguments
+    /// * `prefix` - Prefix of all metric names (e.g. `pmtiles`)
+    /// * `labels` - Labels which are added to all samples (e.g. `[("archive", "planet")]`)
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::metrics::Metrics;
+    /// let metrics = Metrics::new();
+    /// metrics.record_tile_read();
+    ///
+    /// let text = metrics.snapshot().to_prometheus("pmtiles", &[("archive", "planet")]);
+    ///
+    /// assert!(text.contains("pmtiles_tile_reads_total{archive=\"planet\"} 1\n"));
+    /// ```
+    pub fn to_prometheus(&self, prefix: &str, labels: &[(&str, &str)]) -> String {
+        let labels = format_labels(labels);
+        let mut output = String::new();
+
+        let counters = [
+            ("tile_reads_total", "Number of tiles read.", self.tile_reads),
+            ("cache_hits_total", "Number of cache hits.", self.cache_hits),
+            (
+                "cache_misses_total",
+                "Number of cache misses.",
+                self.cache_misses,
+            ),
+            (
+                "bytes_read_total",
+                "Number of bytes read from the underlying storage.",
+                self.bytes_read,
+            ),
+            (
+                "read_requests_total",
+                "Number of (range) requests to the underlying storage.",
+                self.read_requests,
+            ),
+        ];
+
+        for (name, help, value) in counters {
+            let _ = writeln!(output, "# HELP {prefix}_{name} {help}");
+            let _ = writeln!(output, "# TYPE {prefix}_{name} counter");
+            let _ = writeln!(output, "{prefix}_{name}{labels} {value}");
+        }
+
+        let name = "read_duration_seconds";
+        let _ = writeln!(
+            output,
+            "# HELP {prefix}_{name} Time spent reading from the underlying storage."
+        );
+        let _ = writeln!(output, "# TYPE {prefix}_{name} summary");
+        let _ = writeln!(
+            output,
+            "{prefix}_{name}_sum{labels} {}",
+            self.read_duration.as_secs_f64()
+        );
+        let _ = writeln!(output, "{prefix}_{name}_count{labels} {}", self.timed_reads);
+
+        output
+    }
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+
+    let labels = labels
+        .iter()
+        .map(|(key, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{key}=\"{value}\"")
+        })
+        .collect::<Vec<_>>();
+
+    format!("{{{}}}", labels.join(","))
+}
+
+/// A reader, which records all reads from the wrapped reader in [`Metrics`] (or any other [`MetricsRecorder`]).
+///
+/// Every read following a seek (and the first read) is recorded as a new read request.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{metrics::{Metrics, MeteredReader}, PMTiles};
+/// # use std::sync::Arc;
+/// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+/// let metrics = Arc::new(Metrics::new());
+///
+/// let reader = MeteredReader::new(std::io::Cursor::new(bytes), metrics.clone());
+/// let mut pm_tiles = PMTiles::from_reader(reader).unwrap();
+/// pm_tiles.get_tile(0, 0, 0).unwrap();
+///
+/// assert!(metrics.snapshot().read_requests > 0);
+/// ```
+#[derive(Debug)]
+pub struct MeteredReader<R, M: ?Sized = Metrics> {
+    inner: R,
+    metrics: Arc<M>,
+    is_new_request: bool,
+    #[cfg(feature = "async")]
+    read_started: Option<Instant>,
+}
+
+impl<R, M: MetricsRecorder + ?Sized> MeteredReader<R, M> {
+    /// Wraps `inner`, recording all reads in `metrics`.
+    pub const fn new(inner: R, metrics: Arc<M>) -> Self {
+        Self {
+            inner,
+            metrics,
+            is_new_request: true,
+            #[cfg(feature = "async")]
+            read_started: None,
+        }
+    }
+
+    /// Returns the metrics, in which reads are recorded.
+    pub const fn metrics(&self) -> &Arc<M> {
+        &self.metrics
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn record_read(&mut self, bytes: usize, duration: Duration) {
+        if self.is_new_request {
+            self.metrics.record_read_request();
+            self.is_new_request = false;
+        }
+
+        self.metrics.record_bytes_read(bytes as u64, duration);
+    }
+}
+
+impl<R: Read, M: MetricsRecorder + ?Sized> Read for MeteredReader<R, M> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let start = Instant::now();
+        let bytes = self.inner.read(buf)?;
+        self.record_read(bytes, start.elapsed());
+
+        Ok(bytes)
+    }
+}
+
+impl<R: Seek, M: MetricsRecorder + ?Sized> Seek for MeteredReader<R, M> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.is_new_request = true;
+        self.inner.seek(pos)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncRead + Unpin, M: MetricsRecorder + ?Sized> AsyncRead for MeteredReader<R, M> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let start = *self.read_started.get_or_insert_with(Instant::now);
+
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+
+        if let Poll::Ready(result) = &result {
+            self.read_started = None;
+
+            if let Ok(bytes) = result {
+                self.record_read(*bytes, start.elapsed());
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncSeek + Unpin, M: MetricsRecorder + ?Sized> AsyncSeek for MeteredReader<R, M> {
+    fn poll_seek(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<Result<u64>> {
+        self.is_new_request = true;
+        Pin::new(&mut self.inner).poll_seek(cx, pos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let metrics = Metrics::new();
+
+        metrics.record_tile_read();
+        metrics.record_tile_read();
+        metrics.record_cache_hit();
+        metrics.record_cache_miss();
+        metrics.record_read_request();
+        metrics.record_bytes_read(42, Duration::from_millis(3));
+
+        assert_eq!(
+            metrics.snapshot(),
+            MetricsSnapshot {
+                tile_reads: 2,
+                cache_hits: 1,
+                cache_misses: 1,
+                bytes_read: 42,
+                read_requests: 1,
+                timed_reads: 1,
+                read_duration: Duration::from_millis(3),
+            }
+        );
+    }
+
+    #[test]
+    fn test_to_prometheus() {
+        let snapshot = MetricsSnapshot {
+            tile_reads: 2,
+            cache_hits: 1,
+            cache_misses: 0,
+            bytes_read: 42,
+            read_requests: 1,
+            timed_reads: 3,
+            read_duration: Duration::from_millis(1500),
+        };
+
+        let text = snapshot.to_prometheus("pmtiles", &[]);
+
+        assert!(text.contains("# TYPE pmtiles_tile_reads_total counter\n"));
+        assert!(text.contains("pmtiles_tile_reads_total 2\n"));
+        assert!(text.contains("pmtiles_cache_hits_total 1\n"));
+        assert!(text.contains("pmtiles_cache_misses_total 0\n"));
+        assert!(text.contains("pmtiles_bytes_read_total 42\n"));
+        assert!(text.contains("pmtiles_read_requests_total 1\n"));
+        assert!(text.contains("# TYPE pmtiles_read_duration_seconds summary\n"));
+        assert!(text.contains("pmtiles_read_duration_seconds_sum 1.5\n"));
+        assert!(text.contains("pmtiles_read_duration_seconds_count 3\n"));
+
+        let text = snapshot.to_prometheus("foo", &[("archive", "a\"b"), ("zone", "eu")]);
+
+        assert!(text.contains("foo_tile_reads_total{archive=\"a\\\"b\",zone=\"eu\"} 2\n"));
+    }
+
+    #[test]
+    fn test_metered_reader() -> Result<()> {
+        let metrics = Arc::new(Metrics::new());
+        let mut reader = MeteredReader::new(Cursor::new(vec![1u8, 3, 3, 7]), metrics.clone());
+
+        let mut buf = [0u8; 2];
+        reader.read_exact(&mut buf)?;
+        reader.read_exact(&mut buf)?;
+
+        reader.seek(SeekFrom::Start(1))?;
+        reader.read_exact(&mut buf)?;
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.read_requests, 2);
+        assert_eq!(snapshot.timed_reads, 3);
+        assert_eq!(snapshot.bytes_read, 6);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    io::{Error, ErrorKind, Result, SeekFrom},
+    ops::Range,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::{future::BoxFuture, AsyncRead, AsyncSeek, FutureExt};
+use object_store::{path::Path, ObjectStore};
+
+use crate::{PMTiles, DEFAULT_MIN_REQUEST_SIZE};
+
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsRecorder;
+
+/// A pending ranged GET request, resolving to the offset and the data of the requested range.
+type RangeRequest = BoxFuture<'static, Result<(u64, Vec<u8>)>>;
+
+/// A reader, which reads from an object in an [`ObjectStore`] (e.g. Amazon S3, Google Cloud Storage
+/// or Azure Blob Storage) using ranged GET requests.
+///
+/// Only the ranges actually read are requested, so opening an archive and reading tiles does not
+/// download the whole object. Every read, which can not be served from the previously requested range,
+/// results in a new request for at least [`min_request_size`](Self::with_min_request_size) bytes.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{ObjectStoreReader, PMTiles};
+/// # use object_store::{memory::InMemory, path::Path, ObjectStore};
+/// # use std::sync::Arc;
+/// # tokio_test::block_on(async {
+/// # let store = Arc::new(InMemory::new());
+/// # let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+/// # store.put(&Path::from("stamen.pmtiles"), bytes.to_vec().into()).await.unwrap();
+/// // store could also be `object_store::aws::AmazonS3`, `object_store::gcp::GoogleCloudStorage`, ...
+/// let reader = ObjectStoreReader::new(store, Path::from("stamen.pmtiles")).await.unwrap();
+///
+/// let mut pm_tiles = PMTiles::from_async_reader(reader).await.unwrap();
+/// let tile = pm_tiles.get_tile_async(0, 0, 0).await.unwrap();
+/// # assert!(tile.is_some());
+/// # })
+/// ```
+pub struct ObjectStoreReader {
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+    size: u64,
+    position: u64,
+    min_request_size: u64,
+    buffer: Vec<u8>,
+    buffer_offset: u64,
+    // only accessed mutably using `Mutex::get_mut`, which makes the reader `Sync` without locking
+    request: Mutex<Option<RangeRequest>>,
+
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<dyn MetricsRecorder>>,
+}
+
+impl std::fmt::Debug for ObjectStoreReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObjectStoreReader")
+            .field("store", &self.store.to_string())
+            .field("location", &self.location)
+            .field("size", &self.size)
+            .field("position", &self.position)
+            .field("min_request_size", &self.min_request_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ObjectStoreReader {
+    /// Creates a new reader for the object at `location` in `store`.
+    ///
+    /// This issues a HEAD request to determine the size of the object.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the HEAD request failed (e.g. because the object does not exist).
+    pub async fn new(store: Arc<dyn ObjectStore>, location: Path) -> crate::Result<Self> {
+        let meta = store.head(&location).await.map_err(to_io_error)?;
+
+        Ok(Self::with_size(store, location, meta.size))
+    }
+
+    /// Creates a new reader for the object at `location` in `store`, whose size is already known.
+    pub fn with_size(store: Arc<dyn ObjectStore>, location: Path, size: u64) -> Self {
+        Self {
+            store,
+            location,
+            size,
+            position: 0,
+            min_request_size: DEFAULT_MIN_REQUEST_SIZE,
+            buffer: Vec::new(),
+            buffer_offset: 0,
+            request: Mutex::new(None),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// Sets the minimum number of bytes requested at once (defaults to [`DEFAULT_MIN_REQUEST_SIZE`]).
+    #[must_use]
+    pub const fn with_min_request_size(mut self, min_request_size: u64) -> Self {
+        self.min_request_size = min_request_size;
+        self
+    }
+
+    /// Sets the metrics, into which all range requests and the number of bytes received are reported.
+    #[cfg(feature = "metrics")]
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsRecorder>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Returns the size of the object in bytes.
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn request_mut(&mut self) -> &mut Option<RangeRequest> {
+        self.request
+            .get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    const fn buffered_range(&self) -> Range<u64> {
+        self.buffer_offset..self.buffer_offset + self.buffer.len() as u64
+    }
+}
+
+fn to_io_error(err: object_store::Error) -> Error {
+    match err {
+        object_store::Error::NotFound { .. } => Error::new(ErrorKind::NotFound, err),
+        err => Error::other(err),
+    }
+}
+
+impl AsyncRead for ObjectStoreReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        if buf.is_empty() || self.position >= self.size {
+            return Poll::Ready(Ok(0));
+        }
+
+        if !self.buffered_range().contains(&self.position) {
+            let start = self.position;
+            let end = self
+                .size
+                .min(start + self.min_request_size.max(buf.len() as u64));
+            let store = self.store.clone();
+            let location = self.location.clone();
+
+            let request = async move {
+                let bytes = store
+                    .get_range(&location, start..end)
+                    .await
+                    .map_err(to_io_error)?;
+
+                Ok((start, bytes.to_vec()))
+            };
+            #[cfg(feature = "metrics")]
+            let request = crate::metrics::record_request(self.metrics.clone(), request);
+
+            let request = self.request_mut().get_or_insert_with(|| request.boxed());
+
+            let (offset, buffer) = match request.poll_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => {
+                    *self.request_mut() = None;
+                    result?
+                }
+            };
+
+            self.buffer = buffer;
+            self.buffer_offset = offset;
+
+            if !self.buffered_range().contains(&self.position) {
+                return Poll::Ready(Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "Object store returned less data than requested",
+                )));
+            }
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let start = (self.position - self.buffer_offset) as usize;
+        let len = buf.len().min(self.buffer.len() - start);
+
+        buf[..len].copy_from_slice(&self.buffer[start..start + len]);
+        self.position += len as u64;
+
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncSeek for ObjectStoreReader {
+    fn poll_seek(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<Result<u64>> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        let Some(position) = position else {
+            return Poll::Ready(Err(Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )));
+        };
+
+        // a pending request is for the previous position
+        *self.request_mut() = None;
+        self.position = position;
+
+        Poll::Ready(Ok(position))
+    }
+}
+
+impl PMTiles<ObjectStoreReader> {
+    /// Reads a `PMTiles` archive from the object at `location` in an [`ObjectStore`]
+    /// (e.g. Amazon S3, Google Cloud Storage or Azure Blob Storage).
+    ///
+    /// Only the header, meta data and directories are requested. Tiles are requested on demand
+    /// using ranged GET requests.
+    ///
+    /// # Arguments
+    /// * `store` - Object store containing the archive
+    /// * `location` - Location of the archive within `store`
+    ///
+    /// # Errors
+    /// Will return [`Err`] if a request to the object store failed, the object was no valid `PMTiles`
+    /// archive or the internal compression of the archive is set to "Unknown".
+    pub async fn from_object_store(
+        store: Arc<dyn ObjectStore>,
+        location: Path,
+    ) -> crate::Result<Self> {
+        let reader = ObjectStoreReader::new(store, location).await?;
+
+        Self::from_async_reader(reader).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{AsyncReadExt, AsyncSeekExt};
+    use object_store::memory::InMemory;
+
+    use super::*;
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    async fn store() -> Result<Arc<dyn ObjectStore>> {
+        let store = InMemory::new();
+        store
+            .put(
+                &Path::from("stamen.pmtiles"),
+                PM_TILES_BYTES.to_vec().into(),
+            )
+            .await
+            .map_err(to_io_error)?;
+
+        Ok(Arc::new(store))
+    }
+
+    #[test]
+    fn test_read_and_seek() -> Result<()> {
+        tokio_test::block_on(async {
+            let reader =
+                ObjectStoreReader::new(store().await?, Path::from("stamen.pmtiles")).await?;
+            let mut reader = reader.with_min_request_size(4);
+
+            assert_eq!(reader.size(), PM_TILES_BYTES.len() as u64);
+
+            let mut buf = [0u8; 7];
+            reader.read_exact(&mut buf).await?;
+            assert_eq!(buf, PM_TILES_BYTES[..7]);
+
+            reader.seek(SeekFrom::End(-10)).await?;
+            let mut rest = Vec::new();
+            reader.read_to_end(&mut rest).await?;
+            assert_eq!(rest, PM_TILES_BYTES[PM_TILES_BYTES.len() - 10..]);
+
+            reader.seek(SeekFrom::Start(0)).await?;
+            assert!(reader.seek(SeekFrom::Current(-1)).await.is_err());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_not_found() -> Result<()> {
+        tokio_test::block_on(async {
+            let result = ObjectStoreReader::new(store().await?, Path::from("foo.pmtiles")).await;
+
+            assert!(
+                matches!(result, Err(crate::Error::Io(err)) if err.kind() == ErrorKind::NotFound)
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_from_object_store() -> Result<()> {
+        tokio_test::block_on(async {
+            let mut pm_tiles =
+                PMTiles::from_object_store(store().await?, Path::from("stamen.pmtiles")).await?;
+            let mut expected = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+            assert_eq!(pm_tiles.num_tiles(), expected.num_tiles());
+            assert_eq!(
+                pm_tiles.get_tile_async(1, 1, 1).await?,
+                expected.get_tile(1, 1, 1)?
+            );
+
+            Ok(())
+        })
+    }
+}
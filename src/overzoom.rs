@@ -0,0 +1,215 @@
+use std::io::{Read, Seek};
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+use crate::{util::MAX_Z, Error, PMTiles, PMTilesReader, Result};
+
+/// A tile returned by [`PMTiles::get_tile_or_ancestor`], which is either the requested tile or
+/// its nearest ancestor with data.
+///
+/// To render the requested tile from an ancestor (overzooming), the ancestor is divided into
+/// `scale` × `scale` parts and the part at (`offset_x`, `offset_y`) is scaled up to the full size of a tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverzoomedTile {
+    /// Zoom level of the returned tile
+    pub z: u8,
+
+    /// X coordinate of the returned tile
+    pub x: u64,
+
+    /// Y coordinate of the returned tile
+    pub y: u64,
+
+    /// Data of the returned tile (see [`PMTiles::get_tile_by_id`])
+    pub data: Vec<u8>,
+
+    /// Number of requested tiles along each axis of the returned tile (`2^(requested z - z)`),
+    /// i.e. `1` if the requested tile itself was returned
+    pub scale: u64,
+
+    /// Column of the requested tile within the returned tile (from `0` to `scale - 1`, from west to east)
+    pub offset_x: u64,
+
+    /// Row of the requested tile within the returned tile (from `0` to `scale - 1`, from north to south)
+    pub offset_y: u64,
+}
+
+impl OverzoomedTile {
+    /// Returns `true` if this is an ancestor of the requested tile and not the requested tile itself.
+    pub const fn is_overzoomed(&self) -> bool {
+        self.scale > 1
+    }
+}
+
+#[duplicate_item(
+    fn_name                       cfg_async_filter       async   add_await(code) RTraits                                                  Archive         get_tile;
+    [get_tile_or_ancestor]        [cfg(all())]           []      [code]          [Read + Seek]                                            [PMTiles]       [get_tile];
+    [get_tile_or_ancestor_async]  [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [PMTiles]       [get_tile_async];
+    [get_tile_or_ancestor]        [cfg(all())]           []      [code]          [Read + Seek]                                            [PMTilesReader] [get_tile];
+    [get_tile_or_ancestor_async]  [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [PMTilesReader] [get_tile_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> Archive<R> {
+    /// Returns the data of the tile with the specified coordinates or, if it does not exist, of its
+    /// nearest ancestor (the tile of a lower zoom level covering it), which exists.
+    ///
+    /// This handles the lookup half of overzooming, where clients render missing tiles (e.g. beyond
+    /// the maximum zoom level of the archive) from a part of a tile of a lower zoom level. See
+    /// [`OverzoomedTile`] for how to find that part.
+    ///
+    /// Returns [`None`] if neither the tile nor any of its ancestors exist.
+    ///
+    /// # Arguments
+    /// * `x` - X coordinate of the requested tile
+    /// * `y` - Y coordinate of the requested tile
+    /// * `z` - Zoom level of the requested tile
+    ///
+    /// # Errors
+    /// Will return [`Error::InvalidTileCoordinates`] if the coordinates are out of range for the
+    /// zoom level, or [`Err`] if there was an error while reading tiles.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::PMTiles;
+    /// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+    /// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+    ///
+    /// // the archive only includes tiles up to zoom level 3
+    /// let tile = pm_tiles.get_tile_or_ancestor(21, 14, 5).unwrap().unwrap();
+    ///
+    /// assert_eq!((tile.z, tile.x, tile.y), (3, 5, 3));
+    /// assert_eq!((tile.scale, tile.offset_x, tile.offset_y), (4, 1, 2));
+    /// ```
+    pub async fn fn_name(&mut self, x: u64, y: u64, z: u8) -> Result<Option<OverzoomedTile>> {
+        if z >= MAX_Z || x >> z != 0 || y >> z != 0 {
+            return Err(Error::InvalidTileCoordinates { z, x, y });
+        }
+
+        for levels in 0..=z {
+            let (ancestor_x, ancestor_y) = (x >> levels, y >> levels);
+
+            if let Some(data) = add_await([self.get_tile(ancestor_x, ancestor_y, z - levels)])? {
+                return Ok(Some(OverzoomedTile {
+                    z: z - levels,
+                    x: ancestor_x,
+                    y: ancestor_y,
+                    data,
+                    scale: 1 << levels,
+                    offset_x: x - (ancestor_x << levels),
+                    offset_y: y - (ancestor_y << levels),
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{util::tile_id, Compression, TileType};
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    #[test]
+    fn test_get_tile_or_ancestor() -> Result<()> {
+        let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+        pm_tiles.add_tile(tile_id(1, 1, 0), vec![1])?;
+        pm_tiles.add_tile(tile_id(3, 5, 2), vec![3])?;
+
+        // existing tile
+        let tile = pm_tiles.get_tile_or_ancestor(5, 2, 3)?;
+        assert_eq!(
+            tile,
+            Some(OverzoomedTile {
+                z: 3,
+                x: 5,
+                y: 2,
+                data: vec![3],
+                scale: 1,
+                offset_x: 0,
+                offset_y: 0,
+            })
+        );
+        assert!(!tile.is_some_and(|tile| tile.is_overzoomed()));
+
+        // tile 3/5/3 is missing, so 2/2/1 and 1/1/0 are tried
+        let tile = pm_tiles.get_tile_or_ancestor(5, 3, 3)?;
+        assert_eq!(
+            tile,
+            Some(OverzoomedTile {
+                z: 1,
+                x: 1,
+                y: 0,
+                data: vec![1],
+                scale: 4,
+                offset_x: 1,
+                offset_y: 3,
+            })
+        );
+        assert!(tile.is_some_and(|tile| tile.is_overzoomed()));
+
+        // neither the tile nor any of its ancestors exist
+        assert_eq!(pm_tiles.get_tile_or_ancestor(0, 0, 4)?, None);
+
+        assert!(matches!(
+            pm_tiles.get_tile_or_ancestor(4, 0, 2),
+            Err(Error::InvalidTileCoordinates { z: 2, x: 4, y: 0 })
+        ));
+        assert!(pm_tiles.get_tile_or_ancestor(0, 0, 32).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tile_or_ancestor_reader() -> Result<()> {
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        let mut reader = PMTilesReader::from_reader(Cursor::new(PM_TILES_BYTES))?;
+
+        for (x, y, z) in [(0, 0, 0), (3, 5, 3), (100, 200, 8), (0, (1 << 31) - 1, 31)] {
+            let tile = reader.get_tile_or_ancestor(x, y, z)?;
+
+            assert!(tile.is_some());
+            assert_eq!(tile, pm_tiles.get_tile_or_ancestor(x, y, z)?);
+        }
+
+        let tile = reader
+            .get_tile_or_ancestor(100, 200, 8)?
+            .unwrap_or_else(|| unreachable!());
+        assert_eq!((tile.z, tile.x, tile.y), (3, 3, 6));
+        assert_eq!((tile.scale, tile.offset_x, tile.offset_y), (32, 4, 8));
+        assert_eq!(Some(tile.data), reader.get_tile(3, 6, 3)?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_get_tile_or_ancestor_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let mut pm_tiles =
+                PMTiles::from_async_reader(futures::io::Cursor::new(PM_TILES_BYTES)).await?;
+            let mut reader =
+                PMTilesReader::from_async_reader(futures::io::Cursor::new(PM_TILES_BYTES)).await?;
+            let expected = PMTiles::from_bytes(PM_TILES_BYTES)?.get_tile_or_ancestor(21, 14, 5)?;
+
+            assert!(expected.is_some());
+            assert_eq!(
+                pm_tiles.get_tile_or_ancestor_async(21, 14, 5).await?,
+                expected
+            );
+            assert_eq!(
+                reader.get_tile_or_ancestor_async(21, 14, 5).await?,
+                expected
+            );
+
+            Ok(())
+        })
+    }
+}
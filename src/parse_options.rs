@@ -0,0 +1,276 @@
+use std::{fmt, sync::Arc};
+
+use crate::{util::Codec, Error, Result};
+
+/// Options deciding, which deviations from the specification are tolerated while reading an
+/// archive (see [`PMTiles::from_reader_with_options`](crate::PMTiles::from_reader_with_options)).
+///
+/// Tolerated deviations are recorded as [`ParseWarning`]s (see
+/// [`PMTiles::parse_warnings`](crate::PMTiles::parse_warnings)), all others result in an error.
+///
+/// The default options match the behavior of [`PMTiles::from_reader`](crate::PMTiles::from_reader):
+/// Unknown compressions, other spec versions and unordered directory entries are rejected, while
+/// unknown tile types, counts, which do not match the directories, and root directories ending
+/// after the first 16 KiB are tolerated.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{ParseOptions, PMTiles};
+/// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+///
+/// let pm_tiles =
+///     PMTiles::from_reader_with_options(std::io::Cursor::new(bytes), ParseOptions::strict())
+///         .unwrap();
+/// assert!(pm_tiles.parse_warnings().is_empty());
+/// ```
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ParseOptions {
+    /// Whether compression bytes, which are not known to this crate, are read as
+    /// [`Compression::Unknown`](crate::Compression::Unknown) (default `false`)
+    ///
+    /// _Directories and meta data with an unknown internal compression can only be read, if a
+    /// [`codec`](Self::codec) is given._
+    pub allow_unknown_compression: bool,
+
+    /// Whether tile type bytes, which are not known to this crate, are read as
+    /// [`TileType::Unknown`](crate::TileType::Unknown) (default `true`)
+    pub allow_unknown_tile_type: bool,
+
+    /// Whether archives with a spec version other than 3 are read as if they were version 3
+    /// (default `false`)
+    pub allow_other_spec_versions: bool,
+
+    /// Whether the number of addressed tiles, tile entries and tile contents in the header may
+    /// differ from the directories (default `true`)
+    ///
+    /// _Counts are only compared, if the whole archive is read and the count in the header
+    /// is not `0` (which means unknown)._
+    pub allow_inconsistent_counts: bool,
+
+    /// Whether directory entries may be out of order or cover overlapping tile ids (default `false`)
+    ///
+    /// _Tiles of such archives may be looked up incorrectly, so this should only be enabled to
+    /// recover data from broken archives._
+    pub allow_unordered_entries: bool,
+
+    /// Whether the root directory may end after the first 16 KiB of the archive (default `true`)
+    ///
+    /// _The specification requires header and root directory to fit into the first 16 KiB, so
+    /// they can be fetched with a single request (e.g. from a CDN). Archives, which are only read
+    /// from local disk, may exceed this (see [`WriteOptions::max_root_dir_length`](crate::WriteOptions::max_root_dir_length))._
+    pub allow_large_root_directory: bool,
+
+    /// Codec used to decompress directories and meta data with the internal compression
+    /// [`Compression::Unknown`](crate::Compression::Unknown) (see [`Codec`])
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub codec: Option<Arc<dyn Codec>>,
+}
+
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseOptions")
+            .field("allow_unknown_compression", &self.allow_unknown_compression)
+            .field("allow_unknown_tile_type", &self.allow_unknown_tile_type)
+            .field("allow_other_spec_versions", &self.allow_other_spec_versions)
+            .field("allow_inconsistent_counts", &self.allow_inconsistent_counts)
+            .field("allow_unordered_entries", &self.allow_unordered_entries)
+            .field(
+                "allow_large_root_directory",
+                &self.allow_large_root_directory,
+            )
+            .field("codec", &self.codec.is_some())
+            .finish()
+    }
+}
+
+impl PartialEq for ParseOptions {
+    fn eq(&self, other: &Self) -> bool {
+        self.allow_unknown_compression == other.allow_unknown_compression
+            && self.allow_unknown_tile_type == other.allow_unknown_tile_type
+            && self.allow_other_spec_versions == other.allow_other_spec_versions
+            && self.allow_inconsistent_counts == other.allow_inconsistent_counts
+            && self.allow_unordered_entries == other.allow_unordered_entries
+            && self.allow_large_root_directory == other.allow_large_root_directory
+            && match (&self.codec, &other.codec) {
+                (Some(codec), Some(other)) => Arc::ptr_eq(codec, other),
+                (codec, other) => codec.is_none() && other.is_none(),
+            }
+    }
+}
+
+impl Eq for ParseOptions {}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            allow_unknown_compression: false,
+            allow_unknown_tile_type: true,
+            allow_other_spec_versions: false,
+            allow_inconsistent_counts: true,
+            allow_unordered_entries: false,
+            allow_large_root_directory: true,
+            codec: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Returns options, which reject every deviation from the specification.
+    pub const fn strict() -> Self {
+        Self {
+            allow_unknown_compression: false,
+            allow_unknown_tile_type: false,
+            allow_other_spec_versions: false,
+            allow_inconsistent_counts: false,
+            allow_unordered_entries: false,
+            allow_large_root_directory: false,
+            codec: None,
+        }
+    }
+
+    /// Returns options, which tolerate every deviation from the specification
+    /// (e.g. for archives written by older or less careful tools).
+    pub const fn lenient() -> Self {
+        Self {
+            allow_unknown_compression: true,
+            allow_unknown_tile_type: true,
+            allow_other_spec_versions: true,
+            allow_inconsistent_counts: true,
+            allow_unordered_entries: true,
+            allow_large_root_directory: true,
+            codec: None,
+        }
+    }
+
+    /// Records `warning`, if it is tolerated by these options, or returns it as an error otherwise.
+    pub(crate) fn tolerate(
+        &self,
+        warning: ParseWarning,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<()> {
+        let allowed = match warning {
+            ParseWarning::UnknownCompression { .. } => self.allow_unknown_compression,
+            ParseWarning::UnknownTileType(_) => self.allow_unknown_tile_type,
+            ParseWarning::UnsupportedSpecVersion(_) => self.allow_other_spec_versions,
+            ParseWarning::CountMismatch { .. } => self.allow_inconsistent_counts,
+            ParseWarning::EntriesNotAscending { .. } => self.allow_unordered_entries,
+            ParseWarning::RootDirectoryTooLarge(_) => self.allow_large_root_directory,
+        };
+
+        if !allowed {
+            return Err(warning.into());
+        }
+
+        warnings.push(warning);
+        Ok(())
+    }
+}
+
+/// A deviation from the specification, which was tolerated while reading an archive
+/// according to its [`ParseOptions`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum ParseWarning {
+    /// A compression byte is not known to this crate and was read as
+    /// [`Compression::Unknown`](crate::Compression::Unknown).
+    #[error("Unknown {field} {value}")]
+    UnknownCompression {
+        /// Name of the header field (`internal compression` or `tile compression`)
+        field: &'static str,
+        /// Value of the header field
+        value: u8,
+    },
+
+    /// The tile type byte is not known to this crate and was read as
+    /// [`TileType::Unknown`](crate::TileType::Unknown).
+    #[error("Unknown tile type {0}")]
+    UnknownTileType(u8),
+
+    /// The archive uses a version of the specification other than 3 and was read as version 3.
+    #[error("Unsupported PMTiles spec version {0} (only version 3 is supported)")]
+    UnsupportedSpecVersion(u8),
+
+    /// A count in the header does not match the directories of the archive.
+    #[error("Header states {header} for {field}, but directories include {actual}")]
+    CountMismatch {
+        /// Name of the header field (e.g. `number of addressed tiles`)
+        field: &'static str,
+        /// Value of the header field
+        header: u64,
+        /// Actual value according to the directories
+        actual: u64,
+    },
+
+    /// A directory entry does not start after the tile ids covered by the preceding entry
+    /// (only the first one is recorded).
+    #[error("Entry for tile id {tile_id} does not follow entry for tile id {previous}")]
+    EntriesNotAscending {
+        /// First tile id of the entry
+        tile_id: u64,
+        /// First tile id of the preceding entry
+        previous: u64,
+    },
+
+    /// The root directory ends at this byte, which is after the first 16 KiB of the archive.
+    #[error("Root directory ends at byte {0}, but must end within the first 16384 bytes")]
+    RootDirectoryTooLarge(u64),
+}
+
+impl From<ParseWarning> for Error {
+    fn from(warning: ParseWarning) -> Self {
+        match warning {
+            ParseWarning::UnsupportedSpecVersion(version) => Self::UnsupportedSpecVersion(version),
+            ParseWarning::CountMismatch { .. }
+            | ParseWarning::EntriesNotAscending { .. }
+            | ParseWarning::RootDirectoryTooLarge(_) => Self::Inconsistent(warning),
+            ParseWarning::UnknownCompression { .. } | ParseWarning::UnknownTileType(_) => {
+                Self::InvalidHeader(warning.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tolerate() {
+        let warning = ParseWarning::UnknownTileType(42);
+
+        let mut warnings = Vec::new();
+        assert!(ParseOptions::default()
+            .tolerate(warning.clone(), &mut warnings)
+            .is_ok());
+        assert_eq!(warnings, vec![warning.clone()]);
+
+        let mut warnings = Vec::new();
+        assert!(matches!(
+            ParseOptions::strict().tolerate(warning, &mut warnings),
+            Err(Error::InvalidHeader(_))
+        ));
+        assert!(warnings.is_empty());
+
+        assert!(matches!(
+            ParseOptions::default()
+                .tolerate(ParseWarning::UnsupportedSpecVersion(2), &mut warnings),
+            Err(Error::UnsupportedSpecVersion(2))
+        ));
+
+        let warning = ParseWarning::CountMismatch {
+            field: "number of tile entries",
+            header: 1,
+            actual: 2,
+        };
+        assert!(matches!(
+            ParseOptions::strict().tolerate(warning.clone(), &mut warnings),
+            Err(Error::Inconsistent(err)) if err == warning
+        ));
+        assert!(ParseOptions::lenient()
+            .tolerate(warning, &mut warnings)
+            .is_ok());
+        assert_eq!(warnings.len(), 1);
+    }
+}
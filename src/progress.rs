@@ -0,0 +1,96 @@
+/// A phase of writing an archive, as reported to a [`WriteProgress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WritePhase {
+    /// Tiles are read and deduplicated (progress is counted in tiles)
+    Dedupe,
+
+    /// The root and leaf directories are written (progress is counted in bytes)
+    Directories,
+
+    /// The JSON meta data is written (progress is counted in uncompressed bytes)
+    Metadata,
+
+    /// The tile data is written (progress is counted in bytes)
+    TileData,
+}
+
+/// Receives progress updates while an archive is written (see [`WriteOptions::progress`](crate::WriteOptions::progress)).
+///
+/// This is implemented for all closures taking the same arguments as [`on_progress`](Self::on_progress).
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{PMTiles, TileType, Compression, WriteOptions, WritePhase};
+/// let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+/// pm_tiles.add_tile(0, vec![1, 2, 3]).unwrap();
+///
+/// let mut tiles_done = 0;
+/// pm_tiles
+///     .to_writer_with_options(
+///         &mut std::io::Cursor::new(Vec::new()),
+///         WriteOptions {
+///             progress: Some(&mut |phase, processed, _total| {
+///                 if phase == WritePhase::Dedupe {
+///                     tiles_done = processed;
+///                 }
+///             }),
+///             ..WriteOptions::default()
+///         },
+///     )
+///     .unwrap();
+///
+/// assert_eq!(tiles_done, 1);
+/// ```
+pub trait WriteProgress {
+    /// Called whenever progress was made.
+    ///
+    /// # Arguments
+    /// * `phase` - Current phase
+    /// * `processed` - Number of tiles or bytes processed in this phase so far
+    /// * `total` - Total number of tiles or bytes of this phase
+    fn on_progress(&mut self, phase: WritePhase, processed: u64, total: u64);
+}
+
+impl<F: FnMut(WritePhase, u64, u64)> WriteProgress for F {
+    fn on_progress(&mut self, phase: WritePhase, processed: u64, total: u64) {
+        self(phase, processed, total);
+    }
+}
+
+/// Receives progress updates while the directories of an archive are read
+/// (see [`PMTiles::from_reader_with_progress`](crate::PMTiles::from_reader_with_progress)).
+///
+/// The total number of leaf directories and entries is not known before all directories were read,
+/// so only the counts so far are reported.
+///
+/// This is implemented for all closures taking the same arguments as [`on_progress`](Self::on_progress).
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::PMTiles;
+/// # let file_path = "./test/protomaps_vector_planet_odbl_z10_without_data.pmtiles";
+/// let file = std::fs::File::open(file_path).unwrap();
+///
+/// let mut leaf_directories_read = 0;
+/// PMTiles::from_reader_with_progress(file, &mut |leaf_directories, _entries| {
+///     leaf_directories_read = leaf_directories;
+/// })
+/// .unwrap();
+///
+/// assert!(leaf_directories_read > 0);
+/// ```
+pub trait ReadProgress {
+    /// Called whenever a directory was read.
+    ///
+    /// # Arguments
+    /// * `leaf_directories` - Number of leaf directories read so far
+    /// * `entries` - Number of tile entries loaded so far
+    fn on_progress(&mut self, leaf_directories: u64, entries: u64);
+}
+
+impl<F: FnMut(u64, u64)> ReadProgress for F {
+    fn on_progress(&mut self, leaf_directories: u64, entries: u64) {
+        self(leaf_directories, entries);
+    }
+}
@@ -0,0 +1,263 @@
+//! [`Arbitrary`] implementations for property based tests with [`proptest`], which only produce
+//! values that can be written and read again without losing any information.
+
+use std::fmt::Debug;
+
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    collection::vec,
+    sample::select,
+    strategy::{BoxedStrategy, Strategy},
+};
+
+use crate::{header::LatLng, Compression, Directory, Entry, Header, PMTiles, TileType};
+
+/// Maximum zoom level of tiles in generated archives, which keeps archives small.
+const MAX_ZOOM: u8 = 5;
+
+/// Maximum number of tiles in generated archives.
+const MAX_TILES: usize = 64;
+
+/// Maximum length of tiles in generated entries (see [`ReadLimits::max_tile_length`](crate::ReadLimits::max_tile_length)).
+const MAX_TILE_LENGTH: u32 = 256 * 1024 * 1024;
+
+impl Arbitrary for Compression {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        select(vec![
+            Self::Unknown,
+            Self::None,
+            Self::GZip,
+            Self::Brotli,
+            Self::ZStd,
+        ])
+        .boxed()
+    }
+}
+
+impl Arbitrary for TileType {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        select(vec![
+            Self::Unknown,
+            Self::Mvt,
+            Self::Png,
+            Self::Jpeg,
+            Self::WebP,
+            Self::Avif,
+        ])
+        .boxed()
+    }
+}
+
+impl Arbitrary for LatLng {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        // positions are stored as integers, so only those can be read back unchanged
+        (
+            -1_800_000_000..=1_800_000_000i32,
+            -900_000_000..=900_000_000i32,
+        )
+            .prop_map(|(longitude, latitude)| Self {
+                longitude: f64::from(longitude) / 10_000_000.0,
+                latitude: f64::from(latitude) / 10_000_000.0,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Header {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        let sections = any::<[u64; 11]>();
+        let properties = (
+            any::<bool>(),
+            any::<Compression>(),
+            any::<Compression>(),
+            any::<TileType>(),
+            any::<[u8; 3]>(),
+            any::<[LatLng; 3]>(),
+        );
+
+        (sections, properties)
+            .prop_map(
+                |(
+                    sections,
+                    (
+                        clustered,
+                        internal_compression,
+                        tile_compression,
+                        tile_type,
+                        [min_zoom, max_zoom, center_zoom],
+                        [min_pos, max_pos, center_pos],
+                    ),
+                )| Self {
+                    spec_version: 3,
+                    root_directory_offset: sections[0],
+                    root_directory_length: sections[1],
+                    json_metadata_offset: sections[2],
+                    json_metadata_length: sections[3],
+                    leaf_directories_offset: sections[4],
+                    leaf_directories_length: sections[5],
+                    tile_data_offset: sections[6],
+                    tile_data_length: sections[7],
+                    num_addressed_tiles: sections[8],
+                    num_tile_entries: sections[9],
+                    num_tile_content: sections[10],
+                    clustered,
+                    internal_compression,
+                    tile_compression,
+                    tile_type,
+                    min_zoom,
+                    max_zoom,
+                    min_pos,
+                    max_pos,
+                    center_zoom,
+                    center_pos,
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for Entry {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (
+            0..=u64::MAX / 2,
+            // offsets are stored incremented by one and are added to lengths while writing
+            0..=u64::MAX / 2,
+            // entries must be neither empty nor exceed the default read limits
+            1..=MAX_TILE_LENGTH,
+            any::<u32>(),
+        )
+            .prop_map(|(tile_id, offset, length, run_length)| Self {
+                tile_id,
+                offset,
+                length,
+                run_length,
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Directory {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        vec((any::<u32>(), any::<Entry>()), 0..=256)
+            .prop_map(|entries| {
+                let mut next_tile_id = 0u64;
+
+                // entries are sorted by tile id and do not overlap
+                entries
+                    .into_iter()
+                    .map(|(gap, entry)| {
+                        let tile_id = next_tile_id + u64::from(gap);
+                        next_tile_id = tile_id + u64::from(entry.run_length.max(1));
+
+                        Entry { tile_id, ..entry }
+                    })
+                    .collect::<Vec<_>>()
+                    .into()
+            })
+            .boxed()
+    }
+}
+
+impl<R: Debug + 'static> Arbitrary for PMTiles<R> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Generates small archives with up to 64 tiles of up to zoom level 5.
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        let max_tile_id = crate::util::tile_id(MAX_ZOOM + 1, 0, 0) - 1;
+
+        (
+            any::<TileType>(),
+            any::<Compression>(),
+            select(vec![
+                Compression::None,
+                Compression::GZip,
+                Compression::Brotli,
+                Compression::ZStd,
+            ]),
+            (0..=MAX_ZOOM, 0..=MAX_ZOOM),
+            vec((0..=max_tile_id, vec(any::<u8>(), 1..=64)), 0..=MAX_TILES),
+        )
+            .prop_filter_map(
+                "tiles could not be added",
+                |(tile_type, tile_compression, internal_compression, (zoom_a, zoom_b), tiles)| {
+                    let mut pm_tiles = Self::default();
+                    pm_tiles.tile_type = tile_type;
+                    pm_tiles.tile_compression = tile_compression;
+                    pm_tiles.internal_compression = internal_compression;
+                    pm_tiles.min_zoom = zoom_a.min(zoom_b);
+                    pm_tiles.max_zoom = zoom_a.max(zoom_b);
+
+                    for (tile_id, data) in tiles {
+                        pm_tiles.add_tile(tile_id, data).ok()?;
+                    }
+
+                    Some(pm_tiles)
+                },
+            )
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::io::Cursor;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn test_header_round_trip(header in any::<Header>()) {
+            let mut buf = Vec::new();
+            header.to_writer(&mut buf).unwrap();
+            prop_assert_eq!(Header::from_bytes(&buf).unwrap(), header);
+        }
+
+        #[test]
+        fn test_directory_round_trip(directory in any::<Directory>()) {
+            prop_assert!(directory.check_entries().is_ok());
+
+            let mut buf = Vec::new();
+            directory.to_writer(&mut buf, Compression::GZip).unwrap();
+            prop_assert_eq!(Directory::from_bytes(&buf, Compression::GZip).unwrap(), directory);
+        }
+
+        #[test]
+        fn test_pm_tiles_round_trip(mut pm_tiles in any::<PMTiles<Cursor<&'static [u8]>>>()) {
+            let expected = pm_tiles
+                .tile_ids_sorted()
+                .into_iter()
+                .map(|tile_id| (tile_id, pm_tiles.get_tile_by_id(tile_id).unwrap()))
+                .collect::<Vec<_>>();
+
+            let mut read = PMTiles::from_bytes(pm_tiles.to_vec().unwrap()).unwrap();
+            prop_assert_eq!(read.num_tiles(), expected.len());
+            for (tile_id, data) in expected {
+                prop_assert_eq!(read.get_tile_by_id(tile_id).unwrap(), data);
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{Entry, Error, Result};
+
+/// Limits, which are enforced while reading an archive
+/// (see [`PMTiles::from_reader_with_limits`](crate::PMTiles::from_reader_with_limits)).
+///
+/// Directories and meta data are compressed and directory entries are run-length encoded, so a
+/// small (malicious or corrupted) archive could otherwise make a reader allocate an unbounded
+/// amount of memory. Exceeding any limit results in an [`Error::LimitExceeded`].
+///
+/// The default limits are chosen generously, so valid archives (including planet-scale ones)
+/// can be read. Use [`ReadLimits::unlimited`] to disable all limits for trusted archives.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{Error, PMTiles, ReadLimits};
+/// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+///
+/// let limits = ReadLimits {
+///     max_tiles: 10,
+///     ..ReadLimits::default()
+/// };
+///
+/// let result = PMTiles::from_reader_with_limits(std::io::Cursor::new(bytes), limits);
+/// assert!(matches!(result, Err(Error::LimitExceeded { .. })));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReadLimits {
+    /// Maximum length (in bytes) of a single decompressed directory (default 128 MiB)
+    pub max_directory_length: u64,
+
+    /// Maximum length (in bytes) of the decompressed meta data (default 64 MiB)
+    pub max_meta_data_length: u64,
+
+    /// Maximum number of entries of all directories combined (default 2<sup>28</sup>)
+    pub max_entries: u64,
+
+    /// Maximum number of tiles addressed by all entries combined, counting every tile of a
+    /// run of tiles separately (default 2<sup>32</sup>)
+    pub max_tiles: u64,
+
+    /// Maximum length (in bytes) of the data of a single tile (default 256 MiB)
+    pub max_tile_length: u32,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self {
+            max_directory_length: 128 * 1024 * 1024,
+            max_meta_data_length: 64 * 1024 * 1024,
+            max_entries: 1 << 28,
+            max_tiles: 1 << 32,
+            max_tile_length: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl ReadLimits {
+    /// Returns limits, which are never exceeded (e.g. for reading trusted archives).
+    pub const fn unlimited() -> Self {
+        Self {
+            max_directory_length: u64::MAX,
+            max_meta_data_length: u64::MAX,
+            max_entries: u64::MAX,
+            max_tiles: u64::MAX,
+            max_tile_length: u32::MAX,
+        }
+    }
+
+    /// Checks the length of the tile data `entry` points to.
+    pub(crate) fn check_tile_length(&self, entry: &Entry) -> Result<()> {
+        if !entry.is_leaf_dir_entry() && entry.length > self.max_tile_length {
+            return Err(Error::LimitExceeded {
+                limit: "tile length",
+                value: entry.length.into(),
+                max: self.max_tile_length.into(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns an [`Error::LimitExceeded`] if `value` exceeds `max`.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) const fn check_limit(limit: &'static str, value: u64, max: u64) -> Result<()> {
+    if value > max {
+        return Err(Error::LimitExceeded { limit, value, max });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_check_tile_length() {
+        let limits = ReadLimits {
+            max_tile_length: 10,
+            ..ReadLimits::default()
+        };
+
+        let mut entry = Entry {
+            tile_id: 0,
+            offset: 0,
+            length: 11,
+            run_length: 1,
+        };
+        assert!(matches!(
+            limits.check_tile_length(&entry),
+            Err(Error::LimitExceeded { value: 11, .. })
+        ));
+
+        // leaf directory entries are checked against the directory length instead
+        entry.run_length = 0;
+        assert!(limits.check_tile_length(&entry).is_ok());
+
+        assert!(ReadLimits::unlimited()
+            .check_tile_length(&Entry {
+                length: u32::MAX,
+                run_length: 1,
+                ..entry
+            })
+            .is_ok());
+    }
+}
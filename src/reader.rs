@@ -0,0 +1,589 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Seek},
+    ops::{Range, RangeInclusive},
+};
+
+#[cfg(feature = "metrics")]
+use std::sync::Arc;
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+use serde_json::{Map as JSONMap, Value as JSONValue};
+
+use crate::{
+    directory::MAX_DIRECTORY_DEPTH,
+    util::{coalesce_ranges, decompress, tile_id, tile_id_ranges_in_bbox},
+    BBox, Directory, Entry, Error, Header, Result,
+};
+
+#[cfg(feature = "async")]
+use crate::util::decompress_async;
+
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsRecorder;
+
+/// A reader for `PMTiles` archives, which only parses the directories required to find a tile.
+///
+/// In contrast to [`PMTiles`](crate::PMTiles), which parses all directories up front,
+/// opening an archive only reads the header and the root directory. Every call of
+/// [`get_tile_by_id`](Self::get_tile_by_id) walks from the root directory down to the leaf directory
+/// containing the tile. This makes opening planet-scale archives with millions of entries cheap,
+/// at the cost of reading (and decompressing) leaf directories per tile.
+///
+/// Leaf directories of a region can be fetched ahead of traffic with [`warm_cache`](Self::warm_cache),
+/// which avoids reading them again for every tile of that region.
+///
+/// Use [`PMTiles`](crate::PMTiles) if you want to modify an archive or need to access most of its tiles.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::PMTilesReader;
+/// let file = std::fs::File::open("./test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles").unwrap();
+/// let mut reader = PMTilesReader::from_reader(file).unwrap();
+///
+/// let tile = reader.get_tile(1, 1, 1).unwrap();
+/// # assert!(tile.is_some());
+/// ```
+#[derive(Debug)]
+pub struct PMTilesReader<R> {
+    header: Header,
+    root_directory: Directory,
+    reader: R,
+
+    /// offset of leaf directory (relative to leaf directories section) -> leaf directory
+    leaf_directories: HashMap<u64, Directory>,
+
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<dyn MetricsRecorder>>,
+}
+
+impl<R> PMTilesReader<R> {
+    /// Returns the header of the archive.
+    pub const fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns the root directory of the archive.
+    pub const fn root_directory(&self) -> &Directory {
+        &self.root_directory
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Returns the number of leaf directories in the cache (see [`warm_cache`](Self::warm_cache)).
+    pub fn num_cached_leaf_directories(&self) -> usize {
+        self.leaf_directories.len()
+    }
+
+    /// Removes all leaf directories from the cache (see [`warm_cache`](Self::warm_cache)).
+    pub fn clear_cache(&mut self) {
+        self.leaf_directories.clear();
+    }
+
+    /// Sets the metrics, into which read tiles and hits / misses of the leaf directory cache are reported.
+    #[cfg(feature = "metrics")]
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsRecorder>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Returns the leaf directory entries of `directory`, which cover any of the (sorted) `ranges`.
+    fn leaf_entries_in_ranges(directory: &Directory, ranges: &[Range<u64>]) -> Vec<Entry> {
+        directory
+            .into_iter()
+            .enumerate()
+            .filter(|(index, entry)| {
+                if !entry.is_leaf_dir_entry() {
+                    return false;
+                }
+
+                // a leaf directory covers all tile ids up to the next entry
+                let end = if index + 1 < directory.len() {
+                    directory[index + 1].tile_id
+                } else {
+                    u64::MAX
+                };
+
+                ranges
+                    .iter()
+                    .any(|range| range.start < end && entry.tile_id < range.end)
+            })
+            .map(|(_, entry)| *entry)
+            .collect()
+    }
+
+    fn parse_meta_data(val: JSONValue) -> Result<JSONMap<String, JSONValue>> {
+        let JSONValue::Object(map) = val else {
+            return Err(Error::MetaDataNotAnObject);
+        };
+
+        Ok(map)
+    }
+}
+
+#[duplicate_item(
+    cfg_async_filter       async   add_await(code) RTraits                                                  SeekFrom                from_reader         get_tile_by_id         get_tile         meta_data         get_tile_entry         decompress         contains_tile         tile_byte_range         tile_byte_ranges         read_leaf_directory         warm_cache         tile_size;
+    [cfg(all())]           []      [code]          [Read + Seek]                                            [std::io::SeekFrom]     [from_reader]       [get_tile_by_id]       [get_tile]       [meta_data]       [get_tile_entry]       [decompress]       [contains_tile]       [tile_byte_range]       [tile_byte_ranges]       [read_leaf_directory]       [warm_cache]       [tile_size];
+    [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [futures::io::SeekFrom] [from_async_reader] [get_tile_by_id_async] [get_tile_async] [meta_data_async] [get_tile_entry_async] [decompress_async] [contains_tile_async] [tile_byte_range_async] [tile_byte_ranges_async] [read_leaf_directory_async] [warm_cache_async] [tile_size_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTilesReader<R> {
+    /// Opens a `PMTiles` archive by reading its header and root directory.
+    ///
+    /// Leaf directories, meta data and tiles are only read when required.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error reading from `input`, the data was no valid `PMTiles`
+    /// archive or the internal compression of the archive is set to "Unknown".
+    pub async fn from_reader(mut input: R) -> Result<Self> {
+        add_await([input.seek(SeekFrom::Start(0))])?;
+        let header = add_await([Header::from_reader(&mut input)])?;
+
+        add_await([input.seek(SeekFrom::Start(header.root_directory_offset))])?;
+        let root_directory = add_await([Directory::from_reader(
+            &mut input,
+            header.root_directory_length,
+            header.internal_compression,
+        )])?;
+
+        Ok(Self {
+            header,
+            root_directory,
+            reader: input,
+            leaf_directories: HashMap::new(),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        })
+    }
+
+    /// Reads the leaf directory, which the leaf directory entry `entry` points to.
+    async fn read_leaf_directory(&mut self, entry: &Entry) -> Result<Directory> {
+        add_await([self.reader.seek(SeekFrom::Start(
+            self.header.leaf_directories_offset + entry.offset,
+        ))])?;
+
+        add_await([Directory::from_reader(
+            &mut self.reader,
+            u64::from(entry.length),
+            self.header.internal_compression,
+        )])
+    }
+
+    /// Reads all leaf directories covering tiles within `zoom_range`, which intersect `bbox`, and keeps
+    /// them in a cache.
+    ///
+    /// Subsequent lookups of tiles within that region do not have to read any directories, which
+    /// greatly reduces the latency of the first requests to a region of a remote archive. Leaf
+    /// directories, which are already cached, are not read again. The cache is not limited in size, so
+    /// warming large regions of planet-scale archives may use a lot of memory (use
+    /// [`clear_cache`](Self::clear_cache) to free it).
+    ///
+    /// Returns the number of leaf directories read.
+    ///
+    /// # Arguments
+    /// * `bbox` - Bounding box (e.g. `(min_longitude, min_latitude, max_longitude, max_latitude)`, see [`BBox`](crate::BBox))
+    /// * `zoom_range` - Zoom levels to warm the cache for
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading leaf directories or they are nested too deeply.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::PMTilesReader;
+    /// let file = std::fs::File::open("./test/protomaps_vector_planet_odbl_z10_without_data.pmtiles").unwrap();
+    /// let mut reader = PMTilesReader::from_reader(file).unwrap();
+    ///
+    /// // Florence, Italy
+    /// let num_read = reader.warm_cache((11.15, 43.72, 11.33, 43.84), 0..=10).unwrap();
+    /// assert_eq!(reader.num_cached_leaf_directories(), num_read);
+    /// ```
+    pub async fn warm_cache(
+        &mut self,
+        bbox: impl Into<BBox>,
+        zoom_range: RangeInclusive<u8>,
+    ) -> Result<usize> {
+        let bbox = bbox.into().to_tuple();
+        let ranges = coalesce_ranges(zoom_range.flat_map(|z| tile_id_ranges_in_bbox(bbox, z)), 0);
+
+        let mut num_read = 0;
+        let mut pending = Self::leaf_entries_in_ranges(&self.root_directory, &ranges)
+            .into_iter()
+            .map(|entry| (entry, 1))
+            .collect::<Vec<_>>();
+
+        while let Some((entry, depth)) = pending.pop() {
+            if depth >= MAX_DIRECTORY_DEPTH {
+                return Err(Error::DirectoryDecode(
+                    "Leaf directories are nested too deeply".to_string(),
+                ));
+            }
+
+            if !self.leaf_directories.contains_key(&entry.offset) {
+                let leaf_directory = add_await([self.read_leaf_directory(&entry)])?;
+                self.leaf_directories.insert(entry.offset, leaf_directory);
+                num_read += 1;
+            }
+
+            if let Some(leaf_directory) = self.leaf_directories.get(&entry.offset) {
+                pending.extend(
+                    Self::leaf_entries_in_ranges(leaf_directory, &ranges)
+                        .into_iter()
+                        .map(|entry| (entry, depth + 1)),
+                );
+            }
+        }
+
+        Ok(num_read)
+    }
+
+    /// Reads and parses the JSON meta data of the archive.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error reading the meta data or it is no JSON object.
+    pub async fn meta_data(&mut self) -> Result<JSONMap<String, JSONValue>> {
+        if self.header.json_metadata_length == 0 {
+            return Ok(JSONMap::new());
+        }
+
+        add_await([self
+            .reader
+            .seek(SeekFrom::Start(self.header.json_metadata_offset))])?;
+
+        let mut compressed = (&mut self.reader).take(self.header.json_metadata_length);
+        let mut reader = decompress(self.header.internal_compression, &mut compressed)?;
+
+        let mut output = Vec::new();
+        add_await([reader.read_to_end(&mut output)])?;
+
+        Self::parse_meta_data(serde_json::from_slice(&output)?)
+    }
+
+    /// Returns the directory entry, which includes the tile with the specified id.
+    ///
+    /// Leaf directories are read as required (unless cached), but no tile data is read. The
+    /// [`offset`](Entry::offset) of the entry is relative to the start of the tile data section,
+    /// so the tile data is located at `header().tile_data_offset + entry.offset`. As the entry may be
+    /// valid for multiple tiles, its [`tile_id`](Entry::tile_id) is not necessarily equal to `tile_id`.
+    ///
+    /// This is useful to redirect clients to byte ranges of the archive instead of serving tile data.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading leaf directories or they are nested too deeply.
+    pub async fn get_tile_entry(&mut self, tile_id: u64) -> Result<Option<Entry>> {
+        let Some(&(mut entry)) = self.root_directory.find_entry(tile_id) else {
+            return Ok(None);
+        };
+
+        for _ in 1..MAX_DIRECTORY_DEPTH {
+            if !entry.is_leaf_dir_entry() {
+                return Ok(Some(entry));
+            }
+
+            let cached = self.leaf_directories.get(&entry.offset);
+
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = &self.metrics {
+                if cached.is_some() {
+                    metrics.record_cache_hit();
+                } else {
+                    metrics.record_cache_miss();
+                }
+            }
+
+            let found = if let Some(leaf_directory) = cached {
+                leaf_directory.find_entry(tile_id).copied()
+            } else {
+                add_await([self.read_leaf_directory(&entry)])?
+                    .find_entry(tile_id)
+                    .copied()
+            };
+
+            match found {
+                Some(e) => entry = e,
+                None => return Ok(None),
+            }
+        }
+
+        if entry.is_leaf_dir_entry() {
+            return Err(Error::DirectoryDecode(
+                "Leaf directories are nested too deeply".to_string(),
+            ));
+        }
+
+        Ok(Some(entry))
+    }
+
+    /// Returns `true` if the archive includes a tile with the specified id.
+    ///
+    /// Only the directories required to find the tile are read, but no tile data.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading leaf directories.
+    pub async fn contains_tile(&mut self, tile_id: u64) -> Result<bool> {
+        Ok(add_await([self.get_tile_entry(tile_id)])?.is_some())
+    }
+
+    /// Returns the absolute byte range of the data of a tile within the archive.
+    ///
+    /// This can be used to redirect clients to the byte range (e.g. using signed range URLs or
+    /// `X-Accel-Redirect`) instead of serving tile data. Only the directories required to find the
+    /// tile are read, but no tile data.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading leaf directories.
+    pub async fn tile_byte_range(&mut self, tile_id: u64) -> Result<Option<Range<u64>>> {
+        let Some(entry) = add_await([self.get_tile_entry(tile_id)])? else {
+            return Ok(None);
+        };
+
+        let start = self.header.tile_data_offset + entry.offset;
+        Ok(Some(start..start + u64::from(entry.length)))
+    }
+
+    /// Returns the size (in bytes) of the data of a tile, as it is stored in the archive.
+    ///
+    /// Only the directories required to find the tile are read, but no tile data, so servers can
+    /// use it to send `Content-Length` headers, enforce quotas or estimate bandwidth.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading leaf directories.
+    pub async fn tile_size(&mut self, tile_id: u64) -> Result<Option<u32>> {
+        Ok(add_await([self.get_tile_entry(tile_id)])?.map(|entry| entry.length))
+    }
+
+    /// Returns a minimal list of absolute byte ranges of the archive, which include the data of
+    /// all tiles in `tile_ids`.
+    ///
+    /// Ranges of tiles, which overlap, are adjacent or are at most `max_gap` bytes apart, are merged
+    /// (see [`util::coalesce_ranges`](crate::util::coalesce_ranges)), so a remote backend can fetch a
+    /// whole viewport using a handful of range requests. Tiles, which do not exist, are ignored.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading leaf directories.
+    pub async fn tile_byte_ranges(
+        &mut self,
+        tile_ids: &[u64],
+        max_gap: u64,
+    ) -> Result<Vec<Range<u64>>> {
+        let mut ranges = Vec::with_capacity(tile_ids.len());
+
+        for tile_id in tile_ids {
+            if let Some(range) = add_await([self.tile_byte_range(*tile_id)])? {
+                ranges.push(range);
+            }
+        }
+
+        Ok(coalesce_ranges(ranges, max_gap))
+    }
+
+    /// Get data of a tile by its id.
+    ///
+    /// The returned data is the raw data, meaning It is NOT uncompressed automatically,
+    /// if it was compressed in the first place.
+    ///
+    /// Will return [`Ok`] with an value of [`None`] if no a tile with the specified tile id was found.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading directories or tile data.
+    pub async fn get_tile_by_id(&mut self, tile_id: u64) -> Result<Option<Vec<u8>>> {
+        let Some(entry) = add_await([self.get_tile_entry(tile_id)])? else {
+            return Ok(None);
+        };
+
+        add_await([self
+            .reader
+            .seek(SeekFrom::Start(self.header.tile_data_offset + entry.offset))])?;
+        let mut buf = vec![0; entry.length as usize];
+        add_await([self.reader.read_exact(&mut buf)])?;
+
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_tile_read();
+        }
+
+        Ok(Some(buf))
+    }
+
+    /// Returns the data of the tile with the specified coordinates.
+    ///
+    /// See [`get_tile_by_id`](Self::get_tile_by_id) for further details on the return type.
+    ///
+    /// # Errors
+    /// See [`get_tile_by_id`](Self::get_tile_by_id) for details on possible errors.
+    pub async fn get_tile(&mut self, x: u64, y: u64, z: u8) -> Result<Option<Vec<u8>>> {
+        add_await([self.get_tile_by_id(tile_id(z, x, y))])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::PMTiles;
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    const PM_TILES_BYTES2: &[u8] = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+
+    #[test]
+    fn test_get_tile_by_id() -> Result<()> {
+        for bytes in [PM_TILES_BYTES, PM_TILES_BYTES2] {
+            let mut expected = PMTiles::from_bytes(bytes)?;
+            let mut reader = PMTilesReader::from_reader(Cursor::new(bytes))?;
+
+            let mut ids = expected.tile_ids().collect::<Vec<_>>();
+            ids.push(u64::from(u32::MAX));
+
+            for id in ids {
+                assert_eq!(reader.get_tile_by_id(id)?, expected.get_tile_by_id(id)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_leaf_directories() -> Result<()> {
+        // write an archive with leaf directories
+        let mut pm_tiles = PMTiles::new(crate::TileType::Png, crate::Compression::None);
+        pm_tiles.internal_compression = crate::Compression::None;
+        for id in 0..20_000 {
+            pm_tiles.add_tile(id, id.to_le_bytes())?;
+        }
+        let mut bytes = Vec::new();
+        pm_tiles.to_writer(&mut Cursor::new(&mut bytes))?;
+
+        let mut reader = PMTilesReader::from_reader(Cursor::new(&bytes))?;
+        assert!(reader
+            .root_directory()
+            .into_iter()
+            .any(Entry::is_leaf_dir_entry));
+
+        assert_eq!(reader.get_tile_by_id(0)?, Some(0u64.to_le_bytes().to_vec()));
+        assert_eq!(
+            reader.get_tile_by_id(12_345)?,
+            Some(12_345u64.to_le_bytes().to_vec())
+        );
+        assert_eq!(reader.get_tile_by_id(20_000)?, None);
+
+        assert!(reader.contains_tile(12_345)?);
+        assert!(!reader.contains_tile(20_000)?);
+
+        let entry = reader
+            .get_tile_entry(12_345)?
+            .unwrap_or_else(|| unreachable!());
+        assert_eq!(entry.tile_id, 12_345);
+        assert_eq!(entry.length, 8);
+        assert_eq!(entry.run_length, 1);
+
+        assert_eq!(reader.tile_size(12_345)?, Some(8));
+        assert_eq!(reader.tile_size(20_000)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_warm_cache() -> Result<()> {
+        let file_path = "./test/protomaps_vector_planet_odbl_z10_without_data.pmtiles";
+        let mut reader = PMTilesReader::from_reader(std::fs::File::open(file_path)?)?;
+        let mut uncached = PMTilesReader::from_reader(std::fs::File::open(file_path)?)?;
+
+        let bbox = (11.15, 43.72, 11.33, 43.84);
+        let num_read = reader.warm_cache(bbox, 0..=10)?;
+        assert!(num_read > 0);
+        assert_eq!(reader.num_cached_leaf_directories(), num_read);
+
+        // cached leaf directories are not read again
+        assert_eq!(reader.warm_cache(bbox, 5..=10)?, 0);
+
+        for z in 0..=10 {
+            for range in tile_id_ranges_in_bbox(bbox, z) {
+                for id in range {
+                    assert_eq!(reader.get_tile_entry(id)?, uncached.get_tile_entry(id)?);
+                }
+            }
+        }
+
+        // tiles outside of the warmed region are still found
+        assert_eq!(
+            reader.get_tile_entry(1_027_840)?,
+            uncached.get_tile_entry(1_027_840)?
+        );
+
+        reader.clear_cache();
+        assert_eq!(reader.num_cached_leaf_directories(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn test_metrics() -> Result<()> {
+        let file_path = "./test/protomaps_vector_planet_odbl_z10_without_data.pmtiles";
+        let metrics = Arc::new(crate::metrics::Metrics::new());
+        let mut reader = PMTilesReader::from_reader(std::fs::File::open(file_path)?)?
+            .with_metrics(metrics.clone());
+
+        reader.get_tile_entry(1_027_840)?;
+        let snapshot = metrics.snapshot();
+        assert!(snapshot.cache_misses > 0);
+        assert_eq!(snapshot.cache_hits, 0);
+
+        let bbox = (11.15, 43.72, 11.33, 43.84);
+        reader.warm_cache(bbox, 10..=10)?;
+
+        let tile_id = tile_id_ranges_in_bbox(bbox, 10)[0].start;
+        reader.get_tile_entry(tile_id)?;
+        assert!(metrics.snapshot().cache_hits > 0);
+        assert_eq!(metrics.snapshot().cache_misses, snapshot.cache_misses);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_warm_cache_async() -> Result<()> {
+        let bytes = std::fs::read("./test/protomaps_vector_planet_odbl_z10_without_data.pmtiles")?;
+        let bbox = (-10.0, 35.0, 30.0, 60.0);
+
+        let mut reader = PMTilesReader::from_reader(Cursor::new(&bytes))?;
+        let num_read = reader.warm_cache(bbox, 0..=10)?;
+
+        tokio_test::block_on(async {
+            let mut async_reader =
+                PMTilesReader::from_async_reader(futures::io::Cursor::new(&bytes)).await?;
+            assert_eq!(async_reader.warm_cache_async(bbox, 0..=10).await?, num_read);
+
+            let id = tile_id(10, 540, 360);
+            assert_eq!(
+                async_reader.get_tile_entry_async(id).await?,
+                reader.get_tile_entry(id)?
+            );
+            assert_eq!(
+                async_reader.tile_size_async(id).await?,
+                reader.tile_size(id)?
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_meta_data() -> Result<()> {
+        let expected = PMTiles::from_bytes(PM_TILES_BYTES2)?;
+        let mut reader = PMTilesReader::from_reader(Cursor::new(PM_TILES_BYTES2))?;
+
+        assert_eq!(reader.meta_data()?, expected.meta_data);
+        assert_eq!(reader.header().tile_type, expected.tile_type);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io::{Error, ErrorKind, Read, Result, Seek, SeekFrom},
+    time::Duration,
+};
+
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncSeek};
+#[cfg(feature = "async")]
+use futures_timer::Delay;
+
+/// Policy describing which reads are retried and how long to wait between attempts
+/// (see [`RetryReader`]).
+///
+/// The delay before the `n`-th retry is `initial_backoff * 2^(n - 1)`, limited to `max_backoff`. With
+/// [`jitter`](Self::jitter) enabled, a random delay between half and the full delay is used instead,
+/// so many clients failing at the same time do not retry in lockstep.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::RetryPolicy;
+/// # use std::time::Duration;
+/// let policy = RetryPolicy {
+///     max_retries: 5,
+///     initial_backoff: Duration::from_millis(50),
+///     retry_client_errors: true,
+///     ..RetryPolicy::default()
+/// };
+///
+/// assert_eq!(policy.backoff(1), Duration::from_millis(50));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct RetryPolicy {
+    /// Maximum number of retries of a single read (default `3`)
+    pub max_retries: u32,
+
+    /// Delay before the first retry (default 100 ms)
+    pub initial_backoff: Duration,
+
+    /// Maximum delay between two attempts (default 10 s)
+    pub max_backoff: Duration,
+
+    /// Whether the delay is randomized (default `true`)
+    pub jitter: bool,
+
+    /// Whether HTTP responses with a `5xx` status (server errors) are retried (default `true`)
+    pub retry_server_errors: bool,
+
+    /// Whether HTTP responses with a `429 Too Many Requests` status are retried (default `true`)
+    pub retry_too_many_requests: bool,
+
+    /// Whether HTTP responses with any other `4xx` status (client errors) are retried (default `false`)
+    pub retry_client_errors: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+            retry_server_errors: true,
+            retry_too_many_requests: true,
+            retry_client_errors: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before retry number `retry` (starting at `1`), without jitter.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Returns the delay before retry number `retry` (starting at `1`), including jitter if enabled.
+    fn delay(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        if !self.jitter {
+            return backoff;
+        }
+
+        // every `RandomState` is seeded randomly, which is good enough for jitter
+        let random = RandomState::new().build_hasher().finish();
+        let half = backoff / 2;
+
+        #[allow(clippy::cast_precision_loss)]
+        half.mul_f64(1.0 + random as f64 / u64::MAX as f64)
+    }
+
+    /// Returns `true` if a read, which failed with `err`, should be retried.
+    ///
+    /// Time outs, interrupted or reset connections and short reads ([`ErrorKind::UnexpectedEof`]) are
+    /// retried. Errors of HTTP backends (e.g. [`HttpReader`](crate::HttpReader)) are retried according
+    /// to the status class of the response.
+    pub fn should_retry(&self, err: &Error) -> bool {
+        #[cfg(feature = "reqwest")]
+        if let Some(status) = err
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<reqwest::Error>())
+            .and_then(reqwest::Error::status)
+        {
+            return self.should_retry_status(status.as_u16());
+        }
+
+        matches!(
+            err.kind(),
+            ErrorKind::TimedOut
+                | ErrorKind::Interrupted
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe
+                | ErrorKind::UnexpectedEof
+        )
+    }
+
+    /// Returns `true` if an HTTP response with the status code `status` should be retried.
+    pub const fn should_retry_status(&self, status: u16) -> bool {
+        match status {
+            429 => self.retry_too_many_requests,
+            400..=499 => self.retry_client_errors,
+            500..=599 => self.retry_server_errors,
+            _ => false,
+        }
+    }
+}
+
+/// A reader adapter, which retries failed reads of the wrapped reader with exponential backoff
+/// according to a [`RetryPolicy`].
+///
+/// This makes serving tiles from remote archives (e.g. [`ObjectStoreReader`](crate::ObjectStoreReader)
+/// or [`HttpReader`](crate::HttpReader)) resilient to transient errors, without wrapping every call of
+/// [`get_tile`](crate::PMTiles::get_tile). A failed read must not have consumed any data of the wrapped
+/// reader (as guaranteed by [`Read::read`]), so it can simply be repeated. Seeks are passed through.
+///
+/// Synchronous reads block the current thread while waiting, asynchronous reads use a timer,
+/// which does not depend on any specific runtime.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{PMTiles, RetryPolicy, RetryReader};
+/// let file = std::fs::File::open("./test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles").unwrap();
+///
+/// let reader = RetryReader::new(file, RetryPolicy::default());
+/// let mut pm_tiles = PMTiles::from_reader(reader).unwrap();
+/// # assert!(pm_tiles.get_tile(0, 0, 0).unwrap().is_some());
+/// ```
+#[derive(Debug)]
+pub struct RetryReader<R> {
+    inner: R,
+    policy: RetryPolicy,
+    retries: u32,
+    #[cfg(feature = "async")]
+    delay: Option<Delay>,
+}
+
+impl<R> RetryReader<R> {
+    /// Wraps `inner`, retrying failed reads according to `policy`.
+    pub const fn new(inner: R, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            retries: 0,
+            #[cfg(feature = "async")]
+            delay: None,
+        }
+    }
+
+    /// Returns the policy, according to which failed reads are retried.
+    pub const fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Returns a reference to the wrapped reader.
+    pub const fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps this reader, returning the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns the delay before the next retry or [`None`] if `err` should not be retried (anymore).
+    fn next_delay(&mut self, err: &Error) -> Option<Duration> {
+        if self.retries >= self.policy.max_retries || !self.policy.should_retry(err) {
+            self.retries = 0;
+            return None;
+        }
+
+        self.retries += 1;
+        Some(self.policy.delay(self.retries))
+    }
+}
+
+impl<R: Read> Read for RetryReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        loop {
+            match self.inner.read(buf) {
+                Ok(len) => {
+                    self.retries = 0;
+                    return Ok(len);
+                }
+                Err(err) => match self.next_delay(&err) {
+                    Some(delay) => std::thread::sleep(delay),
+                    None => return Err(err),
+                },
+            }
+        }
+    }
+}
+
+impl<R: Seek> Seek for RetryReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncRead + Unpin> AsyncRead for RetryReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        loop {
+            if let Some(delay) = &mut self.delay {
+                if Pin::new(delay).poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+
+                self.delay = None;
+            }
+
+            match Pin::new(&mut self.inner).poll_read(cx, buf) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(len)) => {
+                    self.retries = 0;
+                    return Poll::Ready(Ok(len));
+                }
+                Poll::Ready(Err(err)) => match self.next_delay(&err) {
+                    Some(delay) => self.delay = Some(Delay::new(delay)),
+                    None => return Poll::Ready(Err(err)),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncSeek + Unpin> AsyncSeek for RetryReader<R> {
+    fn poll_seek(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<Result<u64>> {
+        // a pending retry is for the previous position
+        self.delay = None;
+        self.retries = 0;
+
+        Pin::new(&mut self.inner).poll_seek(cx, pos)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// A reader, which fails with `kind` the first `failures` times it is read from.
+    struct FlakyReader {
+        inner: Cursor<Vec<u8>>,
+        failures: u32,
+        kind: ErrorKind,
+    }
+
+    impl Read for FlakyReader {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(Error::new(self.kind, "flaky"));
+            }
+
+            self.inner.read(buf)
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl AsyncRead for FlakyReader {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<Result<usize>> {
+            Poll::Ready(self.get_mut().read(buf))
+        }
+    }
+
+    fn flaky(failures: u32, kind: ErrorKind) -> FlakyReader {
+        FlakyReader {
+            inner: Cursor::new(vec![1, 2, 3]),
+            failures,
+            kind,
+        }
+    }
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        max_retries: 2,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(5),
+        jitter: true,
+        retry_server_errors: true,
+        retry_too_many_requests: true,
+        retry_client_errors: false,
+    };
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(100), Duration::from_secs(10));
+
+        for retry in 1..10 {
+            let delay = policy.delay(retry);
+            assert!(delay >= policy.backoff(retry) / 2 && delay <= policy.backoff(retry));
+        }
+    }
+
+    #[test]
+    fn test_should_retry_status() {
+        let policy = RetryPolicy::default();
+
+        assert!(policy.should_retry_status(503));
+        assert!(policy.should_retry_status(429));
+        assert!(!policy.should_retry_status(404));
+        assert!(!policy.should_retry_status(200));
+        assert!(RetryPolicy {
+            retry_client_errors: true,
+            ..policy
+        }
+        .should_retry_status(404));
+    }
+
+    #[test]
+    fn test_read() -> Result<()> {
+        let mut reader = RetryReader::new(flaky(2, ErrorKind::TimedOut), POLICY);
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        assert_eq!(buf, [1, 2, 3]);
+
+        let mut reader = RetryReader::new(flaky(3, ErrorKind::TimedOut), POLICY);
+        assert!(reader.read_to_end(&mut Vec::new()).is_err());
+
+        let mut reader = RetryReader::new(flaky(1, ErrorKind::NotFound), POLICY);
+        let err = reader.read(&mut [0; 3]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(reader.read(&mut [0; 3])?, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_read_async() -> Result<()> {
+        use futures::AsyncReadExt;
+
+        tokio_test::block_on(async {
+            let mut reader = RetryReader::new(flaky(2, ErrorKind::UnexpectedEof), POLICY);
+            let mut buf = Vec::new();
+            AsyncReadExt::read_to_end(&mut reader, &mut buf).await?;
+            assert_eq!(buf, [1, 2, 3]);
+
+            let mut reader = RetryReader::new(flaky(3, ErrorKind::UnexpectedEof), POLICY);
+            assert!(AsyncReadExt::read_to_end(&mut reader, &mut Vec::new())
+                .await
+                .is_err());
+
+            Ok(())
+        })
+    }
+}
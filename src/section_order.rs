@@ -0,0 +1,86 @@
+/// A section of a `PMTiles` archive, whose position within the archive can be chosen freely.
+///
+/// The header and the root directory are not included, as they always have to be located
+/// at the very start of an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// JSON meta data section
+    Metadata,
+
+    /// Leaf directories section
+    LeafDirectories,
+
+    /// Tile data section
+    TileData,
+}
+
+/// The order in which sections are written after the header and root directory.
+///
+/// As all sections are addressed by offsets in the header, readers do not depend on
+/// a specific order. Placing the meta data directly after the root directory allows readers to
+/// fetch both with a single request, while placing the tile data last makes it
+/// easier to append to an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum SectionOrder {
+    /// Meta data, leaf directories, tile data (default)
+    #[default]
+    MetadataLeafDirectoriesTileData,
+
+    /// Meta data, tile data, leaf directories
+    MetadataTileDataLeafDirectories,
+
+    /// Leaf directories, meta data, tile data
+    LeafDirectoriesMetadataTileData,
+
+    /// Leaf directories, tile data, meta data
+    LeafDirectoriesTileDataMetadata,
+
+    /// Tile data, meta data, leaf directories
+    TileDataMetadataLeafDirectories,
+
+    /// Tile data, leaf directories, meta data
+    TileDataLeafDirectoriesMetadata,
+}
+
+impl SectionOrder {
+    /// Returns the sections in the order in which they are written.
+    pub const fn sections(&self) -> [Section; 3] {
+        use Section::{LeafDirectories, Metadata, TileData};
+
+        match self {
+            Self::MetadataLeafDirectoriesTileData => [Metadata, LeafDirectories, TileData],
+            Self::MetadataTileDataLeafDirectories => [Metadata, TileData, LeafDirectories],
+            Self::LeafDirectoriesMetadataTileData => [LeafDirectories, Metadata, TileData],
+            Self::LeafDirectoriesTileDataMetadata => [LeafDirectories, TileData, Metadata],
+            Self::TileDataMetadataLeafDirectories => [TileData, Metadata, LeafDirectories],
+            Self::TileDataLeafDirectoriesMetadata => [TileData, LeafDirectories, Metadata],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sections() {
+        assert_eq!(
+            SectionOrder::default().sections(),
+            [
+                Section::Metadata,
+                Section::LeafDirectories,
+                Section::TileData
+            ]
+        );
+
+        assert_eq!(
+            SectionOrder::TileDataLeafDirectoriesMetadata.sections(),
+            [
+                Section::TileData,
+                Section::LeafDirectories,
+                Section::Metadata
+            ]
+        );
+    }
+}
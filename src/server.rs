@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+use crate::{
+    util::{is_not_modified, tile_etag, tile_id, ReadAt},
+    PMTiles,
+};
+
+/// A `PMTiles` archive, which is shared between all requests handled by a [`router`].
+pub type SharedPMTiles<R> = Arc<PMTiles<R>>;
+
+/// Creates an [`axum::Router`] serving the tiles of `pm_tiles` at `/{z}/{x}/{y}`.
+///
+/// An optional file extension of `y` is ignored, so `/{z}/{x}/{y}.pbf` or `/{z}/{x}/{y}.png` work as
+/// well. Responses of existing tiles have their `Content-Type` and `Content-Encoding` headers set
+/// according to the tile type and tile compression of the archive, as well as an `ETag` header
+/// (see [`tile_etag`]). Requests with a matching `If-None-Match` header are answered with
+/// `304 Not Modified`. Missing tiles (or invalid
+/// coordinates) are answered with `404 Not Found` and errors while reading tiles are logged
+/// (with the [`log`](https://docs.rs/log) crate) and answered with an empty `500 Internal Server Error`.
+///
+/// Tiles are read through a shared reference with [`PMTiles::read_tile_by_id`], so requests are
+/// handled concurrently. Wrap readers, which only implement [`Read`](std::io::Read) +
+/// [`Seek`](std::io::Seek), in a [`SharedReader`](crate::util::SharedReader).
+///
+/// As every [`Router`] implements [`tower::Service`](https://docs.rs/tower/latest/tower/trait.Service.html),
+/// the result can also be nested into other routers or used with any tower compatible server.
+///
+/// # Example
+/// ```rust,no_run
+/// # use pmtiles2::{server, PMTiles};
+/// let file = std::fs::File::open("./test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles").unwrap();
+/// let pm_tiles = PMTiles::from_reader(file).unwrap();
+///
+/// let app = axum::Router::new().nest("/tiles", server::router(pm_tiles));
+/// // axum::serve(listener, app).await
+/// ```
+pub fn router<R>(pm_tiles: PMTiles<R>) -> Router
+where
+    R: ReadAt + Send + Sync + 'static,
+{
+    router_with_shared(Arc::new(pm_tiles))
+}
+
+/// Creates an [`axum::Router`] serving the tiles of an archive, which is shared with other parts
+/// of the application.
+///
+/// See [`router`] for details.
+pub fn router_with_shared<R>(pm_tiles: SharedPMTiles<R>) -> Router
+where
+    R: ReadAt + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/{z}/{x}/{y}", get(get_tile::<R>))
+        .with_state(pm_tiles)
+}
+
+/// Parses the y coordinate of a request, ignoring an optional file extension.
+fn parse_y(y: &str) -> Option<u64> {
+    y.split_once('.').map_or(y, |(y, _)| y).parse().ok()
+}
+
+async fn get_tile<R>(
+    State(pm_tiles): State<SharedPMTiles<R>>,
+    Path((z, x, y)): Path<(u8, u64, String)>,
+    request_headers: HeaderMap,
+) -> Response
+where
+    R: ReadAt + Send + Sync + 'static,
+{
+    let Some(y) = parse_y(&y) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    if z > 31 || x >= 1 << z || y >= 1 << z {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let data = match pm_tiles.read_tile_by_id(tile_id(z, x, y)) {
+        Ok(Some(data)) => data,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            log::error!("Failed to read tile {z}/{x}/{y}: {err}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let etag = tile_etag(&data);
+    let if_none_match = request_headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok());
+
+    let mut response = if is_not_modified(&etag, None, if_none_match, None) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        data.into_response()
+    };
+    let headers = response.headers_mut();
+
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, etag);
+    }
+
+    if let Some(content_type) = pm_tiles.tile_type.http_content_type() {
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    }
+
+    if let Some(content_encoding) = pm_tiles.tile_compression.http_content_encoding() {
+        headers.insert(
+            header::CONTENT_ENCODING,
+            HeaderValue::from_static(content_encoding),
+        );
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{Compression, TileType};
+
+    const PM_TILES_BYTES: &[u8] = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+
+    async fn request(app: Router, uri: &str) -> Response {
+        request_with_etag(app, uri, "").await
+    }
+
+    async fn request_with_etag(app: Router, uri: &str, etag: &str) -> Response {
+        let mut builder = Request::builder().uri(uri);
+        if !etag.is_empty() {
+            builder = builder.header(header::IF_NONE_MATCH, etag);
+        }
+
+        let request = builder
+            .body(Body::empty())
+            .unwrap_or_else(|_| unreachable!());
+
+        app.oneshot(request)
+            .await
+            .unwrap_or_else(|err| match err {})
+    }
+
+    #[test]
+    fn test_router() -> crate::Result<()> {
+        tokio_test::block_on(async {
+            let app = router(PMTiles::from_bytes(PM_TILES_BYTES)?);
+
+            let response = request(app.clone(), "/0/0/0.pbf").await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response.headers()[header::CONTENT_TYPE],
+                "application/vnd.mapbox-vector-tile"
+            );
+            assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+
+            let response = request(app.clone(), "/0/0/0").await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let etag = response.headers()[header::ETAG]
+                .to_str()
+                .unwrap_or_default();
+            let response = request_with_etag(app.clone(), "/0/0/0", etag).await;
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+            let response = request_with_etag(app.clone(), "/0/0/0", "\"abc\"").await;
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let response = request(app.clone(), "/1/0/0").await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            let response = request(app.clone(), "/1/2/0").await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            let response = request(app, "/0/0/abc").await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            // errors are not exposed to clients
+            let mut broken = PMTiles::new(TileType::Png, Compression::None);
+            broken.tile_manager.add_offset_tile(0, 0, 1)?;
+            let response = request(router(broken), "/0/0/0").await;
+            assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap_or_default();
+            assert!(body.is_empty());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_parse_y() {
+        assert_eq!(parse_y("42"), Some(42));
+        assert_eq!(parse_y("42.png"), Some(42));
+        assert_eq!(parse_y("x.png"), None);
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+/// Statistics on the tiles of a single zoom level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZoomStats {
+    /// Number of tiles
+    pub num_tiles: u64,
+
+    /// Sum of the sizes of all tiles (in bytes), counting deduplicated tiles multiple times
+    pub total_bytes: u64,
+
+    /// Size of the smallest tile (in bytes)
+    pub min_tile_size: u32,
+
+    /// Size of the largest tile (in bytes)
+    pub max_tile_size: u32,
+}
+
+impl ZoomStats {
+    /// Returns the average size of a tile (in bytes).
+    #[allow(clippy::cast_precision_loss)]
+    pub fn avg_tile_size(&self) -> f64 {
+        if self.num_tiles == 0 {
+            return 0.0;
+        }
+
+        self.total_bytes as f64 / self.num_tiles as f64
+    }
+
+    fn add(&mut self, size: u32) {
+        self.num_tiles += 1;
+        self.total_bytes += u64::from(size);
+        self.min_tile_size = self.min_tile_size.min(size);
+        self.max_tile_size = self.max_tile_size.max(size);
+    }
+}
+
+impl Default for ZoomStats {
+    fn default() -> Self {
+        Self {
+            num_tiles: 0,
+            total_bytes: 0,
+            min_tile_size: u32::MAX,
+            max_tile_size: 0,
+        }
+    }
+}
+
+/// Statistics on the tiles of an archive, as returned by [`PMTiles::stats`](crate::PMTiles::stats).
+///
+/// Sizes are the sizes of the (possibly compressed) tile data as stored in the archive.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::PMTiles;
+/// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+/// let pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+///
+/// let stats = pm_tiles.stats();
+/// assert_eq!(stats.zooms[&3].num_tiles, 64);
+/// assert!(stats.dedup_savings() > 0);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileStats {
+    /// Statistics per zoom level (only includes zoom levels with at least one tile)
+    pub zooms: BTreeMap<u8, ZoomStats>,
+
+    /// Number of distinct tile contents
+    pub num_unique_tiles: u64,
+
+    /// Sum of the sizes of all distinct tile contents (in bytes), i.e. the size of the tile data section
+    pub unique_bytes: u64,
+}
+
+impl TileStats {
+    /// Returns the number of tiles of all zoom levels.
+    pub fn num_tiles(&self) -> u64 {
+        self.zooms.values().map(|z| z.num_tiles).sum()
+    }
+
+    /// Returns the sum of the sizes of all tiles of all zoom levels (in bytes),
+    /// counting deduplicated tiles multiple times.
+    pub fn total_bytes(&self) -> u64 {
+        self.zooms.values().map(|z| z.total_bytes).sum()
+    }
+
+    /// Returns the number of bytes saved by deduplicating tiles with the same content.
+    pub fn dedup_savings(&self) -> u64 {
+        self.total_bytes().saturating_sub(self.unique_bytes)
+    }
+
+    #[allow(clippy::redundant_pub_crate)]
+    pub(crate) fn add_tile(&mut self, z: u8, size: u32) {
+        self.zooms.entry(z).or_default().add(size);
+    }
+
+    #[allow(clippy::redundant_pub_crate)]
+    pub(crate) fn add_unique_tile(&mut self, size: u32) {
+        self.num_unique_tiles += 1;
+        self.unique_bytes += u64::from(size);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut stats = TileStats::default();
+        stats.add_tile(0, 10);
+        stats.add_tile(1, 4);
+        stats.add_tile(1, 8);
+        stats.add_unique_tile(10);
+        stats.add_unique_tile(4);
+
+        assert_eq!(stats.num_tiles(), 3);
+        assert_eq!(stats.total_bytes(), 22);
+        assert_eq!(stats.dedup_savings(), 8);
+
+        let z1 = stats.zooms[&1];
+        assert_eq!((z1.min_tile_size, z1.max_tile_size), (4, 8));
+        assert!((z1.avg_tile_size() - 6.0).abs() < f64::EPSILON);
+
+        assert!(ZoomStats::default().avg_tile_size().abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,719 @@
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    io::{Cursor, ErrorKind, Read, Seek, Write},
+    ops::RangeBounds,
+};
+
+use ahash::{AHasher, RandomState};
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use serde_json::{Map as JSONMap, Value as JSONValue};
+
+#[cfg(feature = "async")]
+use crate::util::read_entries_async;
+use crate::{
+    header::{LatLng, HEADER_BYTES},
+    tile_manager::push_entry,
+    util::{compress, read_entries, write_directories},
+    Compression, Entry, Error, Header, Result, TileType,
+};
+
+/// Number of bytes reserved for the header and the root directory at the start of the archive.
+const RESERVED_BYTES: u64 = 16384;
+
+/// A writer for `PMTiles` archives, which writes tile data to the output as soon as it is added.
+///
+/// In contrast to [`PMTiles`](crate::PMTiles), which holds all tiles until the archive is written,
+/// only directory entries and a hash per distinct tile content (used to dedupe tiles) are kept in memory.
+/// This allows writing archives, whose tile data does not fit into memory.
+///
+/// Tiles have to be added in strictly ascending order of their tile ids. The first 16 KiB of the
+/// output are reserved for the header and the root directory, which are written by [`finish`](Self::finish)
+/// after all tiles have been added. Meta data and leaf directories are written after the tile data.
+///
+/// The header fields (e.g. [`min_zoom`](Self::min_zoom)) and the [`meta_data`](Self::meta_data) can be
+/// changed at any time before calling [`finish`](Self::finish).
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{PMTilesStreamWriter, TileType, Compression, util::tile_id};
+/// # let dir = temp_dir::TempDir::new().unwrap();
+/// # let file_path = dir.path().join("foo.pmtiles");
+/// let file = std::fs::File::create(file_path).unwrap();
+/// let mut writer = PMTilesStreamWriter::new(file, TileType::Png, Compression::None);
+///
+/// writer.add_tile(tile_id(0, 0, 0), vec![1, 3, 3, 7]).unwrap();
+/// writer.add_tile(tile_id(1, 0, 0), vec![4, 2]).unwrap();
+/// writer.max_zoom = 1;
+///
+/// let file = writer.finish().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct PMTilesStreamWriter<W> {
+    /// Type of tiles
+    pub tile_type: TileType,
+
+    /// Compression of tiles
+    pub tile_compression: Compression,
+
+    /// Compression of directories and meta data
+    pub internal_compression: Compression,
+
+    /// Minimum zoom of all tiles this archive
+    pub min_zoom: u8,
+
+    /// Maximum zoom of all tiles this archive
+    pub max_zoom: u8,
+
+    /// Center zoom
+    ///
+    /// _Implementations may use this to set the default zoom_
+    pub center_zoom: u8,
+
+    /// Minimum longitude of bounds of available tiles
+    pub min_longitude: f64,
+
+    /// Minimum latitude of bounds of available tiles
+    pub min_latitude: f64,
+
+    /// Maximum longitude of bounds of available tiles
+    pub max_longitude: f64,
+
+    /// Maximum latitude of bounds of available tiles
+    pub max_latitude: f64,
+
+    /// Center longitude
+    ///
+    /// _Implementations may use the center longitude and latitude to set the default location_
+    pub center_longitude: f64,
+
+    /// Center latitude
+    ///
+    /// _Implementations may use the center longitude and latitude to set the default location_
+    pub center_latitude: f64,
+
+    /// JSON meta data of this archive
+    pub meta_data: JSONMap<String, JSONValue>,
+
+    output: W,
+
+    /// Position of the start of the archive in `output`, once anything was written
+    start_offset: Option<u64>,
+
+    entries: Vec<Entry>,
+
+    /// hash => offset+length
+    offset_length_by_hash: HashMap<u64, (u64, u32), RandomState>,
+
+    tile_data_length: u64,
+    last_tile_id: Option<u64>,
+    num_addressed_tiles: u64,
+    num_tile_content: u64,
+}
+
+impl<W> PMTilesStreamWriter<W> {
+    /// Constructs a new writer, with no meta data, an [`internal_compression`](Self::internal_compression) of GZIP
+    /// and all numeric fields set to `0`.
+    ///
+    /// Nothing is written to `output` until the first tile is added.
+    ///
+    /// # Arguments
+    /// * `output` - Writer to write the archive to
+    /// * `tile_type` - Type of tiles in this archive
+    /// * `tile_compression` - Compression of tiles in this archive
+    pub fn new(output: W, tile_type: TileType, tile_compression: Compression) -> Self {
+        Self {
+            tile_type,
+            tile_compression,
+            internal_compression: Compression::GZip,
+            min_zoom: 0,
+            max_zoom: 0,
+            center_zoom: 0,
+            min_longitude: 0.0,
+            min_latitude: 0.0,
+            max_longitude: 0.0,
+            max_latitude: 0.0,
+            center_longitude: 0.0,
+            center_latitude: 0.0,
+            meta_data: JSONMap::new(),
+            output,
+            start_offset: None,
+            entries: Vec::new(),
+            offset_length_by_hash: HashMap::default(),
+            tile_data_length: 0,
+            last_tile_id: None,
+            num_addressed_tiles: 0,
+            num_tile_content: 0,
+        }
+    }
+
+    /// Returns the number of tiles added so far.
+    pub const fn num_addressed_tiles(&self) -> u64 {
+        self.num_addressed_tiles
+    }
+
+    fn calculate_hash(data: &[u8]) -> u64 {
+        let mut hasher = AHasher::default();
+        data.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    const fn check_tile_id(&self, tile_id: u64) -> Result<()> {
+        match self.last_tile_id {
+            Some(previous) if tile_id <= previous => {
+                Err(Error::TileIdNotAscending { tile_id, previous })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn header(
+        &self,
+        root_directory_length: u64,
+        meta_data_length: u64,
+        leaf_directories_length: u64,
+    ) -> Header {
+        let tile_data_offset = RESERVED_BYTES;
+        let json_metadata_offset = tile_data_offset + self.tile_data_length;
+        let leaf_directories_offset = json_metadata_offset + meta_data_length;
+
+        Header {
+            spec_version: 3,
+            root_directory_offset: u64::from(HEADER_BYTES),
+            root_directory_length,
+            json_metadata_offset,
+            json_metadata_length: meta_data_length,
+            leaf_directories_offset,
+            leaf_directories_length,
+            tile_data_offset,
+            tile_data_length: self.tile_data_length,
+            num_addressed_tiles: self.num_addressed_tiles,
+            num_tile_entries: self.entries.len() as u64,
+            num_tile_content: self.num_tile_content,
+            clustered: true,
+            internal_compression: self.internal_compression,
+            tile_compression: self.tile_compression,
+            tile_type: self.tile_type,
+            min_zoom: self.min_zoom,
+            max_zoom: self.max_zoom,
+            min_pos: LatLng {
+                longitude: self.min_longitude,
+                latitude: self.min_latitude,
+            },
+            max_pos: LatLng {
+                longitude: self.max_longitude,
+                latitude: self.max_latitude,
+            },
+            center_zoom: self.center_zoom,
+            center_pos: LatLng {
+                longitude: self.center_longitude,
+                latitude: self.center_latitude,
+            },
+        }
+    }
+}
+
+#[duplicate_item(
+    cfg_async_filter       async   add_await(code) WTraits                                    RTraits                                                    SeekFrom                start         add_tile         copy_tile_data         copy_clustered_tiles         finish         read_header(input)                        read_entries         copy(reader, writer);
+    [cfg(all())]           []      [code]          [Write + Seek]                             [(impl Read + Seek)]                                       [std::io::SeekFrom]     [start]       [add_tile]       [copy_tile_data]       [copy_clustered_tiles]       [finish]       [Header::from_reader(input)]              [read_entries]       [std::io::copy(reader, writer)]    ;
+    [cfg(feature="async")] [async] [code.await]    [AsyncWrite + Send + Unpin + AsyncSeekExt] [(impl AsyncReadExt + Send + Unpin + AsyncSeekExt)] [futures::io::SeekFrom] [start_async] [add_tile_async] [copy_tile_data_async] [copy_clustered_tiles_async] [finish_async] [Header::from_async_reader(input).await] [read_entries_async] [futures::io::copy(reader, writer).await];
+)]
+#[cfg_async_filter]
+impl<W: WTraits> PMTilesStreamWriter<W> {
+    /// Reserves space for the header and root directory, if this was not done yet,
+    /// and returns the position of the start of the archive.
+    async fn start(&mut self) -> Result<u64> {
+        if let Some(start_offset) = self.start_offset {
+            return Ok(start_offset);
+        }
+
+        let start_offset = add_await([self.output.stream_position()])?;
+        #[allow(clippy::cast_possible_truncation)]
+        add_await([self.output.write_all(&[0; RESERVED_BYTES as usize])])?;
+        self.start_offset = Some(start_offset);
+
+        Ok(start_offset)
+    }
+
+    /// Adds a tile and writes its data to the output, unless a tile with the same data was added before.
+    ///
+    /// # Arguments
+    /// * `tile_id` - Id of the tile; must be greater than the id of the previously added tile
+    /// * `data` - Data of the tile
+    ///
+    /// # Errors
+    /// Will return [`Err`] if `tile_id` is not greater than the id of the previously added tile,
+    /// `data` is empty or an I/O error occurred while writing to the output.
+    pub async fn add_tile(&mut self, tile_id: u64, data: impl AsRef<[u8]>) -> Result<()> {
+        let data = data.as_ref();
+
+        self.check_tile_id(tile_id)?;
+
+        if data.is_empty() {
+            return Err(Error::EmptyTile);
+        }
+
+        let length = u32::try_from(data.len()).map_err(|_| Error::TileTooLarge)?;
+
+        add_await([self.start()])?;
+
+        let hash = Self::calculate_hash(data);
+
+        let (offset, length) = if let Some(offset_length) = self.offset_length_by_hash.get(&hash) {
+            *offset_length
+        } else {
+            add_await([self.output.write_all(data)])?;
+
+            let offset = self.tile_data_length;
+            self.tile_data_length += u64::from(length);
+            self.num_tile_content += 1;
+            self.offset_length_by_hash.insert(hash, (offset, length));
+
+            (offset, length)
+        };
+
+        push_entry(&mut self.entries, tile_id, offset, length);
+        self.last_tile_id = Some(tile_id);
+        self.num_addressed_tiles += 1;
+
+        Ok(())
+    }
+
+    /// Copies `length` bytes starting at `offset` from `input` to the output.
+    async fn copy_tile_data(
+        &mut self,
+        input: &mut RTraits,
+        offset: u64,
+        length: u64,
+    ) -> Result<()> {
+        add_await([input.seek(SeekFrom::Start(offset))])?;
+
+        let mut reader = (&mut *input).take(length);
+        if copy([&mut reader], [&mut self.output])? != length {
+            return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(())
+    }
+
+    /// Copies tiles of a clustered archive by copying their raw tile data, without decoding or hashing it.
+    ///
+    /// Consecutive tile data is copied in a single pass and only the directories of `input` and
+    /// the offset of each distinct tile content are held in memory. This makes filtering an archive
+    /// (e.g. to a range of zoom levels) a lot cheaper than reading all tiles into a [`PMTiles`](crate::PMTiles)
+    /// and writing it again.
+    ///
+    /// Tiles, which share their data in `input`, also share their data in the output. They are
+    /// however not deduplicated against tiles added with [`add_tile`](Self::add_tile).
+    /// Header fields and meta data are not copied.
+    ///
+    /// # Arguments
+    /// * `input` - Reader with a clustered archive, whose tile compression equals [`tile_compression`](Self::tile_compression)
+    /// * `filter_range` - Range of tile ids to copy (use `..` to copy all tiles); the first tile id has to be
+    ///   greater than the id of the previously added tile
+    ///
+    /// # Errors
+    /// Will return [`Err`] if...
+    /// - `input` is not clustered ([`Error::Unsupported`])
+    /// - the tile compression of `input` differs from [`tile_compression`](Self::tile_compression) ([`Error::TileCompressionMismatch`])
+    /// - the first copied tile id is not greater than the id of the previously added tile ([`Error::TileIdNotAscending`])
+    /// - `input` is no valid archive or an I/O error occurred while reading from `input` or writing to the output
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{PMTiles, PMTilesStreamWriter, TileType, Compression, util::tile_id};
+    /// # use std::io::Cursor;
+    /// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+    /// let mut input = Cursor::new(bytes);
+    ///
+    /// let mut writer = PMTilesStreamWriter::new(Cursor::new(Vec::new()), TileType::Png, Compression::None);
+    /// writer.max_zoom = 2;
+    ///
+    /// // copy zoom levels 0 to 2
+    /// let num_tiles = writer.copy_clustered_tiles(&mut input, ..tile_id(3, 0, 0)).unwrap();
+    /// assert_eq!(num_tiles, 1 + 4 + 16);
+    ///
+    /// let bytes = writer.finish().unwrap().into_inner();
+    /// assert_eq!(PMTiles::from_bytes(bytes).unwrap().num_tiles(), 21);
+    /// ```
+    pub async fn copy_clustered_tiles(
+        &mut self,
+        input: &mut RTraits,
+        filter_range: impl RangeBounds<u64> + Sync + Send,
+    ) -> Result<u64> {
+        add_await([input.seek(SeekFrom::Start(0))])?;
+        let header = read_header([input])?;
+
+        if !header.clustered {
+            return Err(Error::Unsupported(
+                "Only tiles of clustered archives can be copied without decoding them",
+            ));
+        }
+        if header.tile_compression != self.tile_compression {
+            return Err(Error::TileCompressionMismatch(self.tile_compression));
+        }
+
+        let entries = add_await([read_entries(
+            input,
+            header.internal_compression,
+            (header.root_directory_offset, header.root_directory_length),
+            header.leaf_directories_offset,
+            filter_range,
+        )])?;
+
+        let Some(first) = entries.into_iter().next() else {
+            return Ok(0);
+        };
+        self.check_tile_id(first.tile_id)?;
+
+        add_await([self.start()])?;
+
+        // offset in input => offset in output
+        let mut offset_by_source_offset = HashMap::<u64, u64, RandomState>::default();
+
+        // offset & length of tile data in input, which still has to be copied
+        let mut pending: Option<(u64, u64)> = None;
+
+        let mut num_tiles = 0;
+
+        for entry in &entries {
+            let offset = if let Some(offset) = offset_by_source_offset.get(&entry.offset) {
+                *offset
+            } else {
+                let length = u64::from(entry.length);
+
+                match pending {
+                    Some((start, pending_length)) if start + pending_length == entry.offset => {
+                        pending = Some((start, pending_length + length));
+                    }
+                    _ => {
+                        if let Some((start, pending_length)) =
+                            pending.replace((entry.offset, length))
+                        {
+                            add_await([self.copy_tile_data(
+                                input,
+                                header.tile_data_offset + start,
+                                pending_length,
+                            )])?;
+                        }
+                    }
+                }
+
+                let offset = self.tile_data_length;
+                self.tile_data_length += length;
+                self.num_tile_content += 1;
+                offset_by_source_offset.insert(entry.offset, offset);
+
+                offset
+            };
+
+            push_entry(&mut self.entries, entry.tile_id, offset, entry.length);
+            if let Some(last) = self.entries.last_mut() {
+                last.run_length += entry.run_length - 1;
+            }
+
+            num_tiles += u64::from(entry.run_length);
+            self.last_tile_id = Some(entry.tile_id + u64::from(entry.run_length) - 1);
+        }
+
+        if let Some((start, length)) = pending {
+            add_await([self.copy_tile_data(input, header.tile_data_offset + start, length)])?;
+        }
+
+        self.num_addressed_tiles += num_tiles;
+
+        Ok(num_tiles)
+    }
+
+    /// Writes the meta data, directories and header and returns the output.
+    ///
+    /// The output is positioned at the end of the archive afterwards.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if [`internal_compression`](Self::internal_compression) was set to
+    /// [`Compression::Unknown`], the header and root directory do not fit into the reserved 16 KiB
+    /// or an I/O error occurred while writing to the output.
+    pub async fn finish(mut self) -> Result<W> {
+        let start_offset = add_await([self.start()])?;
+
+        let mut root_directory = Cursor::new(Vec::<u8>::new());
+        let leaf_directories = write_directories(
+            &mut root_directory,
+            &self.entries,
+            self.internal_compression,
+            None,
+            None,
+        )?;
+        #[allow(clippy::cast_possible_truncation)]
+        let root_directory_length = root_directory.position() as usize;
+        let mut root_directory = root_directory.into_inner();
+
+        // the root directory may have been rewritten with fewer entries, if leaf directories are required
+        root_directory.truncate(root_directory_length);
+
+        // header and root directory are written into the reserved space in front of the tile data
+        let reserved_end = u64::from(HEADER_BYTES) + root_directory.len() as u64;
+        if reserved_end > RESERVED_BYTES {
+            return Err(Error::Validation(format!(
+                "header and root directory ({reserved_end} bytes) exceed the reserved {RESERVED_BYTES} bytes"
+            )));
+        }
+
+        let mut meta_data = Vec::<u8>::new();
+        {
+            let mut writer = compress(self.internal_compression, &mut meta_data)?;
+            serde_json::to_writer(&mut writer, &self.meta_data)?;
+            writer.flush()?;
+        }
+
+        add_await([self.output.write_all(&meta_data)])?;
+        add_await([self.output.write_all(&leaf_directories)])?;
+        let end_offset = add_await([self.output.stream_position()])?;
+
+        let header = self.header(
+            root_directory.len() as u64,
+            meta_data.len() as u64,
+            leaf_directories.len() as u64,
+        );
+
+        let mut header_and_root_directory = Vec::<u8>::new();
+        header.to_writer(&mut header_and_root_directory)?;
+        header_and_root_directory.extend(root_directory);
+
+        add_await([self.output.seek(SeekFrom::Start(start_offset))])?;
+        add_await([self.output.write_all(&header_and_root_directory)])?;
+        add_await([self.output.seek(SeekFrom::Start(end_offset))])?;
+        add_await([self.output.flush()])?;
+
+        Ok(self.output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{util::tile_id, PMTiles};
+
+    #[test]
+    fn test_write() -> Result<()> {
+        let mut writer =
+            PMTilesStreamWriter::new(Cursor::new(Vec::new()), TileType::Png, Compression::None);
+        writer.max_zoom = 3;
+        writer
+            .meta_data
+            .insert("name".to_string(), JSONValue::from("test"));
+
+        for id in 0..85u64 {
+            // tiles with even ids share their data
+            let data = if id % 2 == 0 {
+                vec![0]
+            } else {
+                id.to_le_bytes().to_vec()
+            };
+            writer.add_tile(id, data)?;
+        }
+
+        let bytes = writer.finish()?.into_inner();
+        let mut pm_tiles = PMTiles::from_bytes(&bytes)?;
+
+        assert_eq!(pm_tiles.num_tiles(), 85);
+        assert_eq!(pm_tiles.max_zoom, 3);
+        assert_eq!(
+            pm_tiles.meta_data.get("name"),
+            Some(&JSONValue::from("test"))
+        );
+        assert_eq!(pm_tiles.get_tile_by_id(2)?, Some(vec![0]));
+        assert_eq!(
+            pm_tiles.get_tile_by_id(3)?,
+            Some(3u64.to_le_bytes().to_vec())
+        );
+        assert_eq!(pm_tiles.get_tile(0, 0, 0)?, Some(vec![0]));
+
+        let header = Header::from_reader(&mut Cursor::new(&bytes))?;
+        assert_eq!(header.num_tile_content, 43);
+        assert_eq!(header.tile_data_length, 1 + 42 * 8);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_leaf_directories() -> Result<()> {
+        let mut writer =
+            PMTilesStreamWriter::new(Cursor::new(Vec::new()), TileType::Png, Compression::None);
+
+        writer.internal_compression = Compression::None;
+
+        // gaps between tile ids prevent entries from being combined into runs
+        let tile_ids = (0..20_000u64).map(|i| i * 3);
+        for id in tile_ids.clone() {
+            writer.add_tile(id, id.to_le_bytes())?;
+        }
+
+        let bytes = writer.finish()?.into_inner();
+        let header = Header::from_reader(&mut Cursor::new(&bytes))?;
+        assert!(header.leaf_directories_length > 0);
+        assert!(header.root_directory_length <= RESERVED_BYTES - u64::from(HEADER_BYTES));
+        assert!(PMTiles::validate(&mut Cursor::new(&bytes))?.is_valid());
+
+        let mut pm_tiles = PMTiles::from_bytes(&bytes)?;
+        assert_eq!(pm_tiles.num_tiles(), 20_000);
+        for id in tile_ids {
+            assert_eq!(
+                pm_tiles.get_tile_by_id(id)?,
+                Some(id.to_le_bytes().to_vec())
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty() -> Result<()> {
+        let writer =
+            PMTilesStreamWriter::new(Cursor::new(Vec::new()), TileType::Png, Compression::None);
+
+        let bytes = writer.finish()?.into_inner();
+        let pm_tiles = PMTiles::from_bytes(&bytes)?;
+        assert_eq!(pm_tiles.num_tiles(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_order() -> Result<()> {
+        let mut writer =
+            PMTilesStreamWriter::new(Cursor::new(Vec::new()), TileType::Png, Compression::None);
+
+        writer.add_tile(tile_id(1, 0, 0), [1])?;
+        assert!(matches!(
+            writer.add_tile(tile_id(1, 0, 0), [1]),
+            Err(Error::TileIdNotAscending { .. })
+        ));
+        assert!(writer.add_tile(tile_id(0, 0, 0), [1]).is_err());
+        assert!(writer.add_tile(tile_id(1, 1, 0), []).is_err());
+
+        assert_eq!(writer.num_addressed_tiles(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_clustered_tiles() -> Result<()> {
+        let bytes: &[u8] = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+        let mut source = PMTiles::from_bytes(bytes)?;
+
+        let mut writer =
+            PMTilesStreamWriter::new(Cursor::new(Vec::new()), TileType::Mvt, Compression::GZip);
+        writer.add_tile(0, [0x1f, 0x8b])?;
+
+        let num_tiles = writer.copy_clustered_tiles(&mut Cursor::new(bytes), 1..)?;
+        assert_eq!(num_tiles, source.num_tiles() as u64 - 1);
+
+        let bytes = writer.finish()?.into_inner();
+        let mut pm_tiles = PMTiles::from_bytes(&bytes)?;
+        assert!(PMTiles::validate(&mut Cursor::new(&bytes))?.is_valid());
+        assert_eq!(pm_tiles.num_tiles(), source.num_tiles());
+        assert_eq!(pm_tiles.get_tile_by_id(0)?, Some(vec![0x1f, 0x8b]));
+
+        for tile_id in source.tile_ids_sorted().into_iter().skip(1) {
+            assert_eq!(
+                pm_tiles.get_tile_by_id(tile_id)?,
+                source.get_tile_by_id(tile_id)?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_clustered_tiles_errors() -> Result<()> {
+        let bytes: &[u8] = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+        let mut writer =
+            PMTilesStreamWriter::new(Cursor::new(Vec::new()), TileType::Png, Compression::GZip);
+        assert!(matches!(
+            writer.copy_clustered_tiles(&mut Cursor::new(bytes), ..),
+            Err(Error::TileCompressionMismatch(Compression::GZip))
+        ));
+
+        let mut writer =
+            PMTilesStreamWriter::new(Cursor::new(Vec::new()), TileType::Png, Compression::None);
+        writer.add_tile(tile_id(1, 0, 0), [1])?;
+        assert!(matches!(
+            writer.copy_clustered_tiles(&mut Cursor::new(bytes), ..),
+            Err(Error::TileIdNotAscending { tile_id: 0, .. })
+        ));
+
+        let mut header = Header::from_bytes(bytes)?;
+        header.clustered = false;
+        let mut bytes = bytes.to_vec();
+        let mut header_bytes = Vec::new();
+        header.to_writer(&mut header_bytes)?;
+        bytes[..header_bytes.len()].copy_from_slice(&header_bytes);
+
+        assert!(matches!(
+            writer.copy_clustered_tiles(&mut Cursor::new(bytes), ..),
+            Err(Error::Unsupported(_))
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_copy_clustered_tiles_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let bytes: &[u8] = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+            let mut writer = PMTilesStreamWriter::new(
+                futures::io::Cursor::new(Vec::new()),
+                TileType::Png,
+                Compression::None,
+            );
+
+            let num_tiles = writer
+                .copy_clustered_tiles_async(&mut futures::io::Cursor::new(bytes), 5..=20)
+                .await?;
+            assert_eq!(num_tiles, 16);
+
+            let bytes = writer.finish_async().await?.into_inner();
+            let mut pm_tiles = PMTiles::from_bytes(&bytes)?;
+            let mut source = PMTiles::from_bytes(include_bytes!(
+                "../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles"
+            ))?;
+
+            assert_eq!(pm_tiles.num_tiles(), 16);
+            assert_eq!(pm_tiles.get_tile_by_id(19)?, source.get_tile_by_id(19)?);
+
+            Ok(())
+        })
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_write_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let mut writer = PMTilesStreamWriter::new(
+                futures::io::Cursor::new(Vec::new()),
+                TileType::Png,
+                Compression::None,
+            );
+
+            for id in 0..10u64 {
+                writer.add_tile_async(id, id.to_le_bytes()).await?;
+            }
+
+            let bytes = writer.finish_async().await?.into_inner();
+            let mut pm_tiles = PMTiles::from_bytes(&bytes)?;
+
+            assert_eq!(pm_tiles.num_tiles(), 10);
+            assert_eq!(
+                pm_tiles.get_tile_by_id(7)?,
+                Some(7u64.to_le_bytes().to_vec())
+            );
+
+            Ok(())
+        })
+    }
+}
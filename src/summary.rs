@@ -0,0 +1,164 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+use serde_json::{Map as JSONMap, Value as JSONValue};
+
+use crate::{Header, PMTiles, ParseOptions, ParseWarning, ReadLimits, Result};
+
+/// Header and meta data of an archive, as returned by [`PMTiles::read_summary`].
+///
+/// Bounds, center and zoom levels of the archive are part of the [`Header`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArchiveSummary {
+    /// Header of the archive
+    pub header: Header,
+
+    /// JSON meta data of the archive
+    pub meta_data: JSONMap<String, JSONValue>,
+}
+
+impl ArchiveSummary {
+    /// Returns the `name` field of the meta data, if it is set to a string.
+    pub fn name(&self) -> Option<&str> {
+        self.meta_data.get("name").and_then(JSONValue::as_str)
+    }
+}
+
+#[duplicate_item(
+    fn_name              cfg_async_filter       async   add_await(code) RTraits                                                  SeekFrom                from_reader_with_options         read_meta_data         read_summary_with_options;
+    [read_summary]       [cfg(all())]           []      [code]          [Read + Seek]                                            [SeekFrom]              [from_reader_with_options]       [read_meta_data]       [read_summary_with_options];
+    [read_summary_async] [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [futures::io::SeekFrom] [from_async_reader_with_options] [read_meta_data_async] [read_summary_with_options_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Reads only the header and the meta data of an archive.
+    ///
+    /// In contrast to [`from_reader`](Self::from_reader), no directories are read, which makes this
+    /// a lot faster for listing many archives.
+    ///
+    /// # Arguments
+    /// * `input` - Reader with the archive
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was any kind of I/O error while reading from `input`, the data
+    /// stream was no valid `PMTiles` archive or the internal compression of the archive is set to "Unknown".
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::PMTiles;
+    /// # let file_path = "./test/protomaps_vector_planet_odbl_z10_without_data.pmtiles";
+    /// let mut file = std::fs::File::open(file_path).unwrap();
+    ///
+    /// let summary = PMTiles::read_summary(&mut file).unwrap();
+    ///
+    /// assert_eq!(summary.name(), Some("protomaps 2022-11-08T03:35:13Z"));
+    /// assert_eq!(summary.header.max_zoom, 10);
+    /// ```
+    pub async fn fn_name(input: &mut R) -> Result<ArchiveSummary> {
+        add_await([Self::read_summary_with_options(
+            input,
+            &ReadLimits::default(),
+            &ParseOptions::default(),
+            &mut Vec::new(),
+        )])
+    }
+
+    /// Same as [`read_summary`](Self::read_summary), but enforces `limits` for the length of the meta data
+    /// and tolerates deviations of the header from the specification according to `options`.
+    pub(crate) async fn read_summary_with_options(
+        input: &mut R,
+        limits: &ReadLimits,
+        options: &ParseOptions,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<ArchiveSummary> {
+        add_await([input.seek(SeekFrom::Start(0))])?;
+        let header = add_await([Header::from_reader_with_options(input, options, warnings)])?;
+
+        let meta_data = if header.json_metadata_length == 0 {
+            JSONMap::new()
+        } else {
+            add_await([input.seek(SeekFrom::Start(header.json_metadata_offset))])?;
+
+            let mut meta_data_reader = input.take(header.json_metadata_length);
+            add_await([Self::read_meta_data(
+                header.internal_compression,
+                options.codec.as_deref(),
+                &mut meta_data_reader,
+                limits.max_meta_data_length,
+            )])?
+        };
+
+        Ok(ArchiveSummary { header, meta_data })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::Error;
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    #[test]
+    fn test_read_summary() -> Result<()> {
+        let summary = PMTiles::read_summary(&mut Cursor::new(PM_TILES_BYTES))?;
+        let pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+        assert_eq!(summary.header, Header::from_bytes(PM_TILES_BYTES)?);
+        assert_eq!(summary.meta_data, pm_tiles.meta_data);
+        assert_eq!(
+            summary.name(),
+            pm_tiles.meta_data.get("name").and_then(JSONValue::as_str)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_summary_invalid() {
+        let res = PMTiles::read_summary(&mut Cursor::new(&PM_TILES_BYTES[1..]));
+
+        assert!(matches!(res, Err(Error::InvalidMagic)));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_read_summary_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let mut reader = futures::io::Cursor::new(PM_TILES_BYTES);
+
+            let summary = PMTiles::read_summary_async(&mut reader).await?;
+            assert_eq!(
+                summary,
+                PMTiles::read_summary(&mut Cursor::new(PM_TILES_BYTES))?
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() -> Result<()> {
+        let summary = PMTiles::read_summary(&mut Cursor::new(PM_TILES_BYTES))?;
+
+        let json = serde_json::to_value(&summary)?;
+        assert_eq!(json["header"]["tile_type"], "png");
+        assert_eq!(json["header"]["internal_compression"], "gzip");
+        assert_eq!(json["header"]["max_zoom"], 3);
+        assert_eq!(
+            json["meta_data"],
+            JSONValue::Object(summary.meta_data.clone())
+        );
+
+        assert_eq!(serde_json::from_value::<ArchiveSummary>(json)?, summary);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Read, Seek},
+};
+
+#[cfg(feature = "metrics")]
+use std::sync::Arc;
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+#[cfg(feature = "metrics")]
+use crate::metrics::MetricsRecorder;
+
+use crate::{
+    util::{decompress_all, tile_id},
+    PMTiles, Result,
+};
+
+#[derive(Debug)]
+struct CachedTile {
+    data: Vec<u8>,
+    last_used: u64,
+}
+
+/// A wrapper around [`PMTiles`], which caches recently served tiles in memory.
+///
+/// The cache is limited to a configurable budget of bytes. Once the budget is exceeded, the least
+/// recently used tiles are evicted. Tiles larger than the budget are never cached.
+///
+/// If [`with_decompression`](Self::with_decompression) is enabled, tiles are decompressed according to the
+/// [`tile_compression`](PMTiles::tile_compression) of the archive before they are cached and returned.
+///
+/// Note that changes to the wrapped archive (via [`pm_tiles_mut`](Self::pm_tiles_mut)) are not reflected
+/// by already cached tiles. Call [`clear`](Self::clear) after modifying the archive.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{PMTiles, TileCache};
+/// let bytes = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+/// let pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+///
+/// // cache up to 1 MiB of decompressed tiles
+/// let mut cache = TileCache::new(pm_tiles, 1024 * 1024).with_decompression(true);
+///
+/// let tile = cache.get_tile_by_id(0).unwrap(); // read from archive
+/// let tile = cache.get_tile_by_id(0).unwrap(); // served from cache
+/// # assert!(tile.is_some());
+/// ```
+#[derive(Debug)]
+pub struct TileCache<R> {
+    pm_tiles: PMTiles<R>,
+    decompress: bool,
+    max_bytes: usize,
+    used_bytes: usize,
+    tick: u64,
+
+    /// `tile_id` -> cached tile
+    tiles: HashMap<u64, CachedTile>,
+
+    /// last use -> `tile_id`
+    lru: BTreeMap<u64, u64>,
+
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<dyn MetricsRecorder>>,
+}
+
+impl<R> TileCache<R> {
+    /// Wraps `pm_tiles` in a cache, which holds up to `max_bytes` bytes of tile data.
+    pub fn new(pm_tiles: PMTiles<R>, max_bytes: usize) -> Self {
+        Self {
+            pm_tiles,
+            decompress: false,
+            max_bytes,
+            used_bytes: 0,
+            tick: 0,
+            tiles: HashMap::new(),
+            lru: BTreeMap::new(),
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// Sets the metrics, into which served tiles and cache hits / misses are reported.
+    #[cfg(feature = "metrics")]
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsRecorder>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Sets whether tiles should be decompressed before they are cached and returned (default `false`).
+    ///
+    /// Changing this clears the cache.
+    #[must_use]
+    pub fn with_decompression(mut self, decompress: bool) -> Self {
+        if self.decompress != decompress {
+            self.clear();
+        }
+
+        self.decompress = decompress;
+        self
+    }
+
+    /// Returns a reference to the wrapped archive.
+    pub const fn pm_tiles(&self) -> &PMTiles<R> {
+        &self.pm_tiles
+    }
+
+    /// Returns a mutable reference to the wrapped archive.
+    ///
+    /// Already cached tiles are not updated, so [`clear`](Self::clear) should be called after modifying tiles.
+    pub const fn pm_tiles_mut(&mut self) -> &mut PMTiles<R> {
+        &mut self.pm_tiles
+    }
+
+    /// Unwraps this cache, returning the wrapped archive.
+    pub fn into_inner(self) -> PMTiles<R> {
+        self.pm_tiles
+    }
+
+    /// Returns the number of bytes of all currently cached tiles.
+    pub const fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    /// Returns the number of currently cached tiles.
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Returns `true` if no tiles are cached.
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Removes all tiles from the cache.
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.lru.clear();
+        self.used_bytes = 0;
+    }
+
+    const fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn get_cached(&mut self, tile_id: u64) -> Option<Vec<u8>> {
+        let tick = self.next_tick();
+        let Some(tile) = self.tiles.get_mut(&tile_id) else {
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = &self.metrics {
+                metrics.record_cache_miss();
+            }
+
+            return None;
+        };
+
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_cache_hit();
+            metrics.record_tile_read();
+        }
+
+        self.lru.remove(&tile.last_used);
+        self.lru.insert(tick, tile_id);
+        tile.last_used = tick;
+
+        Some(tile.data.clone())
+    }
+
+    fn insert(&mut self, tile_id: u64, data: Vec<u8>) {
+        if data.len() > self.max_bytes {
+            return;
+        }
+
+        while self.used_bytes + data.len() > self.max_bytes {
+            let Some((_, id)) = self.lru.pop_first() else {
+                break;
+            };
+
+            if let Some(evicted) = self.tiles.remove(&id) {
+                self.used_bytes -= evicted.data.len();
+            }
+        }
+
+        let tick = self.next_tick();
+        self.used_bytes += data.len();
+        self.lru.insert(tick, tile_id);
+        self.tiles.insert(
+            tile_id,
+            CachedTile {
+                data,
+                last_used: tick,
+            },
+        );
+    }
+
+    fn prepare(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        if self.decompress {
+            decompress_all(self.pm_tiles.tile_compression, &data)
+        } else {
+            Ok(data)
+        }
+    }
+}
+
+#[duplicate_item(
+    cfg_async_filter       async   add_await(code) RTraits                                                  get_tile_by_id         get_tile;
+    [cfg(all())]           []      [code]          [Read + Seek]                                            [get_tile_by_id]       [get_tile];
+    [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [get_tile_by_id_async] [get_tile_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> TileCache<R> {
+    /// Get data of a tile by its id, serving it from the cache if possible.
+    ///
+    /// The data is decompressed if [`with_decompression`](Self::with_decompression) is enabled.
+    ///
+    /// Will return [`Ok`] with an value of [`None`] if no a tile with the specified tile id was found.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the tile was not cached and there was an error while attempting to read
+    /// or decompress it.
+    pub async fn get_tile_by_id(&mut self, tile_id: u64) -> Result<Option<Vec<u8>>> {
+        if let Some(data) = self.get_cached(tile_id) {
+            return Ok(Some(data));
+        }
+
+        let Some(data) = add_await([self.pm_tiles.get_tile_by_id(tile_id)])? else {
+            return Ok(None);
+        };
+
+        let data = self.prepare(data)?;
+        self.insert(tile_id, data.clone());
+
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_tile_read();
+        }
+
+        Ok(Some(data))
+    }
+
+    /// Returns the data of the tile with the specified coordinates, serving it from the cache if possible.
+    ///
+    /// See [`get_tile_by_id`](Self::get_tile_by_id) for further details on the return type.
+    ///
+    /// # Errors
+    /// See [`get_tile_by_id`](Self::get_tile_by_id) for details on possible errors.
+    pub async fn get_tile(&mut self, x: u64, y: u64, z: u8) -> Result<Option<Vec<u8>>> {
+        add_await([self.get_tile_by_id(tile_id(z, x, y))])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Compression, TileType};
+
+    const PM_TILES_BYTES2: &[u8] = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+
+    #[test]
+    fn test_get_tile_by_id() -> Result<()> {
+        let mut expected = PMTiles::from_bytes(PM_TILES_BYTES2)?;
+        let mut cache = TileCache::new(PMTiles::from_bytes(PM_TILES_BYTES2)?, usize::MAX);
+
+        for _ in 0..2 {
+            assert_eq!(cache.get_tile_by_id(0)?, expected.get_tile_by_id(0)?);
+            assert_eq!(cache.len(), 1);
+        }
+
+        assert_eq!(cache.get_tile_by_id(1_000_000)?, None);
+        assert_eq!(cache.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompression() -> Result<()> {
+        let mut expected = PMTiles::from_bytes(PM_TILES_BYTES2)?;
+        let mut cache = TileCache::new(PMTiles::from_bytes(PM_TILES_BYTES2)?, usize::MAX)
+            .with_decompression(true);
+
+        let data = expected.get_tile_by_id(0)?.unwrap_or_default();
+        let data = decompress_all(Compression::GZip, &data)?;
+
+        assert_eq!(cache.get_tile_by_id(0)?, Some(data.clone()));
+        assert_eq!(cache.used_bytes(), data.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_eviction() -> Result<()> {
+        let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+        for tile_id in 0..4u8 {
+            pm_tiles.add_tile(tile_id.into(), vec![tile_id; 10])?;
+        }
+        pm_tiles.add_tile(4, vec![4; 100])?;
+
+        let mut cache = TileCache::new(pm_tiles, 20);
+
+        cache.get_tile_by_id(0)?;
+        cache.get_tile_by_id(1)?;
+        assert_eq!(cache.used_bytes(), 20);
+
+        // 0 is now the most recently used tile, so 1 gets evicted
+        cache.get_tile_by_id(0)?;
+        cache.get_tile_by_id(2)?;
+        assert_eq!(cache.len(), 2);
+        assert!(cache.tiles.contains_key(&0));
+        assert!(cache.tiles.contains_key(&2));
+
+        // tiles larger than the budget are not cached
+        assert_eq!(cache.get_tile_by_id(4)?, Some(vec![4; 100]));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.used_bytes(), 20);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.used_bytes(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn test_metrics() -> Result<()> {
+        let metrics = Arc::new(crate::metrics::Metrics::new());
+        let mut cache = TileCache::new(PMTiles::from_bytes(PM_TILES_BYTES2)?, usize::MAX)
+            .with_metrics(metrics.clone());
+
+        cache.get_tile_by_id(0)?;
+        cache.get_tile_by_id(0)?;
+        cache.get_tile_by_id(1_000_000)?;
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.tile_reads, 2);
+        assert_eq!(snapshot.cache_hits, 1);
+        assert_eq!(snapshot.cache_misses, 2);
+
+        Ok(())
+    }
+}
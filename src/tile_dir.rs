@@ -0,0 +1,495 @@
+use std::{
+    fs,
+    io::{Cursor, Read, Seek},
+    path::Path,
+};
+
+use crate::{
+    util::{decompress_all, detect_compression, tile_id, tms_to_xyz, xyz_to_tms, zxy},
+    Compression, Error, PMTiles, Result, TileType,
+};
+
+/// Maximum zoom level of tiles in a tile directory.
+const MAX_DIR_ZOOM: u8 = 31;
+
+/// Returns the tile type of tiles with the specified file extension (case-insensitive).
+fn tile_type_from_extension(extension: &str) -> Option<TileType> {
+    match extension.to_ascii_lowercase().as_str() {
+        "pbf" | "mvt" => Some(TileType::Mvt),
+        "png" => Some(TileType::Png),
+        "jpg" | "jpeg" => Some(TileType::Jpeg),
+        "webp" => Some(TileType::WebP),
+        "avif" => Some(TileType::Avif),
+        _ => None,
+    }
+}
+
+/// Returns the file extension used for tiles of the specified tile type.
+const fn tile_extension(tile_type: TileType) -> &'static str {
+    match tile_type {
+        TileType::Mvt => "pbf",
+        TileType::Png => "png",
+        TileType::Jpeg => "jpg",
+        TileType::WebP => "webp",
+        TileType::Avif => "avif",
+        TileType::Unknown => "bin",
+    }
+}
+
+/// The tiling scheme of a tile directory, which determines the direction of the y axis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TileScheme {
+    /// XYZ tiling scheme, where `y = 0` is the northernmost row of tiles (like in `PMTiles`)
+    #[default]
+    Xyz,
+
+    /// TMS tiling scheme, where `y = 0` is the southernmost row of tiles (like in `MBTiles`)
+    Tms,
+}
+
+/// Options for [`PMTiles::extract_to_dir`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExtractOptions {
+    /// Tiling scheme of the written directory structure (default [`TileScheme::Xyz`])
+    pub scheme: TileScheme,
+
+    /// Whether tiles should be decompressed according to the
+    /// [`tile_compression`](PMTiles::tile_compression) of the archive before they are written (default `false`)
+    pub decompress: bool,
+
+    /// Minimum zoom level of extracted tiles; if [`None`] there is no lower limit
+    pub min_zoom: Option<u8>,
+
+    /// Maximum zoom level of extracted tiles; if [`None`] there is no upper limit
+    pub max_zoom: Option<u8>,
+}
+
+/// Returns the entries of the directory at `path`, whose names parse as `T`, together with the parsed values.
+fn parse_dir_entries<T: std::str::FromStr>(
+    path: &Path,
+    parse: impl Fn(&Path) -> Option<&str>,
+) -> Result<Vec<(T, fs::DirEntry)>> {
+    let mut entries = Vec::new();
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let Some(value) = parse(&entry.path()).and_then(|s| s.parse::<T>().ok()) else {
+            continue;
+        };
+
+        entries.push((value, entry));
+    }
+
+    Ok(entries)
+}
+
+impl PMTiles<Cursor<&[u8]>> {
+    /// Builds a `PMTiles` archive from a directory of tiles with the structure `{z}/{x}/{y}.{ext}`
+    /// (XYZ tiling scheme), like it is commonly used for tile caches.
+    ///
+    /// The [`tile_type`](Self::tile_type) is inferred from the file extensions (`pbf`/`mvt`, `png`,
+    /// `jpg`/`jpeg`, `webp` and `avif`), and [`min_zoom`](Self::min_zoom) and [`max_zoom`](Self::max_zoom)
+    /// are set to the lowest and highest zoom levels found. For vector tiles, the
+    /// [`tile_compression`](Self::tile_compression) is detected from the data of the first tile;
+    /// all other tile types are assumed to be uncompressed.
+    ///
+    /// Files and directories, which do not match the structure or have an unknown extension, are ignored.
+    /// All tiles are read into memory.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the directory containing the zoom level directories
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an I/O error while reading the directory, a tile is empty,
+    /// has coordinates out of range ([`Error::InvalidTileCoordinates`]) or tiles with different tile types
+    /// were found ([`Error::MixedTileTypes`]).
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{PMTiles, TileType};
+    /// # let dir = temp_dir::TempDir::new().unwrap();
+    /// # std::fs::create_dir_all(dir.path().join("1/0")).unwrap();
+    /// # std::fs::write(dir.path().join("1/0/1.png"), [1, 3, 3, 7]).unwrap();
+    /// let pm_tiles = PMTiles::from_dir(dir.path()).unwrap();
+    ///
+    /// assert_eq!(pm_tiles.tile_type, TileType::Png);
+    /// assert_eq!(pm_tiles.num_tiles(), 1);
+    /// ```
+    pub fn from_dir(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_dir_with_scheme(path, TileScheme::Xyz)
+    }
+
+    /// Same as [`from_dir`](Self::from_dir), but with an extra parameter.
+    ///
+    /// Builds a `PMTiles` archive from a directory of tiles with the structure `{z}/{x}/{y}.{ext}`,
+    /// whose y coordinates are interpreted according to `scheme`.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the directory containing the zoom level directories
+    /// * `scheme` - Tiling scheme of the directory (e.g. [`TileScheme::Tms`] for tiles exported from `MBTiles`)
+    ///
+    /// # Errors
+    /// See [`from_dir`](Self::from_dir) for details on possible errors.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{PMTiles, TileScheme};
+    /// # let dir = temp_dir::TempDir::new().unwrap();
+    /// # std::fs::create_dir_all(dir.path().join("1/0")).unwrap();
+    /// # std::fs::write(dir.path().join("1/0/1.png"), [1, 3, 3, 7]).unwrap();
+    /// let mut pm_tiles = PMTiles::from_dir_with_scheme(dir.path(), TileScheme::Tms).unwrap();
+    ///
+    /// assert_eq!(pm_tiles.get_tile(0, 0, 1).unwrap(), Some(vec![1, 3, 3, 7]));
+    /// ```
+    pub fn from_dir_with_scheme(path: impl AsRef<Path>, scheme: TileScheme) -> Result<Self> {
+        let mut collector = TileCollector::new(scheme);
+
+        for (z, z_entry) in parse_dir_entries::<u8>(path.as_ref(), |p| p.file_name()?.to_str())? {
+            if !z_entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            for (x, x_entry) in
+                parse_dir_entries::<u64>(&z_entry.path(), |p| p.file_name()?.to_str())?
+            {
+                if !x_entry.file_type()?.is_dir() {
+                    continue;
+                }
+
+                for (y, y_entry) in
+                    parse_dir_entries::<u64>(&x_entry.path(), |p| p.file_stem()?.to_str())?
+                {
+                    let path = y_entry.path();
+                    let Some(tile_type) = path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .and_then(tile_type_from_extension)
+                    else {
+                        continue;
+                    };
+
+                    if !y_entry.file_type()?.is_file() {
+                        continue;
+                    }
+
+                    collector.add_tile(z, x, y, tile_type, fs::read(&path)?)?;
+                }
+            }
+        }
+
+        Ok(collector.finish())
+    }
+}
+
+/// Collects tiles of a `{z}/{x}/{y}.{ext}` structure into an archive, inferring its tile type,
+/// tile compression and zoom levels from the collected tiles.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) struct TileCollector<'a> {
+    pm_tiles: PMTiles<Cursor<&'a [u8]>>,
+    scheme: TileScheme,
+    zoom_range: Option<(u8, u8)>,
+}
+
+#[allow(clippy::redundant_pub_crate)]
+impl<'a> TileCollector<'a> {
+    /// Returns a collector for tiles, whose y coordinates are interpreted according to `scheme`.
+    pub(crate) fn new(scheme: TileScheme) -> Self {
+        Self {
+            pm_tiles: PMTiles::new(TileType::Unknown, Compression::None),
+            scheme,
+            zoom_range: None,
+        }
+    }
+
+    /// Adds a tile with the coordinates `z`/`x`/`y` (according to the scheme of the collector).
+    ///
+    /// Will return [`Err`] if the coordinates are out of range, the tile is empty or `tile_type`
+    /// differs from the tile type of previously added tiles.
+    pub(crate) fn add_tile(
+        &mut self,
+        z: u8,
+        x: u64,
+        y: u64,
+        tile_type: TileType,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        if z > MAX_DIR_ZOOM || x >> z != 0 || y >> z != 0 {
+            return Err(Error::InvalidTileCoordinates { z, x, y });
+        }
+
+        let (z, x, y) = match self.scheme {
+            TileScheme::Xyz => (z, x, y),
+            TileScheme::Tms => {
+                tms_to_xyz(z, x, y).ok_or(Error::InvalidTileCoordinates { z, x, y })?
+            }
+        };
+
+        let pm_tiles = &mut self.pm_tiles;
+        if pm_tiles.tile_type == TileType::Unknown {
+            pm_tiles.tile_type = tile_type;
+
+            if tile_type == TileType::Mvt {
+                pm_tiles.tile_compression = detect_compression(&data).unwrap_or(Compression::None);
+            }
+        } else if pm_tiles.tile_type != tile_type {
+            return Err(Error::MixedTileTypes {
+                expected: pm_tiles.tile_type,
+                found: tile_type,
+            });
+        }
+
+        pm_tiles.add_tile(tile_id(z, x, y), data)?;
+
+        self.zoom_range = Some(
+            self.zoom_range
+                .map_or((z, z), |(min, max)| (min.min(z), max.max(z))),
+        );
+
+        Ok(())
+    }
+
+    /// Returns the archive holding all collected tiles.
+    pub(crate) fn finish(self) -> PMTiles<Cursor<&'a [u8]>> {
+        let mut pm_tiles = self.pm_tiles;
+
+        if let Some((min_zoom, max_zoom)) = self.zoom_range {
+            pm_tiles.min_zoom = min_zoom;
+            pm_tiles.max_zoom = max_zoom;
+        }
+
+        pm_tiles
+    }
+}
+
+/// Returns the coordinates and tile type of a path ending in `{z}/{x}/{y}.{ext}` (with `/` as
+/// separator), like the paths of entries of tar and zip files.
+#[cfg(any(feature = "tar", feature = "zip"))]
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn parse_tile_path(path: &str) -> Option<(u8, u64, u64, TileType)> {
+    let mut components = path.trim_end_matches('/').rsplit('/');
+    let (y, extension) = components.next()?.rsplit_once('.')?;
+    let x = components.next()?;
+    let z = components.next()?;
+
+    Some((
+        z.parse().ok()?,
+        x.parse().ok()?,
+        y.parse().ok()?,
+        tile_type_from_extension(extension)?,
+    ))
+}
+
+impl<R: Read + Seek> PMTiles<R> {
+    /// Writes every tile of the archive to a directory with the structure `{z}/{x}/{y}.{ext}`
+    /// (XYZ tiling scheme), which can be served by any static file server.
+    ///
+    /// The file extension is derived from the [`tile_type`](Self::tile_type) (`pbf`, `png`, `jpg`, `webp`,
+    /// `avif` or `bin` for unknown tile types). Existing files are overwritten.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the directory to write the zoom level directories to; created if it does not exist
+    /// * `options` - Options to decompress tiles or limit the extracted zoom levels
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an I/O error while reading a tile or writing files, or a tile
+    /// could not be decompressed.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{ExtractOptions, PMTiles};
+    /// # let dir = temp_dir::TempDir::new().unwrap();
+    /// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+    /// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+    ///
+    /// let options = ExtractOptions {
+    ///     max_zoom: Some(1),
+    ///     ..Default::default()
+    /// };
+    /// pm_tiles.extract_to_dir(dir.path(), options).unwrap();
+    ///
+    /// assert!(dir.path().join("1/1/1.png").is_file());
+    /// ```
+    pub fn extract_to_dir(
+        &mut self,
+        path: impl AsRef<Path>,
+        options: ExtractOptions,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let extension = tile_extension(self.tile_type);
+        let tile_ids = self.tile_ids().collect::<Vec<_>>();
+
+        for tile_id in tile_ids {
+            let (z, x, y) = zxy(tile_id).map_err(|_| Error::InvalidTileId(tile_id))?;
+
+            if options.min_zoom.is_some_and(|min| z < min)
+                || options.max_zoom.is_some_and(|max| z > max)
+            {
+                continue;
+            }
+
+            let Some(mut data) = self.get_tile_by_id(tile_id)? else {
+                continue;
+            };
+
+            if options.decompress {
+                data = decompress_all(self.tile_compression, &data)?;
+            }
+
+            let (z, x, y) = match options.scheme {
+                TileScheme::Xyz => (z, x, y),
+                TileScheme::Tms => xyz_to_tms(z, x, y).ok_or(Error::InvalidTileId(tile_id))?,
+            };
+
+            let dir = path.join(z.to_string()).join(x.to_string());
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join(format!("{y}.{extension}")), data)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use temp_dir::TempDir;
+
+    use super::*;
+    use crate::util::compress_all;
+
+    fn write_tile(dir: &Path, path: &str, data: &[u8]) -> Result<PathBuf> {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, data)?;
+
+        Ok(path)
+    }
+
+    #[test]
+    fn test_from_dir() -> Result<()> {
+        let dir = TempDir::new()?;
+        write_tile(dir.path(), "2/1/3.png", &[2, 1, 3])?;
+        write_tile(dir.path(), "4/15/0.PNG", &[4, 15, 0])?;
+        write_tile(dir.path(), "3/2/1.png", &[3, 2, 1])?;
+        write_tile(dir.path(), "metadata.json", b"{}")?;
+        write_tile(dir.path(), "3/2/readme.txt", b"foo")?;
+        write_tile(dir.path(), "foo/1/1.png", &[1])?;
+
+        let mut pm_tiles = PMTiles::from_dir(dir.path())?;
+
+        assert_eq!(pm_tiles.tile_type, TileType::Png);
+        assert_eq!(pm_tiles.tile_compression, Compression::None);
+        assert_eq!(pm_tiles.min_zoom, 2);
+        assert_eq!(pm_tiles.max_zoom, 4);
+        assert_eq!(pm_tiles.num_tiles(), 3);
+        assert_eq!(pm_tiles.get_tile(1, 3, 2)?, Some(vec![2, 1, 3]));
+        assert_eq!(pm_tiles.get_tile(15, 0, 4)?, Some(vec![4, 15, 0]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_dir_mvt() -> Result<()> {
+        let dir = TempDir::new()?;
+        let data = compress_all(Compression::GZip, &[1, 3, 3, 7])?;
+        write_tile(dir.path(), "0/0/0.pbf", &data)?;
+
+        let pm_tiles = PMTiles::from_dir(dir.path())?;
+
+        assert_eq!(pm_tiles.tile_type, TileType::Mvt);
+        assert_eq!(pm_tiles.tile_compression, Compression::GZip);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_dir_invalid() -> Result<()> {
+        let dir = TempDir::new()?;
+        write_tile(dir.path(), "1/0/0.png", &[1])?;
+        write_tile(dir.path(), "1/0/1.jpg", &[1])?;
+        assert!(PMTiles::from_dir(dir.path()).is_err());
+
+        let dir = TempDir::new()?;
+        write_tile(dir.path(), "1/2/0.png", &[1])?;
+        assert!(PMTiles::from_dir(dir.path()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_to_dir() -> Result<()> {
+        let bytes = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+        let mut pm_tiles = PMTiles::from_bytes(bytes)?;
+
+        let dir = TempDir::new()?;
+        pm_tiles.extract_to_dir(
+            dir.path(),
+            ExtractOptions {
+                decompress: true,
+                min_zoom: Some(1),
+                max_zoom: Some(3),
+                ..Default::default()
+            },
+        )?;
+
+        let mut extracted = PMTiles::from_dir(dir.path())?;
+        assert_eq!(extracted.tile_type, TileType::Mvt);
+        assert_eq!(extracted.tile_compression, Compression::None);
+        assert_eq!(extracted.min_zoom, 1);
+        assert_eq!(extracted.max_zoom, 3);
+
+        let source = PMTiles::from_bytes(bytes)?;
+        for tile_id in source.tile_ids() {
+            let (z, ..) = zxy(tile_id).map_err(|_| Error::InvalidTileId(tile_id))?;
+            let expected = if (1..=3).contains(&z) {
+                pm_tiles
+                    .get_tile_by_id(tile_id)?
+                    .map(|data| decompress_all(pm_tiles.tile_compression, &data))
+                    .transpose()?
+            } else {
+                None
+            };
+
+            assert_eq!(extracted.get_tile_by_id(tile_id)?, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tms_dir() -> Result<()> {
+        let dir = TempDir::new()?;
+        write_tile(dir.path(), "2/1/0.png", &[2, 1, 0])?;
+
+        let mut pm_tiles = PMTiles::from_dir_with_scheme(dir.path(), TileScheme::Tms)?;
+        assert_eq!(pm_tiles.get_tile(1, 3, 2)?, Some(vec![2, 1, 0]));
+        assert_eq!(pm_tiles.get_tile(1, 0, 2)?, None);
+
+        let out = TempDir::new()?;
+        let options = ExtractOptions {
+            scheme: TileScheme::Tms,
+            ..Default::default()
+        };
+        pm_tiles.extract_to_dir(out.path(), options)?;
+        assert_eq!(fs::read(out.path().join("2/1/0.png"))?, vec![2, 1, 0]);
+
+        pm_tiles.extract_to_dir(out.path(), ExtractOptions::default())?;
+        assert_eq!(fs::read(out.path().join("2/1/3.png"))?, vec![2, 1, 0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_dir_empty() -> Result<()> {
+        let dir = TempDir::new()?;
+        let pm_tiles = PMTiles::from_dir(dir.path())?;
+
+        assert_eq!(pm_tiles.tile_type, TileType::Unknown);
+        assert_eq!(pm_tiles.num_tiles(), 0);
+
+        Ok(())
+    }
+}
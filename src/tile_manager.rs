@@ -2,105 +2,292 @@ use duplicate::duplicate_item;
 #[cfg(feature = "async")]
 use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
 use std::{
-    collections::{HashMap, HashSet},
-    hash::{Hash, Hasher},
-    io::{Cursor, Error, ErrorKind, Read, Result, Seek},
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{Cursor, Read, Seek},
+    ops::{RangeBounds, RangeInclusive},
+    sync::Arc,
 };
 
-use ahash::{AHasher, RandomState};
+use ahash::RandomState;
 
-use crate::{Directory, Entry};
+use crate::{
+    util::{tile_zoom, ReadAt},
+    CancellationToken, DedupeHash, Directory, Entry, Error, Result, TileStats, TileTransform,
+    WritePhase, WriteProgress,
+};
 
 #[derive(Debug)]
 enum TileManagerTile {
-    Hash(u64),
+    Hash(u128),
     OffsetLength(u64, u32),
 }
 
+/// The result of [`TileManager::finish`].
+#[derive(Debug)]
 pub struct FinishResult {
+    /// Bytes of the tile data section
     pub data: Vec<u8>,
+
+    /// Number of tiles, which are addressable via [`directory`](Self::directory)
     pub num_addressed_tiles: u64,
+
+    /// Number of entries in [`directory`](Self::directory)
     pub num_tile_entries: u64,
+
+    /// Number of distinct tile contents in [`data`](Self::data)
     pub num_tile_content: u64,
+
+    /// Directory holding one entry per run of tiles, with offsets relative to the start of [`data`](Self::data)
+    ///
+    /// All entries are sorted in ascending order by their tile ids.
     pub directory: Directory,
 }
 
+/// A low level structure, which collects tiles and builds the tile data section
+/// and the directory entries of a `PMTiles` archive from them.
+///
+/// This is what [`PMTiles`](crate::PMTiles) uses internally. It can be used directly to build
+/// custom writers, as it takes care of the following:
+/// - **Deduplication**: Tiles with the same content are only stored once. Tile data, which is held
+///   in memory, is deduplicated as soon as it is added. Tiles, that still have to be read from the
+///   reader, are deduplicated in [`finish`](Self::finish).
+/// - **Clustering**: [`finish`](Self::finish) orders tile data and directory entries in ascending
+///   order by tile id.
+/// - **Run-lengths**: Consecutive tile ids with the same content are combined into a single
+///   directory entry.
+///
+/// Tiles can either be added as bytes ([`add_tile`](Self::add_tile)) or as a reference to data
+/// in the reader ([`add_offset_tile`](Self::add_offset_tile)), which is only read when required.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::TileManager;
+/// let mut manager = TileManager::default();
+///
+/// manager.add_tile(0, vec![1, 3, 3, 7]).unwrap();
+/// manager.add_tile(1, vec![1, 3, 3, 7]).unwrap();
+/// manager.add_tile(2, vec![4, 2]).unwrap();
+///
+/// let result = manager.finish().unwrap();
+///
+/// assert_eq!(result.data, vec![1, 3, 3, 7, 4, 2]);
+/// assert_eq!(result.num_addressed_tiles, 3);
+/// assert_eq!(result.num_tile_entries, 2);
+/// assert_eq!(result.num_tile_content, 2);
+/// ```
 #[derive(Debug)]
 pub struct TileManager<R> {
-    /// hash of tile -> bytes of tile
-    data_by_hash: HashMap<u64, Vec<u8>>,
+    /// hash of tile -> bytes of tile (shared with tiles returned by [`get_tile_shared`](Self::get_tile_shared))
+    data_by_hash: HashMap<u128, Arc<[u8]>>,
 
     /// `tile_id` -> hash of tile
     tile_by_id: HashMap<u64, TileManagerTile>,
 
     /// hash of tile -> ids with this hash
-    ids_by_hash: HashMap<u64, HashSet<u64>, RandomState>,
+    ids_by_hash: HashMap<u128, HashSet<u64>, RandomState>,
+
+    /// first id -> last id of runs of consecutive tiles, which were added via
+    /// [`add_offset_tile`](Self::add_offset_tile) and reference the same data
+    offset_runs: BTreeMap<u64, u64>,
+
+    dedupe_hash: DedupeHash,
 
     reader: Option<R>,
 }
 
 impl<R> TileManager<R> {
+    /// Creates a new, empty tile manager.
+    ///
+    /// # Arguments
+    /// * `reader` - Reader, which holds the data of tiles added via [`add_offset_tile`](Self::add_offset_tile)
     pub fn new(reader: Option<R>) -> Self {
         Self {
             data_by_hash: HashMap::default(),
             tile_by_id: HashMap::default(),
             ids_by_hash: HashMap::default(),
+            offset_runs: BTreeMap::new(),
+            dedupe_hash: DedupeHash::default(),
             reader,
         }
     }
 
-    fn calculate_hash(value: &impl Hash) -> u64 {
-        let mut hasher = AHasher::default();
-        value.hash(&mut hasher);
-        hasher.finish()
+    /// Creates a new tile manager, that can hold at least `capacity` tiles without reallocating.
+    pub fn with_capacity(reader: Option<R>, capacity: usize) -> Self {
+        Self {
+            data_by_hash: HashMap::with_capacity(capacity),
+            tile_by_id: HashMap::with_capacity(capacity),
+            ids_by_hash: HashMap::with_capacity_and_hasher(capacity, RandomState::default()),
+            offset_runs: BTreeMap::new(),
+            dedupe_hash: DedupeHash::default(),
+            reader,
+        }
     }
 
-    /// Add tile to writer
-    pub fn add_tile(&mut self, tile_id: u64, data: impl Into<Vec<u8>>) -> Result<()> {
-        let vec: Vec<u8> = data.into();
+    /// Reserves capacity for at least `additional` more tiles.
+    pub fn reserve(&mut self, additional: usize) {
+        self.data_by_hash.reserve(additional);
+        self.tile_by_id.reserve(additional);
+        self.ids_by_hash.reserve(additional);
+    }
 
-        if vec.is_empty() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "A tile must have at least 1 byte of data.",
-            ));
+    /// Sets the algorithm used to hash the content of tiles for deduplication (default [`DedupeHash::AHash`]).
+    ///
+    /// Tiles, which were already added, are hashed again with the new algorithm.
+    pub fn set_dedupe_hash(&mut self, dedupe_hash: DedupeHash) {
+        if dedupe_hash == self.dedupe_hash {
+            return;
+        }
+
+        self.dedupe_hash = dedupe_hash;
+
+        let data_by_hash = std::mem::take(&mut self.data_by_hash);
+        let mut ids_by_hash = std::mem::take(&mut self.ids_by_hash);
+
+        for (old_hash, data) in data_by_hash {
+            let hash = self.calculate_hash(&data);
+            let ids = ids_by_hash.remove(&old_hash).unwrap_or_default();
+
+            for tile_id in &ids {
+                self.tile_by_id
+                    .insert(*tile_id, TileManagerTile::Hash(hash));
+            }
+
+            self.ids_by_hash.entry(hash).or_default().extend(ids);
+            self.data_by_hash.insert(hash, data);
+        }
+    }
+
+    /// Returns the algorithm used to hash the content of tiles for deduplication.
+    pub const fn dedupe_hash(&self) -> DedupeHash {
+        self.dedupe_hash
+    }
+
+    fn calculate_hash(&self, data: &[u8]) -> u128 {
+        self.dedupe_hash.hash(data)
+    }
+
+    /// Adds a tile with the given data.
+    ///
+    /// If a tile with the same id already exists, it is replaced.
+    /// Tiles with the same content share a single allocation.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if `data` is empty.
+    pub fn add_tile(&mut self, tile_id: u64, data: impl Into<Arc<[u8]>>) -> Result<()> {
+        let data: Arc<[u8]> = data.into();
+
+        if data.is_empty() {
+            return Err(Error::EmptyTile);
         }
 
         // remove tile just to make sure that there
         // are no unreachable tiles
         self.remove_tile(tile_id);
 
-        let hash = Self::calculate_hash(&vec);
+        let hash = self.calculate_hash(&data);
 
         self.tile_by_id.insert(tile_id, TileManagerTile::Hash(hash));
 
-        self.data_by_hash.insert(hash, vec);
+        self.data_by_hash.entry(hash).or_insert(data);
 
         self.ids_by_hash.entry(hash).or_default().insert(tile_id);
 
         Ok(())
     }
 
-    pub(crate) fn add_offset_tile(&mut self, tile_id: u64, offset: u64, length: u32) -> Result<()> {
+    /// Adds a tile with the given data for every id in `tile_ids`, for which no tile exists yet.
+    ///
+    /// This is more efficient than calling [`add_tile`](Self::add_tile) for every id, as the data
+    /// is only hashed once. Returns the number of added tiles.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if `data` is empty.
+    pub fn fill_tiles(
+        &mut self,
+        tile_ids: impl IntoIterator<Item = u64>,
+        data: impl Into<Arc<[u8]>>,
+    ) -> Result<u64> {
+        let data: Arc<[u8]> = data.into();
+
+        if data.is_empty() {
+            return Err(Error::EmptyTile);
+        }
+
+        let hash = self.calculate_hash(&data);
+        let mut num_added = 0;
+
+        for tile_id in tile_ids {
+            if self.tile_by_id.contains_key(&tile_id) {
+                continue;
+            }
+
+            self.tile_by_id.insert(tile_id, TileManagerTile::Hash(hash));
+            self.ids_by_hash.entry(hash).or_default().insert(tile_id);
+            num_added += 1;
+        }
+
+        if num_added > 0 {
+            self.data_by_hash.entry(hash).or_insert(data);
+        }
+
+        Ok(num_added)
+    }
+
+    /// Adds a tile, whose data is located in the reader of this tile manager.
+    ///
+    /// The data is only read when required (e.g. in [`get_tile`](Self::get_tile) or [`finish`](Self::finish)).
+    ///
+    /// If a tile with the same id already exists, it is replaced.
+    ///
+    /// # Arguments
+    /// * `tile_id` - Id of the tile
+    /// * `offset` - Absolute offset (in bytes) of the tile data within the reader
+    /// * `length` - Length (in bytes) of the tile data
+    ///
+    /// # Errors
+    /// Will return [`Err`] if `length` is `0`.
+    pub fn add_offset_tile(&mut self, tile_id: u64, offset: u64, length: u32) -> Result<()> {
         if length == 0 {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Length of a directory entry must be greater than 0.",
-            ));
+            return Err(Error::ZeroLengthEntry(tile_id));
         }
 
+        self.remove_tile(tile_id);
+
         self.tile_by_id
             .insert(tile_id, TileManagerTile::OffsetLength(offset, length));
 
+        // tile_id is not part of any run, as it was just removed, so adjacent runs end right
+        // before or start right after it
+        let same_location = |id: u64| self.get_tile_offset(id) == Some((offset, length));
+        let first_id = match tile_id.checked_sub(1) {
+            Some(previous_id) if same_location(previous_id) => self
+                .offset_runs
+                .range(..tile_id)
+                .next_back()
+                .map_or(tile_id, |(first_id, _)| *first_id),
+            _ => tile_id,
+        };
+        let last_id = match tile_id.checked_add(1) {
+            Some(next_id) if same_location(next_id) => {
+                self.offset_runs.remove(&next_id).unwrap_or(tile_id)
+            }
+            _ => tile_id,
+        };
+        self.offset_runs.insert(first_id, last_id);
+
         Ok(())
     }
 
-    /// Remove tile from writer
+    /// Removes a tile.
+    ///
+    /// Returns `true` if a tile with the given id existed.
     pub fn remove_tile(&mut self, tile_id: u64) -> bool {
         match self.tile_by_id.remove(&tile_id) {
             None => false, // tile was not found
             Some(tile) => {
                 let TileManagerTile::Hash(hash) = tile else {
+                    self.remove_from_offset_runs(tile_id);
                     return true;
                 };
 
@@ -122,46 +309,177 @@ impl<R> TileManager<R> {
         }
     }
 
-    pub fn get_tile_ids(&self) -> Vec<&u64> {
-        self.tile_by_id.keys().collect()
+    /// Splits the run of tiles referencing the same data, which includes `tile_id`.
+    fn remove_from_offset_runs(&mut self, tile_id: u64) {
+        let Some((&first_id, &last_id)) = self.offset_runs.range(..=tile_id).next_back() else {
+            return;
+        };
+        if last_id < tile_id {
+            return;
+        }
+
+        self.offset_runs.remove(&first_id);
+        if first_id < tile_id {
+            self.offset_runs.insert(first_id, tile_id - 1);
+        }
+        if tile_id < last_id {
+            self.offset_runs.insert(tile_id + 1, last_id);
+        }
     }
 
-    pub fn num_addressed_tiles(&self) -> usize {
-        self.tile_by_id.len()
+    /// Removes all tiles, whose ids are included in `range`.
+    ///
+    /// Returns the number of removed tiles.
+    pub fn remove_tiles_in_range(&mut self, range: &impl RangeBounds<u64>) -> usize {
+        let ids = self
+            .tile_by_id
+            .keys()
+            .copied()
+            .filter(|id| range.contains(id))
+            .collect::<Vec<_>>();
+
+        for id in &ids {
+            self.remove_tile(*id);
+        }
+
+        ids.len()
     }
 
-    fn push_entry(entries: &mut Vec<Entry>, tile_id: u64, offset: u64, length: u32) {
-        if let Some(last) = entries.last_mut() {
-            if tile_id == last.tile_id + u64::from(last.run_length)
-                && last.offset == offset
-                && last.length == length
-            {
-                last.run_length += 1;
-                return;
+    /// Returns statistics on the sizes of all tiles, without reading any tile data.
+    ///
+    /// Tiles, whose data is located in the reader, are considered to have the same content if they
+    /// reference the same byte range.
+    pub fn stats(&self) -> TileStats {
+        let mut stats = TileStats::default();
+        let mut seen = HashSet::<(bool, u128, u32), RandomState>::default();
+
+        for (tile_id, tile) in &self.tile_by_id {
+            let key = match tile {
+                TileManagerTile::Hash(hash) => {
+                    let length = self.data_by_hash.get(hash).map_or(0, |data| data.len());
+                    (true, *hash, u32::try_from(length).unwrap_or(u32::MAX))
+                }
+                TileManagerTile::OffsetLength(offset, length) => {
+                    (false, u128::from(*offset), *length)
+                }
+            };
+
+            if let Some(z) = tile_zoom(*tile_id) {
+                stats.add_tile(z, key.2);
+            }
+
+            if seen.insert(key) {
+                stats.add_unique_tile(key.2);
             }
         }
 
-        entries.push(Entry {
-            tile_id,
-            offset,
-            length,
-            run_length: 1,
-        });
+        stats
+    }
+
+    /// Returns `true` if a tile with the specified id was added, without reading its data.
+    pub fn contains_tile(&self, tile_id: u64) -> bool {
+        self.tile_by_id.contains_key(&tile_id)
+    }
+
+    /// Returns the absolute offset and the length of the tile data within the reader, if the tile
+    /// was added via [`add_offset_tile`](Self::add_offset_tile).
+    ///
+    /// Returns [`None`] if there is no tile with the specified id or its data is held in memory.
+    pub fn get_tile_offset(&self, tile_id: u64) -> Option<(u64, u32)> {
+        match self.tile_by_id.get(&tile_id)? {
+            TileManagerTile::OffsetLength(offset, length) => Some((*offset, *length)),
+            TileManagerTile::Hash(_) => None,
+        }
+    }
+
+    /// Returns the hash of the data of a tile (see [`dedupe_hash`](Self::dedupe_hash)), if the tile
+    /// was added via [`add_tile`](Self::add_tile).
+    ///
+    /// Returns [`None`] if there is no tile with the specified id or its data has to be read from the reader.
+    pub fn get_tile_hash(&self, tile_id: u64) -> Option<u128> {
+        match self.tile_by_id.get(&tile_id)? {
+            TileManagerTile::Hash(hash) => Some(*hash),
+            TileManagerTile::OffsetLength(..) => None,
+        }
+    }
+
+    /// Returns the ids of all consecutive tiles around `tile_id`, which reference the same data
+    /// within the reader, if the tile was added via [`add_offset_tile`](Self::add_offset_tile).
+    ///
+    /// Returns [`None`] if there is no tile with the specified id or its data is held in memory.
+    pub fn get_tile_offset_run(&self, tile_id: u64) -> Option<RangeInclusive<u64>> {
+        let (&first_id, &last_id) = self.offset_runs.range(..=tile_id).next_back()?;
+
+        (tile_id <= last_id).then_some(first_id..=last_id)
+    }
+
+    /// Returns a reference to the data of a tile, if the tile was added via [`add_tile`](Self::add_tile).
+    ///
+    /// Returns [`None`] if there is no tile with the specified id or its data has to be read from the reader.
+    pub fn get_tile_ref(&self, tile_id: u64) -> Option<&[u8]> {
+        match self.tile_by_id.get(&tile_id)? {
+            TileManagerTile::Hash(hash) => self.data_by_hash.get(hash).map(AsRef::as_ref),
+            TileManagerTile::OffsetLength(..) => None,
+        }
+    }
+
+    /// Returns an iterator over the ids of all tiles in no particular order.
+    pub fn get_tile_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.tile_by_id.keys().copied()
+    }
+
+    /// Returns all tile ids, that are included in `range`, in ascending order.
+    pub fn get_tile_ids_in_range(&self, range: &impl RangeBounds<u64>) -> Vec<u64> {
+        let mut ids = self
+            .tile_by_id
+            .keys()
+            .copied()
+            .filter(|id| range.contains(id))
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Returns the number of tiles.
+    pub fn num_addressed_tiles(&self) -> usize {
+        self.tile_by_id.len()
     }
 }
 
+/// Appends an entry for a tile to `entries`, extending the run length of the last entry if
+/// the tile directly follows it and references the same data.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn push_entry(entries: &mut Vec<Entry>, tile_id: u64, offset: u64, length: u32) {
+    if let Some(last) = entries.last_mut() {
+        if tile_id == last.tile_id + u64::from(last.run_length)
+            && last.offset == offset
+            && last.length == length
+        {
+            last.run_length += 1;
+            return;
+        }
+    }
+
+    entries.push(Entry {
+        tile_id,
+        offset,
+        length,
+        run_length: 1,
+    });
+}
+
 #[duplicate_item(
-    async    add_await(code) cfg_async_filter       RTraits                                                  SeekFrom                get_tile_content         get_tile         finish;
-    []       [code]          [cfg(all())]           [Read + Seek]                                            [std::io::SeekFrom]     [get_tile_content]       [get_tile]       [finish];
-    [async]  [code.await]    [cfg(feature="async")] [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [futures::io::SeekFrom] [get_tile_content_async] [get_tile_async] [finish_async];
+    async    add_await(code) cfg_async_filter       RTraits                                                  SeekFrom                get_tile_content         get_tile         get_tile_shared         get_tiles         finish         finish_with_progress         finish_cancellable         PTraits;
+    []       [code]          [cfg(all())]           [Read + Seek]                                            [std::io::SeekFrom]     [get_tile_content]       [get_tile]       [get_tile_shared]       [get_tiles]       [finish]       [finish_with_progress]       [finish_cancellable]       [WriteProgress];
+    [async]  [code.await]    [cfg(feature="async")] [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [futures::io::SeekFrom] [get_tile_content_async] [get_tile_async] [get_tile_shared_async] [get_tiles_async] [finish_async] [finish_with_progress_async] [finish_cancellable_async] [WriteProgress + Send];
 )]
 #[cfg_async_filter]
 impl<R: RTraits> TileManager<R> {
     async fn get_tile_content(
         reader: &mut Option<R>,
-        data_by_hash: &HashMap<u64, Vec<u8>>,
+        data_by_hash: &HashMap<u128, Arc<[u8]>>,
         tile: &TileManagerTile,
-    ) -> Result<Option<Vec<u8>>> {
+    ) -> Result<Option<Arc<[u8]>>> {
         match tile {
             TileManagerTile::Hash(hash) => Ok(data_by_hash.get(hash).cloned()),
             TileManagerTile::OffsetLength(offset, length) => match reader {
@@ -169,17 +487,34 @@ impl<R: RTraits> TileManager<R> {
                     add_await([r.seek(SeekFrom::Start(*offset))])?;
                     let mut buf = vec![0; *length as usize];
                     add_await([r.read_exact(&mut buf)])?;
-                    Ok(Some(buf))
+                    Ok(Some(buf.into()))
                 }
-                None => Err(Error::new(
-                    ErrorKind::UnexpectedEof,
-                    "Tried to read from non-existent reader",
-                )),
+                None => Err(Error::NoReader),
             },
         }
     }
 
+    /// Returns the data of a tile by its id.
+    ///
+    /// Returns [`Ok`] with a value of [`None`] if no tile with the specified id exists.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the tile data has to be read from the reader and there was an error
+    /// while attempting to read it, or there is no reader.
     pub async fn get_tile(&mut self, tile_id: u64) -> Result<Option<Vec<u8>>> {
+        let data = add_await([self.get_tile_shared(tile_id)])?;
+        Ok(data.map(|data| data.to_vec()))
+    }
+
+    /// Returns the data of a tile by its id, sharing the allocation with this tile manager, if the
+    /// data is held in memory.
+    ///
+    /// Returns [`Ok`] with a value of [`None`] if no tile with the specified id exists.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the tile data has to be read from the reader and there was an error
+    /// while attempting to read it, or there is no reader.
+    pub async fn get_tile_shared(&mut self, tile_id: u64) -> Result<Option<Arc<[u8]>>> {
         match self.tile_by_id.get(&tile_id) {
             None => Ok(None),
             Some(tile) => add_await([Self::get_tile_content(
@@ -190,14 +525,119 @@ impl<R: RTraits> TileManager<R> {
         }
     }
 
-    pub async fn finish(mut self) -> Result<FinishResult> {
+    /// Returns the data of multiple tiles by their ids.
+    ///
+    /// The returned `Vec` holds the data of each tile in the same order as `tile_ids`, with a value
+    /// of [`None`] for every id, for which no tile exists.
+    ///
+    /// Tiles, whose data has to be read from the reader, are read in ascending order of their offsets
+    /// and adjacent (or shared) byte ranges are merged into a single read, which is a lot faster
+    /// than calling [`get_tile`](Self::get_tile) for each id.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the tile data has to be read from the reader and there was an error
+    /// while attempting to read it, or there is no reader.
+    pub async fn get_tiles(&mut self, tile_ids: &[u64]) -> Result<Vec<Option<Vec<u8>>>> {
+        let mut result = vec![None; tile_ids.len()];
+
+        // (index in result, offset, length)
+        let mut to_read = Vec::<(usize, u64, u32)>::new();
+
+        for (index, tile_id) in tile_ids.iter().enumerate() {
+            match self.tile_by_id.get(tile_id) {
+                None => {}
+                Some(TileManagerTile::Hash(hash)) => {
+                    result[index] = self.data_by_hash.get(hash).map(|data| data.to_vec());
+                }
+                Some(TileManagerTile::OffsetLength(offset, length)) => {
+                    to_read.push((index, *offset, *length));
+                }
+            }
+        }
+
+        if to_read.is_empty() {
+            return Ok(result);
+        }
+
+        let Some(reader) = &mut self.reader else {
+            return Err(Error::NoReader);
+        };
+
+        to_read.sort_unstable_by_key(|(_, offset, _)| *offset);
+
+        let mut start = 0;
+        while start < to_read.len() {
+            let range_start = to_read[start].1;
+            let mut range_end = range_start + u64::from(to_read[start].2);
+
+            // merge all following ranges, which are adjacent to or overlap with the current one
+            let mut end = start + 1;
+            while end < to_read.len() && to_read[end].1 <= range_end {
+                range_end = range_end.max(to_read[end].1 + u64::from(to_read[end].2));
+                end += 1;
+            }
+
+            add_await([reader.seek(SeekFrom::Start(range_start))])?;
+            #[allow(clippy::cast_possible_truncation)]
+            let mut buf = vec![0; (range_end - range_start) as usize];
+            add_await([reader.read_exact(&mut buf)])?;
+
+            for &(index, offset, length) in &to_read[start..end] {
+                #[allow(clippy::cast_possible_truncation)]
+                let relative = (offset - range_start) as usize;
+                result[index] = Some(buf[relative..relative + length as usize].to_vec());
+            }
+
+            start = end;
+        }
+
+        Ok(result)
+    }
+
+    /// Consumes the tile manager and builds the tile data section and directory entries.
+    ///
+    /// See the [type-level documentation](TileManager) for details.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if tile data has to be read from the reader and there was an error
+    /// while attempting to read it, or there is no reader.
+    pub async fn finish(self) -> Result<FinishResult> {
+        add_await([self.finish_with_progress(&mut |_, _, _| {})])
+    }
+
+    /// Same as [`finish`](Self::finish), but reports the number of processed tiles to `progress`
+    /// (as [`WritePhase::Dedupe`]).
+    ///
+    /// # Arguments
+    /// * `progress` - Receiver of progress updates
+    ///
+    /// # Errors
+    /// See [`finish`](Self::finish) for details on possible errors.
+    pub async fn finish_with_progress<P: PTraits>(self, progress: &mut P) -> Result<FinishResult> {
+        add_await([self.finish_cancellable(progress, &CancellationToken::new(), true, None)])
+    }
+
+    /// Same as [`finish_with_progress`](Self::finish_with_progress), but returns [`Error::Cancelled`]
+    /// before reading the next tile, if `cancellation` was cancelled.
+    ///
+    /// If `dedupe` is `false`, the data of each tile is stored separately, even if it has the same content.
+    ///
+    /// If a `transform` is given, it is applied to the data of each tile before deduplicating it.
+    #[allow(clippy::redundant_pub_crate)]
+    pub(crate) async fn finish_cancellable<P: PTraits>(
+        mut self,
+        progress: &mut P,
+        cancellation: &CancellationToken,
+        dedupe: bool,
+        mut transform: Option<&mut (dyn TileTransform + Send + '_)>,
+    ) -> Result<FinishResult> {
         type OffsetLen = (u64, u32);
 
         let mut id_tile = self
             .tile_by_id
             .into_iter()
             .collect::<Vec<(u64, TileManagerTile)>>();
-        id_tile.sort_by(|a, b| a.0.cmp(&b.0));
+        id_tile.sort_by_key(|a| a.0);
 
         let mut entries = Vec::<Entry>::new();
         let mut data = Vec::<u8>::new();
@@ -206,10 +646,15 @@ impl<R: RTraits> TileManager<R> {
         let mut num_tile_content: u64 = 0;
 
         // hash => offset+length
-        let mut offset_length_map = HashMap::<u64, OffsetLen, RandomState>::default();
+        let mut offset_length_map = HashMap::<u128, OffsetLen, RandomState>::default();
+
+        let num_tiles = id_tile.len() as u64;
+
+        for (index, (tile_id, tile)) in id_tile.into_iter().enumerate() {
+            progress.on_progress(WritePhase::Dedupe, index as u64, num_tiles);
+            cancellation.check()?;
 
-        for (tile_id, tile) in id_tile {
-            let Some(mut tile_data) = add_await([Self::get_tile_content(
+            let Some(content) = add_await([Self::get_tile_content(
                 &mut self.reader,
                 &self.data_by_hash,
                 &tile,
@@ -218,30 +663,47 @@ impl<R: RTraits> TileManager<R> {
                 continue;
             };
 
-            let hash = if let TileManagerTile::Hash(h) = tile {
-                h
+            let (tile_data, hash) = if let Some(transform) = &mut transform {
+                let Some(transformed) = transform.transform(tile_id, &content)? else {
+                    continue;
+                };
+                if transformed.is_empty() {
+                    return Err(Error::EmptyTile);
+                }
+
+                let hash = self.dedupe_hash.hash(&transformed);
+                (Cow::Owned(transformed), hash)
             } else {
-                Self::calculate_hash(&tile_data)
+                let hash = if let TileManagerTile::Hash(h) = tile {
+                    h
+                } else {
+                    self.dedupe_hash.hash(&content)
+                };
+                (Cow::Borrowed(&*content), hash)
             };
 
             num_addressed_tiles += 1;
 
             if let Some((offset, length)) = offset_length_map.get(&hash) {
-                Self::push_entry(&mut entries, tile_id, *offset, *length);
+                push_entry(&mut entries, tile_id, *offset, *length);
             } else {
                 let offset = data.len() as u64;
 
                 #[allow(clippy::cast_possible_truncation)]
                 let length = tile_data.len() as u32;
 
-                data.append(&mut tile_data);
+                data.extend_from_slice(&tile_data);
                 num_tile_content += 1;
 
-                Self::push_entry(&mut entries, tile_id, offset, length);
-                offset_length_map.insert(hash, (offset, length));
+                push_entry(&mut entries, tile_id, offset, length);
+                if dedupe {
+                    offset_length_map.insert(hash, (offset, length));
+                }
             }
         }
 
+        progress.on_progress(WritePhase::Dedupe, num_tiles, num_tiles);
+
         let num_tile_entries = entries.len() as u64;
 
         Ok(FinishResult {
@@ -254,6 +716,35 @@ impl<R: RTraits> TileManager<R> {
     }
 }
 
+impl<R: ReadAt> TileManager<R> {
+    /// Returns the data of a tile by its id, reading it through a shared reference to the reader.
+    ///
+    /// In contrast to [`get_tile`](Self::get_tile), this does not require exclusive access,
+    /// so tiles can be read concurrently.
+    ///
+    /// Returns [`Ok`] with a value of [`None`] if no tile with the specified id exists.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the tile data has to be read from the reader and there was an error
+    /// while attempting to read it, or there is no reader.
+    pub fn read_tile(&self, tile_id: u64) -> Result<Option<Vec<u8>>> {
+        match self.tile_by_id.get(&tile_id) {
+            None => Ok(None),
+            Some(TileManagerTile::Hash(hash)) => {
+                Ok(self.data_by_hash.get(hash).map(|data| data.to_vec()))
+            }
+            Some(TileManagerTile::OffsetLength(offset, length)) => match &self.reader {
+                Some(r) => {
+                    let mut buf = vec![0; *length as usize];
+                    r.read_exact_at(*offset, &mut buf)?;
+                    Ok(Some(buf))
+                }
+                None => Err(Error::NoReader),
+            },
+        }
+    }
+}
+
 impl Default for TileManager<Cursor<&[u8]>> {
     fn default() -> Self {
         Self::new(None)
@@ -261,6 +752,7 @@ impl Default for TileManager<Cursor<&[u8]>> {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod test {
     use super::*;
 
@@ -290,6 +782,66 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_with_capacity() {
+        let manager = TileManager::<Cursor<&[u8]>>::with_capacity(None, 1024);
+
+        assert!(manager.tile_by_id.capacity() >= 1024);
+        assert!(manager.data_by_hash.capacity() >= 1024);
+        assert!(manager.ids_by_hash.capacity() >= 1024);
+        assert_eq!(manager.num_addressed_tiles(), 0);
+    }
+
+    #[test]
+    fn test_reserve() -> Result<()> {
+        let mut manager = TileManager::default();
+
+        manager.add_tile(42, vec![1u8, 3, 3, 7, 4, 2])?;
+        manager.reserve(1024);
+
+        assert!(manager.tile_by_id.capacity() >= 1025);
+        assert!(manager.data_by_hash.capacity() >= 1025);
+        assert!(manager.ids_by_hash.capacity() >= 1025);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tile_ids_in_range() -> Result<()> {
+        let mut manager = TileManager::default();
+
+        manager.add_tile(42, vec![42])?;
+        manager.add_tile(7, vec![7])?;
+        manager.add_tile(1337, vec![13, 37])?;
+        manager.add_tile(5, vec![5])?;
+
+        assert_eq!(manager.get_tile_ids_in_range(&(..)), vec![5, 7, 42, 1337]);
+        assert_eq!(manager.get_tile_ids_in_range(&(6..=42)), vec![7, 42]);
+        assert!(manager.get_tile_ids_in_range(&(43..1337)).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fill_tiles() -> Result<()> {
+        let mut manager = TileManager::default();
+
+        manager.add_tile(1, vec![1])?;
+
+        assert_eq!(manager.fill_tiles(0..5, vec![0])?, 4);
+        assert_eq!(manager.tile_by_id.len(), 5);
+        assert_eq!(manager.data_by_hash.len(), 2);
+        assert_eq!(manager.get_tile(1)?, Some(vec![1]));
+        assert_eq!(manager.get_tile(4)?, Some(vec![0]));
+
+        assert_eq!(manager.fill_tiles(0..5, vec![2])?, 0);
+        assert_eq!(manager.data_by_hash.len(), 2);
+
+        assert!(manager.fill_tiles(5..6, vec![]).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_add_tile() -> Result<()> {
         let mut manager = TileManager::default();
@@ -303,6 +855,94 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_contains_tile() -> Result<()> {
+        let mut manager = TileManager::<Cursor<&[u8]>>::new(None);
+        manager.add_tile(1, vec![1])?;
+        manager.add_offset_tile(3, 0, 1)?;
+
+        assert!(manager.contains_tile(1));
+        assert!(manager.contains_tile(3));
+        assert!(!manager.contains_tile(2));
+
+        manager.remove_tile(1);
+        assert!(!manager.contains_tile(1));
+
+        assert_eq!(manager.get_tile_offset(3), Some((0, 1)));
+        assert_eq!(manager.get_tile_offset(2), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_tiles_in_range() -> Result<()> {
+        let mut manager = TileManager::<Cursor<&[u8]>>::new(None);
+        for id in 0..10 {
+            manager.add_tile(id, vec![1])?;
+        }
+
+        assert_eq!(manager.remove_tiles_in_range(&(3..6)), 3);
+        assert_eq!(manager.remove_tiles_in_range(&(8..)), 2);
+        assert_eq!(manager.remove_tiles_in_range(&(3..6)), 0);
+        assert_eq!(manager.get_tile_ids_in_range(&..), vec![0, 1, 2, 6, 7]);
+        assert_eq!(manager.get_tile(0)?, Some(vec![1]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        let mut manager = TileManager::<Cursor<&[u8]>>::new(None);
+        manager.add_tile(0, vec![1; 10])?;
+        manager.add_tile(1, vec![1; 4])?;
+        manager.add_tile(2, vec![1; 4])?;
+        manager.add_offset_tile(3, 0, 8)?;
+        manager.add_offset_tile(4, 0, 8)?;
+
+        let stats = manager.stats();
+        assert_eq!(stats.num_tiles(), 5);
+        assert_eq!(stats.num_unique_tiles, 3);
+        assert_eq!(stats.unique_bytes, 22);
+        assert_eq!(stats.dedup_savings(), 12);
+        assert_eq!(stats.zooms[&0].total_bytes, 10);
+        assert_eq!(stats.zooms[&1].num_tiles, 4);
+        assert_eq!(stats.zooms[&1].min_tile_size, 4);
+        assert_eq!(stats.zooms[&1].max_tile_size, 8);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tiles() -> Result<()> {
+        let reader = Cursor::new(&[0u8, 1, 2, 3, 4, 5, 6, 7, 8, 9][..]);
+        let mut manager = TileManager::new(Some(reader));
+        manager.add_offset_tile(0, 6, 2)?;
+        manager.add_offset_tile(1, 0, 2)?;
+        manager.add_offset_tile(2, 2, 3)?;
+        manager.add_offset_tile(3, 0, 2)?;
+        manager.add_tile(4, vec![42])?;
+
+        assert_eq!(
+            manager.get_tiles(&[3, 0, 5, 4, 2, 1])?,
+            vec![
+                Some(vec![0, 1]),
+                Some(vec![6, 7]),
+                None,
+                Some(vec![42]),
+                Some(vec![2, 3, 4]),
+                Some(vec![0, 1]),
+            ]
+        );
+
+        assert!(manager.get_tiles(&[])?.is_empty());
+
+        let mut manager = TileManager::<Cursor<&[u8]>>::new(None);
+        manager.add_offset_tile(0, 0, 1)?;
+        assert!(manager.get_tiles(&[0]).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_add_tile_dedup() -> Result<()> {
         let mut manager = TileManager::default();
@@ -473,6 +1113,110 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_get_tile_offset_run() -> Result<()> {
+        let mut manager = TileManager::new(Some(Cursor::new(vec![1u8, 3, 3, 7])));
+
+        manager.add_offset_tile(3, 0, 4)?;
+        manager.add_offset_tile(1, 0, 4)?;
+        manager.add_offset_tile(4, 2, 2)?;
+        assert_eq!(manager.get_tile_offset_run(1), Some(1..=1));
+        assert_eq!(manager.get_tile_offset_run(3), Some(3..=3));
+        assert_eq!(manager.get_tile_offset_run(4), Some(4..=4));
+
+        // joins the runs before and after it
+        manager.add_offset_tile(2, 0, 4)?;
+        assert_eq!(manager.get_tile_offset_run(1), Some(1..=3));
+        assert_eq!(manager.get_tile_offset_run(3), Some(1..=3));
+        assert_eq!(manager.get_tile_offset_run(4), Some(4..=4));
+
+        manager.add_tile(2, vec![1])?;
+        assert_eq!(manager.get_tile_offset_run(1), Some(1..=1));
+        assert_eq!(manager.get_tile_offset_run(2), None);
+        assert_eq!(manager.get_tile_offset_run(3), Some(3..=3));
+
+        manager.add_offset_tile(2, 0, 4)?;
+        manager.remove_tile(3);
+        assert_eq!(manager.get_tile_offset_run(2), Some(1..=2));
+        assert_eq!(manager.get_tile_offset_run(3), None);
+
+        manager.add_offset_tile(u64::MAX, 0, 4)?;
+        manager.add_offset_tile(0, 0, 4)?;
+        assert_eq!(manager.get_tile_offset_run(0), Some(0..=2));
+        assert_eq!(
+            manager.get_tile_offset_run(u64::MAX),
+            Some(u64::MAX..=u64::MAX)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_tile() -> Result<()> {
+        let reader = Cursor::new(vec![1u8, 3, 3, 7, 4, 2]);
+
+        let mut manager = TileManager::new(Some(reader));
+
+        manager.add_offset_tile(0, 4, 2)?;
+        manager.add_tile(1, vec![1, 3, 3, 7])?;
+
+        let manager = &manager;
+        assert_eq!(manager.read_tile(0)?, Some(vec![4, 2]));
+        assert_eq!(manager.read_tile(1)?, Some(vec![1, 3, 3, 7]));
+        assert_eq!(manager.read_tile(2)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tile_ref() -> Result<()> {
+        let reader = Cursor::new(vec![1u8, 3, 3, 7, 4, 2]);
+
+        let mut manager = TileManager::new(Some(reader));
+
+        manager.add_offset_tile(0, 4, 2)?;
+        manager.add_tile(1, vec![1, 3, 3, 7])?;
+        manager.add_tile(2, vec![1, 3, 3, 7])?;
+
+        assert_eq!(manager.get_tile_ref(0), None);
+        assert_eq!(manager.get_tile_ref(1), Some(&[1, 3, 3, 7][..]));
+        assert_eq!(manager.get_tile_ref(2), Some(&[1, 3, 3, 7][..]));
+        assert_eq!(manager.get_tile_ref(3), None);
+
+        assert_eq!(manager.get_tile_hash(0), None);
+        assert_eq!(
+            manager.get_tile_hash(1),
+            Some(DedupeHash::AHash.hash(&[1, 3, 3, 7]))
+        );
+        assert_eq!(manager.get_tile_hash(1), manager.get_tile_hash(2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tile_shared() -> Result<()> {
+        let reader = Cursor::new(vec![1u8, 3, 3, 7, 4, 2]);
+        let data: Arc<[u8]> = Arc::from(vec![1, 3, 3, 7]);
+
+        let mut manager = TileManager::new(Some(reader));
+
+        manager.add_offset_tile(0, 4, 2)?;
+        manager.add_tile(1, data.clone())?;
+        manager.add_tile(2, vec![1, 3, 3, 7])?;
+        manager.fill_tiles([3], data.to_vec())?;
+
+        assert_eq!(manager.get_tile_shared(0)?.as_deref(), Some(&[4, 2][..]));
+        for tile_id in 1..=3 {
+            assert!(Arc::ptr_eq(
+                &manager.get_tile_shared(tile_id)?.unwrap(),
+                &data
+            ));
+        }
+        assert_eq!(manager.get_tile_shared(4)?, None);
+
+        Ok(())
+    }
+
     #[test]
     fn test_finish_run_length() -> Result<()> {
         let mut manager = TileManager::default();
@@ -523,4 +1267,37 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_set_dedupe_hash() -> Result<()> {
+        let algorithms = [
+            DedupeHash::AHash,
+            #[cfg(feature = "xxhash")]
+            DedupeHash::XxHash3,
+            #[cfg(feature = "blake3")]
+            DedupeHash::Blake3,
+        ];
+
+        for algorithm in algorithms {
+            let mut manager = TileManager::default();
+            manager.add_tile(0, vec![1, 3, 3, 7])?;
+            manager.add_tile(1, vec![1, 3, 3, 7])?;
+            manager.add_tile(2, vec![4, 2])?;
+
+            // tiles added before and after changing the algorithm are deduplicated
+            manager.set_dedupe_hash(algorithm);
+            manager.add_tile(3, vec![4, 2])?;
+            assert_eq!(manager.dedupe_hash(), algorithm);
+
+            assert!(manager.remove_tile(0));
+            assert_eq!(manager.get_tile(1)?, Some(vec![1, 3, 3, 7]));
+
+            let result = manager.finish()?;
+            assert_eq!(result.data, vec![1, 3, 3, 7, 4, 2]);
+            assert_eq!(result.num_addressed_tiles, 3);
+            assert_eq!(result.num_tile_content, 2);
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,114 @@
+use crate::{
+    util::{compress_all_with_level, decompress_all},
+    Compression, Error, Result,
+};
+
+/// Rewrites the data of each tile while an archive is written
+/// (see [`WriteOptions::transform`](crate::WriteOptions::transform)).
+///
+/// Tiles are transformed one by one in ascending order of their ids, right before they are
+/// deduplicated, so no intermediate copy of the archive is needed. Tiles, which are transformed to
+/// the same data, are still only stored once.
+///
+/// This is implemented for all closures taking the same arguments as [`transform`](Self::transform).
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{PMTiles, TileType, Compression, WriteOptions};
+/// let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+/// pm_tiles.add_tile(0, vec![1, 2, 3]).unwrap();
+/// pm_tiles.add_tile(1, vec![4, 5, 6]).unwrap();
+///
+/// let mut output = std::io::Cursor::new(Vec::new());
+/// pm_tiles
+///     .to_writer_with_options(
+///         &mut output,
+///         WriteOptions {
+///             transform: Some(&mut |tile_id: u64, data: &[u8]| {
+///                 // drop tile 0 and reverse the data of all other tiles
+///                 Ok((tile_id != 0).then(|| data.iter().rev().copied().collect()))
+///             }),
+///             ..WriteOptions::default()
+///         },
+///     )
+///     .unwrap();
+///
+/// let mut pm_tiles = PMTiles::from_bytes(output.into_inner()).unwrap();
+/// assert_eq!(pm_tiles.get_tile_by_id(0).unwrap(), None);
+/// assert_eq!(pm_tiles.get_tile_by_id(1).unwrap(), Some(vec![6, 5, 4]));
+/// ```
+pub trait TileTransform {
+    /// Called for each tile, which is written.
+    ///
+    /// Returns the new data of the tile or [`None`] to leave the tile out of the written archive.
+    ///
+    /// # Arguments
+    /// * `tile_id` - Id of the tile
+    /// * `data` - Data of the tile (compressed with the tile compression of the archive)
+    ///
+    /// # Errors
+    /// Writing the archive is aborted with the returned error.
+    fn transform(&mut self, tile_id: u64, data: &[u8]) -> Result<Option<Vec<u8>>>;
+}
+
+impl<F: FnMut(u64, &[u8]) -> Result<Option<Vec<u8>>>> TileTransform for F {
+    fn transform(&mut self, tile_id: u64, data: &[u8]) -> Result<Option<Vec<u8>>> {
+        self(tile_id, data)
+    }
+}
+
+/// Transformation applied while writing an archive: the [`transform`](crate::WriteOptions::transform)
+/// of the write options, followed by re-compressing the data with
+/// [`tile_compression_level`](crate::WriteOptions::tile_compression_level).
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) struct WriteTransform<'a, 'b> {
+    transform: Option<&'b mut (dyn TileTransform + Send + 'a)>,
+    tile_compression: Compression,
+    level: Option<u32>,
+}
+
+impl<'a, 'b> WriteTransform<'a, 'b> {
+    /// Returns [`None`] if neither a transform nor a compression level is given, so tiles are written as is.
+    pub(crate) fn new(
+        transform: Option<&'b mut (dyn TileTransform + Send + 'a)>,
+        tile_compression: Compression,
+        level: Option<u32>,
+    ) -> Option<Self> {
+        // data, which is not compressed, cannot be re-compressed with another level
+        let level = level.filter(|_| tile_compression != Compression::None);
+
+        (transform.is_some() || level.is_some()).then_some(Self {
+            transform,
+            tile_compression,
+            level,
+        })
+    }
+}
+
+impl TileTransform for WriteTransform<'_, '_> {
+    fn transform(&mut self, tile_id: u64, data: &[u8]) -> Result<Option<Vec<u8>>> {
+        let transformed;
+        let data = match &mut self.transform {
+            Some(transform) => match transform.transform(tile_id, data)? {
+                Some(data) => {
+                    transformed = data;
+                    &transformed[..]
+                }
+                None => return Ok(None),
+            },
+            None => data,
+        };
+
+        let Some(level) = self.level else {
+            return Ok(Some(data.to_vec()));
+        };
+
+        let data = decompress_all(self.tile_compression, data)?;
+        let data = compress_all_with_level(self.tile_compression, Some(level), &data)?;
+        if data.is_empty() {
+            return Err(Error::EmptyTile);
+        }
+
+        Ok(Some(data))
+    }
+}
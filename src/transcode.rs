@@ -0,0 +1,179 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io::{Read, Seek},
+    sync::Arc,
+};
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+use ahash::RandomState;
+
+use crate::{
+    util::{compress_all_with_level, decompress_all},
+    Compression, Error, PMTiles, Result,
+};
+
+#[duplicate_item(
+    fn_name                            cfg_async_filter       async   add_await(code) RTraits                                                  get_tile_shared_by_id;
+    [transcode_tile_compression]       [cfg(all())]           []      [code]          [Read + Seek]                                            [get_tile_shared_by_id];
+    [transcode_tile_compression_async] [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [get_tile_shared_by_id_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Re-compresses the data of all tiles with another compression and sets
+    /// [`tile_compression`](Self::tile_compression) accordingly.
+    ///
+    /// Each tile is decompressed according to the current [`tile_compression`](Self::tile_compression)
+    /// and compressed with `target`. Tiles with the same content are only transcoded once.
+    /// As all tiles are held in memory afterwards, this can be used to migrate an archive
+    /// (e.g. from GZIP to ZSTD) by reading it, transcoding it and writing it again.
+    ///
+    /// Does nothing if `target` equals the current [`tile_compression`](Self::tile_compression).
+    ///
+    /// # Arguments
+    /// * `target` - Compression to re-compress tiles with
+    /// * `level` - Compression level (see [`util::compress_with_level`](crate::util::compress_with_level));
+    ///   if [`None`] the default level is used
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading, decompressing or compressing tile data.
+    /// In that case, the archive is left unchanged, as tiles are only replaced after all of them
+    /// were transcoded successfully.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{util::decompress_all, Compression, PMTiles, TileType};
+    /// let mut pm_tiles = PMTiles::new(TileType::Mvt, Compression::GZip);
+    /// pm_tiles.add_tile_uncompressed(0, &[0x1a, 0x03, 0x78, 0x02, 0x01]).unwrap();
+    ///
+    /// pm_tiles.transcode_tile_compression(Compression::ZStd, Some(19)).unwrap();
+    /// assert_eq!(pm_tiles.tile_compression, Compression::ZStd);
+    ///
+    /// let data = pm_tiles.get_tile_by_id(0).unwrap().unwrap();
+    /// assert_eq!(decompress_all(Compression::ZStd, &data).unwrap(), [0x1a, 0x03, 0x78, 0x02, 0x01]);
+    /// ```
+    pub async fn fn_name(&mut self, target: Compression, level: Option<u32>) -> Result<()> {
+        if target == self.tile_compression {
+            return Ok(());
+        }
+
+        let dedupe_hash = self.tile_manager.dedupe_hash();
+
+        // content hash => transcoded data
+        let mut transcoded_by_hash = HashMap::<u128, Arc<[u8]>, RandomState>::default();
+        let mut transcoded = Vec::with_capacity(self.num_tiles());
+
+        for tile_id in self.tile_ids_sorted() {
+            let Some(data) = add_await([self.get_tile_shared_by_id(tile_id)])? else {
+                continue;
+            };
+
+            let data = match transcoded_by_hash.entry(dedupe_hash.hash(&data)) {
+                Entry::Occupied(entry) => entry.get().clone(),
+                Entry::Vacant(entry) => {
+                    let data = decompress_all(self.tile_compression, &data)?;
+                    let data = compress_all_with_level(target, level, &data)?;
+                    if data.is_empty() {
+                        return Err(Error::EmptyTile);
+                    }
+
+                    entry.insert(data.into()).clone()
+                }
+            };
+
+            transcoded.push((tile_id, data));
+        }
+
+        for (tile_id, data) in transcoded {
+            self.tile_manager.add_tile(tile_id, data)?;
+        }
+        self.tile_compression = target;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PM_TILES_BYTES: &[u8] = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+
+    #[test]
+    fn test_transcode_tile_compression() -> Result<()> {
+        let mut source = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+        pm_tiles.transcode_tile_compression(Compression::Brotli, None)?;
+
+        assert_eq!(pm_tiles.tile_compression, Compression::Brotli);
+        assert_eq!(pm_tiles.num_tiles(), source.num_tiles());
+        assert!(pm_tiles.tiles_with_mismatched_compression()?.is_empty());
+
+        for tile_id in source.tile_ids_sorted() {
+            let expected = source.get_tile_by_id(tile_id)?.unwrap_or_default();
+            let actual = pm_tiles.get_tile_by_id(tile_id)?.unwrap_or_default();
+
+            assert_eq!(
+                decompress_all(Compression::Brotli, &actual)?,
+                decompress_all(Compression::GZip, &expected)?
+            );
+        }
+
+        let mut output = std::io::Cursor::new(Vec::new());
+        pm_tiles.to_writer(&mut output)?;
+
+        let written = PMTiles::from_bytes(output.get_ref().as_slice())?;
+        assert_eq!(written.tile_compression, Compression::Brotli);
+        assert_eq!(written.num_tiles(), source.num_tiles());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcode_tile_compression_error() -> Result<()> {
+        let mut pm_tiles = PMTiles::new(crate::TileType::Mvt, Compression::GZip);
+        pm_tiles.add_tile_uncompressed(0, [1, 2, 3])?;
+        pm_tiles.add_tile_uncompressed(1, [1, 2, 3])?;
+        pm_tiles.add_tile(2, vec![4, 5, 6])?; // not compressed with GZIP
+        let expected = pm_tiles.get_tile_by_id(0)?;
+
+        assert!(pm_tiles
+            .transcode_tile_compression(Compression::Brotli, None)
+            .is_err());
+
+        // tiles transcoded before the error were not replaced
+        assert_eq!(pm_tiles.tile_compression, Compression::GZip);
+        assert_eq!(pm_tiles.get_tile_by_id(0)?, expected);
+        assert_eq!(pm_tiles.get_tile_by_id(2)?, Some(vec![4, 5, 6]));
+
+        // tiles with the same content still share their data
+        pm_tiles.remove_tile(2);
+        pm_tiles.transcode_tile_compression(Compression::Brotli, None)?;
+        assert_eq!(pm_tiles.get_tile_by_id(0)?, pm_tiles.get_tile_by_id(1)?);
+        assert_eq!(pm_tiles.stats().num_unique_tiles, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_transcode_tile_compression_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let reader = futures::io::Cursor::new(PM_TILES_BYTES);
+            let mut pm_tiles = PMTiles::from_async_reader(reader).await?;
+
+            pm_tiles
+                .transcode_tile_compression_async(Compression::None, None)
+                .await?;
+
+            assert_eq!(pm_tiles.tile_compression, Compression::None);
+            let data = pm_tiles.get_tile_by_id_async(0).await?.unwrap_or_default();
+            assert_eq!(data.first(), Some(&0x1a));
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{Entry, Header};
+
+/// A difference between the [`clustered`](Header::clustered) flag of a header and the actual order
+/// of the tile data, as returned by [`check_clustered`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClusteringMismatch {
+    /// The header marks the archive as clustered, but the data of the tile with this id is not
+    /// in order of tile ids.
+    NotClustered(u64),
+
+    /// The tile data is in order of tile ids, but the header does not mark the archive as clustered.
+    NotMarked,
+}
+
+/// Returns the first tile entry of `entries`, whose tile data neither directly follows the tile
+/// data of the preceding entries, nor references tile data of a preceding entry.
+///
+/// This is the first entry, which violates clustering as defined by the specification.
+/// `entries` must be sorted in ascending order by their tile ids (e.g. as returned by
+/// [`read_entries`](super::read_entries)). Leaf directory entries are skipped.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::find_unclustered_entry, Entry};
+/// let entries = [
+///     Entry { tile_id: 0, offset: 0, length: 10, run_length: 1 },
+///     Entry { tile_id: 1, offset: 20, length: 10, run_length: 1 },
+///     Entry { tile_id: 2, offset: 10, length: 10, run_length: 1 },
+/// ];
+///
+/// assert_eq!(find_unclustered_entry(&entries), Some(&entries[1]));
+/// ```
+pub fn find_unclustered_entry(entries: &[Entry]) -> Option<&Entry> {
+    let mut next_offset = 0;
+
+    for entry in entries.iter().filter(|entry| !entry.is_leaf_dir_entry()) {
+        if entry.offset == next_offset {
+            next_offset += u64::from(entry.length);
+        } else if entry.offset > next_offset {
+            return Some(entry);
+        }
+    }
+
+    None
+}
+
+/// Returns `true` if the tile data `entries` point to is clustered (see [`find_unclustered_entry`]).
+pub fn is_clustered(entries: &[Entry]) -> bool {
+    find_unclustered_entry(entries).is_none()
+}
+
+/// Compares the [`clustered`](Header::clustered) flag of `header` with the actual order of the
+/// tile data `entries` point to.
+///
+/// Archives written by this crate are always clustered, but archives written by other tools
+/// (or merged from multiple archives) may not be, regardless of their header.
+///
+/// Returns [`None`] if the flag matches the order of the tile data.
+///
+/// # Arguments
+/// * `header` - Header of the archive
+/// * `entries` - All tile entries of the archive, sorted by their tile ids
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::{check_clustered, read_entries}, Header};
+/// # let bytes: &[u8] = include_bytes!("../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+/// # let mut reader = std::io::Cursor::new(bytes);
+/// let header = Header::from_reader(&mut reader).unwrap();
+///
+/// let entries = read_entries(
+///     &mut reader,
+///     header.internal_compression,
+///     (header.root_directory_offset, header.root_directory_length),
+///     header.leaf_directories_offset,
+///     ..,
+/// ).unwrap();
+///
+/// assert_eq!(check_clustered(&header, &entries[..]), None);
+/// ```
+pub fn check_clustered(header: &Header, entries: &[Entry]) -> Option<ClusteringMismatch> {
+    match (header.clustered, find_unclustered_entry(entries)) {
+        (true, Some(entry)) => Some(ClusteringMismatch::NotClustered(entry.tile_id)),
+        (false, None) => Some(ClusteringMismatch::NotMarked),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const fn entry(tile_id: u64, offset: u64, run_length: u32) -> Entry {
+        Entry {
+            tile_id,
+            offset,
+            length: 10,
+            run_length,
+        }
+    }
+
+    #[test]
+    fn test_find_unclustered_entry() {
+        assert_eq!(find_unclustered_entry(&[]), None);
+
+        // references to preceding tile data (deduplicated tiles) and leaf directories are fine
+        let entries = [
+            entry(0, 0, 1),
+            entry(1, 10, 2),
+            entry(3, 0, 1),
+            entry(4, 50, 0),
+        ];
+        assert!(is_clustered(&entries));
+
+        let entries = [entry(0, 10, 1), entry(1, 0, 1)];
+        assert_eq!(find_unclustered_entry(&entries), Some(&entries[0]));
+        assert!(!is_clustered(&entries));
+    }
+
+    #[test]
+    fn test_check_clustered() {
+        let clustered = [entry(0, 0, 1), entry(1, 10, 1)];
+        let unclustered = [entry(0, 10, 1), entry(1, 0, 1)];
+
+        let mut header = Header {
+            clustered: true,
+            ..Header::default()
+        };
+        assert_eq!(check_clustered(&header, &clustered), None);
+        assert_eq!(
+            check_clustered(&header, &unclustered),
+            Some(ClusteringMismatch::NotClustered(0))
+        );
+
+        header.clustered = false;
+        assert_eq!(
+            check_clustered(&header, &clustered),
+            Some(ClusteringMismatch::NotMarked)
+        );
+        assert_eq!(check_clustered(&header, &unclustered), None);
+    }
+}
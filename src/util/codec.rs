@@ -0,0 +1,303 @@
+use std::{
+    collections::BTreeMap,
+    io::{Read, Result, Write},
+    sync::{Arc, PoisonError, RwLock},
+};
+
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncWrite};
+#[cfg(feature = "async")]
+use std::io::{Error, ErrorKind};
+
+/// A user-provided compression codec, which is used for [`Compression::Unknown`](crate::Compression::Unknown).
+///
+/// Codecs are bound to the call reading or writing an archive: pass the codec as
+/// [`ParseOptions::codec`](crate::ParseOptions::codec) to read archives or as
+/// [`WriteOptions::codec`](crate::WriteOptions::codec) to write archives with an in-house compression.
+/// It is then used for directories and meta data, while tile data is always read and written as is.
+/// All other functions (e.g. [`compress`](crate::util::compress) and [`decompress`](crate::util::decompress))
+/// fail for [`Compression::Unknown`](crate::Compression::Unknown).
+///
+/// Writers returned by [`encoder`](Self::encoder) must write all remaining data to the underlying writer
+/// once they are flushed or dropped.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::Codec;
+/// # use pmtiles2::{Compression, ParseOptions, PMTiles, TileType, WriteOptions};
+/// # use std::io::{Read, Result, Write};
+/// # use std::sync::Arc;
+/// /// A codec, which does not compress at all.
+/// struct Identity;
+///
+/// impl Codec for Identity {
+///     fn encoder<'a>(&self, writer: Box<dyn Write + 'a>) -> Result<Box<dyn Write + 'a>> {
+///         Ok(writer)
+///     }
+///
+///     fn decoder<'a>(&self, reader: Box<dyn Read + 'a>) -> Result<Box<dyn Read + 'a>> {
+///         Ok(reader)
+///     }
+/// }
+///
+/// let codec: Arc<dyn Codec> = Arc::new(Identity);
+///
+/// let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+/// pm_tiles.internal_compression = Compression::Unknown;
+/// pm_tiles.add_tile(0, vec![1, 3, 3, 7]).unwrap();
+///
+/// let bytes = pm_tiles
+///     .to_vec_with_options(WriteOptions {
+///         codec: Some(codec.clone()),
+///         ..WriteOptions::default()
+///     })
+///     .unwrap();
+///
+/// let options = ParseOptions {
+///     allow_unknown_compression: true,
+///     codec: Some(codec),
+///     ..ParseOptions::default()
+/// };
+/// let mut pm_tiles = PMTiles::from_reader_with_options(std::io::Cursor::new(bytes), options).unwrap();
+/// assert_eq!(pm_tiles.get_tile_by_id(0).unwrap(), Some(vec![1, 3, 3, 7]));
+/// ```
+pub trait Codec: Send + Sync {
+    /// Wraps `writer` in a writer, which will emit compressed data to `writer`.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the encoder could not be created.
+    fn encoder<'a>(&self, writer: Box<dyn Write + 'a>) -> Result<Box<dyn Write + 'a>>;
+
+    /// Wraps `reader` in a reader, which will emit uncompressed data from `reader`.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the decoder could not be created.
+    fn decoder<'a>(&self, reader: Box<dyn Read + 'a>) -> Result<Box<dyn Read + 'a>>;
+
+    /// Async version of [`encoder`](Self::encoder).
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the encoder could not be created. The default implementation always
+    /// returns an error of kind [`ErrorKind::Unsupported`].
+    #[cfg(feature = "async")]
+    fn async_encoder<'a>(
+        &self,
+        writer: Box<dyn AsyncWrite + Unpin + Send + 'a>,
+    ) -> Result<Box<dyn AsyncWrite + Unpin + Send + 'a>> {
+        drop(writer);
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "Custom codec does not support async compression",
+        ))
+    }
+
+    /// Async version of [`decoder`](Self::decoder).
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the decoder could not be created. The default implementation always
+    /// returns an error of kind [`ErrorKind::Unsupported`].
+    #[cfg(feature = "async")]
+    fn async_decoder<'a>(
+        &self,
+        reader: Box<dyn AsyncRead + Unpin + Send + 'a>,
+    ) -> Result<Box<dyn AsyncRead + Unpin + Send + 'a>> {
+        drop(reader);
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "Custom codec does not support async decompression",
+        ))
+    }
+}
+
+/// Codecs registered with [`register_custom_codec`] by their ids.
+static CUSTOM_CODECS: RwLock<BTreeMap<String, Arc<dyn Codec>>> = RwLock::new(BTreeMap::new());
+
+/// Registers `codec` with the id `id`, so it can be looked up with [`custom_codec`] later on
+/// (e.g. by the id stored in the meta data of an archive).
+///
+/// Codecs are registered for the whole process, but are never used implicitly: the codec to use
+/// has to be passed to [`ParseOptions::codec`](crate::ParseOptions::codec) or
+/// [`WriteOptions::codec`](crate::WriteOptions::codec). A codec registered with an id, which is
+/// already in use, replaces the previously registered codec, which is returned.
+pub fn register_custom_codec(
+    id: impl Into<String>,
+    codec: impl Codec + 'static,
+) -> Option<Arc<dyn Codec>> {
+    CUSTOM_CODECS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(id.into(), Arc::new(codec))
+}
+
+/// Removes the codec registered with [`register_custom_codec`] as `id` and returns it.
+pub fn unregister_custom_codec(id: &str) -> Option<Arc<dyn Codec>> {
+    CUSTOM_CODECS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(id)
+}
+
+/// Returns the codec registered with [`register_custom_codec`] as `id` (if any).
+pub fn custom_codec(id: &str) -> Option<Arc<dyn Codec>> {
+    CUSTOM_CODECS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(id)
+        .cloned()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, Read, Write};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        util::{compress_all, decompress_all},
+        Compression, Directory, Entry, Error, PMTiles, ParseOptions, Result, TileType,
+        WriteOptions,
+    };
+
+    /// Codec, which XORs every byte with a constant key.
+    struct Xor;
+
+    struct XorWriter<'a>(Box<dyn Write + 'a>);
+
+    impl Write for XorWriter<'_> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let buf = buf.iter().map(|b| b ^ 0x42).collect::<Vec<_>>();
+            self.0.write(&buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.0.flush()
+        }
+    }
+
+    struct XorReader<'a>(Box<dyn Read + 'a>);
+
+    impl Read for XorReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.0.read(buf)?;
+            for b in &mut buf[..len] {
+                *b ^= 0x42;
+            }
+            Ok(len)
+        }
+    }
+
+    impl Codec for Xor {
+        fn encoder<'a>(&self, writer: Box<dyn Write + 'a>) -> std::io::Result<Box<dyn Write + 'a>> {
+            Ok(Box::new(XorWriter(writer)))
+        }
+
+        fn decoder<'a>(&self, reader: Box<dyn Read + 'a>) -> std::io::Result<Box<dyn Read + 'a>> {
+            Ok(Box::new(XorReader(reader)))
+        }
+    }
+
+    #[test]
+    fn test_custom_codec_directory() -> Result<()> {
+        let directory: Directory = vec![Entry {
+            tile_id: 0,
+            offset: 0,
+            length: 42,
+            run_length: 1,
+        }]
+        .into();
+
+        let mut bytes = Vec::<u8>::new();
+        directory.to_writer_impl(&mut bytes, Compression::Unknown, Some(&Xor))?;
+        let read = Directory::from_reader_impl(
+            &mut Cursor::new(&bytes),
+            bytes.len() as u64,
+            Compression::Unknown,
+            Some(&Xor),
+            &crate::ReadLimits::default(),
+        )?;
+        assert_eq!(read, directory);
+
+        // without a codec, the directory cannot be read
+        assert!(matches!(
+            Directory::from_bytes(&bytes, Compression::Unknown),
+            Err(Error::UnknownCompression)
+        ));
+
+        Ok(())
+    }
+
+    fn xor_archive() -> Result<PMTiles<Cursor<&'static [u8]>>> {
+        let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+        pm_tiles.internal_compression = Compression::Unknown;
+        pm_tiles.meta_data = json!({ "name": "xor" })
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        for tile_id in 0..100u8 {
+            pm_tiles.add_tile(u64::from(tile_id), vec![1, 3, 3, 7, tile_id])?;
+        }
+
+        Ok(pm_tiles)
+    }
+
+    #[test]
+    fn test_custom_codec_archive() -> Result<()> {
+        let codec: Arc<dyn Codec> = Arc::new(Xor);
+
+        assert!(matches!(
+            xor_archive()?.to_vec(),
+            Err(Error::UnknownCompression)
+        ));
+
+        // a small root directory requires leaf directories, which are compressed with the codec as well
+        let bytes = xor_archive()?.to_vec_with_options(WriteOptions {
+            max_root_dir_length: Some(100),
+            codec: Some(codec.clone()),
+            ..WriteOptions::default()
+        })?;
+
+        let options = ParseOptions {
+            allow_unknown_compression: true,
+            ..ParseOptions::default()
+        };
+        assert!(matches!(
+            PMTiles::from_reader_with_options(Cursor::new(&bytes), options.clone()),
+            Err(Error::UnknownCompression)
+        ));
+
+        let mut read = PMTiles::from_reader_with_options(
+            Cursor::new(&bytes),
+            ParseOptions {
+                codec: Some(codec),
+                ..options
+            },
+        )?;
+        assert_eq!(read.meta_data, xor_archive()?.meta_data);
+        assert_eq!(read.num_tiles(), 100);
+        assert_eq!(read.get_tile_by_id(42)?, Some(vec![1, 3, 3, 7, 42]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_codec_registry() {
+        assert!(register_custom_codec("test-registry-xor", Xor).is_none());
+        assert!(register_custom_codec("test-registry-xor", Xor).is_some());
+        assert!(custom_codec("test-registry-xor").is_some());
+        assert!(custom_codec("test-registry-other").is_none());
+
+        // registered codecs are never used implicitly
+        assert!(matches!(
+            compress_all(Compression::Unknown, &[1, 3, 3, 7]),
+            Err(Error::UnknownCompression)
+        ));
+        assert!(matches!(
+            decompress_all(Compression::Unknown, &[1, 3, 3, 7]),
+            Err(Error::UnknownCompression)
+        ));
+
+        assert!(unregister_custom_codec("test-registry-xor").is_some());
+        assert!(custom_codec("test-registry-xor").is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::Compression;
+use crate::{util::Codec, Compression, Error, Result};
 
 #[cfg(feature = "async")]
 use async_compression::futures::{
@@ -17,7 +17,7 @@ use flate2::{read::GzDecoder, write::GzEncoder};
 use futures::{io::BufReader, AsyncRead, AsyncWrite};
 use zstd::{Decoder as ZSTDDecoder, Encoder as ZSTDEncoder};
 
-use std::io::{Cursor, Error, ErrorKind, Read, Result, Write};
+use std::io::{Cursor, Read, Write};
 
 /// Returns a new instance of [`std::io::Write`] that will emit compressed data to the underlying writer.
 ///
@@ -26,8 +26,8 @@ use std::io::{Cursor, Error, ErrorKind, Read, Result, Write};
 /// * `writer` - Underlying writer to write compressed data to
 ///
 /// # Errors
-/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`] or an error occurred
-/// while creating the zstd encoder.
+/// Will return [`Error::UnknownCompression`] if `compression` is set to [`Compression::Unknown`]
+/// or [`Err`] if an error occurred while creating the encoder.
 ///
 /// # Example
 /// ```rust
@@ -44,22 +44,174 @@ use std::io::{Cursor, Error, ErrorKind, Read, Result, Write};
 pub fn compress<'a>(
     compression: Compression,
     writer: &'a mut impl Write,
+) -> Result<Box<dyn Write + 'a>> {
+    compress_with_level(compression, None, writer)
+}
+
+/// Same as [`compress`], but allows to set the compression level.
+///
+/// Higher levels result in smaller data, but take longer to compress. The level is clamped to the
+/// range supported by `compression` (`0..=9` for GZIP, `0..=11` for Brotli and `1..=22` for ZSTD)
+/// and ignored for [`Compression::None`] and [`Compression::Unknown`].
+///
+/// # Arguments
+/// * `compression` - Compression to use
+/// * `level` - Compression level; if [`None`] the same level as in [`compress`] is used
+/// * `writer` - Underlying writer to write compressed data to
+///
+/// # Errors
+/// Will return [`Error::UnknownCompression`] if `compression` is set to [`Compression::Unknown`]
+/// or [`Err`] if an error occurred while creating the encoder.
+pub fn compress_with_level<'a>(
+    compression: Compression,
+    level: Option<u32>,
+    writer: &'a mut impl Write,
+) -> Result<Box<dyn Write + 'a>> {
+    compress_with_codec(compression, level, None, writer)
+}
+
+/// Same as [`compress_with_level`], but uses `codec` for [`Compression::Unknown`].
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn compress_with_codec<'a>(
+    compression: Compression,
+    level: Option<u32>,
+    codec: Option<&dyn Codec>,
+    writer: &'a mut impl Write,
 ) -> Result<Box<dyn Write + 'a>> {
     match compression {
-        Compression::Unknown => Err(Error::new(
-            ErrorKind::Other,
-            "Cannot compress for Compression Unknown",
-        )),
+        Compression::Unknown => Ok(codec
+            .ok_or(Error::UnknownCompression)?
+            .encoder(Box::new(writer))?),
         Compression::None => Ok(Box::new(writer)),
         Compression::GZip => Ok(Box::new(GzEncoder::new(
             writer,
-            flate2::Compression::default(),
+            level.map_or_else(flate2::Compression::default, |l| {
+                flate2::Compression::new(l.min(9))
+            }),
         ))),
-        Compression::Brotli => Ok(Box::new(BrotliEncoder::new(writer, 4096, 11, 24))),
-        Compression::ZStd => Ok(Box::new(ZSTDEncoder::new(writer, 0)?.auto_finish())),
+        Compression::Brotli => {
+            let mut options = BrotliOptions::default();
+            if let Some(level) = level {
+                options.quality = level;
+            }
+
+            Ok(compress_brotli(options, writer))
+        }
+        Compression::ZStd => {
+            #[allow(clippy::cast_possible_wrap)]
+            let level = level.map_or(0, |l| l.clamp(1, 22) as i32);
+            Ok(Box::new(ZSTDEncoder::new(writer, level)?.auto_finish()))
+        }
+    }
+}
+
+/// Options for the Brotli encoder (see [`compress_brotli`]).
+///
+/// The defaults result in the smallest data, but are very slow. For writing many tiles or compressing
+/// tiles on the fly, a quality of `4` to `6` is a much better trade-off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrotliOptions {
+    /// Quality (`0..=11`, default `11`); higher qualities result in smaller data, but take longer
+    pub quality: u32,
+
+    /// Base 2 logarithm of the window size (`10..=24`, default `24`); larger windows result in
+    /// smaller data, but require more memory while compressing and decompressing
+    pub window_size: u32,
+
+    /// Size (in bytes) of the internal buffer of the encoder (default `4096`)
+    ///
+    /// _This is ignored by [`compress_brotli_async`]._
+    pub buffer_size: usize,
+}
+
+impl Default for BrotliOptions {
+    fn default() -> Self {
+        Self {
+            quality: 11,
+            window_size: 24,
+            buffer_size: 4096,
+        }
     }
 }
 
+/// Returns a new instance of [`std::io::Write`] that will emit Brotli compressed data to the underlying writer.
+///
+/// Same as [`compress`] with [`Compression::Brotli`], but allows to configure the encoder. All options
+/// are clamped to their supported ranges.
+///
+/// # Arguments
+/// * `options` - Options of the encoder
+/// * `writer` - Underlying writer to write compressed data to
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::{compress_brotli, decompress_all, BrotliOptions}, Compression};
+/// let mut output = Vec::<u8>::new();
+///
+/// let options = BrotliOptions { quality: 5, window_size: 20, ..BrotliOptions::default() };
+/// let mut writer = compress_brotli(options, &mut output);
+///
+/// writer.write_all(&[1, 3, 3, 7, 0, 4, 2, 0, 6, 9]).unwrap();
+/// drop(writer); // the encoder writes all remaining data, once it is dropped
+///
+/// assert_eq!(decompress_all(Compression::Brotli, &output).unwrap(), [1, 3, 3, 7, 0, 4, 2, 0, 6, 9]);
+/// ```
+pub fn compress_brotli<'a>(
+    options: BrotliOptions,
+    writer: &'a mut impl Write,
+) -> Box<dyn Write + 'a> {
+    Box::new(BrotliEncoder::new(
+        writer,
+        options.buffer_size.max(1),
+        options.quality.min(11),
+        options.window_size.clamp(10, 24),
+    ))
+}
+
+/// Async version of [`compress_brotli`].
+///
+/// [`BrotliOptions::buffer_size`] is ignored, as the async encoder manages its buffers on its own.
+///
+/// # Arguments
+/// * `options` - Options of the encoder
+/// * `writer` - Underlying writer to write compressed data to
+#[cfg(feature = "async")]
+pub fn compress_brotli_async<'a>(
+    options: BrotliOptions,
+    writer: &'a mut (impl AsyncWrite + Unpin + Send),
+) -> Box<dyn AsyncWrite + Unpin + Send + 'a> {
+    #[allow(clippy::cast_possible_wrap)]
+    Box::new(AsyncBrotliEncoder::with_quality_and_params(
+        writer,
+        async_compression::Level::Precise(options.quality.min(11) as i32),
+        async_compression::brotli::EncoderParams::default()
+            .window_size(options.window_size.clamp(10, 24) as i32),
+    ))
+}
+
+/// Compresses a byte slice with Brotli and returns the result as a new [`Vec<u8>`].
+///
+/// Same as [`compress_all`] with [`Compression::Brotli`], but allows to configure the encoder
+/// (see [`compress_brotli`]).
+///
+/// # Arguments
+/// * `options` - Options of the encoder
+/// * `data` - Data to compress
+///
+/// # Errors
+/// Will return [`Err`] if an error occurred while compressing `data`.
+pub fn compress_all_brotli(options: BrotliOptions, data: &[u8]) -> Result<Vec<u8>> {
+    let mut destination = Vec::<u8>::new();
+
+    {
+        let mut writer = compress_brotli(options, &mut destination);
+        writer.write_all(data)?;
+        writer.flush()?;
+    }
+
+    Ok(destination)
+}
+
 /// Async version of [`compress`].
 ///
 /// Returns a new instance of [`futures::io::AsyncWrite`](https://docs.rs/futures/latest/futures/io/trait.AsyncWrite.html) that will emit compressed data to the underlying writer.
@@ -69,8 +221,8 @@ pub fn compress<'a>(
 /// * `writer` - Underlying writer to write compressed data to
 ///
 /// # Errors
-/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`] or an error occurred
-/// while creating the zstd encoder.
+/// Will return [`Error::UnknownCompression`] if `compression` is set to [`Compression::Unknown`]
+/// or [`Err`] if an error occurred while creating the encoder.
 ///
 /// # Example
 /// ```rust
@@ -92,12 +244,22 @@ pub fn compress<'a>(
 pub fn compress_async<'a>(
     compression: Compression,
     writer: &'a mut (impl AsyncWrite + Unpin + Send),
+) -> Result<Box<dyn AsyncWrite + Unpin + Send + 'a>> {
+    compress_async_with_codec(compression, None, writer)
+}
+
+/// Same as [`compress_async`], but uses `codec` for [`Compression::Unknown`].
+#[allow(clippy::redundant_pub_crate)]
+#[cfg(feature = "async")]
+pub(crate) fn compress_async_with_codec<'a>(
+    compression: Compression,
+    codec: Option<&dyn Codec>,
+    writer: &'a mut (impl AsyncWrite + Unpin + Send),
 ) -> Result<Box<dyn AsyncWrite + Unpin + Send + 'a>> {
     match compression {
-        Compression::Unknown => Err(Error::new(
-            ErrorKind::Other,
-            "Cannot compress for Compression Unknown",
-        )),
+        Compression::Unknown => Ok(codec
+            .ok_or(Error::UnknownCompression)?
+            .async_encoder(Box::new(writer))?),
         Compression::None => Ok(Box::new(writer)),
         Compression::GZip => Ok(Box::new(AsyncGzipEncoder::new(writer))),
         Compression::Brotli => Ok(Box::new(AsyncBrotliEncoder::new(writer))),
@@ -112,14 +274,54 @@ pub fn compress_async<'a>(
 /// * `data` - Data to compress
 ///
 /// # Errors
-/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`], there was an error
-/// while creating the zstd encoder or an error occurred while writing to `data`.
+/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`],
+/// there was an error while creating the encoder or an error occurred while writing to `data`.
 #[allow(clippy::module_name_repetitions)]
 pub fn compress_all(compression: Compression, data: &[u8]) -> Result<Vec<u8>> {
+    compress_all_with_level(compression, None, data)
+}
+
+/// Same as [`compress_all`], but allows to set the compression level (see [`compress_with_level`]).
+///
+/// # Arguments
+/// * `compression` - Compression to use
+/// * `level` - Compression level; if [`None`] the same level as in [`compress_all`] is used
+/// * `data` - Data to compress
+///
+/// # Errors
+/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`],
+/// there was an error while creating the encoder or an error occurred while writing to `data`.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::{compress_all_with_level, decompress_all}, Compression};
+/// let data = vec![1, 3, 3, 7, 1, 3, 3, 7, 1, 3, 3, 7];
+///
+/// let compressed = compress_all_with_level(Compression::ZStd, Some(19), &data).unwrap();
+///
+/// assert_eq!(decompress_all(Compression::ZStd, &compressed).unwrap(), data);
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub fn compress_all_with_level(
+    compression: Compression,
+    level: Option<u32>,
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    compress_all_with_codec(compression, level, None, data)
+}
+
+/// Same as [`compress_all_with_level`], but uses `codec` for [`Compression::Unknown`].
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn compress_all_with_codec(
+    compression: Compression,
+    level: Option<u32>,
+    codec: Option<&dyn Codec>,
+    data: &[u8],
+) -> Result<Vec<u8>> {
     let mut destination = Vec::<u8>::new();
 
     {
-        let mut writer = compress(compression, &mut destination)?;
+        let mut writer = compress_with_codec(compression, level, codec, &mut destination)?;
 
         writer.write_all(data)?;
 
@@ -136,8 +338,8 @@ pub fn compress_all(compression: Compression, data: &[u8]) -> Result<Vec<u8>> {
 /// * `compressed_data` - Underlying reader with compressed data
 ///
 /// # Errors
-/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`],there was an
-/// error while creating the zstd decoder.
+/// Will return [`Error::UnknownCompression`] if `compression` is set to [`Compression::Unknown`]
+/// or [`Err`] if there was an error while creating the decoder.
 ///
 /// # Example
 /// ```rust
@@ -154,12 +356,21 @@ pub fn compress_all(compression: Compression, data: &[u8]) -> Result<Vec<u8>> {
 pub fn decompress<'a>(
     compression: Compression,
     compressed_data: &'a mut impl Read,
+) -> Result<Box<dyn Read + 'a>> {
+    decompress_with_codec(compression, None, compressed_data)
+}
+
+/// Same as [`decompress`], but uses `codec` for [`Compression::Unknown`].
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn decompress_with_codec<'a>(
+    compression: Compression,
+    codec: Option<&dyn Codec>,
+    compressed_data: &'a mut impl Read,
 ) -> Result<Box<dyn Read + 'a>> {
     match compression {
-        Compression::Unknown => Err(Error::new(
-            ErrorKind::Other,
-            "Cannot decompress for Compression Unknown",
-        )),
+        Compression::Unknown => Ok(codec
+            .ok_or(Error::UnknownCompression)?
+            .decoder(Box::new(compressed_data))?),
         Compression::None => Ok(Box::new(compressed_data)),
         Compression::GZip => Ok(Box::new(GzDecoder::new(compressed_data))),
         Compression::Brotli => Ok(Box::new(BrotliDecoder::new(compressed_data, 4096))),
@@ -176,19 +387,29 @@ pub fn decompress<'a>(
 /// * `compressed_data` - Underlying reader with compressed data
 ///
 /// # Errors
-/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`],there was an
-/// error while creating the zstd decoder.
+/// Will return [`Error::UnknownCompression`] if `compression` is set to [`Compression::Unknown`]
+/// or [`Err`] if there was an error while creating the decoder.
 ///
 #[cfg(feature = "async")]
 pub fn decompress_async<'a>(
     compression: Compression,
     compressed_data: &'a mut (impl AsyncRead + Unpin + Send),
+) -> Result<Box<dyn AsyncRead + Unpin + Send + 'a>> {
+    decompress_async_with_codec(compression, None, compressed_data)
+}
+
+/// Same as [`decompress_async`], but uses `codec` for [`Compression::Unknown`].
+#[allow(clippy::redundant_pub_crate)]
+#[cfg(feature = "async")]
+pub(crate) fn decompress_async_with_codec<'a>(
+    compression: Compression,
+    codec: Option<&dyn Codec>,
+    compressed_data: &'a mut (impl AsyncRead + Unpin + Send),
 ) -> Result<Box<dyn AsyncRead + Unpin + Send + 'a>> {
     match compression {
-        Compression::Unknown => Err(Error::new(
-            ErrorKind::Other,
-            "Cannot decompress for Compression Unknown",
-        )),
+        Compression::Unknown => Ok(codec
+            .ok_or(Error::UnknownCompression)?
+            .async_decoder(Box::new(compressed_data))?),
         Compression::None => Ok(Box::new(compressed_data)),
         Compression::GZip => Ok(Box::new(AsyncGzipDecoder::new(BufReader::new(
             compressed_data,
@@ -216,9 +437,19 @@ pub fn decompress_async<'a>(
 /// - `data` is not compressed correctly
 ///
 pub fn decompress_all(compression: Compression, data: &[u8]) -> Result<Vec<u8>> {
+    decompress_all_with_codec(compression, None, data)
+}
+
+/// Same as [`decompress_all`], but uses `codec` for [`Compression::Unknown`].
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn decompress_all_with_codec(
+    compression: Compression,
+    codec: Option<&dyn Codec>,
+    data: &[u8],
+) -> Result<Vec<u8>> {
     let mut data_reader = Cursor::new(data);
 
-    let mut reader = decompress(compression, &mut data_reader)?;
+    let mut reader = decompress_with_codec(compression, codec, &mut data_reader)?;
 
     let mut destination = Vec::<u8>::new();
 
@@ -227,6 +458,60 @@ pub fn decompress_all(compression: Compression, data: &[u8]) -> Result<Vec<u8>>
     Ok(destination)
 }
 
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Tries to detect the compression of `data` by its magic bytes.
+///
+/// Only [`Compression::GZip`] and [`Compression::ZStd`] can be detected reliably, as Brotli
+/// streams do not start with any magic bytes. Will return [`None`] if no compression was detected.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::{compress_all, detect_compression}, Compression};
+/// let data = compress_all(Compression::GZip, &[1, 3, 3, 7]).unwrap();
+///
+/// assert_eq!(detect_compression(&data), Some(Compression::GZip));
+/// assert_eq!(detect_compression(&[1, 3, 3, 7]), None);
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub fn detect_compression(data: &[u8]) -> Option<Compression> {
+    if data.starts_with(GZIP_MAGIC) {
+        return Some(Compression::GZip);
+    }
+
+    if data.starts_with(ZSTD_MAGIC) {
+        return Some(Compression::ZStd);
+    }
+
+    None
+}
+
+/// Returns `true` if `data` looks like it is compressed with `compression`.
+///
+/// This is a heuristic based on [`detect_compression`]: Data is considered to match
+/// [`Compression::GZip`] or [`Compression::ZStd`] if it starts with the respective magic bytes
+/// and to match [`Compression::None`] or [`Compression::Brotli`] if it does not start with
+/// any known magic bytes. Any data matches [`Compression::Unknown`].
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::{compress_all, matches_compression}, Compression};
+/// let data = compress_all(Compression::GZip, &[1, 3, 3, 7]).unwrap();
+///
+/// assert!(matches_compression(&data, Compression::GZip));
+/// assert!(!matches_compression(&[1, 3, 3, 7], Compression::GZip));
+/// ```
+pub fn matches_compression(data: &[u8], compression: Compression) -> bool {
+    let detected = detect_compression(data);
+
+    match compression {
+        Compression::Unknown => true,
+        Compression::None | Compression::Brotli => detected.is_none(),
+        Compression::GZip | Compression::ZStd => detected == Some(compression),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -270,6 +555,87 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn compress_all_with_level_roundtrip() -> Result<()> {
+        for compression in [Compression::GZip, Compression::Brotli, Compression::ZStd] {
+            let fast = compress_all_with_level(compression, Some(0), DATA_UNCOMPRESSED)?;
+            let best = compress_all_with_level(compression, Some(u32::MAX), DATA_UNCOMPRESSED)?;
+
+            assert!(best.len() < fast.len());
+            assert_eq!(decompress_all(compression, &fast)?, DATA_UNCOMPRESSED);
+            assert_eq!(decompress_all(compression, &best)?, DATA_UNCOMPRESSED);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn compress_all_brotli_roundtrip() -> Result<()> {
+        let fast = super::compress_all_brotli(
+            BrotliOptions {
+                quality: 4,
+                window_size: 16,
+                buffer_size: 1024,
+            },
+            DATA_UNCOMPRESSED,
+        )?;
+        let default = super::compress_all_brotli(BrotliOptions::default(), DATA_UNCOMPRESSED)?;
+
+        assert_eq!(
+            default,
+            compress_all(Compression::Brotli, DATA_UNCOMPRESSED)?
+        );
+        assert!(default.len() < fast.len());
+        assert_eq!(
+            decompress_all(Compression::Brotli, &fast)?,
+            DATA_UNCOMPRESSED
+        );
+
+        // options out of range are clamped
+        let clamped = super::compress_all_brotli(
+            BrotliOptions {
+                quality: 100,
+                window_size: 0,
+                buffer_size: 0,
+            },
+            DATA_UNCOMPRESSED,
+        )?;
+        assert_eq!(
+            decompress_all(Compression::Brotli, &clamped)?,
+            DATA_UNCOMPRESSED
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn compress_brotli_async_roundtrip() -> Result<()> {
+        use futures::AsyncWriteExt;
+
+        tokio_test::block_on(async {
+            let mut output = Vec::<u8>::new();
+            {
+                let mut writer = compress_brotli_async(
+                    BrotliOptions {
+                        quality: 5,
+                        ..BrotliOptions::default()
+                    },
+                    &mut output,
+                );
+                writer.write_all(DATA_UNCOMPRESSED).await?;
+                writer.close().await?;
+            }
+
+            assert_eq!(
+                decompress_all(Compression::Brotli, &output)?,
+                DATA_UNCOMPRESSED
+            );
+
+            Ok(())
+        })
+    }
+
     #[test]
     fn compress_all_unknown() {
         let res = compress_all(Compression::Unknown, &Vec::new());
@@ -303,4 +669,30 @@ mod test {
         assert_eq!(data, DATA_ZST);
         Ok(())
     }
+
+    #[test]
+    fn test_detect_compression() {
+        assert_eq!(detect_compression(DATA_UNCOMPRESSED), None);
+        assert_eq!(detect_compression(DATA_GZIP), Some(Compression::GZip));
+        assert_eq!(detect_compression(DATA_BR), None);
+        assert_eq!(detect_compression(DATA_ZST), Some(Compression::ZStd));
+        assert_eq!(detect_compression(&[]), None);
+    }
+
+    #[test]
+    fn test_matches_compression() {
+        assert!(matches_compression(DATA_UNCOMPRESSED, Compression::None));
+        assert!(!matches_compression(DATA_UNCOMPRESSED, Compression::GZip));
+        assert!(!matches_compression(DATA_UNCOMPRESSED, Compression::ZStd));
+        assert!(matches_compression(DATA_UNCOMPRESSED, Compression::Unknown));
+
+        assert!(matches_compression(DATA_GZIP, Compression::GZip));
+        assert!(!matches_compression(DATA_GZIP, Compression::None));
+        assert!(!matches_compression(DATA_GZIP, Compression::Brotli));
+
+        assert!(matches_compression(DATA_BR, Compression::Brotli));
+
+        assert!(matches_compression(DATA_ZST, Compression::ZStd));
+        assert!(!matches_compression(DATA_ZST, Compression::GZip));
+    }
 }
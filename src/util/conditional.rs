@@ -0,0 +1,252 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Computes a strong `ETag` for tile data, which can be used as the value of the `ETag` HTTP header.
+///
+/// The `ETag` is a quoted 64-bit FNV-1a hash of `data`, so it is stable across processes and
+/// versions of this crate.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::tile_etag;
+/// assert_eq!(tile_etag(&[1, 3, 3, 7]), tile_etag(&[1, 3, 3, 7]));
+/// assert_ne!(tile_etag(&[1, 3, 3, 7]), tile_etag(&[4, 2]));
+/// ```
+pub fn tile_etag(data: &[u8]) -> String {
+    let hash = data.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    });
+
+    format!("\"{hash:016x}\"")
+}
+
+/// Returns `true` if a conditional GET request can be answered with `304 Not Modified`.
+///
+/// This evaluates the request headers as described in [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-13.2.2):
+/// If an `If-None-Match` header is present, it is compared to `etag` (using the weak comparison and
+/// supporting lists and `*`) and `If-Modified-Since` is ignored. Otherwise the resource is considered
+/// not modified, if `last_modified` is not later than the date of the `If-Modified-Since` header.
+/// Dates, which are not in the IMF-fixdate format, are ignored.
+///
+/// # Arguments
+/// * `etag` - `ETag` of the tile (e.g. as returned by [`tile_etag`])
+/// * `last_modified` - Last modification time of the tile (e.g. of the archive)
+/// * `if_none_match` - Value of the `If-None-Match` request header
+/// * `if_modified_since` - Value of the `If-Modified-Since` request header
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::{is_not_modified, tile_etag};
+/// let etag = tile_etag(&[1, 3, 3, 7]);
+///
+/// assert!(is_not_modified(&etag, None, Some(&etag), None));
+/// assert!(!is_not_modified(&etag, None, Some("\"abc\""), None));
+/// ```
+pub fn is_not_modified(
+    etag: &str,
+    last_modified: Option<SystemTime>,
+    if_none_match: Option<&str>,
+    if_modified_since: Option<&str>,
+) -> bool {
+    if let Some(if_none_match) = if_none_match {
+        let etag = strip_weak(etag);
+
+        return if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || strip_weak(tag) == etag);
+    }
+
+    match (last_modified, if_modified_since.and_then(parse_http_date)) {
+        (Some(last_modified), Some(since)) => {
+            // HTTP dates only have a precision of seconds
+            let last_modified = last_modified
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            let since = since.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+
+            last_modified <= since
+        }
+        _ => false,
+    }
+}
+
+fn strip_weak(etag: &str) -> &str {
+    etag.strip_prefix("W/").unwrap_or(etag)
+}
+
+/// Formats a time as an HTTP date in the IMF-fixdate format (e.g. `Sun, 06 Nov 1994 08:49:37 GMT`),
+/// which can be used as the value of the `Last-Modified` HTTP header.
+///
+/// Times before the unix epoch are formatted as the unix epoch.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::http_date;
+/// # use std::time::{Duration, UNIX_EPOCH};
+/// let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+///
+/// assert_eq!(http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+/// ```
+pub fn http_date(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let days = secs / 86_400;
+    let secs_of_day = secs % 86_400;
+
+    let (year, month, day) = civil_from_days(days);
+
+    #[allow(clippy::cast_possible_truncation)]
+    let weekday = WEEKDAYS[(days % 7) as usize];
+
+    format!(
+        "{weekday}, {day:02} {} {year:04} {:02}:{:02}:{:02} GMT",
+        MONTHS[usize::from(month - 1)],
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+/// Parses an HTTP date in the IMF-fixdate format (e.g. `Sun, 06 Nov 1994 08:49:37 GMT`).
+///
+/// Returns [`None`] if `date` is not a valid IMF-fixdate (including years before 1970 or after 9999).
+pub fn parse_http_date(date: &str) -> Option<SystemTime> {
+    let (_, rest) = date.trim().split_once(", ")?;
+    let mut parts = rest.split(' ');
+
+    let day: u8 = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = u8::try_from(MONTHS.iter().position(|m| *m == month)? + 1).ok()?;
+    let year: u64 = parts.next()?.parse().ok()?;
+
+    let mut time = parts.next()?.split(':');
+    let hours: u64 = time.next()?.parse().ok()?;
+    let minutes: u64 = time.next()?.parse().ok()?;
+    let seconds: u64 = time.next()?.parse().ok()?;
+
+    if parts.next()? != "GMT"
+        || parts.next().is_some()
+        || time.next().is_some()
+        || !(1..=31).contains(&day)
+        || !(1970..=9999).contains(&year)
+        || hours > 23
+        || minutes > 59
+        || seconds > 60
+    {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86_400 + hours * 3600 + minutes * 60 + seconds;
+
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Converts days since the unix epoch to a (year, month, day) date.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u8, u8) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    #[allow(clippy::cast_possible_truncation)]
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Converts a (year, month, day) date to days since the unix epoch.
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: u64, month: u8, day: u8) -> u64 {
+    let year = year - u64::from(month <= 2);
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = u64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + u64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tile_etag() {
+        // FNV-1a of an empty input is the offset basis
+        assert_eq!(tile_etag(&[]), "\"cbf29ce484222325\"");
+        assert_eq!(tile_etag(b"a"), "\"af63dc4c8601ec8c\"");
+    }
+
+    #[test]
+    fn test_is_not_modified() {
+        let etag = "\"abc\"";
+        let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+
+        assert!(is_not_modified(etag, None, Some("\"abc\""), None));
+        assert!(is_not_modified(etag, None, Some("W/\"abc\""), None));
+        assert!(is_not_modified(etag, None, Some("\"x\", \"abc\""), None));
+        assert!(is_not_modified(etag, None, Some("*"), None));
+        assert!(!is_not_modified(etag, None, Some("\"x\""), None));
+        assert!(!is_not_modified(etag, None, None, None));
+
+        let since = "Sun, 06 Nov 1994 08:49:37 GMT";
+        assert!(is_not_modified(etag, Some(time), None, Some(since)));
+        assert!(!is_not_modified(
+            etag,
+            Some(time + Duration::from_secs(1)),
+            None,
+            Some(since)
+        ));
+        assert!(!is_not_modified(etag, Some(time), None, Some("yesterday")));
+
+        // If-None-Match takes precedence over If-Modified-Since
+        assert!(!is_not_modified(
+            etag,
+            Some(time),
+            Some("\"x\""),
+            Some(since)
+        ));
+    }
+
+    #[test]
+    fn test_http_date() {
+        assert_eq!(http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(
+            http_date(UNIX_EPOCH + Duration::from_secs(951_782_401)),
+            "Tue, 29 Feb 2000 00:00:01 GMT"
+        );
+
+        for secs in [0, 784_111_777, 951_782_400, 1_700_000_000, 4_102_444_800] {
+            let time = UNIX_EPOCH + Duration::from_secs(secs);
+            assert_eq!(parse_http_date(&http_date(time)), Some(time));
+        }
+
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 UTC"), None);
+        assert_eq!(parse_http_date("Sun, 32 Nov 1994 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1969 08:49:37 GMT"), None);
+        assert!(parse_http_date("Fri, 31 Dec 9999 23:59:59 GMT").is_some());
+        assert_eq!(parse_http_date("Sun, 06 Nov 10000 08:49:37 GMT"), None);
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 18446744073709551615 08:49:37 GMT"),
+            None
+        );
+    }
+}
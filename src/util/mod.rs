@@ -1,9 +1,37 @@
+mod clustering;
+mod codec;
 mod compress;
+mod conditional;
+mod offset;
+mod ranges;
+mod read_at;
 mod read_directories;
+mod reader_pool;
+mod replace_tile;
+mod shared_reader;
+mod test_archive;
 mod tile_id;
+#[cfg(feature = "mvt")]
+mod vector_layers;
 mod write_directories;
+#[cfg(feature = "zstd-dictionary")]
+mod zstd_dictionary;
 
+pub use clustering::*;
+pub use codec::*;
 pub use compress::*;
+pub use conditional::*;
+pub use offset::*;
+pub use ranges::*;
+pub use read_at::*;
 pub use read_directories::*;
+pub use reader_pool::*;
+pub use replace_tile::*;
+pub use shared_reader::*;
+pub use test_archive::*;
 pub use tile_id::*;
+#[cfg(feature = "mvt")]
+pub use vector_layers::*;
 pub use write_directories::*;
+#[cfg(feature = "zstd-dictionary")]
+pub use zstd_dictionary::*;
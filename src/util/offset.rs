@@ -0,0 +1,220 @@
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncSeek, AsyncWrite};
+
+use super::ReadAt;
+
+/// An adapter, which shifts all positions of the wrapped reader / writer by a fixed offset.
+///
+/// This allows reading and writing an archive, which is embedded at a non-zero offset inside a
+/// larger container file (e.g. a game or app bundle packing multiple assets into one file), as
+/// positions within the archive are relative to its first byte.
+///
+/// Seeking to [`SeekFrom::Start`] is relative to the offset, while [`SeekFrom::End`] is still relative
+/// to the end of the wrapped value. Seeking to a position before the offset is an error.
+/// Note that wrapping a value does not seek; use [`seek`](Seek::seek) with `SeekFrom::Start(0)`
+/// to move to the offset.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::OffsetIo;
+/// # use std::io::{Cursor, Read, Seek, SeekFrom};
+/// let mut io = OffsetIo::new(Cursor::new(vec![1, 3, 3, 7]), 2);
+///
+/// let mut buf = Vec::new();
+/// io.seek(SeekFrom::Start(0)).unwrap();
+/// io.read_to_end(&mut buf).unwrap();
+///
+/// assert_eq!(buf, [3, 7]);
+/// assert_eq!(io.stream_position().unwrap(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OffsetIo<T> {
+    inner: T,
+    offset: u64,
+}
+
+impl<T> OffsetIo<T> {
+    /// Wraps `inner`, shifting all positions by `offset`.
+    pub const fn new(inner: T, offset: u64) -> Self {
+        Self { inner, offset }
+    }
+
+    /// Returns the offset, by which all positions are shifted.
+    pub const fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns a reference to the wrapped value.
+    pub const fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped value.
+    ///
+    /// Seeking the wrapped value directly changes the position of this adapter.
+    pub const fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Unwraps this adapter, returning the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn inner_pos(&self, pos: SeekFrom) -> Result<SeekFrom> {
+        match pos {
+            SeekFrom::Start(position) => self
+                .offset
+                .checked_add(position)
+                .map(SeekFrom::Start)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        "invalid seek to an overflowing position",
+                    )
+                }),
+            pos => Ok(pos),
+        }
+    }
+
+    fn outer_pos(&self, position: u64) -> Result<u64> {
+        position.checked_sub(self.offset).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a position before the offset",
+            )
+        })
+    }
+}
+
+impl<T: Read> Read for OffsetIo<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<T: Write> Write for OffsetIo<T> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: Seek> Seek for OffsetIo<T> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let pos = self.inner_pos(pos)?;
+        let position = self.inner.seek(pos)?;
+
+        self.outer_pos(position)
+    }
+}
+
+impl<T: ReadAt> ReadAt for OffsetIo<T> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let Some(offset) = self.offset.checked_add(offset) else {
+            return Ok(0);
+        };
+
+        self.inner.read_at(offset, buf)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncRead + Unpin> AsyncRead for OffsetIo<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncWrite + Unpin> AsyncWrite for OffsetIo<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncSeek + Unpin> AsyncSeek for OffsetIo<T> {
+    fn poll_seek(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<Result<u64>> {
+        let pos = self.inner_pos(pos)?;
+
+        match Pin::new(&mut self.inner).poll_seek(cx, pos) {
+            Poll::Ready(Ok(position)) => Poll::Ready(self.outer_pos(position)),
+            poll => poll,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_seek() -> Result<()> {
+        let mut io = OffsetIo::new(Cursor::new(vec![0u8; 10]), 4);
+
+        assert_eq!(io.seek(SeekFrom::Start(0))?, 0);
+        assert_eq!(io.get_ref().position(), 4);
+
+        assert_eq!(io.seek(SeekFrom::Current(3))?, 3);
+        assert_eq!(io.seek(SeekFrom::End(-1))?, 5);
+        assert_eq!(io.get_ref().position(), 9);
+
+        assert!(io.seek(SeekFrom::Current(-6)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_write() -> Result<()> {
+        let mut io = OffsetIo::new(Cursor::new(vec![1, 2]), 2);
+
+        io.seek(SeekFrom::Start(0))?;
+        io.write_all(&[3, 4, 5])?;
+        assert_eq!(io.get_ref().get_ref(), &[1, 2, 3, 4, 5]);
+
+        io.seek(SeekFrom::Start(1))?;
+        let mut buf = [0; 2];
+        io.read_exact(&mut buf)?;
+        assert_eq!(buf, [4, 5]);
+
+        let mut buf = [0; 3];
+        io.read_exact_at(0, &mut buf)?;
+        assert_eq!(buf, [3, 4, 5]);
+
+        Ok(())
+    }
+}
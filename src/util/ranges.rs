@@ -0,0 +1,55 @@
+use std::ops::Range;
+
+/// Sorts byte ranges and merges all ranges, which overlap, are adjacent or are at most
+/// `max_gap` bytes apart, into a minimal list of ranges.
+///
+/// Empty ranges are ignored. A `max_gap` greater than `0` trades reading some unneeded bytes
+/// for fewer requests, which is usually worth it for remote archives (e.g. via HTTP or S3).
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::coalesce_ranges;
+/// let ranges = coalesce_ranges([20..30, 0..10, 10..15, 35..40], 0);
+/// assert_eq!(ranges, vec![0..15, 20..30, 35..40]);
+///
+/// let ranges = coalesce_ranges([20..30, 0..10, 10..15, 35..40], 5);
+/// assert_eq!(ranges, vec![0..40]);
+/// ```
+pub fn coalesce_ranges(
+    ranges: impl IntoIterator<Item = Range<u64>>,
+    max_gap: u64,
+) -> Vec<Range<u64>> {
+    let mut ranges = ranges
+        .into_iter()
+        .filter(|r| !r.is_empty())
+        .collect::<Vec<_>>();
+    ranges.sort_unstable_by_key(|r| r.start);
+
+    let mut coalesced = Vec::<Range<u64>>::with_capacity(ranges.len());
+
+    for range in ranges {
+        match coalesced.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(max_gap) => {
+                last.end = last.end.max(range.end);
+            }
+            _ => coalesced.push(range),
+        }
+    }
+
+    coalesced
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_coalesce_ranges() {
+        assert!(coalesce_ranges([], 0).is_empty());
+        assert_eq!(coalesce_ranges([5..5, 1..2], 0), vec![1..2]);
+        assert_eq!(coalesce_ranges([0..10, 2..4, 4..12], 0), vec![0..12]);
+        assert_eq!(coalesce_ranges([0..10, 11..12], 0), vec![0..10, 11..12]);
+        assert_eq!(coalesce_ranges([0..10, 11..12], 1), vec![0..12]);
+        assert_eq!(coalesce_ranges([0..10, 0..10], 0), vec![0..10]);
+    }
+}
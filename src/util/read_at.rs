@@ -0,0 +1,251 @@
+use std::{
+    fs::File,
+    io::{Cursor, Error, ErrorKind, Read, Result, Seek, SeekFrom},
+    sync::Arc,
+};
+
+/// A source of bytes, which supports reading from arbitrary positions through a shared reference.
+///
+/// In contrast to [`Read`] + [`Seek`], reading does not mutate a cursor, which allows reading
+/// tiles of a single archive concurrently (e.g. when serving tiles from multiple threads).
+///
+/// This trait is implemented for [`File`], [`Cursor`], byte slices and vectors, as well as
+/// references and smart pointers to any of those. Use [`ReadAtCursor`] to use a [`ReadAt`]
+/// where [`Read`] + [`Seek`] is required, and [`SharedReader`](super::SharedReader) to use
+/// any [`Read`] + [`Seek`] as a [`ReadAt`].
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::ReadAt;
+/// let data: &[u8] = &[1, 3, 3, 7];
+///
+/// let mut buf = [0; 2];
+/// data.read_exact_at(1, &mut buf).unwrap();
+///
+/// assert_eq!(buf, [3, 3]);
+/// ```
+pub trait ReadAt {
+    /// Reads some bytes starting at `offset` into `buf` and returns the number of bytes read.
+    ///
+    /// Reading at or past the end returns `Ok(0)`.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if an I/O error occurred.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize>;
+
+    /// Reads exactly `buf.len()` bytes starting at `offset` into `buf`.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if an I/O error occurred or the end was reached before
+    /// `buf` was filled completely.
+    fn read_exact_at(&self, mut offset: u64, mut buf: &mut [u8]) -> Result<()> {
+        while !buf.is_empty() {
+            match self.read_at(offset, buf) {
+                Ok(0) => {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "failed to fill whole buffer",
+                    ))
+                }
+                Ok(n) => {
+                    buf = &mut buf[n..];
+                    offset += n as u64;
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ReadAt for [u8] {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let Ok(offset) = usize::try_from(offset) else {
+            return Ok(0);
+        };
+
+        let Some(data) = self.get(offset..) else {
+            return Ok(0);
+        };
+
+        let len = buf.len().min(data.len());
+        buf[..len].copy_from_slice(&data[..len]);
+
+        Ok(len)
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        self.as_slice().read_at(offset, buf)
+    }
+}
+
+impl<T: AsRef<[u8]>> ReadAt for Cursor<T> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        self.get_ref().as_ref().read_at(offset, buf)
+    }
+}
+
+#[cfg(unix)]
+impl ReadAt for File {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        std::os::unix::fs::FileExt::read_at(self, buf, offset)
+    }
+}
+
+#[cfg(windows)]
+impl ReadAt for File {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        std::os::windows::fs::FileExt::seek_read(self, buf, offset)
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for &T {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        (**self).read_at(offset, buf)
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for Box<T> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        (**self).read_at(offset, buf)
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for Arc<T> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        (**self).read_at(offset, buf)
+    }
+}
+
+/// An adapter, which implements [`Read`] + [`Seek`] for any [`ReadAt`] by keeping track of the current position.
+///
+/// Positional reads ([`ReadAt`]) are passed through to the wrapped value and do not affect the position.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::ReadAtCursor, PMTiles};
+/// let bytes = include_bytes!("../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+///
+/// let pm_tiles = PMTiles::from_reader(ReadAtCursor::new(bytes.to_vec())).unwrap();
+///
+/// // tiles can now be read through a shared reference
+/// let tile = pm_tiles.read_tile(0, 0, 0).unwrap();
+/// # assert!(tile.is_some());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReadAtCursor<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R> ReadAtCursor<R> {
+    /// Wraps `inner`, starting at position `0`.
+    pub const fn new(inner: R) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    /// Returns a reference to the wrapped value.
+    pub const fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwraps this cursor, returning the wrapped value.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: ReadAt> Read for ReadAtCursor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = self.inner.read_at(self.position, buf)?;
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl<R: ReadAt> Seek for ReadAtCursor<R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(_) => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "seeking relative to the end is not supported",
+                ))
+            }
+        };
+
+        self.position = position.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        Ok(self.position)
+    }
+}
+
+impl<R: ReadAt> ReadAt for ReadAtCursor<R> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        self.inner.read_at(offset, buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_at_slice() -> Result<()> {
+        let data: &[u8] = &[1, 3, 3, 7];
+        let mut buf = [0; 3];
+
+        assert_eq!(data.read_at(2, &mut buf)?, 2);
+        assert_eq!(buf[..2], [3, 7]);
+
+        assert_eq!(data.read_at(4, &mut buf)?, 0);
+        assert_eq!(data.read_at(u64::MAX, &mut buf)?, 0);
+
+        assert!(data.read_exact_at(2, &mut buf).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_at_file() -> Result<()> {
+        let file = File::open("./test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles")?;
+        let mut buf = [0; 7];
+
+        file.read_exact_at(0, &mut buf)?;
+        assert_eq!(&buf, b"PMTiles");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_at_cursor() -> Result<()> {
+        let mut cursor = ReadAtCursor::new(vec![1u8, 3, 3, 7]);
+        let mut buf = [0; 2];
+
+        cursor.seek(SeekFrom::Start(1))?;
+        cursor.read_exact(&mut buf)?;
+        assert_eq!(buf, [3, 3]);
+
+        // positional reads do not affect the position
+        cursor.read_exact_at(0, &mut buf)?;
+        assert_eq!(buf, [1, 3]);
+
+        cursor.read_exact(&mut buf[..1])?;
+        assert_eq!(buf[0], 7);
+
+        assert!(cursor.seek(SeekFrom::Current(-5)).is_err());
+
+        Ok(())
+    }
+}
@@ -3,13 +3,18 @@ use async_recursion::async_recursion;
 #[cfg(feature = "async")]
 use futures::io::{AsyncReadExt, AsyncSeekExt};
 use std::collections::HashMap;
-use std::io::{Read, Result, Seek};
-use std::ops::RangeBounds;
+use std::hash::BuildHasher;
+use std::io::{Read, Seek};
+use std::ops::{Bound, Range, RangeBounds};
 
 use ahash::RandomState;
 use duplicate::duplicate_item;
 
-use crate::{Compression, Directory};
+use super::{coalesce_ranges, Codec};
+use crate::{
+    directory::MAX_DIRECTORY_DEPTH, read_limits::check_limit, CancellationToken, Compression,
+    Directory, Entry, Error, ReadLimits, ReadProgress, Result,
+};
 
 /// A structure representing a range of bytes within a larger amount of bytes.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -22,6 +27,12 @@ pub struct OffsetLength {
     pub length: u32,
 }
 
+/// A map of tile ids to the offset & length of the tile data, as returned by [`read_directories`].
+///
+/// The hasher defaults to [`ahash::RandomState`], but can be set to any other hasher with
+/// [`read_directories_with_hasher`].
+pub type TileMap<S = RandomState> = HashMap<u64, OffsetLength, S>;
+
 /// Reads directories (root- & leaf-directories) from a reader and return all entries
 /// as a [`std::collections::HashMap`], with the tile-id as the key and the offset & length as the value.
 ///
@@ -34,7 +45,7 @@ pub struct OffsetLength {
 ///
 /// # Errors
 /// Will return [`Err`] if there was an error reading the bytes from the reader or while decompressing
-/// a directory.
+/// a directory, or if leaf directories are nested more than three levels deep or reference themselves.
 ///
 /// # Example
 /// ```rust
@@ -59,19 +70,207 @@ pub fn read_directories(
     root_dir_offset_length: (u64, u64),
     leaf_dir_offset: u64,
     filter_range: impl RangeBounds<u64>,
-) -> Result<HashMap<u64, OffsetLength, RandomState>> {
-    let mut tiles = HashMap::<u64, OffsetLength, RandomState>::default();
+) -> Result<TileMap> {
+    read_directories_with_hasher(
+        reader,
+        compression,
+        root_dir_offset_length,
+        leaf_dir_offset,
+        filter_range,
+    )
+}
+
+/// Same as [`read_directories`], but the returned map uses the hasher `S` instead of [`ahash::RandomState`].
+///
+/// # Arguments
+/// See [`read_directories`].
+///
+/// # Errors
+/// See [`read_directories`].
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::read_directories_with_hasher, Header};
+/// # use std::collections::hash_map::RandomState;
+/// # let bytes: &[u8] = include_bytes!("../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+/// # let mut reader = std::io::Cursor::new(bytes);
+/// let header = Header::from_reader(&mut reader).unwrap();
+///
+/// let entries_map = read_directories_with_hasher::<RandomState>(
+///     &mut reader,
+///     header.internal_compression,
+///     (header.root_directory_offset, header.root_directory_length),
+///     header.leaf_directories_offset,
+///     ..,
+/// ).unwrap();
+/// ```
+pub fn read_directories_with_hasher<S: BuildHasher + Default>(
+    reader: &mut (impl Read + Seek),
+    compression: Compression,
+    root_dir_offset_length: (u64, u64),
+    leaf_dir_offset: u64,
+    filter_range: impl RangeBounds<u64>,
+) -> Result<TileMap<S>> {
+    let entries = read_entries(
+        reader,
+        compression,
+        root_dir_offset_length,
+        leaf_dir_offset,
+        filter_range,
+    )?;
+
+    Ok(expand_entries(&entries))
+}
+
+/// Same as [`read_directories`], but with an extra parameter.
+///
+/// Reports the number of leaf directories read and tile entries loaded so far to `progress`
+/// after each directory, as reading the directories of a large (e.g. planet) archive can take a while.
+///
+/// # Arguments
+/// * `reader` - Reader with root- and leaf-directories
+/// * `compression` - Compression of directories
+/// * `root_dir_offset_length` - Offset and length (in bytes) of root directory section
+/// * `leaf_dir_offset` - Offset (in bytes) of leaf directories section
+/// * `filter_range` - Range of Tile IDs to load (use `..` to include all)
+/// * `progress` - Receiver of progress updates (e.g. a closure)
+///
+/// # Errors
+/// See [`read_directories`].
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::read_directories_with_progress, Header};
+/// # let bytes: &[u8] = include_bytes!("../../test/protomaps_vector_planet_odbl_z10_without_data.pmtiles");
+/// # let mut reader = std::io::Cursor::new(bytes);
+/// let header = Header::from_reader(&mut reader).unwrap();
+///
+/// let entries_map = read_directories_with_progress(
+///     &mut reader,
+///     header.internal_compression,
+///     (header.root_directory_offset, header.root_directory_length),
+///     header.leaf_directories_offset,
+///     ..,
+///     &mut |leaf_directories, entries| println!("{leaf_directories} leaf directories, {entries} entries"),
+/// ).unwrap();
+/// ```
+pub fn read_directories_with_progress(
+    reader: &mut (impl Read + Seek),
+    compression: Compression,
+    root_dir_offset_length: (u64, u64),
+    leaf_dir_offset: u64,
+    filter_range: impl RangeBounds<u64>,
+    progress: &mut impl ReadProgress,
+) -> Result<TileMap> {
+    let entries = read_entries_in_ranges(
+        reader,
+        compression,
+        None,
+        root_dir_offset_length,
+        leaf_dir_offset,
+        [bounds_to_range(&filter_range)],
+        &CancellationToken::new(),
+        progress,
+        &ReadLimits::default(),
+    )?;
+
+    Ok(expand_entries(&entries))
+}
+
+/// Reads directories (root- & leaf-directories) from a reader and returns all tile entries
+/// as a single [`Directory`], retaining their run lengths.
+///
+/// In contrast to [`read_directories`], which inserts one map entry per addressed tile, this keeps
+/// one [`Entry`](crate::Entry) per run of tiles. This uses a lot less memory for archives with large run
+/// lengths (e.g. a planet archive with an ocean tile repeated millions of times).
+/// Use [`Directory::find_entry`] to look up the entry of a tile.
+///
+/// Entries are sorted in ascending order by their tile ids and do not include leaf directory entries.
+/// Entries, which are only partially included in `filter_range`, are clipped to it.
+///
+/// # Arguments
+/// See [`read_directories`].
+///
+/// # Errors
+/// See [`read_directories`].
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::read_entries, Header};
+/// # let bytes: &[u8] = include_bytes!("../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+/// # let mut reader = std::io::Cursor::new(bytes);
+/// let header = Header::from_reader(&mut reader).unwrap();
+///
+/// let entries = read_entries(
+///     &mut reader,
+///     header.internal_compression,
+///     (header.root_directory_offset, header.root_directory_length),
+///     header.leaf_directories_offset,
+///     ..,
+/// ).unwrap();
+///
+/// let entry = entries.find_entry(19).unwrap();
+/// let tile_offset = header.tile_data_offset + entry.offset;
+/// ```
+pub fn read_entries(
+    reader: &mut (impl Read + Seek),
+    compression: Compression,
+    root_dir_offset_length: (u64, u64),
+    leaf_dir_offset: u64,
+    filter_range: impl RangeBounds<u64>,
+) -> Result<Directory> {
+    read_entries_in_ranges(
+        reader,
+        compression,
+        None,
+        root_dir_offset_length,
+        leaf_dir_offset,
+        [bounds_to_range(&filter_range)],
+        &CancellationToken::new(),
+        &mut |_, _| {},
+        &ReadLimits::default(),
+    )
+}
+
+/// Same as [`read_entries`], but only includes tile entries whose tile ids are included in any of
+/// `filter_ranges`, which may overlap and do not have to be sorted.
+///
+/// Returns [`Error::Cancelled`](crate::Error::Cancelled) before reading a directory, if `cancellation` was cancelled.
+/// Reports the number of leaf directories read and entries loaded to `progress` after each directory.
+#[allow(clippy::redundant_pub_crate, clippy::too_many_arguments)]
+pub(crate) fn read_entries_in_ranges(
+    reader: &mut (impl Read + Seek),
+    compression: Compression,
+    codec: Option<&dyn Codec>,
+    root_dir_offset_length: (u64, u64),
+    leaf_dir_offset: u64,
+    filter_ranges: impl IntoIterator<Item = Range<u64>>,
+    cancellation: &CancellationToken,
+    progress: &mut impl ReadProgress,
+    limits: &ReadLimits,
+) -> Result<Directory> {
+    let mut entries = Vec::new();
 
     read_dir_rec(
         reader,
-        &mut tiles,
+        &mut entries,
         compression,
         root_dir_offset_length,
         leaf_dir_offset,
-        &filter_range,
+        &coalesce_ranges(filter_ranges, 0),
+        &mut ReadContext {
+            codec,
+            cancellation,
+            progress,
+            limits,
+            leaf_directories: 0,
+            num_entries: 0,
+            num_tiles: 0,
+            path: Vec::with_capacity(MAX_DIRECTORY_DEPTH),
+        },
     )?;
 
-    Ok(tiles)
+    Ok(entries.into())
 }
 
 /// Async version of [`read_directories`](read_directories).
@@ -88,7 +287,7 @@ pub fn read_directories(
 ///
 /// # Errors
 /// Will return [`Err`] if there was an error reading the bytes from the reader or while decompressing
-/// a directory.
+/// a directory, or if leaf directories are nested more than three levels deep or reference themselves.
 ///
 /// # Example
 /// ```rust
@@ -116,85 +315,326 @@ pub async fn read_directories_async(
     compression: Compression,
     root_dir_offset_length: (u64, u64),
     leaf_dir_offset: u64,
-    filter_range: (impl RangeBounds<u64> + Sync + Send),
-) -> Result<HashMap<u64, OffsetLength, RandomState>> {
-    let mut tiles = HashMap::<u64, OffsetLength, RandomState>::default();
+    filter_range: impl RangeBounds<u64> + Sync + Send,
+) -> Result<TileMap> {
+    read_directories_with_hasher_async(
+        reader,
+        compression,
+        root_dir_offset_length,
+        leaf_dir_offset,
+        filter_range,
+    )
+    .await
+}
 
-    read_dir_rec_async(
+/// Async version of [`read_directories_with_hasher`](read_directories_with_hasher).
+///
+/// Same as [`read_directories_async`], but the returned map uses the hasher `S` instead of [`ahash::RandomState`].
+///
+/// # Arguments
+/// See [`read_directories_async`].
+///
+/// # Errors
+/// See [`read_directories_async`].
+#[allow(clippy::module_name_repetitions)]
+#[cfg(feature = "async")]
+pub async fn read_directories_with_hasher_async<S: BuildHasher + Default + Send>(
+    reader: &mut (impl Unpin + Send + AsyncReadExt + AsyncSeekExt),
+    compression: Compression,
+    root_dir_offset_length: (u64, u64),
+    leaf_dir_offset: u64,
+    filter_range: impl RangeBounds<u64> + Sync + Send,
+) -> Result<TileMap<S>> {
+    let entries = read_entries_async(
+        reader,
+        compression,
+        root_dir_offset_length,
+        leaf_dir_offset,
+        filter_range,
+    )
+    .await?;
+
+    Ok(expand_entries(&entries))
+}
+
+/// Async version of [`read_directories_with_progress`](read_directories_with_progress).
+///
+/// Reports the number of leaf directories read and tile entries loaded so far to `progress`
+/// after each directory.
+///
+/// # Arguments
+/// See [`read_directories_with_progress`].
+///
+/// # Errors
+/// See [`read_directories_async`].
+#[allow(clippy::module_name_repetitions)]
+#[cfg(feature = "async")]
+pub async fn read_directories_with_progress_async(
+    reader: &mut (impl Unpin + Send + AsyncReadExt + AsyncSeekExt),
+    compression: Compression,
+    root_dir_offset_length: (u64, u64),
+    leaf_dir_offset: u64,
+    filter_range: impl RangeBounds<u64> + Sync + Send,
+    progress: &mut (impl ReadProgress + Send),
+) -> Result<TileMap> {
+    let entries = read_entries_in_ranges_async(
         reader,
-        &mut tiles,
         compression,
+        None,
         root_dir_offset_length,
         leaf_dir_offset,
-        &filter_range,
+        [bounds_to_range(&filter_range)],
+        &CancellationToken::new(),
+        progress,
+        &ReadLimits::default(),
     )
     .await?;
 
-    Ok(tiles)
+    Ok(expand_entries(&entries))
 }
 
-/// Get (inclusive) end of range bounds.
+/// Async version of [`read_entries`](read_entries).
+///
+/// Reads directories (root- & leaf-directories) from a reader and returns all tile entries
+/// as a single [`Directory`], retaining their run lengths.
+///
+/// # Arguments
+/// See [`read_directories_async`].
 ///
-/// Will return [`None`] if range has no end bound.
-fn range_end_inc(range: &impl RangeBounds<u64>) -> Option<u64> {
-    match range.end_bound() {
-        std::ops::Bound::Included(val) => Some(*val),
-        std::ops::Bound::Excluded(val) => Some(*val - 1),
-        std::ops::Bound::Unbounded => None,
+/// # Errors
+/// See [`read_directories_async`].
+#[cfg(feature = "async")]
+pub async fn read_entries_async(
+    reader: &mut (impl Unpin + Send + AsyncReadExt + AsyncSeekExt),
+    compression: Compression,
+    root_dir_offset_length: (u64, u64),
+    leaf_dir_offset: u64,
+    filter_range: impl RangeBounds<u64> + Sync + Send,
+) -> Result<Directory> {
+    read_entries_in_ranges_async(
+        reader,
+        compression,
+        None,
+        root_dir_offset_length,
+        leaf_dir_offset,
+        [bounds_to_range(&filter_range)],
+        &CancellationToken::new(),
+        &mut |_, _| {},
+        &ReadLimits::default(),
+    )
+    .await
+}
+
+/// Async version of [`read_entries_in_ranges`](read_entries_in_ranges).
+#[allow(clippy::redundant_pub_crate, clippy::too_many_arguments)]
+#[cfg(feature = "async")]
+pub(crate) async fn read_entries_in_ranges_async(
+    reader: &mut (impl Unpin + Send + AsyncReadExt + AsyncSeekExt),
+    compression: Compression,
+    codec: Option<&dyn Codec>,
+    root_dir_offset_length: (u64, u64),
+    leaf_dir_offset: u64,
+    filter_ranges: impl IntoIterator<Item = Range<u64>>,
+    cancellation: &CancellationToken,
+    progress: &mut (impl ReadProgress + Send),
+    limits: &ReadLimits,
+) -> Result<Directory> {
+    let mut entries = Vec::new();
+
+    read_dir_rec_async(
+        reader,
+        &mut entries,
+        compression,
+        root_dir_offset_length,
+        leaf_dir_offset,
+        &coalesce_ranges(filter_ranges, 0),
+        &mut ReadContext {
+            codec,
+            cancellation,
+            progress,
+            limits,
+            leaf_directories: 0,
+            num_entries: 0,
+            num_tiles: 0,
+            path: Vec::with_capacity(MAX_DIRECTORY_DEPTH),
+        },
+    )
+    .await?;
+
+    Ok(entries.into())
+}
+
+/// Expands tile entries into a map with one item per addressed tile.
+fn expand_entries<S: BuildHasher + Default>(entries: &Directory) -> TileMap<S> {
+    let mut tiles = TileMap::<S>::default();
+
+    for entry in entries {
+        for tile_id in entry.tile_id_range() {
+            tiles.insert(
+                tile_id,
+                OffsetLength {
+                    offset: entry.offset,
+                    length: entry.length,
+                },
+            );
+        }
     }
+
+    tiles
+}
+
+/// Converts range bounds to a range with an exclusive end.
+///
+/// Bounds, which cannot be represented (e.g. a start bound excluding [`u64::MAX`]), result in an
+/// empty range.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn bounds_to_range(range: &impl RangeBounds<u64>) -> Range<u64> {
+    let start = match range.start_bound() {
+        Bound::Included(start) => *start,
+        Bound::Excluded(start) => {
+            let Some(start) = start.checked_add(1) else {
+                return 0..0;
+            };
+            start
+        }
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => end.saturating_add(1),
+        Bound::Excluded(end) => *end,
+        Bound::Unbounded => u64::MAX,
+    };
+
+    start..end
+}
+
+/// Returns the sorted, non-overlapping `ranges`, which intersect with `range`.
+fn intersecting_ranges<'a>(
+    ranges: &'a [Range<u64>],
+    range: &'a Range<u64>,
+) -> impl Iterator<Item = &'a Range<u64>> + 'a {
+    let first = ranges.partition_point(|r| r.end <= range.start);
+
+    ranges[first..]
+        .iter()
+        .take_while(move |r| r.start < range.end)
+}
+
+/// State shared by all (recursive) calls of `read_dir_rec`.
+struct ReadContext<'a, P> {
+    codec: Option<&'a dyn Codec>,
+    cancellation: &'a CancellationToken,
+    progress: &'a mut P,
+    limits: &'a ReadLimits,
+    leaf_directories: u64,
+    num_entries: u64,
+    num_tiles: u64,
+    /// Offsets of the directories from the root directory to the one currently being read
+    path: Vec<u64>,
 }
 
 #[duplicate_item(
-    fn_name              cfg_async_filter       async                      add_await(code) seek_start(reader, offset)                                 FilterRangeTraits                       input_traits                                        read_directory(reader, len, compression);
-    [read_dir_rec]       [cfg(all())]           []                         [code]          [reader.seek(std::io::SeekFrom::Start(offset))]            [(impl RangeBounds<u64>)]               [(impl Read + Seek)]                                [Directory::from_reader(reader, len, compression)];
-    [read_dir_rec_async] [cfg(feature="async")] [#[async_recursion] async] [code.await]    [reader.seek(futures::io::SeekFrom::Start(offset)).await]  [(impl RangeBounds<u64> + Sync + Send)] [(impl Unpin + Send + AsyncReadExt + AsyncSeekExt)] [Directory::from_async_reader(reader, len, compression).await];
+    fn_name              cfg_async_filter       async                      add_await(code) seek_start(reader, offset)                                 input_traits                                        progress_traits         read_directory(reader, len, compression, codec, limits);
+    [read_dir_rec]       [cfg(all())]           []                         [code]          [reader.seek(std::io::SeekFrom::Start(offset))]            [(impl Read + Seek)]                                [ReadProgress]          [Directory::from_reader_impl(reader, len, compression, codec, limits)];
+    [read_dir_rec_async] [cfg(feature="async")] [#[async_recursion] async] [code.await]    [reader.seek(futures::io::SeekFrom::Start(offset)).await]  [(impl Unpin + Send + AsyncReadExt + AsyncSeekExt)] [ReadProgress + Send]   [Directory::from_async_reader_impl(reader, len, compression, codec, limits).await];
 )]
 #[cfg_async_filter]
 async fn fn_name(
     reader: &mut input_traits,
-    tiles: &mut HashMap<u64, OffsetLength, RandomState>,
+    entries: &mut Vec<Entry>,
     compression: Compression,
     (dir_offset, dir_length): (u64, u64),
     leaf_dir_offset: u64,
-    filter_range: &FilterRangeTraits,
+    filter_ranges: &[Range<u64>],
+    context: &mut ReadContext<'_, impl progress_traits>,
 ) -> Result<()> {
+    context.cancellation.check()?;
+
+    if context.path.contains(&dir_offset) {
+        return Err(Error::DirectoryDecode(format!(
+            "Leaf directory at offset {dir_offset} references itself"
+        )));
+    }
+    if context.path.len() >= MAX_DIRECTORY_DEPTH {
+        return Err(Error::DirectoryDecode(
+            "Leaf directories are nested too deeply".to_string(),
+        ));
+    }
+    context.path.push(dir_offset);
+
     seek_start([reader], [dir_offset])?;
-    let directory = read_directory([reader], [dir_length], [compression])?;
-    let range_end = range_end_inc(filter_range).unwrap_or(u64::MAX);
+    let directory = read_directory(
+        [reader],
+        [dir_length],
+        [compression],
+        [context.codec],
+        [context.limits],
+    )?;
 
-    for entry in &directory {
+    context.num_entries += directory.len() as u64;
+    check_limit(
+        "number of directory entries",
+        context.num_entries,
+        context.limits.max_entries,
+    )?;
+
+    for (index, entry) in directory.into_iter().enumerate() {
         if entry.is_leaf_dir_entry() {
-            // skip leaf directory, if it starts after range
-            if entry.tile_id > range_end {
+            // a leaf directory covers all tile ids up to the next entry
+            let end = if index + 1 < directory.len() {
+                directory[index + 1].tile_id
+            } else {
+                u64::MAX
+            };
+
+            // skip leaf directory, if it does not intersect with any range
+            if intersecting_ranges(filter_ranges, &(entry.tile_id..end))
+                .next()
+                .is_none()
+            {
                 continue;
             }
 
+            context.leaf_directories += 1;
             add_await([fn_name(
                 reader,
-                tiles,
+                entries,
                 compression,
                 (leaf_dir_offset + entry.offset, u64::from(entry.length)),
                 leaf_dir_offset,
-                filter_range,
+                filter_ranges,
+                context,
             )])?;
             continue;
         }
 
-        for tile_id in entry.tile_id_range() {
-            if !filter_range.contains(&tile_id) {
-                continue;
-            }
-
-            tiles.insert(
-                tile_id,
-                OffsetLength {
-                    offset: entry.offset,
-                    length: entry.length,
-                },
-            );
+        // clip entry to filter ranges
+        for range in intersecting_ranges(filter_ranges, &entry.tile_id_range()) {
+            let first = entry.tile_id.max(range.start);
+            let end = (entry.tile_id + u64::from(entry.run_length)).min(range.end);
+
+            context.num_tiles += end - first;
+            check_limit(
+                "number of tiles",
+                context.num_tiles,
+                context.limits.max_tiles,
+            )?;
+
+            #[allow(clippy::cast_possible_truncation)]
+            entries.push(Entry {
+                tile_id: first,
+                offset: entry.offset,
+                length: entry.length,
+                run_length: (end - first) as u32,
+            });
         }
     }
 
+    context.path.pop();
+    context
+        .progress
+        .on_progress(context.leaf_directories, entries.len() as u64);
+
     Ok(())
 }
 
@@ -263,10 +703,242 @@ mod test {
     }
 
     #[test]
-    fn test_range_end_inc() {
-        assert_eq!(range_end_inc(&(..)), None);
-        assert_eq!(range_end_inc(&(..=3)), Some(3));
-        assert_eq!(range_end_inc(&(..3)), Some(2));
-        assert_eq!(range_end_inc(&(1..)), None);
+    fn test_read_directories_with_hasher() -> Result<()> {
+        let bytes: &[u8] = include_bytes!("../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+        let mut reader = Cursor::new(bytes);
+
+        let map = read_directories_with_hasher::<std::collections::hash_map::RandomState>(
+            &mut reader,
+            Compression::GZip,
+            (127, 246),
+            395,
+            ..,
+        )?;
+
+        assert_eq!(map.len(), 85);
+
+        assert_eq!(
+            map.get(&19).unwrap(),
+            &OffsetLength {
+                offset: 225_929,
+                length: 11259
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_entries_with_leaf() -> Result<()> {
+        let bytes: &[u8] =
+            include_bytes!("../../test/protomaps_vector_planet_odbl_z10_without_data.pmtiles");
+        let mut reader = Cursor::new(bytes);
+
+        let entries = read_entries(&mut reader, Compression::GZip, (127, 389), 1173, ..)?;
+
+        assert!(entries.len() < 1_398_101);
+        assert_eq!(entries.num_leaf_entries(), 0);
+        assert_eq!(entries.addressed_tile_count(), 1_398_101);
+
+        let entry = entries.find_entry(1_027_840).unwrap();
+        assert_eq!(entry.offset, 1_105_402_834);
+        assert_eq!(entry.length, 59);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_entries_filter_range() -> Result<()> {
+        let bytes: &[u8] = include_bytes!("../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+        let mut reader = Cursor::new(bytes);
+
+        let entries = read_entries(&mut reader, Compression::GZip, (127, 246), 395, 10..=20)?;
+
+        assert_eq!(entries[0].tile_id, 10);
+        assert_eq!(entries.addressed_tile_count(), 11);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_entries_in_ranges() -> Result<()> {
+        let bytes: &[u8] =
+            include_bytes!("../../test/protomaps_vector_planet_odbl_z10_without_data.pmtiles");
+        let mut reader = Cursor::new(bytes);
+
+        let entries = read_entries_in_ranges(
+            &mut reader,
+            Compression::GZip,
+            None,
+            (127, 389),
+            1173,
+            [1_027_840..1_027_842, 0..1, 1_000_000..1_000_001, 5..5],
+            &CancellationToken::new(),
+            &mut |_, _| {},
+            &ReadLimits::default(),
+        )?;
+
+        let tile_ids = entries
+            .into_iter()
+            .flat_map(Entry::tile_id_range)
+            .collect::<Vec<_>>();
+        assert_eq!(tile_ids, vec![0, 1_000_000, 1_027_840, 1_027_841]);
+        assert_eq!(entries.find_entry(1_027_840).unwrap().offset, 1_105_402_834);
+
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let res = read_entries_in_ranges(
+            &mut reader,
+            Compression::GZip,
+            None,
+            (127, 389),
+            1173,
+            [bounds_to_range(&..)],
+            &cancellation,
+            &mut |_, _| {},
+            &ReadLimits::default(),
+        );
+        assert!(matches!(res, Err(crate::Error::Cancelled)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_directories_with_progress() -> Result<()> {
+        let bytes: &[u8] =
+            include_bytes!("../../test/protomaps_vector_planet_odbl_z10_without_data.pmtiles");
+        let mut reader = Cursor::new(bytes);
+
+        let mut updates = Vec::new();
+        let map = read_directories_with_progress(
+            &mut reader,
+            Compression::GZip,
+            (127, 389),
+            1173,
+            ..,
+            &mut |leaf_directories, entries| updates.push((leaf_directories, entries)),
+        )?;
+        assert_eq!(map.len(), 1_398_101);
+
+        let entries = read_entries(&mut reader, Compression::GZip, (127, 389), 1173, ..)?;
+
+        // one update per leaf directory and one for the root directory
+        let (leaf_directories, num_entries) = *updates.last().unwrap();
+        assert_eq!(updates.len() as u64, leaf_directories + 1);
+        assert!(leaf_directories > 0);
+        assert_eq!(num_entries, entries.len() as u64);
+        assert!(updates.windows(2).all(|w| w[0].1 <= w[1].1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_intersecting_ranges() {
+        let ranges = [0..2, 5..10, 20..30];
+
+        let find = |range| {
+            intersecting_ranges(&ranges, &range)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(find(0..1), vec![0..2]);
+        assert_eq!(find(2..5), vec![]);
+        assert_eq!(find(1..21), vec![0..2, 5..10, 20..30]);
+        assert_eq!(find(30..u64::MAX), vec![]);
+    }
+
+    #[test]
+    fn test_bounds_to_range() {
+        assert_eq!(bounds_to_range(&(..)), 0..u64::MAX);
+        assert_eq!(bounds_to_range(&(3..=5)), 3..6);
+        assert_eq!(bounds_to_range(&(3..5)), 3..5);
+        assert_eq!(bounds_to_range(&(3..)), 3..u64::MAX);
+        assert_eq!(bounds_to_range(&(..=u64::MAX)), 0..u64::MAX);
+        assert_eq!(
+            bounds_to_range(&(Bound::Excluded(3), Bound::Included(5))),
+            4..6
+        );
+
+        // bounds, which overflow, result in empty ranges
+        assert!(bounds_to_range(&(..0)).is_empty());
+        assert!(bounds_to_range(&(Bound::Excluded(u64::MAX), Bound::Unbounded)).is_empty());
+    }
+
+    /// Returns directories at multiples of 32 bytes, where the directory with index `i` only
+    /// includes a leaf directory entry pointing to the directory with index `targets[i]`.
+    fn linked_directories(targets: &[u64]) -> crate::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+
+        for &target in targets {
+            let directory = Directory::from(vec![Entry {
+                tile_id: 0,
+                offset: target * 32,
+                length: 32,
+                run_length: 0,
+            }]);
+
+            let start = bytes.len();
+            directory.to_writer(&mut bytes, Compression::None)?;
+            bytes.resize(start + 32, 0);
+        }
+
+        Ok(bytes)
+    }
+
+    #[test]
+    fn test_read_entries_nested_too_deeply() -> crate::Result<()> {
+        // root directory and three levels of leaf directories are fine
+        let mut bytes = linked_directories(&[1, 2, 3])?;
+        Directory::from(vec![Entry {
+            tile_id: 0,
+            offset: 0,
+            length: 1,
+            run_length: 1,
+        }])
+        .to_writer(&mut bytes, Compression::None)?;
+
+        let entries = read_entries(&mut Cursor::new(&bytes), Compression::None, (0, 32), 0, ..)?;
+        assert_eq!(entries.len(), 1);
+
+        let bytes = linked_directories(&[1, 2, 3, 4, 5])?;
+        let res = read_entries(&mut Cursor::new(&bytes), Compression::None, (0, 32), 0, ..);
+        assert!(matches!(res, Err(Error::DirectoryDecode(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_entries_cycle() -> crate::Result<()> {
+        for targets in [&[0][..], &[1, 0], &[1, 2, 1]] {
+            let bytes = linked_directories(targets)?;
+            let res = read_entries(&mut Cursor::new(&bytes), Compression::None, (0, 32), 0, ..);
+
+            assert!(
+                matches!(&res, Err(Error::DirectoryDecode(msg)) if msg.contains("references itself")),
+                "{res:?}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_read_entries_cycle_async() -> crate::Result<()> {
+        let bytes = linked_directories(&[1, 0])?;
+
+        tokio_test::block_on(async {
+            let res = read_entries_async(
+                &mut futures::io::Cursor::new(&bytes),
+                Compression::None,
+                (0, 32),
+                0,
+                ..,
+            )
+            .await;
+            assert!(matches!(res, Err(Error::DirectoryDecode(_))));
+
+            Ok(())
+        })
     }
 }
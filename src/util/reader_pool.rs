@@ -0,0 +1,443 @@
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Read, Result, Seek, SeekFrom},
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+    sync::{Condvar, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use super::ReadAt;
+
+type OpenFn<R> = Box<dyn Fn() -> Result<R> + Send + Sync>;
+
+struct PoolState<R> {
+    /// Idle readers and the instant they were returned at, most recently used last
+    idle: Vec<(R, Instant)>,
+    num_open: usize,
+}
+
+/// A pool of readers of the same archive (e.g. multiple handles of the same file), which are checked
+/// out for every read, so reads through a shared reference do not have to wait for each other.
+///
+/// Readers are opened on demand, until [`max_readers`](Self::with_max_readers) readers are open.
+/// Further reads wait for a reader to be returned to the pool. Returned readers are kept open for
+/// reuse, but at most [`max_idle`](Self::with_max_idle) of them and, if an
+/// [`idle_timeout`](Self::with_idle_timeout) is set, only for as long as they are used regularly.
+///
+/// The pool implements [`ReadAt`], so it can be used to read tiles through a shared reference
+/// (e.g. [`PMTiles::read_tile`](crate::PMTiles::read_tile)). It also implements [`Read`] + [`Seek`]
+/// by keeping track of its own position, so it can be passed to
+/// [`PMTiles::from_reader`](crate::PMTiles::from_reader) directly.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::ReaderPool, PMTiles};
+/// # use std::time::Duration;
+/// let pool = ReaderPool::open("./test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles")
+///     .unwrap()
+///     .with_max_readers(4)
+///     .with_idle_timeout(Duration::from_secs(60));
+///
+/// let pm_tiles = PMTiles::from_reader(pool).unwrap();
+///
+/// std::thread::scope(|s| {
+///     s.spawn(|| pm_tiles.read_tile(0, 0, 0).unwrap());
+///     s.spawn(|| pm_tiles.read_tile(0, 0, 1).unwrap());
+/// });
+/// ```
+pub struct ReaderPool<R> {
+    open: OpenFn<R>,
+    max_readers: usize,
+    max_idle: usize,
+    idle_timeout: Option<Duration>,
+    state: Mutex<PoolState<R>>,
+    returned: Condvar,
+    position: u64,
+}
+
+impl<R> std::fmt::Debug for ReaderPool<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReaderPool")
+            .field("max_readers", &self.max_readers)
+            .field("max_idle", &self.max_idle)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("num_open", &self.num_open())
+            .field("num_idle", &self.num_idle())
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ReaderPool<File> {
+    /// Creates a pool of handles of the file at `path`.
+    ///
+    /// The first handle is opened immediately, so a missing or inaccessible file is reported early.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the file could not be opened.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = File::open(&path)?;
+
+        let pool = Self::new(move || File::open(&path));
+        pool.put(file);
+
+        Ok(pool)
+    }
+}
+
+impl<R> ReaderPool<R> {
+    /// Creates a pool, which opens new readers using `open`.
+    ///
+    /// All readers returned by `open` must provide the same data.
+    /// No reader is opened until the first read.
+    pub fn new(open: impl Fn() -> Result<R> + Send + Sync + 'static) -> Self {
+        let max_readers = std::thread::available_parallelism().map_or(4, usize::from);
+
+        Self {
+            open: Box::new(open),
+            max_readers,
+            max_idle: usize::MAX,
+            idle_timeout: None,
+            state: Mutex::new(PoolState {
+                idle: Vec::new(),
+                num_open: 0,
+            }),
+            returned: Condvar::new(),
+            position: 0,
+        }
+    }
+
+    /// Sets the maximum number of readers open at the same time
+    /// (defaults to the available parallelism; at least `1`).
+    #[must_use]
+    pub fn with_max_readers(mut self, max_readers: usize) -> Self {
+        self.max_readers = max_readers.max(1);
+        self
+    }
+
+    /// Sets the maximum number of idle readers kept open for reuse (defaults to all readers).
+    ///
+    /// Any further readers are closed when they are returned to the pool.
+    #[must_use]
+    pub fn with_max_idle(mut self, max_idle: usize) -> Self {
+        self.max_idle = max_idle;
+        self.prune(&mut self.lock_state());
+        self
+    }
+
+    /// Sets the duration after which idle readers are closed (defaults to never).
+    #[must_use]
+    pub const fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Returns the number of readers currently open, including idle readers.
+    pub fn num_open(&self) -> usize {
+        self.lock_state().num_open
+    }
+
+    /// Returns the number of idle readers currently kept open for reuse.
+    pub fn num_idle(&self) -> usize {
+        self.lock_state().idle.len()
+    }
+
+    /// Checks a reader out of the pool, which is returned to the pool when the guard is dropped.
+    ///
+    /// This reuses the most recently used idle reader, opens a new reader if there is no idle
+    /// reader, or waits for a reader to be returned if [`max_readers`](Self::with_max_readers)
+    /// readers are open already.
+    ///
+    /// # Errors
+    /// Will return [`Err`] if a new reader had to be opened and opening it failed.
+    pub fn get(&self) -> Result<PooledReader<'_, R>> {
+        let mut state = self.lock_state();
+
+        loop {
+            self.prune(&mut state);
+
+            if let Some((reader, _)) = state.idle.pop() {
+                return Ok(PooledReader {
+                    pool: self,
+                    reader: Some(reader),
+                });
+            }
+
+            if state.num_open < self.max_readers {
+                break;
+            }
+
+            state = self
+                .returned
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+
+        // open the reader without holding the lock, so other readers can be checked out meanwhile
+        state.num_open += 1;
+        drop(state);
+
+        match (self.open)() {
+            Ok(reader) => Ok(PooledReader {
+                pool: self,
+                reader: Some(reader),
+            }),
+            Err(err) => {
+                self.lock_state().num_open -= 1;
+                self.returned.notify_one();
+                Err(err)
+            }
+        }
+    }
+
+    /// Closes all idle readers.
+    pub fn clear_idle(&self) {
+        let mut state = self.lock_state();
+        state.num_open -= state.idle.len();
+        state.idle.clear();
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, PoolState<R>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Closes all idle readers, which exceed `max_idle` or have been idle for longer than `idle_timeout`.
+    fn prune(&self, state: &mut PoolState<R>) {
+        let num_idle = state.idle.len();
+
+        if num_idle > self.max_idle {
+            state.idle.drain(..num_idle - self.max_idle);
+        }
+
+        if let Some(idle_timeout) = self.idle_timeout {
+            state
+                .idle
+                .retain(|(_, returned_at)| returned_at.elapsed() < idle_timeout);
+        }
+
+        state.num_open -= num_idle - state.idle.len();
+    }
+
+    fn put(&self, reader: R) {
+        let mut state = self.lock_state();
+        state.num_open += 1;
+        state.idle.push((reader, Instant::now()));
+    }
+}
+
+/// A reader checked out of a [`ReaderPool`], which is returned to the pool when dropped.
+pub struct PooledReader<'a, R> {
+    pool: &'a ReaderPool<R>,
+    reader: Option<R>,
+}
+
+impl<R: std::fmt::Debug> std::fmt::Debug for PooledReader<'_, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PooledReader").field(&self.reader).finish()
+    }
+}
+
+impl<R> Deref for PooledReader<'_, R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        // the reader is only taken when dropping
+        self.reader.as_ref().unwrap_or_else(|| unreachable!())
+    }
+}
+
+impl<R> DerefMut for PooledReader<'_, R> {
+    fn deref_mut(&mut self) -> &mut R {
+        self.reader.as_mut().unwrap_or_else(|| unreachable!())
+    }
+}
+
+impl<R> Drop for PooledReader<'_, R> {
+    fn drop(&mut self) {
+        let Some(reader) = self.reader.take() else {
+            return;
+        };
+
+        let mut state = self.pool.lock_state();
+        state.idle.push((reader, Instant::now()));
+        self.pool.prune(&mut state);
+        drop(state);
+
+        self.pool.returned.notify_one();
+    }
+}
+
+impl<R: Read + Seek> ReadAt for ReaderPool<R> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let mut reader = self.get()?;
+
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read(buf)
+    }
+}
+
+impl<R: Read + Seek> Read for ReaderPool<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = self.read_at(self.position, buf)?;
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl<R: Read + Seek> Seek for ReaderPool<R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => {
+                let end = self.get()?.seek(SeekFrom::End(0))?;
+                end.checked_add_signed(offset)
+            }
+        };
+
+        self.position = position.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::{
+        io::Cursor,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+
+    const PM_TILES_PATH: &str = "./test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles";
+
+    fn counting_pool() -> (ReaderPool<Cursor<Vec<u8>>>, Arc<AtomicUsize>) {
+        let opened = Arc::new(AtomicUsize::new(0));
+        let counter = opened.clone();
+
+        let pool = ReaderPool::new(move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+            Ok(Cursor::new(vec![1u8, 3, 3, 7]))
+        })
+        .with_max_readers(2);
+
+        (pool, opened)
+    }
+
+    #[test]
+    fn test_open() -> Result<()> {
+        let mut pool = ReaderPool::open(PM_TILES_PATH)?;
+        assert_eq!(pool.num_open(), 1);
+        assert_eq!(pool.num_idle(), 1);
+
+        let mut buf = [0; 7];
+        pool.read_exact(&mut buf)?;
+        assert_eq!(&buf, b"PMTiles");
+        assert_eq!(pool.num_open(), 1);
+
+        assert_eq!(
+            pool.seek(SeekFrom::End(0))?,
+            std::fs::metadata(PM_TILES_PATH)?.len()
+        );
+
+        assert!(ReaderPool::open("./test/missing.pmtiles").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reuse() -> Result<()> {
+        let (pool, opened) = counting_pool();
+        let mut buf = [0; 2];
+
+        pool.read_exact_at(1, &mut buf)?;
+        pool.read_exact_at(2, &mut buf)?;
+        assert_eq!(buf, [3, 7]);
+        assert_eq!(opened.load(Ordering::Relaxed), 1);
+
+        let first = pool.get()?;
+        let second = pool.get()?;
+        assert_eq!(opened.load(Ordering::Relaxed), 2);
+        assert_eq!(pool.num_open(), 2);
+        assert_eq!(pool.num_idle(), 0);
+
+        drop(first);
+        drop(second);
+        assert_eq!(pool.num_idle(), 2);
+
+        pool.clear_idle();
+        assert_eq!(pool.num_open(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_idle() -> Result<()> {
+        let (pool, _) = counting_pool();
+        let pool = pool.with_max_idle(1);
+
+        let first = pool.get()?;
+        let second = pool.get()?;
+        drop(first);
+        drop(second);
+
+        assert_eq!(pool.num_idle(), 1);
+        assert_eq!(pool.num_open(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_idle_timeout() -> Result<()> {
+        let (pool, opened) = counting_pool();
+        let pool = pool.with_idle_timeout(Duration::ZERO);
+
+        drop(pool.get()?);
+        drop(pool.get()?);
+
+        assert_eq!(pool.num_open(), 0);
+        assert_eq!(opened.load(Ordering::Relaxed), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_readers() {
+        let (pool, opened) = counting_pool();
+        std::thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        let mut buf = [0; 1];
+                        pool.read_exact_at(3, &mut buf).unwrap();
+                        assert_eq!(buf, [7]);
+                    }
+                });
+            }
+        });
+
+        assert!(opened.load(Ordering::Relaxed) <= 2);
+        assert!(pool.num_open() <= 2);
+    }
+
+    #[test]
+    fn test_open_error() {
+        let pool: ReaderPool<Cursor<Vec<u8>>> =
+            ReaderPool::new(|| Err(Error::new(ErrorKind::NotFound, "missing")));
+
+        assert!(pool.get().is_err());
+        assert_eq!(pool.num_open(), 0);
+    }
+}
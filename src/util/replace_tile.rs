@@ -0,0 +1,390 @@
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use std::io::{Read, Seek, Write};
+
+use crate::{directory::MAX_DIRECTORY_DEPTH, Directory, Entry, Error, Header, Result};
+
+/// Returns the index of the entry in `directory`, which either includes `tile_id`
+/// or points to the leaf directory, which may include `tile_id`.
+fn find_entry_index(directory: &Directory, tile_id: u64) -> Option<usize> {
+    let entry = directory.find_entry(tile_id)?;
+
+    Some(directory[..].partition_point(|e| e.tile_id < entry.tile_id))
+}
+
+fn encode_directory(directory: &Directory, header: &Header) -> Result<Vec<u8>> {
+    let mut bytes = Vec::<u8>::new();
+    directory.to_writer(&mut bytes, header.internal_compression)?;
+    Ok(bytes)
+}
+
+/// Returns `true` if a tile entry of `directory` other than `entry` references the data of `entry`.
+///
+/// Leaf directories of `directory` are pushed to `pending` as absolute offset, length and depth.
+fn shares_tile_data(
+    directory: &Directory,
+    entry: &Entry,
+    depth: usize,
+    leaf_directories_offset: u64,
+    pending: &mut Vec<(u64, u64, usize)>,
+) -> bool {
+    for other in directory {
+        if other.is_leaf_dir_entry() {
+            pending.push((
+                leaf_directories_offset + other.offset,
+                u64::from(other.length),
+                depth + 1,
+            ));
+        } else if other.offset == entry.offset && other.tile_id != entry.tile_id {
+            return true;
+        }
+    }
+
+    false
+}
+
+#[duplicate_item(
+    fn_name                      cfg_async_filter       async   add_await(code) SeekFrom                RTraits                                          from_reader;
+    [tile_data_shared]           [cfg(all())]           []      [code]          [std::io::SeekFrom]     [(impl Read + Seek)]                             [from_reader];
+    [tile_data_shared_async]     [cfg(feature="async")] [async] [code.await]    [futures::io::SeekFrom] [(impl AsyncReadExt + AsyncSeekExt + Unpin + Send)] [from_async_reader];
+)]
+#[cfg_async_filter]
+/// Returns `true` if the data of `entry` is referenced by any other tile entry of the archive.
+///
+/// Directories are read one by one and reading stops at the first other entry referencing the data,
+/// so no map of all tiles is built.
+async fn fn_name(
+    archive: &mut RTraits,
+    header: &Header,
+    root_directory: &Directory,
+    entry: &Entry,
+) -> Result<bool> {
+    let mut pending = Vec::new();
+    if shares_tile_data(
+        root_directory,
+        entry,
+        1,
+        header.leaf_directories_offset,
+        &mut pending,
+    ) {
+        return Ok(true);
+    }
+
+    while let Some((offset, length, depth)) = pending.pop() {
+        if depth > MAX_DIRECTORY_DEPTH {
+            return Err(Error::DirectoryDecode(
+                "Leaf directories are nested too deeply".to_string(),
+            ));
+        }
+
+        add_await([archive.seek(SeekFrom::Start(offset))])?;
+        let directory = add_await([Directory::from_reader(
+            archive,
+            length,
+            header.internal_compression,
+        )])?;
+
+        if shares_tile_data(
+            &directory,
+            entry,
+            depth,
+            header.leaf_directories_offset,
+            &mut pending,
+        ) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[duplicate_item(
+    fn_name                           cfg_async_filter       async   add_await(code) SeekFrom                IOTraits                                                            tile_data_shared         from_reader         to_writer;
+    [replace_tile_in_place_impl]      [cfg(all())]           []      [code]          [std::io::SeekFrom]     [(impl Read + Write + Seek)]                                        [tile_data_shared]       [from_reader]       [to_writer];
+    [replace_tile_in_place_impl_async] [cfg(feature="async")] [async] [code.await]    [futures::io::SeekFrom] [(impl AsyncReadExt + AsyncWrite + AsyncSeekExt + Unpin + Send)] [tile_data_shared_async] [from_async_reader] [to_async_writer];
+)]
+#[cfg_async_filter]
+#[allow(clippy::too_many_lines)]
+async fn fn_name(archive: &mut IOTraits, tile_id: u64, data: &[u8]) -> Result<()> {
+    if data.is_empty() {
+        return Err(Error::EmptyTile);
+    }
+
+    add_await([archive.seek(SeekFrom::Start(0))])?;
+    let mut header = add_await([Header::from_reader(archive)])?;
+
+    // find entry of tile in root directory or leaf directory
+    add_await([archive.seek(SeekFrom::Start(header.root_directory_offset))])?;
+    let mut root_directory = add_await([Directory::from_reader(
+        archive,
+        header.root_directory_length,
+        header.internal_compression,
+    )])?;
+
+    let root_index =
+        find_entry_index(&root_directory, tile_id).ok_or(Error::TileNotFound(tile_id))?;
+
+    let mut leaf = None;
+    let entry: Entry = if root_directory[root_index].is_leaf_dir_entry() {
+        let leaf_entry = root_directory[root_index];
+        add_await([archive.seek(SeekFrom::Start(
+            header.leaf_directories_offset + leaf_entry.offset,
+        ))])?;
+        let leaf_directory = add_await([Directory::from_reader(
+            archive,
+            u64::from(leaf_entry.length),
+            header.internal_compression,
+        )])?;
+
+        let leaf_index =
+            find_entry_index(&leaf_directory, tile_id).ok_or(Error::TileNotFound(tile_id))?;
+        let entry = leaf_directory[leaf_index];
+
+        if entry.is_leaf_dir_entry() {
+            return Err(Error::Unsupported(
+                "Leaf directories pointing to other leaf directories are not supported.",
+            ));
+        }
+
+        leaf = Some((leaf_directory, leaf_index));
+        entry
+    } else {
+        root_directory[root_index]
+    };
+
+    // make sure the data of the tile is not shared with other tiles
+    if entry.run_length > 1
+        || add_await([tile_data_shared(archive, &header, &root_directory, &entry)])?
+    {
+        return Err(Error::Unsupported(
+            "Tile data is shared with other tiles and can therefore not be replaced in place.",
+        ));
+    }
+
+    let new_length = u32::try_from(data.len()).unwrap_or(u32::MAX);
+    if new_length > entry.length {
+        return Err(Error::ReplacementTooLarge {
+            new: data.len(),
+            existing: entry.length,
+        });
+    }
+
+    // patch directory entry (only needed if length changed)
+    if new_length < entry.length {
+        // encode all patched directories before writing anything, so the archive
+        // stays unchanged if one of them does not fit
+        let mut leaf_bytes = None;
+        match leaf {
+            None => {
+                root_directory[root_index].length = new_length;
+            }
+            Some((mut leaf_directory, leaf_index)) => {
+                leaf_directory[leaf_index].length = new_length;
+
+                let leaf_entry = &mut root_directory[root_index];
+                let bytes = encode_directory(&leaf_directory, &header)?;
+                let bytes_len = u32::try_from(bytes.len()).unwrap_or(u32::MAX);
+
+                if bytes_len > leaf_entry.length {
+                    return Err(Error::Unsupported(
+                        "Patched leaf directory does not fit into space of existing leaf directory.",
+                    ));
+                }
+
+                leaf_entry.length = bytes_len;
+                leaf_bytes = Some((leaf_entry.offset, bytes));
+            }
+        }
+
+        let root_bytes = encode_directory(&root_directory, &header)?;
+
+        if root_bytes.len() as u64 > header.root_directory_length {
+            return Err(Error::Unsupported(
+                "Patched root directory does not fit into space of existing root directory.",
+            ));
+        }
+
+        if let Some((offset, bytes)) = leaf_bytes {
+            add_await([archive.seek(SeekFrom::Start(header.leaf_directories_offset + offset))])?;
+            add_await([archive.write_all(&bytes)])?;
+        }
+
+        add_await([archive.seek(SeekFrom::Start(header.root_directory_offset))])?;
+        add_await([archive.write_all(&root_bytes)])?;
+
+        header.root_directory_length = root_bytes.len() as u64;
+        add_await([archive.seek(SeekFrom::Start(0))])?;
+        add_await([header.to_writer(archive)])?;
+    }
+
+    // write tile data and pad remaining bytes with zeros
+    add_await([archive.seek(SeekFrom::Start(header.tile_data_offset + entry.offset))])?;
+    add_await([archive.write_all(data)])?;
+    add_await([archive.write_all(&vec![0; (entry.length - new_length) as usize])])?;
+
+    add_await([archive.flush()])?;
+
+    Ok(())
+}
+
+/// Replaces the data of a single tile of an existing archive in place, without rewriting the whole archive.
+///
+/// This is only possible if the new data is not larger than the existing data of the tile. Remaining bytes
+/// are padded with zeros and only the directory entry of the tile (and the entry of the leaf directory it
+/// is located in, if any) is patched.
+///
+/// Note that shrinking a tile may increase the encoded size of its directory (e.g. because the following
+/// entry can no longer be encoded as being contiguous to it). If the patched directory does not fit into
+/// the space of the existing directory, an error is returned and the archive is left unchanged.
+///
+/// # Arguments
+/// * `archive` - Archive to modify (e.g. a [`std::fs::File`] opened for reading and writing)
+/// * `tile_id` - Id of the tile to replace
+/// * `data` - New data of the tile (must already be compressed according to the archive's tile compression)
+///
+/// # Errors
+/// Will return [`Err`] if...
+/// - the archive does not include a tile with the specified id ([`Error::TileNotFound`])
+/// - `data` is empty ([`Error::EmptyTile`]) or larger than the existing data of the tile ([`Error::ReplacementTooLarge`])
+/// - the existing data of the tile is shared with other tiles (deduplicated)
+/// - the patched directories do not fit into the space of the existing directories
+/// - an I/O error occurred while reading from or writing to `archive`
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::replace_tile_in_place, Compression, PMTiles, TileType};
+/// # use std::io::Cursor;
+/// let mut archive = Cursor::new(Vec::<u8>::new());
+///
+/// let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+/// pm_tiles.add_tile(0, vec![0; 8]).unwrap();
+/// pm_tiles.to_writer(&mut archive).unwrap();
+///
+/// replace_tile_in_place(&mut archive, 0, &[1, 3, 3, 7]).unwrap();
+///
+/// archive.set_position(0);
+/// let mut pm_tiles = PMTiles::from_reader(archive).unwrap();
+/// assert_eq!(pm_tiles.get_tile_by_id(0).unwrap(), Some(vec![1, 3, 3, 7]));
+/// ```
+pub fn replace_tile_in_place(
+    archive: &mut (impl Read + Write + Seek),
+    tile_id: u64,
+    data: &[u8],
+) -> Result<()> {
+    replace_tile_in_place_impl(archive, tile_id, data)
+}
+
+/// Async version of [`replace_tile_in_place`].
+///
+/// Replaces the data of a single tile of an existing archive in place, without rewriting the whole archive.
+///
+/// # Errors
+/// See [`replace_tile_in_place`] for details on possible errors.
+#[cfg(feature = "async")]
+pub async fn replace_tile_in_place_async(
+    archive: &mut (impl AsyncReadExt + AsyncWrite + AsyncSeekExt + Unpin + Send),
+    tile_id: u64,
+    data: &[u8],
+) -> Result<()> {
+    replace_tile_in_place_impl_async(archive, tile_id, data).await
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{Compression, PMTiles, TileType};
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    #[test]
+    fn test_replace_tile_in_place_same_size() -> Result<()> {
+        let mut archive = Cursor::new(PM_TILES_BYTES.to_vec());
+
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        let length = pm_tiles.get_tile_by_id(19)?.unwrap_or_default().len();
+
+        replace_tile_in_place(&mut archive, 19, &vec![42; length])?;
+
+        let bytes = archive.into_inner();
+        assert_eq!(bytes.len(), PM_TILES_BYTES.len());
+
+        let mut pm_tiles = PMTiles::from_bytes(&bytes)?;
+        assert_eq!(pm_tiles.get_tile_by_id(19)?, Some(vec![42; length]));
+        assert_eq!(pm_tiles.num_tiles(), 85);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replace_tile_in_place_smaller() -> Result<()> {
+        let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+        pm_tiles.internal_compression = Compression::None;
+        for tile_id in 0..5u8 {
+            pm_tiles.add_tile(tile_id.into(), vec![tile_id; 16])?;
+        }
+
+        let mut original = Vec::<u8>::new();
+        pm_tiles.to_writer(&mut Cursor::new(&mut original))?;
+
+        let mut archive = Cursor::new(original.clone());
+        replace_tile_in_place(&mut archive, 4, &[1, 3, 3, 7])?;
+
+        let bytes = archive.into_inner();
+        assert_eq!(bytes.len(), original.len());
+
+        let mut pm_tiles = PMTiles::from_bytes(&bytes)?;
+        assert_eq!(pm_tiles.get_tile_by_id(4)?, Some(vec![1, 3, 3, 7]));
+        assert_eq!(pm_tiles.get_tile_by_id(3)?, Some(vec![3; 16]));
+        assert_eq!(pm_tiles.num_tiles(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replace_tile_in_place_errors() {
+        let mut archive = Cursor::new(PM_TILES_BYTES.to_vec());
+
+        // too large
+        assert!(replace_tile_in_place(&mut archive, 19, &vec![0; 100_000]).is_err());
+
+        // empty
+        assert!(replace_tile_in_place(&mut archive, 19, &[]).is_err());
+
+        // shared with other tiles (tile 58 has a run length of 2)
+        assert!(replace_tile_in_place(&mut archive, 58, &[1]).is_err());
+
+        // not found
+        assert!(replace_tile_in_place(&mut archive, 1337, &[1]).is_err());
+
+        assert_eq!(archive.into_inner(), PM_TILES_BYTES);
+    }
+
+    #[test]
+    fn test_replace_tile_in_place_deduplicated() -> Result<()> {
+        let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+        pm_tiles.add_tile(0, vec![1; 16])?;
+        pm_tiles.add_tile(1, vec![2; 16])?;
+        pm_tiles.add_tile(2, vec![1; 16])?;
+
+        let mut original = Vec::<u8>::new();
+        pm_tiles.to_writer(&mut Cursor::new(&mut original))?;
+
+        // tiles 0 and 2 share their data without forming a run
+        let mut archive = Cursor::new(original.clone());
+        assert!(replace_tile_in_place(&mut archive, 2, &[3; 16]).is_err());
+        assert_eq!(archive.get_ref(), &original);
+
+        replace_tile_in_place(&mut archive, 1, &[3; 16])?;
+
+        let bytes = archive.into_inner();
+        let mut pm_tiles = PMTiles::from_bytes(&bytes)?;
+        assert_eq!(pm_tiles.get_tile_by_id(1)?, Some(vec![3; 16]));
+        assert_eq!(pm_tiles.get_tile_by_id(2)?, Some(vec![1; 16]));
+
+        Ok(())
+    }
+}
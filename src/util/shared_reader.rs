@@ -0,0 +1,178 @@
+use std::{
+    io::{Read, Result, Seek, SeekFrom},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, MutexGuard, PoisonError, TryLockError,
+    },
+};
+
+use super::ReadAt;
+
+/// An adapter, which implements [`ReadAt`] for any [`Read`] + [`Seek`] by locking the wrapped
+/// reader for the duration of every positional read.
+///
+/// This allows reading tiles of a single archive through a shared reference
+/// (e.g. [`PMTiles::read_tile`](crate::PMTiles::read_tile)), even if the reader does not support
+/// positional reads natively. In contrast to wrapping the whole archive in a [`Mutex`], only
+/// actual reads are serialized, while in-memory lookups (e.g. of directories) are not.
+///
+/// To allow reads to run concurrently as well, additional readers of the same data (e.g. multiple
+/// handles of the same file) can be added to a pool using [`with_reader`](Self::with_reader).
+/// Every positional read then uses the first reader, which is not locked at the moment.
+/// Use a [`ReaderPool`](super::ReaderPool) to open readers on demand instead.
+///
+/// [`Read`] + [`Seek`] are implemented by using the first reader directly. Positional reads move
+/// the position of the readers, so seek before reading after positional reads.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::SharedReader, PMTiles};
+/// # use std::fs::File;
+/// let path = "./test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles";
+/// let reader = SharedReader::new(File::open(path).unwrap())
+///     .with_reader(File::open(path).unwrap());
+///
+/// let pm_tiles = PMTiles::from_reader(reader).unwrap();
+///
+/// std::thread::scope(|s| {
+///     s.spawn(|| pm_tiles.read_tile(0, 0, 0).unwrap());
+///     s.spawn(|| pm_tiles.read_tile(0, 0, 1).unwrap());
+/// });
+/// ```
+#[derive(Debug)]
+pub struct SharedReader<R> {
+    readers: Vec<Mutex<R>>,
+    next: AtomicUsize,
+}
+
+impl<R> SharedReader<R> {
+    /// Wraps `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            readers: vec![Mutex::new(reader)],
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Adds another reader to the pool, which must provide the same data as the readers
+    /// already in the pool.
+    #[must_use]
+    pub fn with_reader(mut self, reader: R) -> Self {
+        self.readers.push(Mutex::new(reader));
+        self
+    }
+
+    /// Returns the number of readers in the pool.
+    pub const fn num_readers(&self) -> usize {
+        self.readers.len()
+    }
+
+    /// Unwraps this adapter, returning all readers of the pool.
+    pub fn into_inner(self) -> Vec<R> {
+        self.readers
+            .into_iter()
+            .map(|reader| reader.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .collect()
+    }
+
+    fn first_mut(&mut self) -> &mut R {
+        self.readers[0]
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the first reader of the pool, which is not locked at the moment, or waits for the
+    /// next reader in turn, if all of them are locked.
+    fn lock(&self) -> MutexGuard<'_, R> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let len = self.readers.len();
+
+        for i in 0..len {
+            match self.readers[(start + i) % len].try_lock() {
+                Ok(guard) => return guard,
+                Err(TryLockError::Poisoned(err)) => return err.into_inner(),
+                Err(TryLockError::WouldBlock) => {}
+            }
+        }
+
+        self.readers[start % len]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<R: Read> Read for SharedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.first_mut().read(buf)
+    }
+}
+
+impl<R: Seek> Seek for SharedReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.first_mut().seek(pos)
+    }
+}
+
+impl<R: Read + Seek> ReadAt for SharedReader<R> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let mut reader = self.lock();
+
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_read_at() -> Result<()> {
+        let reader = SharedReader::new(Cursor::new(vec![1u8, 3, 3, 7]))
+            .with_reader(Cursor::new(vec![1u8, 3, 3, 7]));
+        assert_eq!(reader.num_readers(), 2);
+
+        let mut buf = [0; 2];
+        reader.read_exact_at(2, &mut buf)?;
+        assert_eq!(buf, [3, 7]);
+
+        reader.read_exact_at(1, &mut buf)?;
+        assert_eq!(buf, [3, 3]);
+
+        assert_eq!(reader.read_at(4, &mut buf)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_at_locked() -> Result<()> {
+        let reader = SharedReader::new(Cursor::new(vec![1u8, 3, 3, 7]))
+            .with_reader(Cursor::new(vec![1u8, 3, 3, 7]));
+
+        // locked readers are skipped, as long as another reader is available
+        let _guard = reader.lock();
+        for _ in 0..4 {
+            let mut buf = [0; 1];
+            reader.read_exact_at(3, &mut buf)?;
+            assert_eq!(buf, [7]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_seek() -> Result<()> {
+        let mut reader = SharedReader::new(Cursor::new(vec![1u8, 3, 3, 7]));
+
+        reader.seek(SeekFrom::Start(1))?;
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        assert_eq!(buf, [3, 3, 7]);
+
+        assert_eq!(reader.into_inner().len(), 1);
+
+        Ok(())
+    }
+}
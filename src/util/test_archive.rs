@@ -0,0 +1,210 @@
+use std::ops::RangeInclusive;
+
+use super::zoom_range;
+use crate::{Compression, Error, PMTiles, Result, TileType};
+
+/// Maximum zoom level supported by [`generate_test_archive`].
+const MAX_TEST_ZOOM: u8 = 31;
+
+/// Distribution of the lengths (in bytes) of tiles generated by [`generate_test_archive`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TileSizeDistribution {
+    /// All tiles have the same length (at least 1 byte).
+    Fixed(u32),
+
+    /// Lengths are distributed uniformly between `min` and `max` (both inclusive, at least 1 byte).
+    Uniform {
+        /// Minimum length of a tile
+        min: u32,
+        /// Maximum length of a tile
+        max: u32,
+    },
+
+    /// Lengths are distributed exponentially around `mean`, so most tiles are small and few are
+    /// large (similar to real vector tiles).
+    Exponential {
+        /// Mean length of a tile
+        mean: u32,
+    },
+}
+
+impl TileSizeDistribution {
+    /// Returns a length of at least 1 byte according to this distribution.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn sample(self, rng: &mut SplitMix64) -> u32 {
+        let length = match self {
+            Self::Fixed(length) => length,
+            Self::Uniform { min, max } => {
+                let (min, max) = (min.min(max), min.max(max));
+                min + (rng.next() % (u64::from(max - min) + 1)) as u32
+            }
+            Self::Exponential { mean } => {
+                // uniform value in (0, 1]
+                let uniform = ((rng.next() >> 11) + 1) as f64 / (1u64 << 53) as f64;
+                (-f64::from(mean) * uniform.ln()).min(f64::from(u32::MAX)) as u32
+            }
+        };
+
+        length.max(1)
+    }
+}
+
+/// Small pseudo random number generator, which produces the same numbers on every platform.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    const fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = self.next().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+/// Generates an archive with fake tiles for all tiles of the zoom levels `zooms` (e.g. for
+/// benchmarks and integration tests without shipping large fixtures).
+///
+/// The archive is deterministic: The same arguments always result in the same tiles on every platform.
+/// Tiles consist of pseudo random bytes (of [`TileType::Unknown`] and [`Compression::None`]),
+/// so they are neither deduplicated nor compressible.
+///
+/// Note that zoom level `z` includes `4^z` tiles, so zoom levels above ~10 result in very large archives.
+///
+/// # Arguments
+/// * `zooms` - Zoom levels to generate all tiles for
+/// * `tile_size_distribution` - Distribution of the lengths of generated tiles
+/// * `seed` - Seed of the pseudo random tile lengths and data
+///
+/// # Errors
+/// Will return [`Err`] if `zooms` includes zoom levels above 31.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::{generate_test_archive, TileSizeDistribution}, PMTiles};
+/// # use std::io::Cursor;
+/// let pm_tiles: PMTiles<Cursor<Vec<u8>>> =
+///     generate_test_archive(0..=3, TileSizeDistribution::Fixed(64), 42).unwrap();
+///
+/// assert_eq!(pm_tiles.num_tiles(), 1 + 4 + 16 + 64);
+/// ```
+pub fn generate_test_archive<R>(
+    zooms: RangeInclusive<u8>,
+    tile_size_distribution: TileSizeDistribution,
+    seed: u64,
+) -> Result<PMTiles<R>> {
+    if *zooms.end() > MAX_TEST_ZOOM {
+        return Err(Error::Validation(format!(
+            "Zoom level {} exceeds the maximum of {MAX_TEST_ZOOM}",
+            zooms.end()
+        )));
+    }
+
+    let mut pm_tiles = PMTiles::default();
+    pm_tiles.tile_type = TileType::Unknown;
+    pm_tiles.tile_compression = Compression::None;
+    pm_tiles.min_zoom = *zooms.start();
+    pm_tiles.max_zoom = *zooms.end();
+    pm_tiles.center_zoom = *zooms.start();
+    pm_tiles.min_longitude = -180.0;
+    pm_tiles.max_longitude = 180.0;
+    pm_tiles.min_latitude = -85.0;
+    pm_tiles.max_latitude = 85.0;
+
+    let mut rng = SplitMix64(seed);
+
+    for tile_id in zooms.flat_map(zoom_range) {
+        let mut data = vec![0; tile_size_distribution.sample(&mut rng) as usize];
+        rng.fill(&mut data);
+
+        pm_tiles.add_tile(tile_id, data)?;
+    }
+
+    Ok(pm_tiles)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn generate(
+        zooms: RangeInclusive<u8>,
+        tile_size_distribution: TileSizeDistribution,
+        seed: u64,
+    ) -> PMTiles<Cursor<Vec<u8>>> {
+        generate_test_archive(zooms, tile_size_distribution, seed).unwrap()
+    }
+
+    #[test]
+    fn test_generate_test_archive() {
+        let mut pm_tiles = generate(2..=3, TileSizeDistribution::Fixed(10), 1);
+
+        assert_eq!(pm_tiles.min_zoom, 2);
+        assert_eq!(pm_tiles.max_zoom, 3);
+        assert_eq!(pm_tiles.num_tiles(), 16 + 64);
+        assert_eq!(
+            pm_tiles.tile_ids_sorted(),
+            (zoom_range(2).start..zoom_range(3).end).collect::<Vec<_>>()
+        );
+        assert_eq!(pm_tiles.get_tile(7, 7, 3).unwrap().unwrap().len(), 10);
+
+        let read = PMTiles::from_bytes(pm_tiles.to_vec().unwrap()).unwrap();
+        assert_eq!(read.num_tiles(), 16 + 64);
+    }
+
+    #[test]
+    fn test_generate_test_archive_deterministic() {
+        let distribution = TileSizeDistribution::Exponential { mean: 100 };
+
+        let a = generate(0..=2, distribution, 7).to_vec().unwrap();
+        let b = generate(0..=2, distribution, 7).to_vec().unwrap();
+        let c = generate(0..=2, distribution, 8).to_vec().unwrap();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_generate_test_archive_sizes() {
+        let mut pm_tiles = generate(0..=3, TileSizeDistribution::Uniform { min: 5, max: 8 }, 3);
+
+        for tile_id in pm_tiles.tile_ids_sorted() {
+            let length = pm_tiles.get_tile_by_id(tile_id).unwrap().unwrap().len();
+            assert!((5..=8).contains(&length));
+        }
+
+        let mut pm_tiles = generate(4..=4, TileSizeDistribution::Fixed(0), 3);
+        assert_eq!(
+            pm_tiles
+                .get_tile_by_id(zoom_range(4).start)
+                .unwrap()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_generate_test_archive_invalid_zoom() {
+        assert!(matches!(
+            generate_test_archive::<Cursor<Vec<u8>>>(0..=32, TileSizeDistribution::Fixed(1), 0),
+            Err(Error::Validation(_))
+        ));
+    }
+}
@@ -1,8 +1,12 @@
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, ops::Range};
 
 use hilbert_2d::Variant;
 
-const MAX_Z: u8 = 32;
+use super::coalesce_ranges;
+
+/// Zoom levels must be less than this value, as tile ids of higher zoom levels do not fit into `u64`.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) const MAX_Z: u8 = 32;
 
 /// An error indicating that the specified tile id has a
 /// z value greater than the maximum allowed z value.
@@ -38,6 +42,272 @@ pub fn tile_id(z: u8, x: u64, y: u64) -> u64 {
     base_id + tile_id
 }
 
+/// Returns the range of tile ids of all tiles with zoom level `z`.
+///
+/// As tile ids of lower zoom levels always precede those of higher zoom levels, ranges of multiple
+/// zoom levels can be combined into one (e.g. `zoom_range(0).start..zoom_range(8).end` for zoom levels `0..=8`).
+///
+/// Returns an empty range if `z` exceeds the maximum allowed z value.
+///
+/// # Arguments
+/// * `z` - The z coordinate (lod)
+///
+/// # Example
+/// Only read the tiles of zoom levels `0..=2`:
+/// ```rust
+/// # use pmtiles2::{util::zoom_range, PMTiles};
+/// # let file_path = "./test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles";
+/// assert_eq!(zoom_range(2), 5..21);
+///
+/// let file = std::fs::File::open(file_path).unwrap();
+/// let pm_tiles = PMTiles::from_reader_partially(file, zoom_range(0).start..zoom_range(2).end).unwrap();
+///
+/// assert_eq!(pm_tiles.num_tiles(), 1 + 4 + 16);
+/// ```
+pub fn zoom_range(z: u8) -> Range<u64> {
+    if z >= MAX_Z {
+        return 0..0;
+    }
+
+    tile_id(z, 0, 0)..tile_id(z + 1, 0, 0)
+}
+
+/// Returns the zoom level of the tile with id `tile_id`.
+///
+/// Returns [`None`] if the zoom level exceeds the maximum allowed z value.
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) fn tile_zoom(tile_id: u64) -> Option<u8> {
+    let mut end: u64 = 0;
+
+    for z in 0..MAX_Z {
+        end = end.saturating_add(4u64.saturating_pow(u32::from(z)));
+
+        if tile_id < end {
+            return Some(z);
+        }
+    }
+
+    None
+}
+
+/// Converts a WGS84 position to the x and y coordinates of the tile at zoom level `z`
+/// that contains that position.
+///
+/// Positions outside of the bounds of the Web Mercator projection are clamped.
+///
+/// # Arguments
+/// * `longitude` - Longitude of the position
+/// * `latitude` - Latitude of the position
+/// * `z` - The z coordinate (lod)
+///
+/// # Errors
+/// Will return [`Err`] if `z` exceeds the maximum allowed z value.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::lng_lat_to_xy;
+/// assert_eq!(lng_lat_to_xy(11.25, 43.77, 12).unwrap(), (2176, 1493));
+/// ```
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+pub fn lng_lat_to_xy(longitude: f64, latitude: f64, z: u8) -> Result<(u64, u64), MaxZError> {
+    const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+    if z >= MAX_Z {
+        return Err(MaxZError {});
+    }
+
+    let n = (1u64 << z) as f64;
+    let max = (1u64 << z) - 1;
+
+    let lat_rad = latitude.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+
+    let x = ((longitude.clamp(-180.0, 180.0) + 180.0) / 360.0 * n).floor();
+    let y = ((1.0 - lat_rad.tan().asinh() / std::f64::consts::PI) / 2.0 * n).floor();
+
+    Ok(((x.max(0.0) as u64).min(max), (y.max(0.0) as u64).min(max)))
+}
+
+/// Converts a WGS84 position to the id of the tile at zoom level `z` that contains that position.
+///
+/// Positions outside of the bounds of the Web Mercator projection are clamped.
+/// See [`lng_lat_to_xy`] to get the x and y coordinates of the tile instead.
+///
+/// # Arguments
+/// * `longitude` - Longitude of the position
+/// * `latitude` - Latitude of the position
+/// * `z` - The z coordinate (lod)
+///
+/// # Errors
+/// Will return [`Err`] if `z` exceeds the maximum allowed z value.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::lng_lat_to_tile_id, PMTiles};
+/// let bytes = include_bytes!("../../test/protomaps(vector)ODbL_firenze.pmtiles");
+/// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+///
+/// // tile containing the Florence Cathedral
+/// let tile_id = lng_lat_to_tile_id(11.2562, 43.7731, 14).unwrap();
+/// assert!(pm_tiles.get_tile_by_id(tile_id).unwrap().is_some());
+/// ```
+pub fn lng_lat_to_tile_id(longitude: f64, latitude: f64, z: u8) -> Result<u64, MaxZError> {
+    let (x, y) = lng_lat_to_xy(longitude, latitude, z)?;
+
+    Ok(tile_id(z, x, y))
+}
+
+/// Returns an iterator over the ids of all tiles at zoom level `z`, which intersect a bounding box.
+///
+/// Tile ids are yielded row by row (from north to south), so they are not sorted. Positions outside
+/// of the bounds of the Web Mercator projection are clamped. The iterator is empty if the minimum of
+/// `bbox` exceeds its maximum (e.g. for a bounding box crossing the antimeridian) or `z` exceeds the
+/// maximum allowed z value.
+///
+/// # Arguments
+/// * `bbox` - Bounding box as `(min_longitude, min_latitude, max_longitude, max_latitude)`
+/// * `z` - The z coordinate (lod)
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::{tile_id, tile_ids_in_bbox};
+/// // north-eastern quarter of the world
+/// let ids = tile_ids_in_bbox((0.0, 1.0, 180.0, 85.0), 2).collect::<Vec<_>>();
+///
+/// assert_eq!(ids.len(), 4);
+/// assert!(ids.contains(&tile_id(2, 3, 0)));
+/// ```
+pub fn tile_ids_in_bbox(bbox: (f64, f64, f64, f64), z: u8) -> impl Iterator<Item = u64> {
+    let (min_longitude, min_latitude, max_longitude, max_latitude) = bbox;
+
+    let corners = if min_longitude > max_longitude || min_latitude > max_latitude {
+        None
+    } else {
+        lng_lat_to_xy(min_longitude, max_latitude, z)
+            .ok()
+            .zip(lng_lat_to_xy(max_longitude, min_latitude, z).ok())
+    };
+
+    let (x_range, y_range) = corners.map_or((0..0, 0..0), |((min_x, min_y), (max_x, max_y))| {
+        (min_x..max_x + 1, min_y..max_y + 1)
+    });
+
+    y_range.flat_map(move |y| x_range.clone().map(move |x| tile_id(z, x, y)))
+}
+
+/// Converts z/x/y coordinates of the TMS tiling scheme (y axis pointing north, as used by
+/// `MBTiles`) to coordinates of the XYZ tiling scheme (y axis pointing south, as used by `PMTiles`).
+///
+/// Returns [`None`] if `z` exceeds the maximum allowed z value or the coordinates are out of range
+/// for zoom level `z`.
+///
+/// # Arguments
+/// * `z` - The z coordinate (lod)
+/// * `x` - The x coordinate
+/// * `y` - The y coordinate in the TMS tiling scheme
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::{tms_to_xyz, xyz_to_tms};
+/// assert_eq!(tms_to_xyz(2, 1, 0), Some((2, 1, 3)));
+/// assert_eq!(xyz_to_tms(2, 1, 3), Some((2, 1, 0)));
+/// assert_eq!(tms_to_xyz(2, 1, 4), None);
+/// ```
+pub const fn tms_to_xyz(z: u8, x: u64, y: u64) -> Option<(u8, u64, u64)> {
+    if z >= MAX_Z || x >> z != 0 || y >> z != 0 {
+        return None;
+    }
+
+    Some((z, x, (1u64 << z) - 1 - y))
+}
+
+/// Converts z/x/y coordinates of the XYZ tiling scheme (y axis pointing south, as used by `PMTiles`)
+/// to coordinates of the TMS tiling scheme (y axis pointing north, as used by `MBTiles`).
+///
+/// This is the inverse of [`tms_to_xyz`]. Returns [`None`] if `z` exceeds the maximum allowed z
+/// value or the coordinates are out of range for zoom level `z`.
+///
+/// # Arguments
+/// * `z` - The z coordinate (lod)
+/// * `x` - The x coordinate
+/// * `y` - The y coordinate in the XYZ tiling scheme
+pub const fn xyz_to_tms(z: u8, x: u64, y: u64) -> Option<(u8, u64, u64)> {
+    tms_to_xyz(z, x, y)
+}
+
+/// Returns the ranges of ids of all tiles at zoom level `z`, which intersect a bounding box.
+///
+/// In contrast to [`tile_ids_in_bbox`], this returns few ranges instead of single ids, as the
+/// tiles of a bounding box are mostly consecutive along the Hilbert curve. The ranges are sorted in
+/// ascending order and do not overlap. See [`tile_ids_in_bbox`] for details on how `bbox` is handled.
+///
+/// # Arguments
+/// * `bbox` - Bounding box as `(min_longitude, min_latitude, max_longitude, max_latitude)`
+/// * `z` - The z coordinate (lod)
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::{tile_id, tile_id_ranges_in_bbox};
+/// // north-eastern quarter of the world
+/// let ranges = tile_id_ranges_in_bbox((0.0, 1.0, 180.0, 85.0), 2);
+///
+/// assert_eq!(ranges, vec![17..21]);
+/// assert!(ranges[0].contains(&tile_id(2, 3, 1)));
+/// ```
+pub fn tile_id_ranges_in_bbox(bbox: (f64, f64, f64, f64), z: u8) -> Vec<Range<u64>> {
+    let (min_longitude, min_latitude, max_longitude, max_latitude) = bbox;
+
+    if min_longitude > max_longitude || min_latitude > max_latitude {
+        return Vec::new();
+    }
+
+    let (Ok(min), Ok(max)) = (
+        lng_lat_to_xy(min_longitude, max_latitude, z),
+        lng_lat_to_xy(max_longitude, min_latitude, z),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut ranges = Vec::new();
+    push_tile_id_ranges(&mut ranges, z, (min, max), (0, 0, 0));
+
+    coalesce_ranges(ranges, 0)
+}
+
+/// Pushes the ranges of tile ids at zoom level `z`, which are within `bounds` (inclusive x and y
+/// coordinates at zoom level `z`) and descendants of the tile `cz/cx/cy`.
+///
+/// All descendants of a tile are consecutive along the Hilbert curve, so tiles, which are completely
+/// within `bounds`, are pushed as a single range instead of descending further.
+fn push_tile_id_ranges(
+    ranges: &mut Vec<Range<u64>>,
+    z: u8,
+    bounds @ ((min_x, min_y), (max_x, max_y)): ((u64, u64), (u64, u64)),
+    (cz, cx, cy): (u8, u64, u64),
+) {
+    let shift = z - cz;
+    let (first_x, first_y) = (cx << shift, cy << shift);
+    let (last_x, last_y) = (((cx + 1) << shift) - 1, ((cy + 1) << shift) - 1);
+
+    if first_x > max_x || last_x < min_x || first_y > max_y || last_y < min_y {
+        return;
+    }
+
+    if min_x <= first_x && last_x <= max_x && min_y <= first_y && last_y <= max_y {
+        let num_tiles = 1u64 << (2 * shift);
+        let start = tile_id(z, 0, 0) + (tile_id(cz, cx, cy) - tile_id(cz, 0, 0)) * num_tiles;
+        ranges.push(start..start + num_tiles);
+        return;
+    }
+
+    for (x, y) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+        push_tile_id_ranges(ranges, z, bounds, (cz + 1, 2 * cx + x, 2 * cy + y));
+    }
+}
+
 fn find_z(tile_id: u64) -> Result<u8, MaxZError> {
     let mut z = 0u8;
     let mut acc = 1u64;
@@ -82,6 +352,42 @@ pub fn zxy(tile_id: u64) -> Result<(u8, u64, u64), MaxZError> {
     Ok((z, x as u64, y as u64))
 }
 
+/// Returns the bounds of a tile as `(min_longitude, min_latitude, max_longitude, max_latitude)`.
+///
+/// The bounds are calculated using the Web Mercator projection, so latitudes are within
+/// `-85.0511..=85.0511`.
+///
+/// # Arguments
+/// * `tile_id` - The tile id
+///
+/// # Errors
+/// Will return [`Err`] if `tile_id` has a too large z coordinate.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::{tile_bounds, tile_id};
+/// let (min_lng, min_lat, max_lng, max_lat) = tile_bounds(tile_id(1, 1, 0)).unwrap();
+///
+/// assert_eq!((min_lng, max_lng), (0.0, 180.0));
+/// assert_eq!(min_lat, 0.0);
+/// assert!((max_lat - 85.0511).abs() < 1e-4);
+/// ```
+#[allow(clippy::cast_precision_loss)]
+pub fn tile_bounds(tile_id: u64) -> Result<(f64, f64, f64, f64), MaxZError> {
+    let (z, x, y) = zxy(tile_id)?;
+    let n = (1u64 << z) as f64;
+
+    let longitude = |x: u64| (x as f64 / n).mul_add(360.0, -180.0);
+    let latitude = |y: u64| {
+        (std::f64::consts::PI * (2.0 * y as f64 / n).mul_add(-1.0, 1.0))
+            .sinh()
+            .atan()
+            .to_degrees()
+    };
+
+    Ok((longitude(x), latitude(y + 1), longitude(x + 1), latitude(y)))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -109,6 +415,144 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_zoom_range() {
+        assert_eq!(zoom_range(0), 0..1);
+        assert_eq!(zoom_range(1), 1..5);
+        assert_eq!(zoom_range(2), 5..21);
+        assert!(zoom_range(MAX_Z).is_empty());
+    }
+
+    #[test]
+    fn test_tile_zoom() {
+        assert_eq!(tile_zoom(0), Some(0));
+        assert_eq!(tile_zoom(1), Some(1));
+        assert_eq!(tile_zoom(4), Some(1));
+        assert_eq!(tile_zoom(5), Some(2));
+        assert_eq!(tile_zoom(tile_id(31, 0, 0)), Some(31));
+        assert_eq!(tile_zoom(u64::MAX), None);
+    }
+
+    #[test]
+    fn test_lng_lat_to_xy() -> Result<(), MaxZError> {
+        assert_eq!(lng_lat_to_xy(0.0, 0.0, 0)?, (0, 0));
+        assert_eq!(lng_lat_to_xy(-180.0, 85.0, 1)?, (0, 0));
+        assert_eq!(lng_lat_to_xy(180.0, -85.0, 1)?, (1, 1));
+        assert_eq!(lng_lat_to_xy(11.25, 43.77, 12)?, (2176, 1493));
+        assert_eq!(lng_lat_to_xy(-200.0, 90.0, 3)?, (0, 0));
+        assert_eq!(lng_lat_to_xy(200.0, -90.0, 3)?, (7, 7));
+        assert_eq!(
+            lng_lat_to_xy(180.0, -90.0, MAX_Z - 1)?,
+            ((1 << 31) - 1, (1 << 31) - 1)
+        );
+
+        assert!(lng_lat_to_xy(0.0, 0.0, MAX_Z).is_err());
+        assert!(lng_lat_to_xy(0.0, 0.0, 64).is_err());
+        assert!(lng_lat_to_xy(0.0, 0.0, u8::MAX).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lng_lat_to_tile_id() -> Result<(), MaxZError> {
+        assert_eq!(lng_lat_to_tile_id(0.0, 0.0, 0)?, 0);
+        assert_eq!(lng_lat_to_tile_id(-180.0, 85.0, 1)?, tile_id(1, 0, 0));
+        assert_eq!(lng_lat_to_tile_id(180.0, -85.0, 1)?, tile_id(1, 1, 1));
+        assert_eq!(
+            lng_lat_to_tile_id(11.25, 43.77, 12)?,
+            tile_id(12, 2176, 1493)
+        );
+        assert_eq!(
+            lng_lat_to_tile_id(180.0, -90.0, MAX_Z - 1)?,
+            tile_id(MAX_Z - 1, (1 << 31) - 1, (1 << 31) - 1)
+        );
+
+        assert!(lng_lat_to_tile_id(0.0, 0.0, MAX_Z).is_err());
+        assert!(lng_lat_to_tile_id(0.0, 0.0, 64).is_err());
+        assert!(lng_lat_to_tile_id(0.0, 0.0, u8::MAX).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tile_ids_in_bbox() {
+        let mut ids = tile_ids_in_bbox((-180.0, -90.0, 180.0, 90.0), 1).collect::<Vec<_>>();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+
+        assert_eq!(
+            tile_ids_in_bbox((-180.0, -90.0, 180.0, 90.0), 3).count(),
+            64
+        );
+
+        assert_eq!(
+            tile_ids_in_bbox((11.25, 43.77, 11.25, 43.77), 12).collect::<Vec<_>>(),
+            vec![tile_id(12, 2176, 1493)]
+        );
+
+        assert_eq!(tile_ids_in_bbox((170.0, -10.0, -170.0, 10.0), 4).count(), 0);
+        assert_eq!(tile_ids_in_bbox((0.0, 0.0, 1.0, 1.0), MAX_Z).count(), 0);
+    }
+
+    #[test]
+    fn test_tile_bounds() -> Result<(), MaxZError> {
+        let (min_lng, min_lat, max_lng, max_lat) = tile_bounds(0)?;
+        assert_eq!((min_lng, max_lng), (-180.0, 180.0));
+        assert!((min_lat + 85.051_128).abs() < 1e-6);
+        assert!((max_lat - 85.051_128).abs() < 1e-6);
+
+        assert_eq!(tile_bounds(tile_id(1, 0, 1))?, (-180.0, min_lat, 0.0, 0.0));
+
+        // the tile containing a position has to contain it within its bounds
+        let (min_lng, min_lat, max_lng, max_lat) = tile_bounds(tile_id(12, 2176, 1493))?;
+        assert!((min_lng..max_lng).contains(&11.25));
+        assert!((min_lat..max_lat).contains(&43.77));
+
+        assert!(tile_bounds(u64::MAX).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tms() {
+        assert_eq!(tms_to_xyz(0, 0, 0), Some((0, 0, 0)));
+        assert_eq!(tms_to_xyz(1, 1, 0), Some((1, 1, 1)));
+        assert_eq!(tms_to_xyz(12, 2176, 2602), Some((12, 2176, 1493)));
+        assert_eq!(xyz_to_tms(12, 2176, 1493), Some((12, 2176, 2602)));
+        assert_eq!(tms_to_xyz(31, 0, 0), Some((31, 0, (1 << 31) - 1)));
+
+        assert_eq!(tms_to_xyz(0, 0, 1), None);
+        assert_eq!(tms_to_xyz(1, 2, 0), None);
+        assert_eq!(xyz_to_tms(12, 0, 4096), None);
+        assert_eq!(tms_to_xyz(MAX_Z, 0, 0), None);
+        assert_eq!(tms_to_xyz(64, 0, 0), None);
+        assert_eq!(tms_to_xyz(u8::MAX, 0, 0), None);
+    }
+
+    #[test]
+    fn test_tile_id_ranges_in_bbox() {
+        for (bbox, z) in [
+            ((-180.0, -90.0, 180.0, 90.0), 0),
+            ((-180.0, -90.0, 180.0, 90.0), 4),
+            ((11.1, 43.7, 11.4, 43.9), 12),
+            ((-10.0, 35.0, 30.0, 60.0), 7),
+            ((0.0, 0.0, 0.0, 0.0), 9),
+        ] {
+            let mut expected = tile_ids_in_bbox(bbox, z).collect::<Vec<_>>();
+            expected.sort_unstable();
+
+            let ranges = tile_id_ranges_in_bbox(bbox, z);
+            assert!(ranges.len() <= expected.len());
+            assert_eq!(ranges.into_iter().flatten().collect::<Vec<_>>(), expected);
+        }
+
+        assert_eq!(
+            tile_id_ranges_in_bbox((-180.0, -90.0, 180.0, 90.0), 3),
+            vec![zoom_range(3)]
+        );
+        assert!(tile_id_ranges_in_bbox((10.0, 0.0, 0.0, 10.0), 3).is_empty());
+    }
+
     #[test]
     fn test_extremes() -> Result<(), MaxZError> {
         for z in 0u8..MAX_Z {
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+
+use serde_json::{json, Map as JSONMap, Value as JSONValue};
+
+use crate::{Error, Result};
+
+/// Minimal subset of the Mapbox Vector Tile protobuf schema, which is needed to determine the
+/// layers and attributes of a tile. Geometries are skipped while decoding.
+mod proto {
+    #[derive(Clone, PartialEq, Eq, prost::Message)]
+    pub struct Tile {
+        #[prost(message, repeated, tag = "3")]
+        pub layers: Vec<Layer>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, prost::Message)]
+    pub struct Layer {
+        #[prost(string, required, tag = "1")]
+        pub name: String,
+        #[prost(message, repeated, tag = "2")]
+        pub features: Vec<Feature>,
+        #[prost(string, repeated, tag = "3")]
+        pub keys: Vec<String>,
+        #[prost(message, repeated, tag = "4")]
+        pub values: Vec<Value>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, prost::Message)]
+    pub struct Feature {
+        #[prost(uint32, repeated, tag = "2")]
+        pub tags: Vec<u32>,
+    }
+
+    #[derive(Clone, PartialEq, Eq, prost::Message)]
+    pub struct Value {
+        #[prost(string, optional, tag = "1")]
+        pub string_value: Option<String>,
+        #[prost(bool, optional, tag = "7")]
+        pub bool_value: Option<bool>,
+    }
+}
+
+/// Type of an attribute of a vector layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldType {
+    String,
+    Number,
+    Boolean,
+    Mixed,
+}
+
+impl FieldType {
+    const fn of(value: &proto::Value) -> Self {
+        if value.string_value.is_some() {
+            Self::String
+        } else if value.bool_value.is_some() {
+            Self::Boolean
+        } else {
+            Self::Number
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        if self == other {
+            self
+        } else {
+            Self::Mixed
+        }
+    }
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::String => "String",
+            Self::Number => "Number",
+            Self::Boolean => "Boolean",
+            Self::Mixed => "Mixed",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LayerInfo {
+    fields: BTreeMap<String, FieldType>,
+    min_zoom: u8,
+    max_zoom: u8,
+}
+
+/// Collects the layers and attributes of Mapbox Vector Tiles to generate the `vector_layers` field of the meta data.
+///
+/// The `vector_layers` field is required by the `PMTiles` spec for archives of [`TileType::Mvt`](crate::TileType::Mvt).
+///
+/// See [`PMTiles::generate_vector_layers`](crate::PMTiles::generate_vector_layers) for generating
+/// the `vector_layers` of a whole archive.
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{util::{decompress_all, zxy, VectorLayers}, PMTiles};
+/// let bytes = include_bytes!("../../test/protomaps(vector)ODbL_firenze.pmtiles");
+/// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+///
+/// let mut vector_layers = VectorLayers::default();
+///
+/// for tile_id in pm_tiles.tile_ids_sorted() {
+///     let (z, _, _) = zxy(tile_id).unwrap();
+///     let tile = pm_tiles.get_tile_by_id(tile_id).unwrap().unwrap();
+///
+///     vector_layers.add_tile(z, &decompress_all(pm_tiles.tile_compression, &tile).unwrap()).unwrap();
+/// }
+///
+/// let json = vector_layers.to_json();
+/// assert!(!json.as_array().unwrap().is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VectorLayers {
+    layers: BTreeMap<String, LayerInfo>,
+}
+
+impl VectorLayers {
+    /// Adds the layers and attributes of a tile.
+    ///
+    /// # Arguments
+    /// * `z` - Zoom level of the tile
+    /// * `data` - Uncompressed tile data
+    ///
+    /// # Errors
+    /// Will return [`Err`] if `data` is no valid Mapbox Vector Tile.
+    pub fn add_tile(&mut self, z: u8, data: &[u8]) -> Result<()> {
+        let tile: proto::Tile =
+            prost::Message::decode(data).map_err(|err| Error::MvtDecode(err.to_string()))?;
+
+        for layer in tile.layers {
+            let info = self
+                .layers
+                .entry(layer.name.clone())
+                .or_insert_with(|| LayerInfo {
+                    fields: BTreeMap::new(),
+                    min_zoom: z,
+                    max_zoom: z,
+                });
+
+            info.min_zoom = info.min_zoom.min(z);
+            info.max_zoom = info.max_zoom.max(z);
+
+            for feature in &layer.features {
+                for tag in feature.tags.chunks_exact(2) {
+                    let (Some(key), Some(value)) = (
+                        layer.keys.get(tag[0] as usize),
+                        layer.values.get(tag[1] as usize),
+                    ) else {
+                        return Err(Error::MvtDecode(format!(
+                            "Feature of layer \"{}\" references a missing key or value",
+                            layer.name
+                        )));
+                    };
+
+                    let field_type = FieldType::of(value);
+                    info.fields
+                        .entry(key.clone())
+                        .and_modify(|t| *t = t.merge(field_type))
+                        .or_insert(field_type);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the collected layers as `vector_layers` array (sorted by layer id).
+    ///
+    /// Each layer has an `id`, `minzoom`, `maxzoom` and `fields`, which maps each attribute to
+    /// its type (`"String"`, `"Number"`, `"Boolean"` or `"Mixed"`, if values of different types were found).
+    pub fn to_json(&self) -> JSONValue {
+        self.layers
+            .iter()
+            .map(|(id, info)| {
+                let fields = info
+                    .fields
+                    .iter()
+                    .map(|(key, t)| (key.clone(), JSONValue::from(t.as_str())))
+                    .collect::<JSONMap<_, _>>();
+
+                json!({
+                    "id": id,
+                    "fields": fields,
+                    "minzoom": info.min_zoom,
+                    "maxzoom": info.max_zoom,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use prost::Message;
+
+    use super::*;
+
+    fn string_value(s: &str) -> proto::Value {
+        proto::Value {
+            string_value: Some(s.to_string()),
+            bool_value: None,
+        }
+    }
+
+    fn tile(name: &str, keys: &[&str], values: Vec<proto::Value>, tags: Vec<u32>) -> Vec<u8> {
+        proto::Tile {
+            layers: vec![proto::Layer {
+                name: name.to_string(),
+                features: vec![proto::Feature { tags }],
+                keys: keys.iter().map(ToString::to_string).collect(),
+                values,
+            }],
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_vector_layers() -> Result<()> {
+        let bool_value = proto::Value {
+            string_value: None,
+            bool_value: Some(true),
+        };
+
+        let mut vector_layers = VectorLayers::default();
+        vector_layers.add_tile(
+            4,
+            &tile(
+                "roads",
+                &["name", "oneway"],
+                vec![string_value("Main St"), bool_value],
+                vec![0, 0, 1, 1],
+            ),
+        )?;
+        vector_layers.add_tile(
+            2,
+            &tile("roads", &["oneway"], vec![string_value("yes")], vec![0, 0]),
+        )?;
+        vector_layers.add_tile(7, &tile("water", &[], vec![], vec![]))?;
+
+        assert_eq!(
+            vector_layers.to_json(),
+            json!([
+                {
+                    "id": "roads",
+                    "fields": { "name": "String", "oneway": "Mixed" },
+                    "minzoom": 2,
+                    "maxzoom": 4,
+                },
+                { "id": "water", "fields": {}, "minzoom": 7, "maxzoom": 7 },
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_layers_invalid() {
+        let mut vector_layers = VectorLayers::default();
+
+        assert!(matches!(
+            vector_layers.add_tile(0, &[0xff, 0xff]),
+            Err(Error::MvtDecode(_))
+        ));
+        assert!(matches!(
+            vector_layers.add_tile(0, &tile("roads", &["name"], vec![], vec![0, 0])),
+            Err(Error::MvtDecode(_))
+        ));
+    }
+}
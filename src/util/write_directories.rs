@@ -1,11 +1,19 @@
 use duplicate::duplicate_item;
 #[cfg(feature = "async")]
 use futures::{AsyncSeekExt, AsyncWrite};
-use std::io::{Cursor, Result, Seek, Write};
+use std::io::{Seek, Write};
 
-use crate::{header::HEADER_BYTES, Compression, Directory, Entry};
+use super::{zoom_range, Codec};
+use crate::{header::HEADER_BYTES, Compression, Directory, Entry, Error, Result};
 
-const MAX_ROOT_DIR_LENGTH: u16 = 16384 - HEADER_BYTES as u16;
+/// Default maximum length (in bytes) of the root directory, so header and root directory fit into the first 16 KiB.
+pub const DEFAULT_MAX_ROOT_DIR_LENGTH: u32 = 16384 - HEADER_BYTES as u32;
+
+/// Default target size (in bytes) of leaf directories for [`WriteDirsOverflowStrategy::BalancedLeafSize`].
+const DEFAULT_LEAF_TARGET_SIZE: usize = 1024 * 1024;
+
+/// Maximum number of attempts to find the number of entries of a leaf directory, which matches the target size.
+const MAX_LEAF_SIZE_ITERATIONS: usize = 8;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
@@ -20,6 +28,32 @@ pub enum WriteDirsOverflowStrategy {
         /// The start size of the leaf directories (default 4096)
         start_size: Option<usize>,
     },
+
+    /// Keep entries of low zoom levels in the root directory and move only the remaining
+    /// entries to leaf directories.
+    ///
+    /// This way the most frequently requested tiles can be resolved by reading the root directory only.
+    /// If the root directory does not fit into its max size, `max_root_zoom` is decreased until it does
+    /// (falling back to [`OnlyLeafPointers`](Self::OnlyLeafPointers)).
+    RootPlusLeaves {
+        /// The maximum zoom level of entries kept in the root directory (default 6)
+        max_root_zoom: Option<u8>,
+
+        /// The start size of the leaf directories (default 4096)
+        start_size: Option<usize>,
+    },
+
+    /// Move all entries to leaf directories, which are split by their size (in bytes, after compression)
+    /// instead of their number of entries.
+    ///
+    /// This results in leaf directories of about the same size, so each leaf directory can be fetched
+    /// with a single request of a size, which is optimal for the reader (e.g. for remote storage).
+    ///
+    /// Will double the target size until the root directory fits into its max size.
+    BalancedLeafSize {
+        /// The target size (in bytes) of leaf directories (default 1 MiB)
+        target_size: Option<usize>,
+    },
 }
 
 impl Default for WriteDirsOverflowStrategy {
@@ -29,39 +63,106 @@ impl Default for WriteDirsOverflowStrategy {
 }
 
 #[duplicate_item(
-    fn_name                        async   cfg_async_filter       SeekFrom                input_traits                                      add_await(code) write_directory(directory, output, compression)        only_leaf_pointer_strategy;
-    [write_directories_impl]       []      [cfg(all())]           [std::io::SeekFrom]     [(impl Write + Seek)]                             [code]          [directory.to_writer(output, compression)]             [only_leaf_pointer_strategy];
-    [write_directories_impl_async] [async] [cfg(feature="async")] [futures::io::SeekFrom] [(impl AsyncWrite + Unpin + Send + AsyncSeekExt)] [code.await]    [directory.to_async_writer(output, compression).await] [only_leaf_pointer_strategy_async];
+    fn_name                        async   cfg_async_filter       SeekFrom                input_traits                                      add_await(code) write_directory(directory, output, compression, codec)                      leaf_pointer_strategy         write_root_and_leaves;
+    [write_directories_impl]       []      [cfg(all())]           [std::io::SeekFrom]     [(impl Write + Seek)]                             [code]          [directory.to_writer_impl(output, compression, codec)]                      [leaf_pointer_strategy]       [write_root_and_leaves];
+    [write_directories_impl_async] [async] [cfg(feature="async")] [futures::io::SeekFrom] [(impl AsyncWrite + Unpin + Send + AsyncSeekExt)] [code.await]    [directory.to_async_writer_impl(output, compression, codec).await]          [leaf_pointer_strategy_async] [write_root_and_leaves_async];
 )]
 #[cfg_async_filter]
-async fn fn_name(
+#[allow(clippy::redundant_pub_crate)]
+pub(crate) async fn fn_name(
     output: &mut input_traits,
     all_entries: &[Entry],
     compression: Compression,
+    codec: Option<&dyn Codec>,
     overflow_strategy: Option<WriteDirsOverflowStrategy>,
+    max_root_dir_length: Option<u32>,
 ) -> Result<Vec<u8>> {
+    let max_root_dir_length = max_root_dir_length.unwrap_or(DEFAULT_MAX_ROOT_DIR_LENGTH);
     let start_pos = add_await([output.stream_position()])?;
 
     {
         let root_directory = Directory::from(all_entries.to_vec());
-        write_directory([root_directory], [output], [compression])?;
+        write_directory([root_directory], [output], [compression], [codec])?;
     }
 
     let root_directory_length = add_await([output.stream_position()])? - start_pos;
 
-    if root_directory_length <= u64::from(MAX_ROOT_DIR_LENGTH) {
+    if root_directory_length <= u64::from(max_root_dir_length) {
         return Ok(Vec::new());
     }
 
     match overflow_strategy.unwrap_or_default() {
         WriteDirsOverflowStrategy::OnlyLeafPointers { start_size } => {
-            add_await([only_leaf_pointer_strategy(
+            let leaf_dir_bytes = add_await([leaf_pointer_strategy(
                 output,
                 SeekFrom::Start(start_pos),
+                &[],
                 all_entries,
                 compression,
+                codec,
                 start_size,
-            )])
+                max_root_dir_length,
+            )])?;
+
+            // without further root entries, this only returns if the root directory fits
+            Ok(leaf_dir_bytes.unwrap_or_default())
+        }
+        WriteDirsOverflowStrategy::RootPlusLeaves {
+            max_root_zoom,
+            start_size,
+        } => {
+            let mut max_root_zoom = Some(max_root_zoom.unwrap_or(6).min(31));
+
+            loop {
+                let split = max_root_zoom.map_or(0, |z| {
+                    let end = zoom_range(z).end;
+                    all_entries.partition_point(|entry| entry.tile_id < end)
+                });
+
+                let leaf_dir_bytes = add_await([leaf_pointer_strategy(
+                    output,
+                    SeekFrom::Start(start_pos),
+                    &all_entries[..split],
+                    &all_entries[split..],
+                    compression,
+                    codec,
+                    start_size,
+                    max_root_dir_length,
+                )])?;
+
+                if let Some(leaf_dir_bytes) = leaf_dir_bytes {
+                    return Ok(leaf_dir_bytes);
+                }
+
+                max_root_zoom = max_root_zoom.and_then(|z| z.checked_sub(1));
+            }
+        }
+        WriteDirsOverflowStrategy::BalancedLeafSize { target_size } => {
+            let mut target_size = target_size.unwrap_or(DEFAULT_LEAF_TARGET_SIZE).max(1);
+
+            loop {
+                let leaves = leaves_by_size(all_entries, target_size, compression, codec)?;
+                let num_leaf_dirs = leaves.len();
+
+                let leaf_dir_bytes = add_await([write_root_and_leaves(
+                    output,
+                    SeekFrom::Start(start_pos),
+                    &[],
+                    leaves,
+                    compression,
+                    codec,
+                    max_root_dir_length,
+                )])?;
+
+                if let Some(leaf_dir_bytes) = leaf_dir_bytes {
+                    return Ok(leaf_dir_bytes);
+                }
+                if num_leaf_dirs <= 1 {
+                    return Err(root_dir_too_small(max_root_dir_length));
+                }
+
+                target_size = target_size.saturating_mul(2);
+            }
         }
     }
 }
@@ -72,20 +173,31 @@ async fn fn_name(
 /// * `output` - Writer to write root directory to
 /// * `all_entries` - All tile entries
 /// * `compression` - Compression of directories
-/// * `overflow_strategy` - Strategy to use, when root directory does not fit in the first 16kB.
-///                         If [`None`] is passed, the best strategy is chosen automatically.
+/// * `overflow_strategy` - Strategy to use, when root directory exceeds `max_root_dir_length`.
+///   If [`None`] is passed, the best strategy is chosen automatically.
+/// * `max_root_dir_length` - Maximum length (in bytes) of the root directory
+///   (default [`DEFAULT_MAX_ROOT_DIR_LENGTH`], so header and root directory fit in the first 16kB)
 ///
 /// # Errors
-/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`] or an I/O error
-/// occurred while writing to `output`.
+/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`], an I/O error
+/// occurred while writing to `output` or [`Error::Validation`] if `max_root_dir_length` is too
+/// small to hold a single pointer to a leaf directory.
 ///
 pub fn write_directories(
     output: &mut (impl Write + Seek),
     all_entries: &[Entry],
     compression: Compression,
     overflow_strategy: Option<WriteDirsOverflowStrategy>,
+    max_root_dir_length: Option<u32>,
 ) -> Result<Vec<u8>> {
-    write_directories_impl(output, all_entries, compression, overflow_strategy)
+    write_directories_impl(
+        output,
+        all_entries,
+        compression,
+        None,
+        overflow_strategy,
+        max_root_dir_length,
+    )
 }
 
 /// Async version of [`write_directories`](write_directories).
@@ -96,12 +208,15 @@ pub fn write_directories(
 /// * `output` - Writer to write root directory to
 /// * `all_entries` - All tile entries
 /// * `compression` - Compression of directories
-/// * `overflow_strategy` - Strategy to use, when root directory does not fit in the first 16kB.
-///                         If [`None`] is passed, the best strategy is chosen automatically.
+/// * `overflow_strategy` - Strategy to use, when root directory exceeds `max_root_dir_length`.
+///   If [`None`] is passed, the best strategy is chosen automatically.
+/// * `max_root_dir_length` - Maximum length (in bytes) of the root directory
+///   (default [`DEFAULT_MAX_ROOT_DIR_LENGTH`], so header and root directory fit in the first 16kB)
 ///
 /// # Errors
-/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`] or an I/O error
-/// occurred while writing to `output`.
+/// Will return [`Err`] if `compression` is set to [`Compression::Unknown`], an I/O error
+/// occurred while writing to `output` or [`Error::Validation`] if `max_root_dir_length` is too
+/// small to hold a single pointer to a leaf directory.
 ///
 #[allow(clippy::module_name_repetitions)]
 #[cfg(feature = "async")]
@@ -110,60 +225,418 @@ pub async fn write_directories_async(
     all_entries: &[Entry],
     compression: Compression,
     overflow_strategy: Option<WriteDirsOverflowStrategy>,
+    max_root_dir_length: Option<u32>,
+) -> Result<Vec<u8>> {
+    write_directories_impl_async(
+        output,
+        all_entries,
+        compression,
+        None,
+        overflow_strategy,
+        max_root_dir_length,
+    )
+    .await
+}
+
+/// Builds leaf directories with (up to) `leaf_size` entries each.
+///
+/// Returns the id of the first tile and the bytes of each leaf directory.
+fn leaves_by_count(
+    entries: &[Entry],
+    leaf_size: usize,
+    compression: Compression,
+    codec: Option<&dyn Codec>,
+) -> Result<Vec<(u64, Vec<u8>)>> {
+    entries
+        .chunks(leaf_size)
+        .map(|entries| {
+            Ok((
+                entries[0].tile_id,
+                leaf_directory_bytes(entries, compression, codec)?,
+            ))
+        })
+        .collect()
+}
+
+/// Builds leaf directories, whose size (in bytes, after compression) is close to, but not
+/// larger than `target_size`, unless a leaf directory consists of a single entry.
+///
+/// Returns the id of the first tile and the bytes of each leaf directory.
+fn leaves_by_size(
+    entries: &[Entry],
+    target_size: usize,
+    compression: Compression,
+    codec: Option<&dyn Codec>,
+) -> Result<Vec<(u64, Vec<u8>)>> {
+    let mut leaves = Vec::new();
+
+    // number of entries per leaf, estimated from the previous leaf
+    let mut estimate = 1024;
+    let mut start = 0;
+
+    while start < entries.len() {
+        let remaining = entries.len() - start;
+
+        // largest number of entries known to fit and smallest number known to exceed the target size
+        let mut fits = (0, Vec::new());
+        let mut exceeds = remaining + 1;
+
+        let mut num = estimate.clamp(1, remaining);
+
+        for _ in 0..MAX_LEAF_SIZE_ITERATIONS {
+            let bytes = leaf_directory_bytes(&entries[start..start + num], compression, codec)?;
+            let len = bytes.len().max(1);
+
+            if len <= target_size {
+                fits = (num, bytes);
+                if num == remaining || len >= target_size / 10 * 9 {
+                    break;
+                }
+            } else {
+                exceeds = num;
+            }
+
+            if fits.0 + 1 >= exceeds {
+                break;
+            }
+
+            // estimate the number of entries from the size of the last try, as compressed sizes are not linear
+            num = (num.saturating_mul(target_size) / len).clamp(fits.0 + 1, exceeds - 1);
+        }
+
+        // a leaf directory consists of at least one entry, even if it exceeds the target size
+        let (num, bytes) = if fits.0 == 0 {
+            (
+                1,
+                leaf_directory_bytes(&entries[start..=start], compression, codec)?,
+            )
+        } else {
+            fits
+        };
+
+        leaves.push((entries[start].tile_id, bytes));
+        estimate = num;
+        start += num;
+    }
+
+    Ok(leaves)
+}
+
+/// Returns the error for a maximum root directory length, which cannot hold a single leaf pointer.
+fn root_dir_too_small(max_root_dir_length: u32) -> Error {
+    Error::Validation(format!(
+        "maximum root directory length of {max_root_dir_length} bytes is too small for a pointer to a leaf directory"
+    ))
+}
+
+fn leaf_directory_bytes(
+    entries: &[Entry],
+    compression: Compression,
+    codec: Option<&dyn Codec>,
 ) -> Result<Vec<u8>> {
-    write_directories_impl_async(output, all_entries, compression, overflow_strategy).await
+    let mut bytes = Vec::new();
+    Directory::from(entries.to_vec()).to_writer_impl(&mut bytes, compression, codec)?;
+
+    Ok(bytes)
 }
 
 #[duplicate_item(
-    fn_name                            cfg_async_filter       async   SeekFrom                input_traits                                      add_await(code) write_directory(directory, output, compression);
-    [only_leaf_pointer_strategy]       [cfg(all())]           []      [std::io::SeekFrom]     [(impl Write + Seek)]                             [code]          [directory.to_writer(output, compression)];
-    [only_leaf_pointer_strategy_async] [cfg(feature="async")] [async] [futures::io::SeekFrom] [(impl AsyncWrite + Unpin + Send + AsyncSeekExt)] [code.await]    [directory.to_async_writer(output, compression).await];
+    fn_name                       cfg_async_filter       async   SeekFrom                input_traits                                      add_await(code) write_directory(directory, output, compression, codec);
+    [write_root_and_leaves]       [cfg(all())]           []      [std::io::SeekFrom]     [(impl Write + Seek)]                             [code]          [directory.to_writer_impl(output, compression, codec)];
+    [write_root_and_leaves_async] [cfg(feature="async")] [async] [futures::io::SeekFrom] [(impl AsyncWrite + Unpin + Send + AsyncSeekExt)] [code.await]    [directory.to_async_writer_impl(output, compression, codec).await];
 )]
 #[cfg_async_filter]
+/// Writes a root directory consisting of `root_entries` followed by pointers to `leaves`.
+///
+/// Returns the bytes of the leaf directories section, or [`None`] if the root directory does not fit.
 async fn fn_name(
     output: &mut input_traits,
     root_dir_start: SeekFrom,
-    all_entries: &[Entry],
+    root_entries: &[Entry],
+    leaves: Vec<(u64, Vec<u8>)>,
     compression: Compression,
+    codec: Option<&dyn Codec>,
+    max_root_dir_length: u32,
+) -> Result<Option<Vec<u8>>> {
+    let mut root_dir_entries = root_entries.to_vec();
+    let mut leaf_dir_bytes = Vec::<u8>::new();
+
+    for (tile_id, mut bytes) in leaves {
+        #[allow(clippy::cast_possible_truncation)]
+        root_dir_entries.push(Entry {
+            tile_id,
+            length: bytes.len() as u32,
+            offset: leaf_dir_bytes.len() as u64,
+            run_length: 0,
+        });
+        leaf_dir_bytes.append(&mut bytes);
+    }
+
+    let root_directory = Directory::from(root_dir_entries);
+
+    let start_pos = add_await([output.seek(root_dir_start)])?;
+    write_directory([root_directory], [output], [compression], [codec])?;
+    let root_directory_length = add_await([output.stream_position()])? - start_pos;
+
+    if root_directory_length <= u64::from(max_root_dir_length) {
+        Ok(Some(leaf_dir_bytes))
+    } else {
+        Ok(None)
+    }
+}
+
+#[duplicate_item(
+    fn_name                       cfg_async_filter       async   SeekFrom                input_traits                                      add_await(code) write_root_and_leaves;
+    [leaf_pointer_strategy]       [cfg(all())]           []      [std::io::SeekFrom]     [(impl Write + Seek)]                             [code]          [write_root_and_leaves];
+    [leaf_pointer_strategy_async] [cfg(feature="async")] [async] [futures::io::SeekFrom] [(impl AsyncWrite + Unpin + Send + AsyncSeekExt)] [code.await]    [write_root_and_leaves_async];
+)]
+#[cfg_async_filter]
+/// Writes a root directory consisting of `root_entries` followed by pointers to leaf directories,
+/// which contain `leaf_entries`. The size of leaf directories is doubled until the root directory fits.
+///
+/// Returns [`None`] if the root directory does not fit, even with a single leaf directory.
+///
+/// # Errors
+/// Will return [`Error::Validation`] if `root_entries` is empty and not even a single pointer
+/// to a leaf directory fits into `max_root_dir_length`.
+#[allow(clippy::too_many_arguments)]
+async fn fn_name(
+    output: &mut input_traits,
+    root_dir_start: SeekFrom,
+    root_entries: &[Entry],
+    leaf_entries: &[Entry],
+    compression: Compression,
+    codec: Option<&dyn Codec>,
     start_size: Option<usize>,
-) -> Result<Vec<u8>> {
-    let mut leaf_size = start_size.unwrap_or(4096);
+    max_root_dir_length: u32,
+) -> Result<Option<Vec<u8>>> {
+    let mut leaf_size = start_size.unwrap_or(4096).max(1);
 
     loop {
-        let mut root_entries = Vec::<Entry>::new();
+        let leaves = leaves_by_count(leaf_entries, leaf_size, compression, codec)?;
+        let num_leaf_dirs = leaves.len();
 
-        let mut leaf_dir_bytes = Vec::<u8>::new();
-        let mut leaf_dir_writer = Cursor::new(&mut leaf_dir_bytes);
+        let leaf_dir_bytes = add_await([write_root_and_leaves(
+            output,
+            root_dir_start,
+            root_entries,
+            leaves,
+            compression,
+            codec,
+            max_root_dir_length,
+        )])?;
 
-        for entries in all_entries.chunks(leaf_size) {
-            if entries.is_empty() {
-                continue;
-            }
-
-            let leaf_dir = Directory::from(entries.to_vec());
-            let offset = leaf_dir_writer.stream_position()?;
-            leaf_dir.to_writer(&mut leaf_dir_writer, compression)?;
-            #[allow(clippy::cast_possible_truncation)]
-            let length = (leaf_dir_writer.stream_position()? - offset) as u32;
-
-            root_entries.push(Entry {
-                tile_id: entries[0].tile_id,
-                length,
-                offset,
-                run_length: 0,
-            });
+        if leaf_dir_bytes.is_some() || (num_leaf_dirs <= 1 && !root_entries.is_empty()) {
+            return Ok(leaf_dir_bytes);
+        }
+        if num_leaf_dirs <= 1 {
+            return Err(root_dir_too_small(max_root_dir_length));
         }
 
-        let root_directory = Directory::from(root_entries);
+        leaf_size = leaf_size.saturating_mul(2);
+    }
+}
 
-        let start_pos = add_await([output.seek(root_dir_start)])?;
-        write_directory([root_directory], [output], [compression])?;
-        let root_directory_length = add_await([output.stream_position()])? - start_pos;
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::io::Cursor;
 
-        if root_directory_length <= u64::from(MAX_ROOT_DIR_LENGTH) {
-            return Ok(leaf_dir_bytes);
+    use super::*;
+    use crate::util::{read_entries, tile_id};
+
+    /// Writes `entries` with `strategy` and returns the root directory and the expanded entries read back.
+    fn write_and_read(
+        entries: &[Entry],
+        strategy: WriteDirsOverflowStrategy,
+    ) -> Result<(Directory, Directory)> {
+        let mut output = Cursor::new(Vec::<u8>::new());
+        let leaf_dir_bytes = write_directories(
+            &mut output,
+            entries,
+            Compression::None,
+            Some(strategy),
+            None,
+        )?;
+
+        let root_directory_length = output.position();
+        let mut bytes = output.into_inner();
+        #[allow(clippy::cast_possible_truncation)]
+        bytes.truncate(root_directory_length as usize);
+        bytes.extend_from_slice(&leaf_dir_bytes);
+
+        let root_directory = Directory::from_reader(
+            &mut Cursor::new(&bytes),
+            root_directory_length,
+            Compression::None,
+        )?;
+        let read_entries = read_entries(
+            &mut Cursor::new(&bytes),
+            Compression::None,
+            (0, root_directory_length),
+            root_directory_length,
+            ..,
+        )?;
+
+        assert!(root_directory_length <= u64::from(DEFAULT_MAX_ROOT_DIR_LENGTH));
+
+        Ok((root_directory, read_entries))
+    }
+
+    fn entries(num: u32) -> Vec<Entry> {
+        (0..num)
+            .map(|i| Entry {
+                tile_id: u64::from(i) * 2,
+                offset: u64::from(i) * 100 + u64::from(i % 7),
+                length: 90 + i % 7,
+                run_length: 1,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_root_plus_leaves() -> Result<()> {
+        let entries = entries(40_000);
+
+        let (root, read) = write_and_read(
+            &entries,
+            WriteDirsOverflowStrategy::RootPlusLeaves {
+                max_root_zoom: Some(5),
+                start_size: None,
+            },
+        )?;
+        assert_eq!(read, Directory::from(entries.clone()));
+
+        // entries of zoom levels 0 - 5 are kept in the root directory
+        let root_tile_entries = root.tile_entries().map(|e| e.tile_id).collect::<Vec<_>>();
+        let expected = entries
+            .iter()
+            .map(|e| e.tile_id)
+            .take_while(|id| *id < tile_id(6, 0, 0))
+            .collect::<Vec<_>>();
+        assert_eq!(root_tile_entries, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_root_plus_leaves_fallback() -> Result<()> {
+        let entries = entries(40_000);
+
+        // all entries are within zoom levels 0 - 8, but do not fit into the root directory
+        let (root, read) = write_and_read(
+            &entries,
+            WriteDirsOverflowStrategy::RootPlusLeaves {
+                max_root_zoom: Some(31),
+                start_size: Some(100),
+            },
+        )?;
+        assert_eq!(read, Directory::from(entries));
+        assert!(root.into_iter().any(Entry::is_leaf_dir_entry));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_only_leaf_pointers() -> Result<()> {
+        let entries = entries(40_000);
+
+        let (root, read) = write_and_read(&entries, WriteDirsOverflowStrategy::default())?;
+        assert_eq!(read, Directory::from(entries));
+        assert!(root.into_iter().all(Entry::is_leaf_dir_entry));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_balanced_leaf_size() -> Result<()> {
+        let entries = entries(40_000);
+        let target_size = 16 * 1024;
+
+        let (root, read) = write_and_read(
+            &entries,
+            WriteDirsOverflowStrategy::BalancedLeafSize {
+                target_size: Some(target_size),
+            },
+        )?;
+        assert_eq!(read, Directory::from(entries));
+
+        let leaf_lengths = root
+            .into_iter()
+            .map(|e| {
+                assert!(e.is_leaf_dir_entry());
+                e.length as usize
+            })
+            .collect::<Vec<_>>();
+
+        // all leaf directories (except the last one) are close to the target size
+        let (last, leaf_lengths) = leaf_lengths.split_last().unwrap();
+        assert!(*last <= target_size);
+        for length in leaf_lengths {
+            assert!(*length <= target_size);
+            assert!(*length >= target_size * 3 / 4);
         }
 
-        leaf_size *= 2;
+        Ok(())
+    }
+
+    #[test]
+    fn test_leaves_by_size_single_entry() -> Result<()> {
+        let entries = entries(10);
+
+        // leaf directories may exceed the target size, if they consist of a single entry
+        let leaves = leaves_by_size(&entries, 1, Compression::None, None)?;
+        assert_eq!(leaves.len(), 10);
+        assert!(leaves
+            .iter()
+            .zip(&entries)
+            .all(|((tile_id, _), entry)| *tile_id == entry.tile_id));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_root_dir_length() -> Result<()> {
+        let entries = entries(1000);
+
+        let mut output = Cursor::new(Vec::<u8>::new());
+        let leaf_dir_bytes =
+            write_directories(&mut output, &entries, Compression::None, None, Some(128))?;
+        assert!(output.position() <= 128);
+        assert!(!leaf_dir_bytes.is_empty());
+
+        // the same entries fit into the root directory with the default max length
+        let mut output = Cursor::new(Vec::<u8>::new());
+        let leaf_dir_bytes =
+            write_directories(&mut output, &entries, Compression::None, None, None)?;
+        assert!(leaf_dir_bytes.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_root_dir_length_too_small() {
+        let entries = entries(1000);
+
+        for strategy in [
+            WriteDirsOverflowStrategy::OnlyLeafPointers { start_size: None },
+            WriteDirsOverflowStrategy::RootPlusLeaves {
+                max_root_zoom: None,
+                start_size: Some(1),
+            },
+            WriteDirsOverflowStrategy::BalancedLeafSize {
+                target_size: Some(1),
+            },
+        ] {
+            // not even a single pointer to a leaf directory fits into 3 bytes
+            let result = write_directories(
+                &mut Cursor::new(Vec::<u8>::new()),
+                &entries,
+                Compression::None,
+                Some(strategy),
+                Some(3),
+            );
+            assert!(matches!(result, Err(Error::Validation(_))), "{strategy:?}");
+        }
     }
 }
@@ -0,0 +1,88 @@
+use std::io::{Read, Write};
+
+use zstd::{dict::from_samples, zstd_safe::get_dict_id_from_dict, Decoder, Encoder};
+
+use crate::Result;
+
+/// Key of the meta data entry, which holds the id of the ZSTD dictionary tiles were compressed with.
+///
+/// See [`PMTiles::compress_tiles_with_zstd_dictionary`](crate::PMTiles::compress_tiles_with_zstd_dictionary).
+pub const ZSTD_DICTIONARY_ID_KEY: &str = "zstd_dictionary_id";
+
+/// Trains a ZSTD dictionary from sample data (e.g. uncompressed tiles).
+///
+/// Small tiles (like Mapbox Vector Tiles) share a lot of structure (layer names, attribute keys, ...),
+/// which can not be exploited when compressing each tile on its own. Compressing them with a shared
+/// dictionary ([`compress_all_with_dictionary`]) results in much smaller tiles.
+///
+/// # Arguments
+/// * `samples` - Uncompressed sample data; should consist of at least a few hundred samples
+/// * `max_size` - Maximum size (in bytes) of the dictionary (`112_640` is a reasonable default)
+///
+/// # Errors
+/// Will return [`Err`] if training failed (e.g. there are too few samples).
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::util::{compress_all_with_dictionary, decompress_all_with_dictionary, train_zstd_dictionary};
+/// let samples = (0..1000u32)
+///     .map(|i| format!("{{\"layer\": \"roads\", \"name\": \"Road {i}\", \"id\": {}}}", i * 7).into_bytes())
+///     .collect::<Vec<_>>();
+///
+/// let dictionary = train_zstd_dictionary(&samples, 4096).unwrap();
+///
+/// let compressed = compress_all_with_dictionary(&samples[42], &dictionary, None).unwrap();
+/// assert!(compressed.len() < samples[42].len());
+/// assert_eq!(decompress_all_with_dictionary(&compressed, &dictionary).unwrap(), samples[42]);
+/// ```
+pub fn train_zstd_dictionary(samples: &[impl AsRef<[u8]>], max_size: usize) -> Result<Vec<u8>> {
+    Ok(from_samples(samples, max_size)?)
+}
+
+/// Returns the id of a ZSTD dictionary or [`None`] if `dictionary` has no id (e.g. it is no trained dictionary).
+pub fn zstd_dictionary_id(dictionary: &[u8]) -> Option<u32> {
+    Some(get_dict_id_from_dict(dictionary)).filter(|id| *id != 0)
+}
+
+/// Compresses `data` with ZSTD using a dictionary (see [`train_zstd_dictionary`]).
+///
+/// The data can only be decompressed with the same dictionary ([`decompress_all_with_dictionary`]).
+///
+/// # Arguments
+/// * `data` - Data to compress
+/// * `dictionary` - ZSTD dictionary
+/// * `level` - Compression level (`1..=22`); if [`None`] the default level is used
+///
+/// # Errors
+/// Will return [`Err`] if `dictionary` is invalid or an error occurred while compressing.
+pub fn compress_all_with_dictionary(
+    data: &[u8],
+    dictionary: &[u8],
+    level: Option<u32>,
+) -> Result<Vec<u8>> {
+    #[allow(clippy::cast_possible_wrap)]
+    let level = level.map_or(0, |l| l.clamp(1, 22) as i32);
+
+    let mut encoder = Encoder::with_dictionary(Vec::new(), level, dictionary)?;
+    encoder.write_all(data)?;
+
+    Ok(encoder.finish()?)
+}
+
+/// Decompresses data, which was compressed with ZSTD using a dictionary ([`compress_all_with_dictionary`]).
+///
+/// # Arguments
+/// * `data` - Compressed data
+/// * `dictionary` - ZSTD dictionary, which was used to compress `data`
+///
+/// # Errors
+/// Will return [`Err`] if `data` was compressed with another dictionary, `data` is no valid ZSTD
+/// data or an error occurred while decompressing.
+pub fn decompress_all_with_dictionary(data: &[u8], dictionary: &[u8]) -> Result<Vec<u8>> {
+    let mut decoder = Decoder::with_dictionary(data, dictionary)?;
+
+    let mut destination = Vec::new();
+    decoder.read_to_end(&mut destination)?;
+
+    Ok(destination)
+}
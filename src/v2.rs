@@ -0,0 +1,641 @@
+//! Reading archives of version 2 of the specification, which stores meta data as uncompressed JSON
+//! and addresses tiles by z/x/y in fixed size directory entries.
+//!
+//! See <https://github.com/protomaps/PMTiles/blob/main/spec/v2/spec.md> for details.
+
+#[cfg(feature = "async")]
+use futures::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite};
+use std::io::{Read, Seek, Write};
+
+use duplicate::duplicate_item;
+use serde_json::{Map as JSONMap, Value as JSONValue};
+
+use crate::{
+    header::is_v2_header,
+    read_limits::check_limit,
+    util::{bounds_to_range, detect_compression, tile_id, tile_zoom},
+    CancellationToken, Compression, Entry, Error, PMTiles, ReadLimits, ReadProgress, Result,
+    TileType,
+};
+
+/// Length of the header (magic, version, length of the meta data and number of root entries).
+const HEADER_BYTES: usize = 10;
+
+/// Length of a single directory entry.
+const ENTRY_BYTES: usize = 17;
+
+/// Bit of the zoom level, which marks entries of leaf directories.
+const LEAF_DIRECTORY_BIT: u8 = 0x80;
+
+/// Meta data keys, which are moved into fields of [`PMTiles`].
+const HEADER_KEYS: [&str; 7] = [
+    "format",
+    "compression",
+    "minzoom",
+    "maxzoom",
+    "bounds",
+    "center",
+    "json",
+];
+
+/// Contents of an archive of version 2.
+#[derive(Debug)]
+pub struct V2Archive {
+    /// Meta data as stored in the archive
+    pub meta_data: JSONMap<String, JSONValue>,
+
+    /// Tile entries with absolute offsets, sorted by tile id
+    pub entries: Vec<Entry>,
+
+    /// Compression of tiles, if it is not stated in the meta data
+    pub detected_compression: Compression,
+}
+
+impl V2Archive {
+    /// Sets all fields of `pm_tiles`, which are stored in the meta data of version 2 archives.
+    ///
+    /// Keys, which correspond to fields of the version 3 header, are removed from the meta data,
+    /// and the `json` key (holding e.g. `vector_layers`) is merged into it.
+    pub fn apply_meta_data<R>(mut self, pm_tiles: &mut PMTiles<R>) {
+        let meta_data = &mut self.meta_data;
+
+        pm_tiles.tile_type = meta_data
+            .get("format")
+            .and_then(JSONValue::as_str)
+            .map_or(TileType::Unknown, TileType::from_format);
+
+        pm_tiles.tile_compression = match meta_data.get("compression").and_then(JSONValue::as_str) {
+            Some("none") => Compression::None,
+            Some("gzip") => Compression::GZip,
+            Some("br" | "brotli") => Compression::Brotli,
+            Some("zstd") => Compression::ZStd,
+            Some(_) => Compression::Unknown,
+            None => self.detected_compression,
+        };
+
+        let zooms = self
+            .entries
+            .iter()
+            .filter_map(|entry| tile_zoom(entry.tile_id));
+        pm_tiles.min_zoom = zoom(meta_data.get("minzoom"))
+            .or_else(|| zooms.clone().min())
+            .unwrap_or(0);
+        pm_tiles.max_zoom = zoom(meta_data.get("maxzoom"))
+            .or_else(|| zooms.max())
+            .unwrap_or(0);
+
+        let bounds = numbers(meta_data.get("bounds"));
+        if let [min_longitude, min_latitude, max_longitude, max_latitude] = bounds[..] {
+            pm_tiles.min_longitude = min_longitude;
+            pm_tiles.min_latitude = min_latitude;
+            pm_tiles.max_longitude = max_longitude;
+            pm_tiles.max_latitude = max_latitude;
+        } else {
+            pm_tiles.min_longitude = -180.0;
+            pm_tiles.min_latitude = -85.0;
+            pm_tiles.max_longitude = 180.0;
+            pm_tiles.max_latitude = 85.0;
+        }
+
+        let center = numbers(meta_data.get("center"));
+        if let [longitude, latitude, ..] = center[..] {
+            pm_tiles.center_longitude = longitude;
+            pm_tiles.center_latitude = latitude;
+        } else {
+            pm_tiles.center_longitude =
+                f64::midpoint(pm_tiles.min_longitude, pm_tiles.max_longitude);
+            pm_tiles.center_latitude = f64::midpoint(pm_tiles.min_latitude, pm_tiles.max_latitude);
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let center_zoom = center
+            .get(2)
+            .filter(|zoom| (0.0..=f64::from(u8::MAX)).contains(*zoom))
+            .map(|zoom| *zoom as u8);
+        pm_tiles.center_zoom = center_zoom.unwrap_or(pm_tiles.min_zoom);
+
+        // vector layers are stored as stringified JSON
+        let json = meta_data.get("json").and_then(JSONValue::as_str);
+        if let Some(JSONValue::Object(json)) = json.and_then(|json| serde_json::from_str(json).ok())
+        {
+            meta_data.extend(json);
+        }
+
+        for key in HEADER_KEYS {
+            meta_data.remove(key);
+        }
+
+        pm_tiles.meta_data = self.meta_data;
+    }
+}
+
+/// Returns a zoom level, which is stored as a number or a string.
+fn zoom(value: Option<&JSONValue>) -> Option<u8> {
+    match value? {
+        JSONValue::Number(number) => u8::try_from(number.as_u64()?).ok(),
+        JSONValue::String(string) => string.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Returns a list of numbers, which is stored as an array or as a comma separated string.
+fn numbers(value: Option<&JSONValue>) -> Vec<f64> {
+    match value {
+        Some(JSONValue::Array(values)) => values.iter().filter_map(JSONValue::as_f64).collect(),
+        Some(JSONValue::String(string)) => string
+            .split(',')
+            .map_while(|number| number.trim().parse().ok())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns the length of the meta data and the number of entries of the root directory.
+fn parse_header(buf: &[u8; HEADER_BYTES]) -> (u64, usize) {
+    let meta_data_length = u32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]);
+    let root_entries = u16::from_le_bytes([buf[8], buf[9]]);
+
+    (meta_data_length.into(), root_entries.into())
+}
+
+/// Returns the value of a little endian unsigned integer of up to 8 bytes.
+fn parse_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |value, byte| (value << 8) | u64::from(*byte))
+}
+
+/// Parses the entries of a directory into tile `entries` and (offset, length) of leaf directories.
+fn parse_directory(
+    buf: &[u8],
+    entries: &mut Vec<Entry>,
+    leaf_directories: &mut Vec<(u64, u64)>,
+    limits: &ReadLimits,
+) -> Result<()> {
+    if !buf.len().is_multiple_of(ENTRY_BYTES) {
+        return Err(Error::DirectoryDecode(format!(
+            "Length of directory ({}) is not a multiple of {ENTRY_BYTES}",
+            buf.len()
+        )));
+    }
+
+    for bytes in buf.chunks_exact(ENTRY_BYTES) {
+        let z = bytes[0] & !LEAF_DIRECTORY_BIT;
+        let x = parse_uint(&bytes[1..4]);
+        let y = parse_uint(&bytes[4..7]);
+        let offset = parse_uint(&bytes[7..13]);
+        #[allow(clippy::cast_possible_truncation)]
+        let length = parse_uint(&bytes[13..17]) as u32;
+
+        if bytes[0] & LEAF_DIRECTORY_BIT != 0 {
+            leaf_directories.push((offset, length.into()));
+            continue;
+        }
+
+        if z > 31 || x >> z != 0 || y >> z != 0 {
+            return Err(Error::DirectoryDecode(format!(
+                "Tile coordinates {z}/{x}/{y} are out of range"
+            )));
+        }
+
+        let entry = Entry {
+            tile_id: tile_id(z, x, y),
+            offset,
+            length,
+            run_length: 1,
+        };
+
+        if entry.length == 0 {
+            return Err(Error::ZeroLengthEntry(entry.tile_id));
+        }
+        limits.check_tile_length(&entry)?;
+
+        entries.push(entry);
+    }
+
+    check_limit(
+        "number of directory entries",
+        entries.len() as u64,
+        limits.max_entries,
+    )?;
+    check_limit("number of tiles", entries.len() as u64, limits.max_tiles)
+}
+
+#[duplicate_item(
+    fn_name          cfg_async_filter       async    add_await(code) seek_start(reader, offset)                                input_traits                                        progress_traits;
+    [read_v2]        [cfg(all())]           []       [code]          [reader.seek(std::io::SeekFrom::Start(offset))]           [(impl Read + Seek)]                                [ReadProgress];
+    [read_v2_async]  [cfg(feature="async")] [async]  [code.await]    [reader.seek(futures::io::SeekFrom::Start(offset)).await] [(impl Unpin + Send + AsyncReadExt + AsyncSeekExt)] [ReadProgress + Send];
+)]
+#[cfg_async_filter]
+/// Reads the meta data and all directories of an archive of version 2.
+///
+/// Returns [`None`] if `reader` does not hold an archive of version 2.
+pub async fn fn_name<P: progress_traits>(
+    reader: &mut input_traits,
+    cancellation: &CancellationToken,
+    progress: &mut P,
+    limits: &ReadLimits,
+) -> Result<Option<V2Archive>> {
+    let mut header = [0; HEADER_BYTES];
+    seek_start([reader], [0])?;
+    add_await([reader.read_exact(&mut header)])?;
+
+    if !is_v2_header(&header) {
+        return Ok(None);
+    }
+
+    // meta data and root directory directly follow the header
+    let (meta_data_length, root_entries) = parse_header(&header);
+    check_limit(
+        "meta data length",
+        meta_data_length,
+        limits.max_meta_data_length,
+    )?;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let mut buf = vec![0; meta_data_length as usize + root_entries * ENTRY_BYTES];
+    add_await([reader.read_exact(&mut buf)])?;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let (meta_data, root_directory) = buf.split_at(meta_data_length as usize);
+    let JSONValue::Object(meta_data) = serde_json::from_slice(meta_data)? else {
+        return Err(Error::MetaDataNotAnObject);
+    };
+
+    let mut entries = Vec::new();
+    let mut leaf_directories = Vec::new();
+    parse_directory(root_directory, &mut entries, &mut leaf_directories, limits)?;
+    progress.on_progress(0, entries.len() as u64);
+
+    // leaf directories of version 2 archives are never nested
+    let mut nested = Vec::new();
+    for (index, (offset, length)) in leaf_directories.into_iter().enumerate() {
+        cancellation.check()?;
+        check_limit("directory length", length, limits.max_directory_length)?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        let mut buf = vec![0; length as usize];
+        seek_start([reader], [offset])?;
+        add_await([reader.read_exact(&mut buf)])?;
+
+        parse_directory(&buf, &mut entries, &mut nested, limits)?;
+        if !nested.is_empty() {
+            return Err(Error::DirectoryDecode(
+                "Leaf directories of version 2 archives must not reference other leaf directories"
+                    .to_string(),
+            ));
+        }
+
+        progress.on_progress(index as u64 + 1, entries.len() as u64);
+    }
+
+    entries.sort_by_key(|entry| entry.tile_id);
+
+    // the compression of tiles was only added to the meta data in later versions
+    let mut detected_compression = Compression::None;
+    if !meta_data.contains_key("compression") {
+        if let Some(entry) = entries.iter().min_by_key(|entry| entry.offset) {
+            let mut magic = vec![0; entry.length.min(4) as usize];
+            seek_start([reader], [entry.offset])?;
+            add_await([reader.read_exact(&mut magic)])?;
+
+            detected_compression = detect_compression(&magic).unwrap_or(Compression::None);
+        }
+    }
+
+    Ok(Some(V2Archive {
+        meta_data,
+        entries,
+        detected_compression,
+    }))
+}
+
+#[duplicate_item(
+    fn_name                    cfg_async_filter       async    add_await(code) RTraits                                                  WTraits                                    read_v2         to_writer;
+    [convert_v2_to_v3]         [cfg(all())]           []       [code]          [Read + Seek]                                            [Write + Seek]                             [read_v2]       [to_writer];
+    [convert_v2_to_v3_async]   [cfg(feature="async")] [async]  [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [AsyncWrite + AsyncSeekExt + Unpin + Send] [read_v2_async] [to_async_writer];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Converts an archive of version 2 of the specification into an archive of version 3.
+    ///
+    /// The written archive is clustered and tiles with the same content are only stored once,
+    /// so the counts of its header match the directories. The meta data is converted as
+    /// described in [`from_reader`](Self::from_reader).
+    ///
+    /// # Arguments
+    /// * `input` - Reader holding an archive of version 2
+    /// * `output` - Writer to write the archive of version 3 to
+    ///
+    /// # Errors
+    /// Will return [`Err`] if `input` does not hold an archive of version 2, there was an error
+    /// while reading it (see [`from_reader`](Self::from_reader)) or while writing to `output`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use pmtiles2::PMTiles;
+    /// let input = std::fs::File::open("./archive_v2.pmtiles").unwrap();
+    /// let mut output = std::fs::File::create("./archive_v3.pmtiles").unwrap();
+    ///
+    /// PMTiles::convert_v2_to_v3(input, &mut output).unwrap();
+    /// ```
+    pub async fn fn_name(mut input: R, output: &mut (impl WTraits)) -> Result<()> {
+        let archive = add_await([read_v2(
+            &mut input,
+            &CancellationToken::new(),
+            &mut |_, _| {},
+            &ReadLimits::default(),
+        )])?
+        .ok_or(Error::Unsupported(
+            "Only archives of version 2 can be converted to version 3",
+        ))?;
+
+        let pm_tiles = Self::from_v2_archive(input, archive, &[bounds_to_range(&..)])?;
+        add_await([pm_tiles.to_writer(output)])
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Returns a directory entry of a version 2 archive.
+    fn entry(z: u8, x: u32, y: u32, offset: u64, length: u32) -> Vec<u8> {
+        let mut bytes = vec![z];
+        bytes.extend_from_slice(&x.to_le_bytes()[..3]);
+        bytes.extend_from_slice(&y.to_le_bytes()[..3]);
+        bytes.extend_from_slice(&offset.to_le_bytes()[..6]);
+        bytes.extend_from_slice(&length.to_le_bytes());
+        bytes
+    }
+
+    /// Returns an archive of version 2 with tiles `0/0/0` and `1/0/0` in the root directory and
+    /// tiles `1/1/1` and `2/3/3` in a leaf directory.
+    fn v2_archive(meta_data: &str) -> Vec<u8> {
+        let tiles_offset = 512_000;
+        let leaf_offset = tiles_offset + 10;
+
+        let mut bytes = b"PM".to_vec();
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        #[allow(clippy::cast_possible_truncation)]
+        bytes.extend_from_slice(&(meta_data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&3u16.to_le_bytes());
+        bytes.extend_from_slice(meta_data.as_bytes());
+        bytes.extend(entry(0, 0, 0, tiles_offset, 4));
+        bytes.extend(entry(1, 0, 0, tiles_offset + 4, 6));
+        bytes.extend(entry(1 | LEAF_DIRECTORY_BIT, 1, 1, leaf_offset, 2 * 17));
+        bytes.resize(512_000, 0);
+
+        bytes.extend_from_slice(&[1, 3, 3, 7, 0x1f, 0x8b, 1, 2, 3, 4]);
+        bytes.extend(entry(1, 1, 1, tiles_offset, 4));
+        bytes.extend(entry(2, 3, 3, tiles_offset + 4, 6));
+
+        bytes
+    }
+
+    #[test]
+    fn test_read_v2() {
+        let bytes = v2_archive(r#"{"name":"test"}"#);
+
+        let archive = read_v2(
+            &mut Cursor::new(&bytes),
+            &CancellationToken::new(),
+            &mut |_, _| {},
+            &ReadLimits::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(archive.meta_data.get("name").unwrap(), "test");
+        assert_eq!(
+            archive
+                .entries
+                .iter()
+                .map(|entry| (entry.tile_id, entry.offset, entry.length))
+                .collect::<Vec<_>>(),
+            vec![
+                (tile_id(0, 0, 0), 512_000, 4),
+                (tile_id(1, 0, 0), 512_004, 6),
+                (tile_id(1, 1, 1), 512_000, 4),
+                (tile_id(2, 3, 3), 512_004, 6),
+            ]
+        );
+        assert_eq!(archive.detected_compression, Compression::None);
+
+        let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+        assert!(read_v2(
+            &mut Cursor::new(bytes),
+            &CancellationToken::new(),
+            &mut |_, _| {},
+            &ReadLimits::default(),
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    #[test]
+    fn test_from_reader() -> Result<()> {
+        let bytes = v2_archive(r#"{"name":"test","format":"png","maxzoom":"2"}"#);
+
+        let mut pm_tiles = PMTiles::from_reader(Cursor::new(&bytes))?;
+
+        assert_eq!(pm_tiles.tile_type, TileType::Png);
+        assert_eq!(pm_tiles.num_tiles(), 4);
+        assert_eq!(pm_tiles.get_tile(0, 0, 0)?, Some(vec![1, 3, 3, 7]));
+        assert_eq!(pm_tiles.get_tile(1, 1, 1)?, Some(vec![1, 3, 3, 7]));
+        assert_eq!(
+            pm_tiles.get_tile(3, 3, 2)?,
+            Some(vec![0x1f, 0x8b, 1, 2, 3, 4])
+        );
+        assert_eq!(pm_tiles.get_tile(1, 0, 1)?, None);
+
+        // archives of version 2 can be written as version 3
+        let mut written = PMTiles::from_bytes(pm_tiles.to_vec()?)?;
+        assert_eq!(written.max_zoom, 2);
+        assert_eq!(written.meta_data.get("name").unwrap(), "test");
+        assert_eq!(
+            written.get_tile(3, 3, 2)?,
+            Some(vec![0x1f, 0x8b, 1, 2, 3, 4])
+        );
+
+        let pm_tiles = PMTiles::from_reader_partially_zoom(Cursor::new(&bytes), 0..=1)?;
+        assert_eq!(pm_tiles.tile_ids_sorted(), [0, 1, tile_id(1, 1, 1)]);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_from_async_reader() -> Result<()> {
+        tokio_test::block_on(async {
+            let bytes = v2_archive("{}");
+
+            let mut pm_tiles = PMTiles::from_async_reader(futures::io::Cursor::new(bytes)).await?;
+
+            assert_eq!(pm_tiles.num_tiles(), 4);
+            assert_eq!(pm_tiles.tile_compression, Compression::None);
+            assert_eq!(
+                pm_tiles.get_tile_async(1, 1, 1).await?,
+                Some(vec![1, 3, 3, 7])
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_convert_v2_to_v3() -> Result<()> {
+        // tiles 0/0/0 and 1/1/1 as well as 1/0/0 and 2/3/3 share their data
+        let bytes = v2_archive(r#"{"name":"test","format":"png"}"#);
+
+        let mut output = Cursor::new(Vec::new());
+        PMTiles::convert_v2_to_v3(Cursor::new(&bytes), &mut output)?;
+
+        let output = output.into_inner();
+        let header = crate::Header::from_bytes(&output)?;
+        assert_eq!(header.spec_version, 3);
+        assert!(header.clustered);
+        assert_eq!(header.tile_type, TileType::Png);
+        assert_eq!(header.num_addressed_tiles, 4);
+        assert_eq!(header.num_tile_entries, 4);
+        assert_eq!(header.num_tile_content, 2);
+        assert_eq!(header.tile_data_length, 10);
+
+        let mut pm_tiles =
+            PMTiles::from_reader_with_options(Cursor::new(&output), crate::ParseOptions::strict())?;
+        assert_eq!(pm_tiles.meta_data.get("name").unwrap(), "test");
+        assert_eq!(
+            pm_tiles.get_tile(3, 3, 2)?,
+            Some(vec![0x1f, 0x8b, 1, 2, 3, 4])
+        );
+
+        let v3 = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+        assert!(matches!(
+            PMTiles::convert_v2_to_v3(Cursor::new(v3), &mut Cursor::new(Vec::new())),
+            Err(Error::Unsupported(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_convert_v2_to_v3_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let bytes = v2_archive("{}");
+
+            let mut output = futures::io::Cursor::new(Vec::new());
+            PMTiles::convert_v2_to_v3_async(futures::io::Cursor::new(bytes), &mut output).await?;
+
+            let pm_tiles = PMTiles::from_bytes(output.into_inner())?;
+            assert_eq!(pm_tiles.num_tiles(), 4);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_read_v2_limits() {
+        let bytes = v2_archive("{}");
+
+        let limits = ReadLimits {
+            max_tiles: 3,
+            ..ReadLimits::default()
+        };
+        assert!(matches!(
+            read_v2(
+                &mut Cursor::new(&bytes),
+                &CancellationToken::new(),
+                &mut |_, _| {},
+                &limits
+            ),
+            Err(Error::LimitExceeded {
+                limit: "number of tiles",
+                ..
+            })
+        ));
+
+        let limits = ReadLimits {
+            max_directory_length: 16,
+            ..ReadLimits::default()
+        };
+        assert!(matches!(
+            read_v2(
+                &mut Cursor::new(&bytes),
+                &CancellationToken::new(),
+                &mut |_, _| {},
+                &limits
+            ),
+            Err(Error::LimitExceeded {
+                limit: "directory length",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_apply_meta_data() {
+        let bytes = v2_archive(
+            r#"{
+                "name": "test",
+                "format": "pbf",
+                "compression": "gzip",
+                "minzoom": "0",
+                "maxzoom": 2,
+                "bounds": "-10.5,-20,30,40.25",
+                "center": "1,2,1",
+                "json": "{\"vector_layers\":[]}"
+            }"#,
+        );
+
+        let archive = read_v2(
+            &mut Cursor::new(&bytes),
+            &CancellationToken::new(),
+            &mut |_, _| {},
+            &ReadLimits::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        let mut pm_tiles = PMTiles::<Cursor<&[u8]>>::default();
+        archive.apply_meta_data(&mut pm_tiles);
+
+        assert_eq!(pm_tiles.tile_type, TileType::Mvt);
+        assert_eq!(pm_tiles.tile_compression, Compression::GZip);
+        assert_eq!((pm_tiles.min_zoom, pm_tiles.max_zoom), (0, 2));
+        assert!((pm_tiles.min_longitude + 10.5).abs() < f64::EPSILON);
+        assert!((pm_tiles.max_latitude - 40.25).abs() < f64::EPSILON);
+        assert!((pm_tiles.center_latitude - 2.0).abs() < f64::EPSILON);
+        assert_eq!(pm_tiles.center_zoom, 1);
+
+        let mut keys = pm_tiles.meta_data.keys().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, ["name", "vector_layers"]);
+    }
+
+    #[test]
+    fn test_apply_meta_data_defaults() {
+        let archive = V2Archive {
+            meta_data: JSONMap::new(),
+            entries: vec![Entry {
+                tile_id: tile_id(3, 0, 0),
+                offset: 0,
+                length: 1,
+                run_length: 1,
+            }],
+            detected_compression: Compression::GZip,
+        };
+
+        let mut pm_tiles = PMTiles::<Cursor<&[u8]>>::default();
+        archive.apply_meta_data(&mut pm_tiles);
+
+        assert_eq!(pm_tiles.tile_type, TileType::Unknown);
+        assert_eq!(pm_tiles.tile_compression, Compression::GZip);
+        assert_eq!((pm_tiles.min_zoom, pm_tiles.max_zoom), (3, 3));
+        assert_eq!(pm_tiles.center_zoom, 3);
+        assert!((pm_tiles.max_longitude - 180.0).abs() < f64::EPSILON);
+    }
+}
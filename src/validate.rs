@@ -0,0 +1,833 @@
+use std::collections::HashSet;
+use std::io::{Read, Seek, SeekFrom};
+
+use ahash::RandomState;
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+use serde_json::Value as JSONValue;
+
+use crate::{
+    header::{HEADER_BYTES, MAX_ROOT_DIRECTORY_END},
+    util::{check_clustered, decompress_all, ClusteringMismatch},
+    Directory, Entry, Error, Header, PMTiles, Result,
+};
+
+/// Maximum number of nested leaf directories, which readers are expected to follow.
+const MAX_LEAF_DEPTH: u8 = 3;
+
+/// A violation of the `PMTiles` specification found by [`PMTiles::validate`].
+///
+/// Offsets of directories are absolute (i.e. relative to the start of the archive), while offsets of
+/// directory entries are relative to the start of their section, just like in [`Entry`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum Violation {
+    /// The data does not start with the `PMTiles` magic bytes.
+    #[error("Data is no PMTiles archive (invalid magic bytes)")]
+    InvalidMagic,
+
+    /// The archive uses a version of the specification other than 3.
+    #[error("Unsupported PMTiles spec version {0} (only version 3 is supported)")]
+    UnsupportedSpecVersion(u8),
+
+    /// The header could not be decoded.
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+
+    /// A section exceeds the end of the archive.
+    #[error(
+        "Section {section} ({offset}+{length}) exceeds archive length of {archive_length} bytes"
+    )]
+    SectionOutOfBounds {
+        /// Name of the section
+        section: &'static str,
+        /// Offset (in bytes) of the section
+        offset: u64,
+        /// Length (in bytes) of the section
+        length: u64,
+        /// Length (in bytes) of the whole archive
+        archive_length: u64,
+    },
+
+    /// Two sections overlap each other.
+    #[error("Sections {0} and {1} overlap")]
+    SectionsOverlap(&'static str, &'static str),
+
+    /// The root directory does not end within the first 16 KiB of the archive.
+    #[error("Root directory ends at byte {0}, but must end within the first 16384 bytes")]
+    RootDirectoryTooLarge(u64),
+
+    /// The meta data could not be decompressed or is no JSON object.
+    #[error("Invalid meta data: {0}")]
+    InvalidMetaData(String),
+
+    /// A directory could not be decompressed or decoded.
+    #[error("Invalid directory at offset {offset}: {message}")]
+    InvalidDirectory {
+        /// Offset (in bytes) of the directory
+        offset: u64,
+        /// Description of the error
+        message: String,
+    },
+
+    /// A leaf directory does not include any entries.
+    #[error("Leaf directory at offset {0} is empty")]
+    EmptyLeafDirectory(u64),
+
+    /// Leaf directories are nested deeper than readers are expected to follow.
+    #[error("Leaf directory at offset {offset} is nested {depth} levels deep")]
+    LeafDirectoryTooDeep {
+        /// Offset (in bytes) of the leaf directory
+        offset: u64,
+        /// Number of leaf directories, which have to be followed to reach it
+        depth: u8,
+    },
+
+    /// A directory entry does not start after the tile ids covered by the preceding entry.
+    #[error("Entry for tile id {tile_id} does not follow entry for tile id {previous}")]
+    EntriesNotAscending {
+        /// First tile id of the entry
+        tile_id: u64,
+        /// First tile id of the preceding entry
+        previous: u64,
+    },
+
+    /// An entry of a leaf directory covers tile ids outside of the range of the leaf directory entry
+    /// pointing to it.
+    #[error("Entry for tile id {0} is outside of the range of its leaf directory")]
+    EntryOutsideLeafRange(u64),
+
+    /// A leaf directory entry points outside of the leaf directories section.
+    #[error("Leaf directory entry for tile id {tile_id} ({offset}+{length}) exceeds leaf directories section")]
+    LeafDirectoryOutOfBounds {
+        /// First tile id of the entry
+        tile_id: u64,
+        /// Offset (in bytes) of the leaf directory
+        offset: u64,
+        /// Length (in bytes) of the leaf directory
+        length: u32,
+    },
+
+    /// A tile entry points outside of the tile data section.
+    #[error("Tile entry for tile id {tile_id} ({offset}+{length}) exceeds tile data section")]
+    TileDataOutOfBounds {
+        /// First tile id of the entry
+        tile_id: u64,
+        /// Offset (in bytes) of the tile data
+        offset: u64,
+        /// Length (in bytes) of the tile data
+        length: u32,
+    },
+
+    /// The archive is marked as clustered, but the tile data is not ordered by tile id.
+    #[error("Archive is marked as clustered, but data of tile {0} is not in order of tile ids")]
+    NotClustered(u64),
+
+    /// A count in the header does not match the directory entries.
+    #[error("Header field {field} is {header}, but entries indicate {actual}")]
+    CountMismatch {
+        /// Name of the header field
+        field: &'static str,
+        /// Value of the header field
+        header: u64,
+        /// Value derived from the directory entries
+        actual: u64,
+    },
+}
+
+/// The result of validating an archive with [`PMTiles::validate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// All violations, which were found, in the order in which they were found
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Returns `true` if no violations were found.
+    pub const fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// A directory, which still has to be validated.
+struct PendingDirectory {
+    /// Absolute offset (in bytes)
+    offset: u64,
+    length: u64,
+    depth: u8,
+    /// Range of tile ids all entries must be within (exclusive end)
+    tile_ids: (u64, u64),
+}
+
+/// Maps errors, which occurred while reading the header, to violations.
+///
+/// Errors not caused by invalid data (e.g. I/O errors) are returned as is.
+fn header_violation(err: Error) -> Result<Violation> {
+    match err {
+        Error::InvalidMagic => Ok(Violation::InvalidMagic),
+        Error::UnsupportedSpecVersion(version) => Ok(Violation::UnsupportedSpecVersion(version)),
+        Error::InvalidHeader(message) => Ok(Violation::InvalidHeader(message)),
+        err => Err(err),
+    }
+}
+
+/// Checks that all sections are within the archive and do not overlap.
+///
+/// Returns `true` if all sections are within the archive.
+fn check_sections(header: &Header, archive_length: u64, violations: &mut Vec<Violation>) -> bool {
+    let sections = [
+        ("header", 0, u64::from(HEADER_BYTES)),
+        (
+            "root directory",
+            header.root_directory_offset,
+            header.root_directory_length,
+        ),
+        (
+            "meta data",
+            header.json_metadata_offset,
+            header.json_metadata_length,
+        ),
+        (
+            "leaf directories",
+            header.leaf_directories_offset,
+            header.leaf_directories_length,
+        ),
+        (
+            "tile data",
+            header.tile_data_offset,
+            header.tile_data_length,
+        ),
+    ];
+
+    let mut in_bounds = true;
+    for (section, offset, length) in sections {
+        if offset
+            .checked_add(length)
+            .is_none_or(|end| end > archive_length)
+        {
+            in_bounds = false;
+            violations.push(Violation::SectionOutOfBounds {
+                section,
+                offset,
+                length,
+                archive_length,
+            });
+        }
+    }
+
+    for (i, &(a, a_offset, a_length)) in sections.iter().enumerate() {
+        for &(b, b_offset, b_length) in &sections[i + 1..] {
+            if a_length > 0
+                && b_length > 0
+                && a_offset < b_offset.saturating_add(b_length)
+                && b_offset < a_offset.saturating_add(a_length)
+            {
+                violations.push(Violation::SectionsOverlap(a, b));
+            }
+        }
+    }
+
+    let root_end = header
+        .root_directory_offset
+        .saturating_add(header.root_directory_length);
+    if root_end > MAX_ROOT_DIRECTORY_END {
+        violations.push(Violation::RootDirectoryTooLarge(root_end));
+    }
+
+    in_bounds
+}
+
+/// Checks that the meta data is a (compressed) JSON object.
+fn check_meta_data(header: &Header, bytes: &[u8], violations: &mut Vec<Violation>) {
+    let result = decompress_all(header.internal_compression, bytes).and_then(|data| {
+        match serde_json::from_slice::<JSONValue>(&data)? {
+            JSONValue::Object(_) => Ok(()),
+            _ => Err(Error::MetaDataNotAnObject),
+        }
+    });
+
+    if let Err(err) = result {
+        violations.push(Violation::InvalidMetaData(err.to_string()));
+    }
+}
+
+/// Checks the entries of a directory, collects its tile entries and returns the leaf directories it
+/// points to.
+fn check_directory(
+    header: &Header,
+    pending: &PendingDirectory,
+    bytes: &[u8],
+    tile_entries: &mut Vec<Entry>,
+    violations: &mut Vec<Violation>,
+) -> Vec<PendingDirectory> {
+    let directory = match Directory::from_bytes(bytes, header.internal_compression) {
+        Ok(directory) => directory,
+        Err(err) => {
+            violations.push(Violation::InvalidDirectory {
+                offset: pending.offset,
+                message: err.to_string(),
+            });
+            return Vec::new();
+        }
+    };
+
+    if pending.depth > 0 && directory.is_empty() {
+        violations.push(Violation::EmptyLeafDirectory(pending.offset));
+    }
+    if pending.depth > MAX_LEAF_DEPTH {
+        violations.push(Violation::LeafDirectoryTooDeep {
+            offset: pending.offset,
+            depth: pending.depth,
+        });
+    }
+
+    let (min_tile_id, max_tile_id) = pending.tile_ids;
+    let mut leaf_directories = Vec::new();
+    let mut previous: Option<&Entry> = None;
+
+    for (i, entry) in directory.into_iter().enumerate() {
+        let end = entry
+            .tile_id
+            .saturating_add(u64::from(entry.run_length.max(1)));
+
+        if let Some(previous) = previous {
+            let previous_end = previous
+                .tile_id
+                .saturating_add(u64::from(previous.run_length.max(1)));
+            if entry.tile_id < previous_end {
+                violations.push(Violation::EntriesNotAscending {
+                    tile_id: entry.tile_id,
+                    previous: previous.tile_id,
+                });
+            }
+        }
+        previous = Some(entry);
+
+        if entry.tile_id < min_tile_id || end > max_tile_id {
+            violations.push(Violation::EntryOutsideLeafRange(entry.tile_id));
+        }
+
+        if entry.is_leaf_dir_entry() {
+            if entry.offset.saturating_add(u64::from(entry.length)) > header.leaf_directories_length
+            {
+                violations.push(Violation::LeafDirectoryOutOfBounds {
+                    tile_id: entry.tile_id,
+                    offset: entry.offset,
+                    length: entry.length,
+                });
+                continue;
+            }
+
+            let next_tile_id = (i + 1 < directory.len())
+                .then(|| directory[i + 1].tile_id)
+                .map_or(max_tile_id, |id| id.clamp(entry.tile_id, max_tile_id));
+
+            leaf_directories.push(PendingDirectory {
+                offset: header.leaf_directories_offset + entry.offset,
+                length: u64::from(entry.length),
+                depth: pending.depth + 1,
+                tile_ids: (entry.tile_id, next_tile_id),
+            });
+        } else {
+            if entry.offset.saturating_add(u64::from(entry.length)) > header.tile_data_length {
+                violations.push(Violation::TileDataOutOfBounds {
+                    tile_id: entry.tile_id,
+                    offset: entry.offset,
+                    length: entry.length,
+                });
+            }
+
+            tile_entries.push(*entry);
+        }
+    }
+
+    // leaf directories nested too deeply are not followed, as they may reference themselves
+    if pending.depth > MAX_LEAF_DEPTH {
+        leaf_directories.clear();
+    }
+
+    leaf_directories
+}
+
+/// Checks the clustered flag and the counts of the header against all tile entries.
+fn check_tile_entries(
+    header: &Header,
+    tile_entries: &mut [Entry],
+    violations: &mut Vec<Violation>,
+) {
+    tile_entries.sort_unstable_by_key(|entry| entry.tile_id);
+
+    if let Some(ClusteringMismatch::NotClustered(tile_id)) = check_clustered(header, tile_entries) {
+        violations.push(Violation::NotClustered(tile_id));
+    }
+
+    let contents = tile_entries
+        .iter()
+        .map(|entry| (entry.offset, entry.length))
+        .collect::<HashSet<_, RandomState>>();
+
+    let counts = [
+        (
+            "num_addressed_tiles",
+            header.num_addressed_tiles,
+            tile_entries
+                .iter()
+                .map(|entry| u64::from(entry.run_length))
+                .sum(),
+        ),
+        (
+            "num_tile_entries",
+            header.num_tile_entries,
+            tile_entries.len() as u64,
+        ),
+        (
+            "num_tile_content",
+            header.num_tile_content,
+            contents.len() as u64,
+        ),
+    ];
+
+    // a count of 0 indicates, that the count is unknown
+    for (field, header, actual) in counts {
+        if header != 0 && header != actual {
+            violations.push(Violation::CountMismatch {
+                field,
+                header,
+                actual,
+            });
+        }
+    }
+}
+
+#[duplicate_item(
+    fn_name          cfg_async_filter       async   add_await(code) RTraits                                                  SeekFrom                   read_header;
+    [validate]       [cfg(all())]           []      [code]          [Read + Seek]                                            [SeekFrom]                 [Header::from_reader(input)];
+    [validate_async] [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [futures::io::SeekFrom]    [Header::from_async_reader(input).await];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Checks an archive for conformance with the `PMTiles` specification and returns all violations,
+    /// which were found.
+    ///
+    /// This checks...
+    /// - the magic bytes and spec version of the header
+    /// - that all sections are within the archive and do not overlap
+    /// - that the root directory is within the first 16 KiB of the archive
+    /// - that the meta data is a JSON object
+    /// - that directory entries are in ascending order, do not overlap and point into their sections
+    /// - that leaf directories are not empty and nested no deeper than 3 levels
+    /// - that tile data is ordered by tile id, if the archive is marked as clustered
+    /// - that `num_addressed_tiles`, `num_tile_entries` and `num_tile_content` match the entries
+    ///   (unless they are 0, which indicates an unknown count)
+    ///
+    /// In contrast to [`from_reader`](Self::from_reader), this does not stop at the first problem,
+    /// which makes it useful for debugging archives written by other tools.
+    ///
+    /// # Arguments
+    /// * `input` - Reader with the archive
+    ///
+    /// # Errors
+    /// Will return [`Err`] if an I/O error occurred while reading from `input`. Invalid data is
+    /// reported as [`Violation`] instead.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::PMTiles;
+    /// let bytes = include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+    /// let mut reader = std::io::Cursor::new(bytes);
+    ///
+    /// let report = PMTiles::validate(&mut reader).unwrap();
+    /// assert!(report.is_valid());
+    /// ```
+    pub async fn fn_name(input: &mut R) -> Result<ValidationReport> {
+        let mut report = ValidationReport::default();
+
+        let archive_length = add_await([input.seek(SeekFrom::End(0))])?;
+        if archive_length < u64::from(HEADER_BYTES) {
+            report.violations.push(Violation::InvalidHeader(
+                "Archive is smaller than the header".to_string(),
+            ));
+            return Ok(report);
+        }
+
+        add_await([input.seek(SeekFrom::Start(0))])?;
+        let header = match read_header {
+            Ok(header) => header,
+            Err(err) => {
+                report.violations.push(header_violation(err)?);
+                return Ok(report);
+            }
+        };
+
+        if !check_sections(&header, archive_length, &mut report.violations) {
+            return Ok(report);
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let mut bytes = vec![0; header.json_metadata_length as usize];
+        add_await([input.seek(SeekFrom::Start(header.json_metadata_offset))])?;
+        add_await([input.read_exact(&mut bytes)])?;
+        check_meta_data(&header, &bytes, &mut report.violations);
+
+        let mut tile_entries = Vec::new();
+        let mut pending = vec![PendingDirectory {
+            offset: header.root_directory_offset,
+            length: header.root_directory_length,
+            depth: 0,
+            tile_ids: (0, u64::MAX),
+        }];
+        // leaf directories may be referenced multiple times, but only have to be checked once
+        let mut checked = HashSet::<(u64, u64), RandomState>::default();
+
+        while let Some(directory) = pending.pop() {
+            if !checked.insert((directory.offset, directory.length)) {
+                continue;
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            let mut bytes = vec![0; directory.length as usize];
+            add_await([input.seek(SeekFrom::Start(directory.offset))])?;
+            add_await([input.read_exact(&mut bytes)])?;
+
+            let mut leaf_directories = check_directory(
+                &header,
+                &directory,
+                &bytes,
+                &mut tile_entries,
+                &mut report.violations,
+            );
+
+            // pop leaf directories in the order they appear in the directory
+            leaf_directories.reverse();
+            pending.append(&mut leaf_directories);
+        }
+
+        check_tile_entries(&header, &mut tile_entries, &mut report.violations);
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::Compression;
+
+    const PM_TILES_BYTES: &[u8] =
+        include_bytes!("../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles");
+
+    const PM_TILES_BYTES2: &[u8] = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+
+    fn validate_header(header: &Header) -> Result<ValidationReport> {
+        let mut bytes = PM_TILES_BYTES.to_vec();
+        let mut header_bytes = Vec::new();
+        header.to_writer(&mut header_bytes)?;
+        bytes[..header_bytes.len()].copy_from_slice(&header_bytes);
+
+        PMTiles::validate(&mut Cursor::new(bytes))
+    }
+
+    #[test]
+    fn test_validate_valid() -> Result<()> {
+        for bytes in [PM_TILES_BYTES, PM_TILES_BYTES2] {
+            let report = PMTiles::validate(&mut Cursor::new(bytes))?;
+            assert_eq!(report.violations, Vec::new());
+        }
+
+        let mut output = Cursor::new(Vec::new());
+        PMTiles::from_bytes(PM_TILES_BYTES2)?.to_writer(&mut output)?;
+        assert!(PMTiles::validate(&mut output)?.is_valid());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_invalid_magic() -> Result<()> {
+        let mut bytes = PM_TILES_BYTES.to_vec();
+        bytes[0] = b'X';
+        let report = PMTiles::validate(&mut Cursor::new(&bytes))?;
+        assert_eq!(report.violations, vec![Violation::InvalidMagic]);
+
+        let report = PMTiles::validate(&mut Cursor::new(&bytes[..10]))?;
+        assert!(matches!(
+            report.violations[..],
+            [Violation::InvalidHeader(_)]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_sections() -> Result<()> {
+        let mut header = Header::from_bytes(PM_TILES_BYTES)?;
+        header.tile_data_length += 1;
+        header.json_metadata_offset = header.root_directory_offset;
+
+        let report = validate_header(&header)?;
+        assert_eq!(
+            report.violations,
+            vec![
+                Violation::SectionOutOfBounds {
+                    section: "tile data",
+                    offset: header.tile_data_offset,
+                    length: header.tile_data_length,
+                    archive_length: PM_TILES_BYTES.len() as u64,
+                },
+                Violation::SectionsOverlap("root directory", "meta data"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_counts_and_clustered() -> Result<()> {
+        let mut header = Header::from_bytes(PM_TILES_BYTES)?;
+        header.num_addressed_tiles += 1;
+        header.num_tile_content = 0;
+
+        let report = validate_header(&header)?;
+        assert_eq!(
+            report.violations,
+            vec![Violation::CountMismatch {
+                field: "num_addressed_tiles",
+                header: header.num_addressed_tiles,
+                actual: header.num_addressed_tiles - 1,
+            }]
+        );
+
+        let mut entries = vec![
+            Entry {
+                tile_id: 0,
+                offset: 10,
+                length: 10,
+                run_length: 1,
+            },
+            Entry {
+                tile_id: 1,
+                offset: 0,
+                length: 10,
+                run_length: 1,
+            },
+        ];
+        let header = Header {
+            clustered: true,
+            ..Header::default()
+        };
+        let mut violations = Vec::new();
+        check_tile_entries(&header, &mut entries, &mut violations);
+        assert_eq!(violations, vec![Violation::NotClustered(0)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_directory() -> Result<()> {
+        let header = Header {
+            tile_data_length: 100,
+            leaf_directories_length: 100,
+            ..Header::default()
+        };
+        let directory: Directory = vec![
+            Entry {
+                tile_id: 5,
+                offset: 0,
+                length: 10,
+                run_length: 3,
+            },
+            Entry {
+                tile_id: 6,
+                offset: 95,
+                length: 10,
+                run_length: 1,
+            },
+            Entry {
+                tile_id: 10,
+                offset: 0,
+                length: 10,
+                run_length: 0,
+            },
+        ]
+        .into();
+        let mut bytes = Vec::new();
+        directory.to_writer(&mut bytes, header.internal_compression)?;
+
+        let pending = PendingDirectory {
+            offset: 0,
+            length: bytes.len() as u64,
+            depth: 1,
+            tile_ids: (0, 20),
+        };
+        let mut tile_entries = Vec::new();
+        let mut violations = Vec::new();
+        let leaf_directories = check_directory(
+            &header,
+            &pending,
+            &bytes,
+            &mut tile_entries,
+            &mut violations,
+        );
+
+        assert_eq!(tile_entries.len(), 2);
+        assert_eq!(leaf_directories.len(), 1);
+        assert_eq!(leaf_directories[0].tile_ids, (10, 20));
+        assert_eq!(
+            violations,
+            vec![
+                Violation::EntriesNotAscending {
+                    tile_id: 6,
+                    previous: 5
+                },
+                Violation::TileDataOutOfBounds {
+                    tile_id: 6,
+                    offset: 95,
+                    length: 10
+                },
+            ]
+        );
+
+        let mut violations = Vec::new();
+        let too_deep = PendingDirectory {
+            depth: MAX_LEAF_DEPTH + 1,
+            ..pending
+        };
+        let leaf_directories = check_directory(
+            &header,
+            &too_deep,
+            &bytes,
+            &mut tile_entries,
+            &mut violations,
+        );
+        assert!(leaf_directories.is_empty());
+        assert!(violations.contains(&Violation::LeafDirectoryTooDeep {
+            offset: 0,
+            depth: MAX_LEAF_DEPTH + 1
+        }));
+
+        let mut violations = Vec::new();
+        check_directory(&header, &pending, &[0], &mut tile_entries, &mut violations);
+        assert!(matches!(
+            violations[..],
+            [Violation::InvalidDirectory { offset: 0, .. }]
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_directory_tile_id_overflow() -> Result<()> {
+        let header = Header {
+            tile_data_length: 100,
+            ..Header::default()
+        };
+        let directory: Directory = vec![
+            Entry {
+                tile_id: u64::MAX - 1,
+                offset: 0,
+                length: 10,
+                run_length: u32::MAX,
+            },
+            Entry {
+                tile_id: u64::MAX,
+                offset: 10,
+                length: 10,
+                run_length: 1,
+            },
+        ]
+        .into();
+        let mut bytes = Vec::new();
+        directory.to_writer(&mut bytes, header.internal_compression)?;
+
+        let pending = PendingDirectory {
+            offset: 0,
+            length: bytes.len() as u64,
+            depth: 0,
+            tile_ids: (0, u64::MAX),
+        };
+        let mut tile_entries = Vec::new();
+        let mut violations = Vec::new();
+        check_directory(
+            &header,
+            &pending,
+            &bytes,
+            &mut tile_entries,
+            &mut violations,
+        );
+        assert_eq!(violations, Vec::new());
+        assert_eq!(tile_entries.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_shared_leaf_directory() -> Result<()> {
+        let leaf: Directory = vec![Entry {
+            tile_id: 0,
+            offset: 0,
+            length: 1,
+            run_length: 1,
+        }]
+        .into();
+        let mut leaf_bytes = Vec::new();
+        leaf.to_writer(&mut leaf_bytes, Compression::None)?;
+
+        // both entries of the root directory point to the same leaf directory
+        #[allow(clippy::cast_possible_truncation)]
+        let leaf_length = leaf_bytes.len() as u32;
+        let root: Directory = (0..2)
+            .map(|tile_id| Entry {
+                tile_id,
+                offset: 0,
+                length: leaf_length,
+                run_length: 0,
+            })
+            .collect::<Vec<_>>()
+            .into();
+        let mut root_bytes = Vec::new();
+        root.to_writer(&mut root_bytes, Compression::None)?;
+
+        let meta_data = b"{}";
+        let mut header = Header {
+            internal_compression: Compression::None,
+            root_directory_offset: u64::from(HEADER_BYTES),
+            root_directory_length: root_bytes.len() as u64,
+            json_metadata_length: meta_data.len() as u64,
+            leaf_directories_length: leaf_bytes.len() as u64,
+            tile_data_length: 1,
+            num_addressed_tiles: 1,
+            num_tile_entries: 1,
+            num_tile_content: 1,
+            ..Header::default()
+        };
+        header.json_metadata_offset = header.root_directory_offset + header.root_directory_length;
+        header.leaf_directories_offset = header.json_metadata_offset + header.json_metadata_length;
+        header.tile_data_offset = header.leaf_directories_offset + header.leaf_directories_length;
+
+        let mut bytes = Vec::new();
+        header.to_writer(&mut bytes)?;
+        bytes.extend_from_slice(&root_bytes);
+        bytes.extend_from_slice(meta_data);
+        bytes.extend_from_slice(&leaf_bytes);
+        bytes.push(0);
+
+        let report = PMTiles::validate(&mut Cursor::new(bytes))?;
+        assert_eq!(report.violations, Vec::new());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_validate_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let mut reader = futures::io::Cursor::new(PM_TILES_BYTES2);
+            let report = PMTiles::validate_async(&mut reader).await?;
+            assert!(report.is_valid());
+
+            Ok(())
+        })
+    }
+}
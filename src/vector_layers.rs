@@ -0,0 +1,181 @@
+use std::io::{Read, Seek};
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+use serde_json::Value as JSONValue;
+
+use crate::{
+    util::{decompress_all, zoom_range, VectorLayers},
+    Error, PMTiles, Result, TileType,
+};
+
+/// Number of tiles, which are read at once while generating `vector_layers`.
+const BATCH_SIZE: usize = 1024;
+
+/// Returns the ids of the tiles to scan for each zoom level, given the ids of all tiles in ascending order.
+fn sample_tile_ids(tile_ids: &[u64], tiles_per_zoom: Option<usize>) -> Vec<(u8, Vec<u64>)> {
+    let mut samples = Vec::new();
+
+    for z in 0..=31 {
+        let range = zoom_range(z);
+        let start = tile_ids.partition_point(|id| *id < range.start);
+        let end = tile_ids.partition_point(|id| *id < range.end);
+        let ids = &tile_ids[start..end];
+
+        if ids.is_empty() {
+            continue;
+        }
+
+        let ids = match tiles_per_zoom {
+            Some(n) if n < ids.len() => (0..n).map(|i| ids[i * ids.len() / n]).collect(),
+            _ => ids.to_vec(),
+        };
+
+        samples.push((z, ids));
+    }
+
+    samples
+}
+
+#[duplicate_item(
+    fn_name                          cfg_async_filter       async   add_await(code) RTraits                                                  get_tiles;
+    [generate_vector_layers]         [cfg(all())]           []      [code]          [Read + Seek]                                            [get_tiles];
+    [generate_vector_layers_async]   [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [get_tiles_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Generates the `vector_layers` field of the meta data by decoding the tiles of the archive.
+    ///
+    /// The `PMTiles` spec requires `vector_layers` for archives of [`TileType::Mvt`] and some viewers
+    /// reject archives without it. The result is not added to [`meta_data`](Self::meta_data) automatically.
+    ///
+    /// For large archives, only a sample of the tiles of each zoom level may be decoded, which
+    /// is spread evenly over the tiles of that zoom level.
+    ///
+    /// See [`util::VectorLayers`](crate::util::VectorLayers) for details on the generated JSON.
+    ///
+    /// # Arguments
+    /// * `tiles_per_zoom` - Maximum number of tiles to decode per zoom level (all tiles are decoded if [`None`])
+    ///
+    /// # Errors
+    /// Will return [`Err`] if the tile type of the archive is not [`TileType::Mvt`], a tile could
+    /// not be read or decompressed or a tile is no valid Mapbox Vector Tile.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::PMTiles;
+    /// let bytes = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+    /// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+    ///
+    /// let vector_layers = pm_tiles.generate_vector_layers(Some(10)).unwrap();
+    /// pm_tiles.meta_data.insert("vector_layers".to_string(), vector_layers);
+    /// ```
+    pub async fn fn_name(&mut self, tiles_per_zoom: Option<usize>) -> Result<JSONValue> {
+        if self.tile_type != TileType::Mvt {
+            return Err(Error::Unsupported(
+                "vector_layers can only be generated for archives with tile type Mvt",
+            ));
+        }
+
+        let mut vector_layers = VectorLayers::default();
+
+        for (z, tile_ids) in sample_tile_ids(&self.tile_ids_sorted(), tiles_per_zoom) {
+            for batch in tile_ids.chunks(BATCH_SIZE) {
+                for data in add_await([self.get_tiles(batch)])?.into_iter().flatten() {
+                    vector_layers.add_tile(z, &decompress_all(self.tile_compression, &data)?)?;
+                }
+            }
+        }
+
+        Ok(vector_layers.to_json())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    const PM_TILES_BYTES: &[u8] = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+
+    #[test]
+    fn test_sample_tile_ids() {
+        let tile_ids = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 21];
+
+        assert_eq!(
+            sample_tile_ids(&tile_ids, None),
+            vec![
+                (0, vec![0]),
+                (1, vec![1, 2, 3, 4]),
+                (2, vec![5, 6, 7, 8, 9, 10, 11, 12, 13]),
+                (3, vec![21]),
+            ]
+        );
+        assert_eq!(
+            sample_tile_ids(&tile_ids, Some(2)),
+            vec![
+                (0, vec![0]),
+                (1, vec![1, 3]),
+                (2, vec![5, 9]),
+                (3, vec![21]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_vector_layers() -> Result<()> {
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+        let vector_layers = pm_tiles.generate_vector_layers(None)?;
+        let layers = vector_layers.as_array().unwrap();
+
+        assert!(!layers.is_empty());
+        for layer in layers {
+            assert!(layer["id"].is_string());
+            assert!(layer["fields"].is_object());
+            assert!(layer["minzoom"].as_u64() >= Some(u64::from(pm_tiles.min_zoom)));
+            assert!(layer["maxzoom"].as_u64() <= Some(u64::from(pm_tiles.max_zoom)));
+        }
+
+        assert_eq!(
+            layers.iter().filter_map(|l| l["maxzoom"].as_u64()).max(),
+            Some(u64::from(pm_tiles.max_zoom))
+        );
+
+        let sampled = pm_tiles.generate_vector_layers(Some(1))?;
+        assert!(sampled.as_array().is_some_and(|l| l.len() <= layers.len()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_vector_layers_raster() -> Result<()> {
+        let mut pm_tiles = PMTiles::from_bytes(include_bytes!(
+            "../test/stamen_toner(raster)CC-BY+ODbL_z3.pmtiles"
+        ))?;
+
+        assert!(matches!(
+            pm_tiles.generate_vector_layers(None),
+            Err(Error::Unsupported(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_generate_vector_layers_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let reader = futures::io::Cursor::new(PM_TILES_BYTES);
+            let mut pm_tiles = PMTiles::from_async_reader(reader).await?;
+
+            assert_eq!(
+                pm_tiles.generate_vector_layers_async(Some(5)).await?,
+                PMTiles::from_bytes(PM_TILES_BYTES)?.generate_vector_layers(Some(5))?
+            );
+
+            Ok(())
+        })
+    }
+}
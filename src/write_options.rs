@@ -0,0 +1,143 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::{
+    util::{Codec, WriteDirsOverflowStrategy},
+    CancellationToken, SectionOrder, TileTransform, WriteProgress,
+};
+
+/// Options for writing an archive (see [`PMTiles::to_writer_with_options`](crate::PMTiles::to_writer_with_options)).
+///
+/// All functions writing an archive (e.g. [`to_writer_unseekable_with_options`](crate::PMTiles::to_writer_unseekable_with_options),
+/// [`to_vec_with_options`](crate::PMTiles::to_vec_with_options) or [`to_writer_at_with_options`](crate::PMTiles::to_writer_at_with_options))
+/// accept these options, the variants without options use [`WriteOptions::default`].
+///
+/// # Example
+/// ```rust
+/// # use pmtiles2::{PMTiles, SectionOrder, TileType, Compression, WriteOptions};
+/// let mut pm_tiles = PMTiles::new(TileType::Png, Compression::None);
+/// pm_tiles.add_tile(0, vec![1, 2, 3]).unwrap();
+/// pm_tiles.add_tile(1, vec![1, 2, 3]).unwrap();
+///
+/// let mut tiles_done = 0;
+/// let mut output = std::io::Cursor::new(Vec::new());
+/// pm_tiles
+///     .to_writer_with_options(
+///         &mut output,
+///         WriteOptions {
+///             section_order: SectionOrder::LeafDirectoriesMetadataTileData,
+///             meta_data_compression_level: Some(9),
+///             dedupe: false,
+///             progress: Some(&mut |_, processed, _| tiles_done = processed),
+///             ..WriteOptions::default()
+///         },
+///     )
+///     .unwrap();
+///
+/// let pm_tiles = PMTiles::from_bytes(output.into_inner()).unwrap();
+/// assert_eq!(pm_tiles.num_tiles(), 2);
+/// ```
+pub struct WriteOptions<'a> {
+    /// Order of the meta data, leaf directories and tile data sections
+    pub section_order: SectionOrder,
+
+    /// Strategy to divide entries into leaf directories, when the root directory exceeds
+    /// [`max_root_dir_length`](Self::max_root_dir_length) (chosen automatically if [`None`])
+    ///
+    /// This can be used to configure the size of leaf directories.
+    pub dirs_overflow_strategy: Option<WriteDirsOverflowStrategy>,
+
+    /// Maximum length (in bytes) of the root directory
+    /// (default [`util::DEFAULT_MAX_ROOT_DIR_LENGTH`](crate::util::DEFAULT_MAX_ROOT_DIR_LENGTH) if [`None`])
+    ///
+    /// _The spec requires header and root directory to fit in the first 16 KiB, so larger
+    /// values should only be used for archives, which are read from local disk._
+    ///
+    /// Writing fails with [`Error::Validation`](crate::Error::Validation), if the value is too small
+    /// to hold even a single pointer to a leaf directory.
+    pub max_root_dir_length: Option<u32>,
+
+    /// Level used to compress the meta data with the internal compression of the archive
+    /// (see [`util::compress_with_level`](crate::util::compress_with_level))
+    pub meta_data_compression_level: Option<u32>,
+
+    /// Level used to re-compress the data of each tile with the tile compression of the archive
+    /// (see [`util::compress_with_level`](crate::util::compress_with_level))
+    ///
+    /// Tiles are written as is if [`None`] or the tile compression is [`Compression::None`](crate::Compression::None).
+    /// Otherwise each tile is decompressed and compressed again after [`transform`](Self::transform) was applied.
+    pub tile_compression_level: Option<u32>,
+
+    /// Whether the header marks the archive as clustered (default `true`)
+    ///
+    /// Tile data is always written in ascending order of tile ids (regardless of [`dedupe`](Self::dedupe)),
+    /// so this only has to be disabled for readers, which should not rely on the order of tile data.
+    pub clustered: bool,
+
+    /// Whether tiles with the same content are only stored once (default `true`)
+    ///
+    /// _Disabling this results in larger archives, but may be useful if the data of each tile
+    /// should be stored separately (e.g. to patch tiles in place later on)._
+    pub dedupe: bool,
+
+    /// Receiver of progress updates (see [`WriteProgress`])
+    pub progress: Option<&'a mut (dyn WriteProgress + Send)>,
+
+    /// Transformation applied to the data of each tile before it is written (see [`TileTransform`])
+    ///
+    /// Tiles, for which the transform returns [`None`], are left out. The header (e.g. zoom levels and
+    /// bounds) is written as is, so it has to be updated beforehand if the transform changes it.
+    pub transform: Option<&'a mut (dyn TileTransform + Send)>,
+
+    /// Token to cancel writing (see [`CancellationToken`])
+    ///
+    /// Writing stops with [`Error::Cancelled`](crate::Error::Cancelled) before reading the next tile or
+    /// writing the next chunk of tile data, once the token was cancelled. Seekable writers write the
+    /// header last, so their output never contains a valid archive after a cancelled write.
+    pub cancellation: Option<&'a CancellationToken>,
+
+    /// Codec used to compress directories and meta data, if the internal compression of the archive
+    /// is [`Compression::Unknown`](crate::Compression::Unknown) (see [`Codec`])
+    ///
+    /// Without a codec, writing such an archive fails with [`Error::UnknownCompression`](crate::Error::UnknownCompression).
+    pub codec: Option<Arc<dyn Codec>>,
+}
+
+impl Default for WriteOptions<'_> {
+    fn default() -> Self {
+        Self {
+            section_order: SectionOrder::default(),
+            dirs_overflow_strategy: None,
+            max_root_dir_length: None,
+            meta_data_compression_level: None,
+            tile_compression_level: None,
+            clustered: true,
+            dedupe: true,
+            progress: None,
+            transform: None,
+            cancellation: None,
+            codec: None,
+        }
+    }
+}
+
+impl fmt::Debug for WriteOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteOptions")
+            .field("section_order", &self.section_order)
+            .field("dirs_overflow_strategy", &self.dirs_overflow_strategy)
+            .field("max_root_dir_length", &self.max_root_dir_length)
+            .field(
+                "meta_data_compression_level",
+                &self.meta_data_compression_level,
+            )
+            .field("tile_compression_level", &self.tile_compression_level)
+            .field("clustered", &self.clustered)
+            .field("dedupe", &self.dedupe)
+            .field("progress", &self.progress.is_some())
+            .field("transform", &self.transform.is_some())
+            .field("cancellation", &self.cancellation)
+            .field("codec", &self.codec.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,191 @@
+use std::io::{Read, Seek};
+
+use duplicate::duplicate_item;
+#[cfg(feature = "async")]
+use futures::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+use crate::{
+    util::{
+        compress_all_with_dictionary, decompress_all, train_zstd_dictionary, zstd_dictionary_id,
+        ZSTD_DICTIONARY_ID_KEY,
+    },
+    Compression, PMTiles, Result,
+};
+
+#[duplicate_item(
+    train_fn_name                    compress_fn_name                               cfg_async_filter       async   add_await(code) RTraits                                                  get_tile_by_id;
+    [train_zstd_dictionary]          [compress_tiles_with_zstd_dictionary]          [cfg(all())]           []      [code]          [Read + Seek]                                            [get_tile_by_id];
+    [train_zstd_dictionary_async]    [compress_tiles_with_zstd_dictionary_async]    [cfg(feature="async")] [async] [code.await]    [AsyncRead + AsyncReadExt + Send + Unpin + AsyncSeekExt] [get_tile_by_id_async];
+)]
+#[cfg_async_filter]
+impl<R: RTraits> PMTiles<R> {
+    /// Trains a ZSTD dictionary from a sample of the tiles of this archive.
+    ///
+    /// The sample is spread evenly over all tiles. See [`util::train_zstd_dictionary`](crate::util::train_zstd_dictionary)
+    /// for details.
+    ///
+    /// # Arguments
+    /// * `max_samples` - Maximum number of tiles to train the dictionary with
+    /// * `max_size` - Maximum size (in bytes) of the dictionary
+    ///
+    /// # Errors
+    /// Will return [`Err`] if a tile could not be read or decompressed or training failed (e.g. because
+    /// the archive has too few tiles).
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::PMTiles;
+    /// let bytes = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+    /// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+    ///
+    /// let dictionary = pm_tiles.train_zstd_dictionary(1000, 16 * 1024).unwrap();
+    /// assert!(dictionary.len() <= 16 * 1024);
+    /// ```
+    pub async fn train_fn_name(&mut self, max_samples: usize, max_size: usize) -> Result<Vec<u8>> {
+        let tile_ids = self.tile_ids_sorted();
+        let num_samples = max_samples.min(tile_ids.len());
+
+        let mut samples = Vec::with_capacity(num_samples);
+        for i in 0..num_samples {
+            let tile_id = tile_ids[i * tile_ids.len() / num_samples];
+
+            if let Some(data) = add_await([self.get_tile_by_id(tile_id)])? {
+                samples.push(decompress_all(self.tile_compression, &data)?);
+            }
+        }
+
+        train_zstd_dictionary(&samples, max_size)
+    }
+
+    /// Re-compresses the data of all tiles with ZSTD using a dictionary and sets
+    /// [`tile_compression`](Self::tile_compression) to [`Compression::ZStd`].
+    ///
+    /// If the dictionary has an id, it is stored in the meta data with the key
+    /// [`ZSTD_DICTIONARY_ID_KEY`](crate::util::ZSTD_DICTIONARY_ID_KEY).
+    ///
+    /// **Note:** The dictionary is not part of the archive. It has to be distributed alongside
+    /// (e.g. as a sidecar file), as tiles can only be decompressed with
+    /// [`util::decompress_all_with_dictionary`](crate::util::decompress_all_with_dictionary).
+    /// Readers, which are not aware of the dictionary, can not decompress the tiles.
+    ///
+    /// # Arguments
+    /// * `dictionary` - ZSTD dictionary (see [`train_zstd_dictionary`](Self::train_zstd_dictionary))
+    /// * `level` - Compression level (`1..=22`); if [`None`] the default level is used
+    ///
+    /// # Errors
+    /// Will return [`Err`] if there was an error while reading, decompressing or compressing tile data.
+    /// In that case, some tiles may have already been re-compressed, while
+    /// [`tile_compression`](Self::tile_compression) is left unchanged.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use pmtiles2::{util::{decompress_all, decompress_all_with_dictionary}, Compression, PMTiles};
+    /// let bytes = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+    /// let mut pm_tiles = PMTiles::from_bytes(bytes).unwrap();
+    /// let original = pm_tiles.get_tile_by_id(0).unwrap().unwrap();
+    ///
+    /// let dictionary = pm_tiles.train_zstd_dictionary(1000, 16 * 1024).unwrap();
+    /// pm_tiles.compress_tiles_with_zstd_dictionary(&dictionary, Some(19)).unwrap();
+    ///
+    /// let data = pm_tiles.get_tile_by_id(0).unwrap().unwrap();
+    /// assert_eq!(
+    ///     decompress_all_with_dictionary(&data, &dictionary).unwrap(),
+    ///     decompress_all(Compression::GZip, &original).unwrap()
+    /// );
+    /// ```
+    pub async fn compress_fn_name(&mut self, dictionary: &[u8], level: Option<u32>) -> Result<()> {
+        for tile_id in self.tile_ids_sorted() {
+            let Some(data) = add_await([self.get_tile_by_id(tile_id)])? else {
+                continue;
+            };
+
+            let data = decompress_all(self.tile_compression, &data)?;
+            let data = compress_all_with_dictionary(&data, dictionary, level)?;
+
+            self.tile_manager.add_tile(tile_id, data)?;
+        }
+
+        self.tile_compression = Compression::ZStd;
+
+        if let Some(id) = zstd_dictionary_id(dictionary) {
+            self.meta_data
+                .insert(ZSTD_DICTIONARY_ID_KEY.to_string(), id.into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::decompress_all_with_dictionary;
+
+    const PM_TILES_BYTES: &[u8] = include_bytes!("../test/protomaps(vector)ODbL_firenze.pmtiles");
+
+    #[test]
+    fn test_compress_tiles_with_zstd_dictionary() -> Result<()> {
+        let mut source = PMTiles::from_bytes(PM_TILES_BYTES)?;
+        let mut pm_tiles = PMTiles::from_bytes(PM_TILES_BYTES)?;
+
+        let dictionary = pm_tiles.train_zstd_dictionary(1000, 16 * 1024)?;
+        assert!(zstd_dictionary_id(&dictionary).is_some());
+
+        pm_tiles.compress_tiles_with_zstd_dictionary(&dictionary, None)?;
+        assert_eq!(pm_tiles.tile_compression, Compression::ZStd);
+
+        let bytes = pm_tiles.to_vec()?;
+        let mut written = PMTiles::from_bytes(bytes)?;
+        assert_eq!(written.tile_compression, Compression::ZStd);
+        assert_eq!(
+            written.meta_data[ZSTD_DICTIONARY_ID_KEY],
+            zstd_dictionary_id(&dictionary).unwrap_or_default()
+        );
+
+        for tile_id in source.tile_ids_sorted() {
+            let expected = source.get_tile_by_id(tile_id)?.unwrap_or_default();
+            let actual = written.get_tile_by_id(tile_id)?.unwrap_or_default();
+
+            assert_eq!(
+                decompress_all_with_dictionary(&actual, &dictionary)?,
+                decompress_all(Compression::GZip, &expected)?
+            );
+        }
+
+        // tiles can not be decompressed without the dictionary
+        let data = written.get_tile_by_id(0)?.unwrap_or_default();
+        assert!(decompress_all(Compression::ZStd, &data).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_compress_tiles_with_zstd_dictionary_async() -> Result<()> {
+        tokio_test::block_on(async {
+            let reader = futures::io::Cursor::new(PM_TILES_BYTES);
+            let mut pm_tiles = PMTiles::from_async_reader(reader).await?;
+
+            let dictionary = pm_tiles
+                .train_zstd_dictionary_async(1000, 16 * 1024)
+                .await?;
+            assert_eq!(
+                dictionary,
+                PMTiles::from_bytes(PM_TILES_BYTES)?.train_zstd_dictionary(1000, 16 * 1024)?
+            );
+
+            pm_tiles
+                .compress_tiles_with_zstd_dictionary_async(&dictionary, Some(3))
+                .await?;
+            assert_eq!(pm_tiles.tile_compression, Compression::ZStd);
+
+            let data = pm_tiles.get_tile_by_id_async(0).await?.unwrap_or_default();
+            assert_eq!(
+                decompress_all_with_dictionary(&data, &dictionary)?.first(),
+                Some(&0x1a)
+            );
+
+            Ok(())
+        })
+    }
+}